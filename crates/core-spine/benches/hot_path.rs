@@ -0,0 +1,137 @@
+//! Control-loop hot path: setpoint validation, the lock-free state exchange
+//! and whole control cycles, idle and with bridge-like readers contending
+//! for the exchange.
+//!
+//! Run with `cargo bench -p core-spine --bench hot_path`.
+
+use core_spine::{
+    AgentRecommendation, ControlConfig, IronThread, ProcessSnapshot, SafetyLimits, Setpoint,
+    SimulatedMotor, StateExchange, TimeBase,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// 1 ms cycle
+const DT_S: f64 = 0.001;
+
+fn limits() -> SafetyLimits {
+    ControlConfig::default().safety_limits
+}
+
+fn recommendation(timestamp_us: u64) -> AgentRecommendation {
+    AgentRecommendation {
+        timestamp_us,
+        target_speed_rpm: Some(40.0),
+        confidence: 1.0,
+        ..AgentRecommendation::default()
+    }
+}
+
+fn bench_validate(c: &mut Criterion) {
+    let limits = limits();
+    let mut group = c.benchmark_group("setpoint_validate");
+    group.bench_function("accepted", |b| {
+        b.iter(|| {
+            Setpoint::new(black_box(1500.0)).validate(
+                &limits,
+                black_box(1480.0),
+                black_box(45.0),
+                black_box(2.0),
+                DT_S,
+            )
+        })
+    });
+    group.bench_function("rejected", |b| {
+        b.iter(|| {
+            Setpoint::new(black_box(5000.0)).validate(
+                &limits,
+                black_box(1480.0),
+                black_box(45.0),
+                black_box(2.0),
+                DT_S,
+            )
+        })
+    });
+    group.finish();
+}
+
+fn bench_state_exchange(c: &mut Criterion) {
+    let exchange = StateExchange::new(1_000_000);
+    let snapshot = ProcessSnapshot {
+        motor_speed_rpm: 1500.0,
+        motor_temp_c: 45.0,
+        ..ProcessSnapshot::default()
+    };
+    let mut group = c.benchmark_group("state_exchange");
+    group.bench_function("publish_state", |b| {
+        b.iter(|| exchange.publish_state(black_box(snapshot)))
+    });
+    group.bench_function("read_state", |b| b.iter(|| exchange.read_state()));
+    group.bench_function("submit_and_get_recommendation", |b| {
+        b.iter(|| {
+            exchange.submit_recommendation(recommendation(black_box(1_000)));
+            exchange.get_recommendation(black_box(1_500))
+        })
+    });
+    group.finish();
+}
+
+/// One control cycle on the simulated motor with a fresh recommendation.
+/// `readers` threads poll the exchange as bridge clients would, so the
+/// spread of cycle times shows the jitter contention adds.
+fn bench_control_cycle(c: &mut Criterion, name: &str, readers: usize) {
+    let exchange = Arc::new(StateExchange::new(1_000_000));
+    let clock = TimeBase::new();
+    let config = ControlConfig {
+        max_jitter_us: u64::MAX,
+        ..ControlConfig::default()
+    };
+    let mut iron = IronThread::new(SimulatedMotor::new(), config, Arc::clone(&exchange), clock);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let load: Vec<_> = (0..readers)
+        .map(|_| {
+            let exchange = Arc::clone(&exchange);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    black_box(exchange.read_state_sequenced());
+                    black_box(exchange.execution_stats());
+                }
+            })
+        })
+        .collect();
+
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            exchange.submit_recommendation(recommendation(clock.now_us()));
+            iron.step();
+        })
+    });
+
+    stop.store(true, Ordering::Relaxed);
+    for handle in load {
+        let _ = handle.join();
+    }
+}
+
+fn bench_control_loop(c: &mut Criterion) {
+    bench_control_cycle(c, "control_cycle/idle", 0);
+    bench_control_cycle(c, "control_cycle/contended", 2);
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = bench_validate, bench_state_exchange, bench_control_loop
+}
+criterion_main!(benches);
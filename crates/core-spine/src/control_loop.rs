@@ -1,11 +1,140 @@
-use crate::hal::MachineIO;
-use crate::safety::SafetyLimits;
-use crate::safety_supervisor::{SafetyState, SafetySupervisor};
-use crate::sync::{ProcessSnapshot, StateExchange};
-use crate::timebase::TimeBase;
+use crate::hal::{AlarmFlags, MachineIO};
+use crate::ramp::{RampGenerator, RampProfile};
+use crate::reasoning::ReasoningHash;
+use crate::safety::{SafetyLimits, SafetyViolation};
+use crate::safety_supervisor::{
+    SafetyState, SafetySupervisor, SafetyTransition, ThermalDerate, TransitionCause,
+};
+use crate::sync::{AgentRecommendation, ProcessSnapshot, RejectedRecommendation, StateExchange};
+use crate::timebase::{Clock, TimeBase};
+use serde::Serialize;
 use std::sync::{atomic::AtomicBool, Arc};
 use std::time::{Duration, Instant};
 
+/// What the loop does once recommendations go stale. Every target is still
+/// clamped to the speed bounds, ramped within the accel/decel limits and
+/// validated by the supervisor, which reports `Degraded` until the agent
+/// returns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AgentTimeoutPolicy {
+    /// Hold the last safe setpoint.
+    HoldLast,
+    /// Ramp down at `rate` rpm/s to zero, or to `min_speed_rpm` if that is
+    /// higher.
+    RampToZero { rate: f64 },
+    /// Ramp at `rate` rpm/s to a known safe `speed`.
+    RampToSafe { speed: f64, rate: f64 },
+}
+
+impl AgentTimeoutPolicy {
+    /// `hold`, `ramp-to-zero:<RATE>` or `ramp-to-safe:<SPEED>:<RATE>`, with
+    /// rates in rpm/s. `None` for anything else, including rates that are
+    /// not finite and positive.
+    pub fn parse(value: &str) -> Option<Self> {
+        let num = |part: &str| part.parse::<f64>().ok().filter(|v| v.is_finite());
+        let rate = |part: &str| num(part).filter(|rate| *rate > 0.0);
+        let parts: Vec<&str> = value.split(':').collect();
+        match parts.as_slice() {
+            ["hold"] => Some(Self::HoldLast),
+            ["ramp-to-zero", r] => Some(Self::RampToZero { rate: rate(r)? }),
+            ["ramp-to-safe", s, r] => Some(Self::RampToSafe {
+                speed: num(s)?,
+                rate: rate(r)?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Ramp to follow after a timeout, `None` to hold
+    fn profile(&self, limits: &SafetyLimits) -> Option<RampProfile> {
+        let (target, rate) = match *self {
+            Self::HoldLast => return None,
+            Self::RampToZero { rate } => (0.0, rate),
+            Self::RampToSafe { speed, rate } => (speed, rate),
+        };
+        // Not `clamp`, which panics on inverted or NaN limits.
+        RampProfile::new(
+            target.max(limits.min_speed_rpm).min(limits.max_speed_rpm),
+            rate,
+        )
+    }
+}
+
+/// How the loop brings the motor down on an operator emergency stop.
+///
+/// `Immediate` is the safety-rated stop: the setpoint drops to zero in the
+/// same cycle. `Ramp` is a controlled stop for high-inertia drives, where an
+/// instant zero would shock the mechanics or push the DC bus into
+/// overvoltage; it relies on the loop keeping running and is not safety
+/// rated. Watchdog overruns, latching alarms and HAL faults always stop
+/// immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EStopProfile {
+    #[default]
+    Immediate,
+    /// Decelerate to zero at `decel_rpm_per_s`, ignoring agent input
+    Ramp { decel_rpm_per_s: f64 },
+}
+
+impl EStopProfile {
+    /// `immediate` or `ramp:<RATE>` with the rate in rpm/s. `None` for
+    /// anything else, including rates that are not finite and positive.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.split_once(':') {
+            None if value == "immediate" => Some(Self::Immediate),
+            Some(("ramp", rate)) => rate
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| rate.is_finite() && *rate > 0.0)
+                .map(|decel_rpm_per_s| Self::Ramp { decel_rpm_per_s }),
+            _ => None,
+        }
+    }
+}
+
+/// How [`IronThread::run`] waits for the next cycle, trading wake-up jitter
+/// for CPU use.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WaitStrategy {
+    /// Spin until the deadline: the lowest jitter, but a full core.
+    #[default]
+    BusySpin,
+    /// Sleep until the deadline; wake-up latency depends on the scheduler.
+    Sleep,
+    /// Sleep until `spin_threshold` before the deadline, then spin.
+    Hybrid { spin_threshold: Duration },
+}
+
+impl WaitStrategy {
+    /// `busy-spin`, `sleep` or `hybrid:<US>` with the spin threshold in
+    /// microseconds. `None` for anything else.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.split_once(':') {
+            None if value == "busy-spin" => Some(Self::BusySpin),
+            None if value == "sleep" => Some(Self::Sleep),
+            Some(("hybrid", us)) => us.parse().ok().map(|us| Self::Hybrid {
+                spin_threshold: Duration::from_micros(us),
+            }),
+            _ => None,
+        }
+    }
+
+    fn wait_until(&self, deadline: Instant) {
+        let spin_for = match *self {
+            Self::BusySpin => None,
+            Self::Sleep => Some(Duration::ZERO),
+            Self::Hybrid { spin_threshold } => Some(spin_threshold),
+        };
+        if let Some(spin_for) = spin_for {
+            let wake = deadline.checked_sub(spin_for).unwrap_or(deadline);
+            std::thread::sleep(wake.saturating_duration_since(Instant::now()));
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ControlConfig {
     pub cycle_time: Duration,
@@ -14,6 +143,34 @@ pub struct ControlConfig {
     pub watchdog_timeout: Duration,
     pub max_jitter_us: u64,
     pub jitter_trip_after: u32,
+    /// Cycles whose jitter reaches these are counted in
+    /// [`ExecutionStats::jitter_warn_cycles`] and
+    /// [`ExecutionStats::jitter_crit_cycles`] for alerting. They never
+    /// affect the supervisor. `None` disables the count.
+    pub jitter_warn_us: Option<u64>,
+    pub jitter_crit_us: Option<u64>,
+    pub agent_timeout: AgentTimeoutPolicy,
+    /// Trip the supervisor once the HAL has reported unhealthy for this
+    /// long, so a dead fieldbus link cannot leave the loop acting on frozen
+    /// readings. `None` disables the check.
+    pub hal_fault_timeout: Option<Duration>,
+    /// Skip writing a setpoint that differs from the one already commanded
+    /// by less than this, so small recommendation fluctuations do not wear
+    /// the actuator. Stops are always written. `0.0` disables the deadband.
+    pub setpoint_deadband_rpm: f64,
+    /// Operator speed cap below `safety_limits.max_speed_rpm`. Agent targets
+    /// above it are clamped down to it instead of rejected, so production
+    /// can be throttled without tripping; targets above the hard limit are
+    /// still rejected. `None` disables the cap.
+    pub operational_max_speed_rpm: Option<f64>,
+    /// HAL alarms that trip the supervisor while set, latching the loop
+    /// into `Safe`. Others are only logged.
+    pub latching_alarms: AlarmFlags,
+    /// Slow down above a soft temperature limit instead of running until
+    /// the hard limit trips. `None` disables derating.
+    pub thermal_derate: Option<ThermalDerate>,
+    pub estop_profile: EStopProfile,
+    pub wait_strategy: WaitStrategy,
 }
 
 impl Default for ControlConfig {
@@ -23,53 +180,156 @@ impl Default for ControlConfig {
             safety_limits: SafetyLimits {
                 max_speed_rpm: 3000.0,
                 min_speed_rpm: 0.0,
-                max_rate_of_change: 50.0,
+                // Scaled by the cycle time: 50 rpm over the default 1 ms
+                max_accel_rpm_per_s: 50_000.0,
+                max_decel_rpm_per_s: 50_000.0,
+                min_temp_c: SafetyLimits::NO_MIN_TEMP_C,
                 max_temp_c: 80.0,
+                max_pressure_bar: 1000.0,
             },
             recommendation_timeout: Duration::from_millis(500),
             watchdog_timeout: Duration::from_millis(100),
             max_jitter_us: 500,
             jitter_trip_after: 3,
+            jitter_warn_us: None,
+            jitter_crit_us: None,
+            agent_timeout: AgentTimeoutPolicy::HoldLast,
+            hal_fault_timeout: None,
+            setpoint_deadband_rpm: 0.0,
+            operational_max_speed_rpm: None,
+            latching_alarms: AlarmFlags::all(),
+            thermal_derate: None,
+            estop_profile: EStopProfile::Immediate,
+            wait_strategy: WaitStrategy::BusySpin,
         }
     }
 }
 
-#[derive(Clone, Default, Debug)]
+/// Upper bounds of the recommendation age buckets, from sub-millisecond to
+/// seconds. Ages above the last bound fall in an overflow bucket.
+pub const RECOMMENDATION_AGE_BUCKETS_US: [u64; 14] = [
+    250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000,
+    2_500_000, 5_000_000,
+];
+
+/// Running distribution of the age of each recommendation the loop applied.
+/// Bucket `i` holds ages in `(bound[i-1], bound[i]]`, the last one everything
+/// above the final bound; counts and sums only ever grow.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Serialize)]
+pub struct RecommendationAgeHistogram {
+    pub counts: [u64; RECOMMENDATION_AGE_BUCKETS_US.len() + 1],
+    pub sums_us: [u64; RECOMMENDATION_AGE_BUCKETS_US.len() + 1],
+}
+
+impl RecommendationAgeHistogram {
+    pub fn bucket_index(age_us: u64) -> usize {
+        RECOMMENDATION_AGE_BUCKETS_US.partition_point(|&bound| bound < age_us)
+    }
+
+    pub fn record(&mut self, age_us: u64) {
+        let bucket = Self::bucket_index(age_us);
+        self.counts[bucket] += 1;
+        self.sums_us[bucket] = self.sums_us[bucket].saturating_add(age_us);
+    }
+
+    pub fn sample_count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+#[derive(Clone, Default, Debug, Serialize)]
 pub struct ExecutionStats {
     pub cycles_executed: u64,
     pub cycles_missed: u64,
     pub max_jitter_us: u64,
     pub safety_rejections: u64,
+    /// Rejections by [`SafetyViolation::kind_index`]
+    pub safety_violations: [u64; SafetyViolation::KINDS.len()],
     pub agent_timeouts: u64,
     pub last_recommendation_age_us: u64,
+    pub recommendation_age: RecommendationAgeHistogram,
     pub safety_state: SafetyState,
     pub timing_violations: u64,
+    /// Cycles whose jitter reached [`ControlConfig::jitter_warn_us`],
+    /// including those that also reached the critical threshold
+    pub jitter_warn_cycles: u64,
+    /// Cycles whose jitter reached [`ControlConfig::jitter_crit_us`]
+    pub jitter_crit_cycles: u64,
+    /// Session high-water marks, reset at process start
+    pub max_speed_rpm: f64,
+    pub max_temp_c: f64,
+    pub max_pressure_bar: f64,
+}
+
+/// Called on the control thread once per supervisor state change, with the
+/// cycle timestamp. Keep it short; it runs inside the cycle.
+pub type TransitionHook = Box<dyn FnMut(u64, &SafetyTransition) + Send>;
+
+/// Sensor readings taken during one control cycle
+struct CycleReadings {
+    timestamp_us: u64,
+    speed: f64,
+    temp: f64,
+    pressure: f64,
+    applied_reasoning_hash: ReasoningHash,
 }
 
-pub struct IronThread<IO: MachineIO> {
+pub struct IronThread<IO: MachineIO, C: Clock = TimeBase> {
     io: IO,
     config: ControlConfig,
     exchange: Arc<StateExchange>,
     stats: ExecutionStats,
     safety: SafetySupervisor,
-    timebase: TimeBase,
+    ramp: RampGenerator,
+    /// How long the HAL has been unhealthy without a break
+    hal_unhealthy_for: Duration,
+    /// Last setpoint written to the HAL
+    commanded_speed: Option<f64>,
+    /// Setpoint of a ramped emergency stop still in progress
+    estop_ramp_rpm: Option<f64>,
+    /// Timestamp of the last recommendation clamped to the operational cap,
+    /// so each one is logged once rather than every cycle
+    last_clamped_us: Option<u64>,
+    /// Limits from `config` at construction; runtime changes may only
+    /// tighten them
+    configured_limits: SafetyLimits,
+    /// Alarms seen last cycle, so changes are logged once
+    alarms: AlarmFlags,
+    on_transition: Option<TransitionHook>,
+    clock: C,
 }
 
-impl<IO: MachineIO> IronThread<IO> {
-    pub fn new(
-        io: IO,
-        config: ControlConfig,
-        exchange: Arc<StateExchange>,
-        timebase: TimeBase,
-    ) -> Self {
-        let safety = SafetySupervisor::new(config.safety_limits);
+impl<IO: MachineIO, C: Clock> IronThread<IO, C> {
+    pub fn new(io: IO, config: ControlConfig, exchange: Arc<StateExchange>, clock: C) -> Self {
+        let configured_limits = config.safety_limits;
+        let safety =
+            SafetySupervisor::new(configured_limits).with_thermal_derate(config.thermal_derate);
         Self {
             io,
             config,
             exchange,
             stats: ExecutionStats::default(),
             safety,
-            timebase,
+            ramp: RampGenerator::new(),
+            hal_unhealthy_for: Duration::ZERO,
+            commanded_speed: None,
+            estop_ramp_rpm: None,
+            last_clamped_us: None,
+            configured_limits,
+            alarms: AlarmFlags::empty(),
+            on_transition: None,
+            clock,
+        }
+    }
+
+    /// Report every supervisor state change to `hook`, e.g. for an audit log
+    pub fn with_transition_hook(
+        self,
+        hook: impl FnMut(u64, &SafetyTransition) + Send + 'static,
+    ) -> Self {
+        Self {
+            on_transition: Some(Box::new(hook)),
+            ..self
         }
     }
 
@@ -80,9 +340,7 @@ impl<IO: MachineIO> IronThread<IO> {
         while !stop.load(std::sync::atomic::Ordering::Relaxed) {
             let now = Instant::now();
             if now < next_cycle {
-                while Instant::now() < next_cycle {
-                    std::hint::spin_loop();
-                }
+                self.config.wait_strategy.wait_until(next_cycle);
             } else {
                 self.stats.cycles_missed += 1;
                 let overrun = now.duration_since(next_cycle);
@@ -93,80 +351,1273 @@ impl<IO: MachineIO> IronThread<IO> {
             }
 
             let cycle_start = Instant::now();
-            let timestamp_us = self.timebase.now_us();
-
-            // Advance simulation / I/O
-            self.io.step(cycle_dt_s);
-
-            // Read inputs
-            let current_speed = self.io.read_speed();
-            let current_temp = self.io.read_temperature();
-            let current_pressure = self.io.read_pressure();
-
-            // Read AI recommendation (stale => None)
-            let recommendation = self.exchange.get_recommendation(timestamp_us);
-            let target_speed = match recommendation {
-                Some(rec) if rec.target_speed_rpm.is_some() => {
-                    self.stats.last_recommendation_age_us =
-                        timestamp_us.saturating_sub(rec.timestamp_us);
-                    rec.target_speed_rpm
-                }
-                _ => {
-                    self.stats.agent_timeouts += 1;
-                    None
-                }
-            };
-
-            let (output_speed, violation) =
-                self.safety
-                    .apply_recommendation(target_speed, current_speed, current_temp);
-            if violation.is_some() {
-                self.stats.safety_rejections += 1;
-            }
+            let readings = self.control_cycle(cycle_dt_s);
 
-            // Write outputs
-            self.io.write_speed(output_speed);
-
-            // Publish state
             let cycle_duration = cycle_start.elapsed();
             let jitter_us = if cycle_duration > self.config.cycle_time {
                 (cycle_duration - self.config.cycle_time).as_micros() as u64
             } else {
                 0
             };
-            self.stats.max_jitter_us = self.stats.max_jitter_us.max(jitter_us);
-            if self.safety.note_timing_jitter(
-                jitter_us,
-                self.config.max_jitter_us,
-                self.config.jitter_trip_after,
-            ) {
-                self.stats.timing_violations += 1;
-                if self.safety.state() == SafetyState::Trip {
-                    self.io.write_speed(0.0);
-                }
+            self.finish_cycle(readings, jitter_us);
+
+            next_cycle += self.config.cycle_time;
+        }
+    }
+
+    /// Execute a single cycle at the current clock time without wall-clock
+    /// pacing or jitter measurement. With a [`crate::LogicalClock`] this makes
+    /// runs reproducible regardless of host speed.
+    pub fn step(&mut self) {
+        let readings = self.control_cycle(self.config.cycle_time.as_secs_f64());
+        self.finish_cycle(readings, 0);
+    }
+
+    /// I/O, recommendation intake, and safety validation for one cycle
+    fn control_cycle(&mut self, cycle_dt_s: f64) -> CycleReadings {
+        let timestamp_us = self.clock.now_us();
+
+        // Advance simulation / I/O
+        self.io.step(cycle_dt_s);
+        let alarm_tripped = self.latching_alarm_set();
+        if self.hal_fault_expired(cycle_dt_s) || alarm_tripped {
+            self.safety.trip();
+            self.ramp.clear();
+            self.estop_ramp_rpm = None;
+        }
+
+        // Read inputs
+        let current_speed = self.io.read_speed();
+        let current_temp = self.io.read_temperature();
+        let current_pressure = self.io.read_pressure();
+        if self.exchange.take_session_reset() {
+            self.stats.max_jitter_us = 0;
+            self.stats.max_speed_rpm = 0.0;
+            self.stats.max_temp_c = 0.0;
+            self.stats.max_pressure_bar = 0.0;
+        }
+        if let Some(limits) = self.exchange.take_safety_limits() {
+            self.change_limits(limits);
+        }
+        // f64::max ignores NaN, so a bad sensor read cannot clear a mark
+        self.stats.max_speed_rpm = self.stats.max_speed_rpm.max(current_speed);
+        self.stats.max_temp_c = self.stats.max_temp_c.max(current_temp);
+        self.stats.max_pressure_bar = self.stats.max_pressure_bar.max(current_pressure);
+
+        // Read AI recommendation (stale => None)
+        let recommendation = self.exchange.get_recommendation(timestamp_us);
+        let (target_speed, agent_timed_out) = match recommendation {
+            Some(rec) if rec.target_speed_rpm.is_some() => {
+                let age_us = timestamp_us.saturating_sub(rec.timestamp_us);
+                self.stats.last_recommendation_age_us = age_us;
+                self.stats.recommendation_age.record(age_us);
+                (
+                    self.effective_target(&rec, cycle_dt_s, current_speed),
+                    false,
+                )
             }
-            self.stats.safety_state = self.safety.state();
-            self.stats.cycles_executed += 1;
+            Some(_) => {
+                self.ramp.clear();
+                self.stats.agent_timeouts += 1;
+                (None, false)
+            }
+            None => {
+                self.stats.agent_timeouts += 1;
+                (self.timeout_target(cycle_dt_s, current_speed), true)
+            }
+        };
 
-            self.exchange.publish_state(ProcessSnapshot {
-                timestamp_us,
-                cycle_count: self.stats.cycles_executed,
-                safety_state: self.stats.safety_state,
-                motor_speed_rpm: current_speed,
-                motor_temp_c: current_temp,
-                pressure_bar: current_pressure,
-                cycle_jitter_us: jitter_us as u32,
-            });
+        // A timeout ramp step is validated like a recommendation but keeps
+        // the loop reporting Degraded until the agent returns.
+        let (mut output_speed, violation) = if agent_timed_out {
+            self.safety.apply_timeout_target(
+                target_speed,
+                current_speed,
+                current_temp,
+                current_pressure,
+                cycle_dt_s,
+            )
+        } else {
+            self.safety.apply_recommendation(
+                target_speed,
+                current_speed,
+                current_temp,
+                current_pressure,
+                cycle_dt_s,
+            )
+        };
+        if let Some(violation) = violation {
+            self.stats.safety_rejections += 1;
+            self.stats.safety_violations[violation.kind_index()] += 1;
+            self.ramp.clear();
+            if let Some(rec) = recommendation {
+                self.exchange.publish_rejection(RejectedRecommendation {
+                    recommendation_us: rec.timestamp_us,
+                    requested_rpm: target_speed,
+                    current_speed_rpm: current_speed,
+                    current_temp_c: current_temp,
+                    violation,
+                    reasoning_hash: rec.reasoning_hash,
+                });
+            }
+        }
 
-            next_cycle += self.config.cycle_time;
+        let mut applied_reasoning_hash = match recommendation {
+            Some(rec) if violation.is_none() && target_speed.is_some() => rec.reasoning_hash,
+            _ => ProcessSnapshot::HOLDING_LAST_SAFE,
+        };
+
+        // Operator e-stop: trip (latched into Safe) and override this cycle's output
+        if self.exchange.take_emergency_stop() {
+            self.safety.trip();
+            output_speed = 0.0;
+            applied_reasoning_hash = ProcessSnapshot::HOLDING_LAST_SAFE;
+            if let EStopProfile::Ramp { .. } = self.config.estop_profile {
+                self.estop_ramp_rpm = Some(self.commanded_speed.unwrap_or(current_speed));
+            }
+        }
+        if let Some(speed) = self.estop_ramp_rpm {
+            output_speed = self.estop_ramp_step(speed, cycle_dt_s);
+        }
+
+        // Write outputs
+        match self.commanded_speed {
+            Some(commanded) if self.within_deadband(commanded, output_speed) => {
+                self.safety.hold_setpoint(commanded);
+            }
+            _ => self.command_speed(output_speed),
+        }
+
+        CycleReadings {
+            timestamp_us,
+            speed: current_speed,
+            temp: current_temp,
+            pressure: current_pressure,
+            applied_reasoning_hash,
+        }
+    }
+
+    /// This cycle's target: the next ramp step when the recommendation asks
+    /// for a ramp, otherwise the raw target. Targets outside the speed
+    /// bounds are passed through so the supervisor rejects them immediately
+    /// rather than when the ramp reaches the bound.
+    fn effective_target(
+        &mut self,
+        rec: &AgentRecommendation,
+        dt_s: f64,
+        current_speed: f64,
+    ) -> Option<f64> {
+        let target = self.operational_clamp(rec, rec.target_speed_rpm?);
+        let limits = self.config.safety_limits;
+        let profile = rec
+            .ramp_rate_rpm_per_s
+            .and_then(|rate| RampProfile::new(target, rate))
+            .filter(|_| (limits.min_speed_rpm..=limits.max_speed_rpm).contains(&target));
+        match profile {
+            Some(profile) => {
+                self.ramp.set_profile(profile);
+                self.ramp.advance(dt_s, current_speed, &limits)
+            }
+            None => {
+                self.ramp.clear();
+                Some(target)
+            }
+        }
+    }
+
+    /// `target` capped at the operational maximum. Targets above the hard
+    /// limit, and non-finite ones, are left for the supervisor to reject.
+    fn operational_clamp(&mut self, rec: &AgentRecommendation, target: f64) -> f64 {
+        let Some(cap) = self.config.operational_max_speed_rpm else {
+            return target;
+        };
+        if !(target > cap && target <= self.config.safety_limits.max_speed_rpm) {
+            return target;
+        }
+        if self.last_clamped_us != Some(rec.timestamp_us) {
+            self.last_clamped_us = Some(rec.timestamp_us);
+            log::info!("clamping agent target {target} rpm to operational maximum {cap} rpm");
+        }
+        cap
+    }
+
+    /// Adopt runtime safety limits. Submitters check them already; they
+    /// are checked again here so no path can loosen the configured limits.
+    fn change_limits(&mut self, limits: SafetyLimits) {
+        if let Err(e) = limits.check_within(&self.configured_limits) {
+            log::warn!("refusing safety limits change: {e}");
+            return;
+        }
+        log::info!("safety limits changed to {limits:?}");
+        self.config.safety_limits = limits;
+        self.safety.set_limits(limits);
+    }
+
+    /// This cycle's target under the agent timeout policy
+    fn timeout_target(&mut self, dt_s: f64, current_speed: f64) -> Option<f64> {
+        let limits = self.config.safety_limits;
+        match self.config.agent_timeout.profile(&limits) {
+            Some(profile) => {
+                self.ramp.set_profile(profile);
+                self.ramp.advance(dt_s, current_speed, &limits)
+            }
+            None => {
+                self.ramp.clear();
+                None
+            }
+        }
+    }
+
+    /// Next setpoint of a ramped emergency stop from `speed`; the ramp ends
+    /// once it reaches zero.
+    fn estop_ramp_step(&mut self, speed: f64, dt_s: f64) -> f64 {
+        let decel = match self.config.estop_profile {
+            EStopProfile::Ramp { decel_rpm_per_s } => decel_rpm_per_s,
+            EStopProfile::Immediate => f64::INFINITY,
+        };
+        let next = (speed - decel * dt_s).max(0.0);
+        self.estop_ramp_rpm = (next > 0.0).then_some(next);
+        next
+    }
+
+    fn command_speed(&mut self, rpm: f64) {
+        self.io.write_speed(rpm);
+        self.commanded_speed = Some(rpm);
+    }
+
+    /// True if `output` is close enough to the commanded setpoint to skip
+    /// the write. Never while stopping or outside normal operation.
+    fn within_deadband(&self, commanded: f64, output: f64) -> bool {
+        output != 0.0
+            && matches!(
+                self.safety.state(),
+                SafetyState::Normal | SafetyState::Degraded
+            )
+            && (output - commanded).abs() < self.config.setpoint_deadband_rpm
+    }
+
+    /// Track HAL health; true once it has been unhealthy past `hal_fault_timeout`
+    fn hal_fault_expired(&mut self, dt_s: f64) -> bool {
+        let Some(timeout) = self.config.hal_fault_timeout else {
+            return false;
+        };
+        if self.io.is_healthy() {
+            self.hal_unhealthy_for = Duration::ZERO;
+            return false;
+        }
+        self.hal_unhealthy_for += Duration::from_secs_f64(dt_s);
+        self.hal_unhealthy_for >= timeout
+    }
+
+    /// Read the HAL alarms, logging changes; true if a latching one is set
+    fn latching_alarm_set(&mut self) -> bool {
+        let alarms = self.io.read_alarms();
+        if alarms != self.alarms {
+            if alarms.is_empty() {
+                log::info!("HAL alarms cleared");
+            } else {
+                log::warn!("HAL alarms raised: {alarms:?}");
+            }
+            self.alarms = alarms;
+        }
+        alarms.intersects(self.config.latching_alarms)
+    }
+
+    /// Timing supervision, stats, and state publication for one cycle
+    fn finish_cycle(&mut self, readings: CycleReadings, jitter_us: u64) {
+        self.stats.max_jitter_us = self.stats.max_jitter_us.max(jitter_us);
+        if self
+            .config
+            .jitter_warn_us
+            .is_some_and(|warn| jitter_us >= warn)
+        {
+            self.stats.jitter_warn_cycles += 1;
+        }
+        if self
+            .config
+            .jitter_crit_us
+            .is_some_and(|crit| jitter_us >= crit)
+        {
+            self.stats.jitter_crit_cycles += 1;
+        }
+        if self.safety.note_timing_jitter(
+            jitter_us,
+            self.config.max_jitter_us,
+            self.config.jitter_trip_after,
+        ) {
+            self.stats.timing_violations += 1;
+            if self.safety.state() == SafetyState::Trip {
+                self.command_speed(0.0);
+            }
+        }
+        self.stats.safety_state = self.safety.state();
+        self.stats.cycles_executed += 1;
+        self.report_transitions(readings.timestamp_us);
+
+        self.exchange.publish_hal_health(self.io.is_healthy());
+        self.exchange.publish_stats(&self.stats);
+        self.exchange.publish_state(ProcessSnapshot {
+            timestamp_us: readings.timestamp_us,
+            cycle_count: self.stats.cycles_executed,
+            safety_state: self.stats.safety_state,
+            motor_speed_rpm: readings.speed,
+            motor_temp_c: readings.temp,
+            pressure_bar: readings.pressure,
+            cycle_jitter_us: jitter_us as u32,
+            applied_reasoning_hash: readings.applied_reasoning_hash,
+        });
+    }
+
+    /// Log this cycle's supervisor state changes and pass them to the hook
+    fn report_transitions(&mut self, timestamp_us: u64) {
+        for transition in self.safety.take_transitions() {
+            let SafetyTransition { from, to, cause } = transition;
+            let (from, to) = (from.as_str(), to.as_str());
+            match cause {
+                TransitionCause::Violation(violation) => {
+                    log::warn!("safety state {from} -> {to}: {violation:?}")
+                }
+                TransitionCause::TimingJitter { jitter_us } => {
+                    log::warn!("safety state {from} -> {to}: cycle jitter {jitter_us} us")
+                }
+                cause => log::info!("safety state {from} -> {to}: {}", cause.as_str()),
+            }
+            if let Some(hook) = self.on_transition.as_mut() {
+                hook(timestamp_us, &transition);
+            }
         }
     }
 
     fn emergency_stop(&mut self) {
-        self.io.write_speed(0.0);
+        let timestamp_us = self.clock.now_us();
+        self.safety.trip();
+        self.estop_ramp_rpm = None;
+        self.report_transitions(timestamp_us);
+        self.stats.safety_state = self.safety.state();
+        self.command_speed(0.0);
+        self.exchange.publish_stats(&self.stats);
+
+        let mut snapshot = self.exchange.read_state();
+        snapshot.timestamp_us = timestamp_us;
+        snapshot.safety_state = self.stats.safety_state;
+        snapshot.applied_reasoning_hash = ProcessSnapshot::HOLDING_LAST_SAFE;
+        self.exchange.publish_state(snapshot);
     }
 
     pub fn stats(&self) -> &ExecutionStats {
         &self.stats
     }
+
+    pub fn io(&self) -> &IO {
+        &self.io
+    }
+
+    pub fn io_mut(&mut self) -> &mut IO {
+        &mut self.io
+    }
+
+    /// Stats as last published to the `StateExchange`. Other threads holding
+    /// the exchange can poll the same values with
+    /// `StateExchange::execution_stats` while the loop is running.
+    pub fn stats_snapshot(&self) -> ExecutionStats {
+        self.exchange.execution_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hal::CycleStats;
+    use crate::hal_sim::SimulatedMotor;
+    use std::sync::atomic::Ordering;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_recommendation_age_buckets() {
+        assert_eq!(RecommendationAgeHistogram::bucket_index(0), 0);
+        assert_eq!(RecommendationAgeHistogram::bucket_index(250), 0);
+        assert_eq!(RecommendationAgeHistogram::bucket_index(251), 1);
+        assert_eq!(
+            RecommendationAgeHistogram::bucket_index(u64::MAX),
+            RECOMMENDATION_AGE_BUCKETS_US.len()
+        );
+
+        let mut histogram = RecommendationAgeHistogram::default();
+        histogram.record(100);
+        histogram.record(200);
+        histogram.record(10_000_000);
+        assert_eq!(histogram.counts[0], 2);
+        assert_eq!(histogram.sums_us[0], 300);
+        assert_eq!(histogram.counts[RECOMMENDATION_AGE_BUCKETS_US.len()], 1);
+        assert_eq!(histogram.sample_count(), 3);
+    }
+
+    #[test]
+    fn test_emergency_stop_request_trips_supervisor() {
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let stop = Arc::new(AtomicBool::new(false));
+        let config = ControlConfig {
+            // Generous watchdog so a loaded test machine does not trip it first
+            watchdog_timeout: Duration::from_secs(5),
+            max_jitter_us: u64::MAX,
+            ..Default::default()
+        };
+
+        let handle = {
+            let exchange = Arc::clone(&exchange);
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || {
+                let mut iron =
+                    IronThread::new(SimulatedMotor::new(), config, exchange, TimeBase::new());
+                iron.run(&stop);
+                iron.stats().safety_state
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!matches!(
+            exchange.read_state().safety_state,
+            SafetyState::Trip | SafetyState::Safe
+        ));
+
+        exchange.request_emergency_stop();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(matches!(
+            exchange.read_state().safety_state,
+            SafetyState::Trip | SafetyState::Safe
+        ));
+
+        stop.store(true, Ordering::Relaxed);
+        assert_eq!(handle.join().unwrap(), SafetyState::Safe);
+    }
+
+    #[test]
+    fn test_wait_strategy_parse() {
+        assert_eq!(
+            WaitStrategy::parse("busy-spin"),
+            Some(WaitStrategy::BusySpin)
+        );
+        assert_eq!(WaitStrategy::parse("sleep"), Some(WaitStrategy::Sleep));
+        assert_eq!(
+            WaitStrategy::parse("hybrid:200"),
+            Some(WaitStrategy::Hybrid {
+                spin_threshold: Duration::from_micros(200)
+            })
+        );
+        for bad in ["spin", "hybrid", "hybrid:-1", "sleep:5"] {
+            assert_eq!(WaitStrategy::parse(bad), None, "{bad}");
+        }
+    }
+
+    /// CPU time the calling thread has used
+    #[cfg(target_os = "linux")]
+    fn thread_cpu_ns() -> u64 {
+        std::fs::read_to_string("/proc/thread-self/schedstat")
+            .ok()
+            .and_then(|stat| stat.split_whitespace().next()?.parse().ok())
+            .unwrap_or(0)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sleep_wait_uses_less_cpu_than_busy_spin() {
+        let run_for = |wait_strategy: WaitStrategy| {
+            let stop = Arc::new(AtomicBool::new(false));
+            let config = ControlConfig {
+                cycle_time: Duration::from_millis(2),
+                watchdog_timeout: Duration::from_secs(5),
+                max_jitter_us: u64::MAX,
+                wait_strategy,
+                ..Default::default()
+            };
+            let handle = {
+                let stop = Arc::clone(&stop);
+                std::thread::spawn(move || {
+                    let exchange = Arc::new(StateExchange::new(1_000_000));
+                    let mut iron =
+                        IronThread::new(SimulatedMotor::new(), config, exchange, TimeBase::new());
+                    let start = thread_cpu_ns();
+                    iron.run(&stop);
+                    (thread_cpu_ns() - start, iron.stats().clone())
+                })
+            };
+            std::thread::sleep(Duration::from_millis(200));
+            stop.store(true, Ordering::Relaxed);
+            handle.join().unwrap()
+        };
+
+        let (busy_ns, _) = run_for(WaitStrategy::BusySpin);
+        let (sleep_ns, stats) = run_for(WaitStrategy::Sleep);
+        if busy_ns == 0 {
+            // No per-thread accounting on this kernel
+            return;
+        }
+        assert!(
+            sleep_ns * 4 < busy_ns,
+            "sleep {sleep_ns} ns, busy {busy_ns} ns"
+        );
+        // 100 cycles in 200 ms; oversleeping may stretch some of them.
+        assert!(stats.cycles_executed >= 50, "{stats:?}");
+        assert!(!matches!(
+            stats.safety_state,
+            SafetyState::Trip | SafetyState::Safe
+        ));
+    }
+
+    #[test]
+    fn test_stats_snapshot_counts_safety_rejection() {
+        use crate::sync::AgentRecommendation;
+        use crate::timebase::LogicalClock;
+
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        clock.advance(Duration::from_millis(1));
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+
+        exchange.submit_recommendation(AgentRecommendation {
+            timestamp_us: clock.now_us(),
+            target_speed_rpm: Some(5_000.0),
+            ramp_rate_rpm_per_s: None,
+            confidence: 1.0,
+            reasoning_hash: [0u8; 32].into(),
+        });
+        iron.step();
+
+        let snapshot = iron.stats_snapshot();
+        assert_eq!(snapshot.cycles_executed, 1);
+        assert_eq!(snapshot.safety_rejections, 1);
+        assert_eq!(exchange.execution_stats().safety_rejections, 1);
+
+        assert_eq!(
+            exchange.read_state().applied_reasoning_hash,
+            ProcessSnapshot::HOLDING_LAST_SAFE
+        );
+
+        let rejection = exchange.last_rejection().unwrap();
+        assert_eq!(rejection.recommendation_us, 1_000);
+        assert_eq!(rejection.requested_rpm, Some(5_000.0));
+        assert!(matches!(
+            rejection.violation,
+            crate::safety::SafetyViolation::ExceedsMaxSpeed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_applied_hash_tracks_accepted_recommendation() {
+        use crate::timebase::LogicalClock;
+
+        let exchange = Arc::new(StateExchange::new(2_000));
+        let clock = LogicalClock::new();
+        clock.advance(Duration::from_millis(1));
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+
+        exchange.submit_recommendation(AgentRecommendation {
+            timestamp_us: clock.now_us(),
+            target_speed_rpm: Some(20.0),
+            ramp_rate_rpm_per_s: None,
+            confidence: 1.0,
+            reasoning_hash: [7u8; 32].into(),
+        });
+        iron.step();
+        assert_eq!(exchange.read_state().applied_reasoning_hash, [7u8; 32]);
+
+        // Once the recommendation goes stale the loop holds its last setpoint.
+        clock.advance(Duration::from_millis(5));
+        iron.step();
+        assert_eq!(
+            exchange.read_state().applied_reasoning_hash,
+            ProcessSnapshot::HOLDING_LAST_SAFE
+        );
+    }
+
+    #[test]
+    fn test_session_reset_clears_high_water_marks() {
+        use crate::hal::CycleStats;
+        use crate::timebase::LogicalClock;
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        struct ScriptedIo {
+            temp_c: Arc<AtomicU64>,
+        }
+        impl MachineIO for ScriptedIo {
+            fn step(&mut self, _dt_s: f64) {}
+            fn read_speed(&self) -> f64 {
+                0.0
+            }
+            fn read_temperature(&self) -> f64 {
+                f64::from_bits(self.temp_c.load(Ordering::Relaxed))
+            }
+            fn read_pressure(&self) -> f64 {
+                1.0
+            }
+            fn write_speed(&mut self, _rpm: f64) {}
+            fn cycle_stats(&self) -> CycleStats {
+                CycleStats::default()
+            }
+            fn is_healthy(&self) -> bool {
+                true
+            }
+        }
+
+        let temp_c = Arc::new(AtomicU64::new(70.0f64.to_bits()));
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let mut iron = IronThread::new(
+            ScriptedIo {
+                temp_c: Arc::clone(&temp_c),
+            },
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+        clock.advance(Duration::from_millis(1));
+        iron.step();
+        temp_c.store(40.0f64.to_bits(), Ordering::Relaxed);
+        clock.advance(Duration::from_millis(1));
+        iron.step();
+        assert_eq!(iron.stats().max_temp_c, 70.0);
+
+        exchange.request_session_reset();
+        clock.advance(Duration::from_millis(1));
+        iron.step();
+        // Only the current cycle's readings remain; counters are untouched.
+        let stats = iron.stats();
+        assert_eq!(stats.max_temp_c, 40.0);
+        assert_eq!(stats.max_pressure_bar, 1.0);
+        assert_eq!(stats.cycles_executed, 3);
+        assert!(!exchange.take_session_reset());
+    }
+
+    #[test]
+    fn test_hal_health_published_each_cycle() {
+        use crate::hal::CycleStats;
+        use crate::timebase::LogicalClock;
+
+        struct DisconnectedIo;
+        impl MachineIO for DisconnectedIo {
+            fn step(&mut self, _dt_s: f64) {}
+            fn read_speed(&self) -> f64 {
+                0.0
+            }
+            fn read_temperature(&self) -> f64 {
+                25.0
+            }
+            fn read_pressure(&self) -> f64 {
+                1.0
+            }
+            fn write_speed(&mut self, _rpm: f64) {}
+            fn cycle_stats(&self) -> CycleStats {
+                CycleStats::default()
+            }
+            fn is_healthy(&self) -> bool {
+                false
+            }
+        }
+
+        let healthy = Arc::new(StateExchange::new(1_000_000));
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            ControlConfig::default(),
+            Arc::clone(&healthy),
+            LogicalClock::new(),
+        );
+        assert!(!healthy.hal_healthy());
+        iron.step();
+        assert!(healthy.hal_healthy());
+
+        let disconnected = Arc::new(StateExchange::new(1_000_000));
+        let mut iron = IronThread::new(
+            DisconnectedIo,
+            ControlConfig::default(),
+            Arc::clone(&disconnected),
+            LogicalClock::new(),
+        );
+        iron.step();
+        assert!(!disconnected.hal_healthy());
+    }
+
+    #[test]
+    fn test_sustained_overruns_trip_after_configured_count() {
+        use crate::timebase::LogicalClock;
+
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let config = ControlConfig {
+            max_jitter_us: 200,
+            jitter_trip_after: 4,
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            config,
+            Arc::clone(&exchange),
+            LogicalClock::new(),
+        );
+
+        for cycle in 1..=4u64 {
+            let readings = iron.control_cycle(0.001);
+            iron.finish_cycle(readings, 1_500);
+            assert_eq!(iron.stats().timing_violations, cycle);
+            if cycle < 4 {
+                assert_eq!(iron.stats().safety_state, SafetyState::Degraded);
+            }
+        }
+        assert_eq!(iron.stats().safety_state, SafetyState::Trip);
+        assert_eq!(exchange.execution_stats().timing_violations, 4);
+    }
+
+    #[test]
+    fn test_jitter_alert_thresholds_count_cycles() {
+        use crate::timebase::LogicalClock;
+
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let config = ControlConfig {
+            jitter_warn_us: Some(200),
+            jitter_crit_us: Some(1_000),
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            config,
+            Arc::clone(&exchange),
+            LogicalClock::new(),
+        );
+
+        for jitter_us in [0, 199, 200, 999, 1_000, 5_000] {
+            let readings = iron.control_cycle(0.001);
+            iron.finish_cycle(readings, jitter_us);
+        }
+        let stats = exchange.execution_stats();
+        assert_eq!(stats.jitter_warn_cycles, 4);
+        assert_eq!(stats.jitter_crit_cycles, 2);
+    }
+
+    /// Plant that reaches each written speed by the next cycle
+    struct TrackingIo {
+        speed: f64,
+        writes: Arc<Mutex<Vec<f64>>>,
+    }
+
+    impl MachineIO for TrackingIo {
+        fn step(&mut self, _dt_s: f64) {}
+        fn read_speed(&self) -> f64 {
+            self.speed
+        }
+        fn read_temperature(&self) -> f64 {
+            25.0
+        }
+        fn read_pressure(&self) -> f64 {
+            1.0
+        }
+        fn write_speed(&mut self, rpm: f64) {
+            self.speed = rpm;
+            self.writes.lock().unwrap().push(rpm);
+        }
+        fn cycle_stats(&self) -> CycleStats {
+            CycleStats::default()
+        }
+        fn is_healthy(&self) -> bool {
+            true
+        }
+    }
+
+    /// Setpoints written by a loop running at 100 rpm whose only
+    /// recommendation goes stale after three 1 ms cycles.
+    fn timeout_trajectory(policy: AgentTimeoutPolicy, min_speed_rpm: f64) -> Vec<f64> {
+        use crate::timebase::LogicalClock;
+
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let exchange = Arc::new(StateExchange::new(2_000));
+        let clock = LogicalClock::new();
+        let mut config = ControlConfig {
+            agent_timeout: policy,
+            ..ControlConfig::default()
+        };
+        config.safety_limits.min_speed_rpm = min_speed_rpm;
+        let mut iron = IronThread::new(
+            TrackingIo {
+                speed: 100.0,
+                writes: Arc::clone(&writes),
+            },
+            config,
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+
+        clock.advance(Duration::from_millis(1));
+        exchange.submit_recommendation(AgentRecommendation {
+            timestamp_us: clock.now_us(),
+            target_speed_rpm: Some(100.0),
+            ramp_rate_rpm_per_s: None,
+            confidence: 1.0,
+            reasoning_hash: [0u8; 32].into(),
+        });
+        let mut states = Vec::new();
+        for _ in 0..9 {
+            iron.step();
+            states.push(exchange.read_state().safety_state);
+            clock.advance(Duration::from_millis(1));
+        }
+        assert_eq!(iron.stats().safety_rejections, 0);
+        assert_eq!(iron.stats().agent_timeouts, 6);
+        // Every policy reports the missing agent for the whole timeout,
+        // including while a ramp is still moving the setpoint.
+        assert_eq!(&states[..3], &[SafetyState::Normal; 3]);
+        assert_eq!(&states[3..], &[SafetyState::Degraded; 6]);
+        let writes = writes.lock().unwrap().clone();
+        writes
+    }
+
+    fn assert_trajectory(got: &[f64], want: &[f64]) {
+        assert_eq!(got.len(), want.len(), "{got:?}");
+        for (got, want) in got.iter().zip(want) {
+            assert!((got - want).abs() < 1e-9, "got {got}, want {want}");
+        }
+    }
+
+    #[test]
+    fn test_timeout_hold_last_keeps_setpoint() {
+        assert_trajectory(
+            &timeout_trajectory(AgentTimeoutPolicy::HoldLast, 0.0),
+            &[100.0; 9],
+        );
+    }
+
+    #[test]
+    fn test_timeout_ramp_to_zero() {
+        let policy = AgentTimeoutPolicy::RampToZero { rate: 20_000.0 };
+        assert_trajectory(
+            &timeout_trajectory(policy, 0.0),
+            &[100.0, 100.0, 100.0, 80.0, 60.0, 40.0, 20.0, 0.0, 0.0],
+        );
+        // Zero below the speed bounds stops at min_speed_rpm instead.
+        assert_trajectory(
+            &timeout_trajectory(policy, 50.0),
+            &[100.0, 100.0, 100.0, 80.0, 60.0, 50.0, 50.0, 50.0, 50.0],
+        );
+    }
+
+    #[test]
+    fn test_timeout_ramp_to_safe_speed() {
+        let policy = AgentTimeoutPolicy::RampToSafe {
+            speed: 150.0,
+            rate: 10_000.0,
+        };
+        assert_trajectory(
+            &timeout_trajectory(policy, 0.0),
+            &[
+                100.0, 100.0, 100.0, 110.0, 120.0, 130.0, 140.0, 150.0, 150.0,
+            ],
+        );
+        // A rate beyond the 50 000 rpm/s accel limit is held to it.
+        let policy = AgentTimeoutPolicy::RampToSafe {
+            speed: 250.0,
+            rate: 1_000_000.0,
+        };
+        assert_trajectory(
+            &timeout_trajectory(policy, 0.0),
+            &[
+                100.0, 100.0, 100.0, 150.0, 200.0, 250.0, 250.0, 250.0, 250.0,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_setpoint_deadband_skips_small_changes() {
+        use crate::timebase::LogicalClock;
+
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let config = ControlConfig {
+            setpoint_deadband_rpm: 5.0,
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(
+            TrackingIo {
+                speed: 100.0,
+                writes: Arc::clone(&writes),
+            },
+            config,
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+
+        for target in [100.0, 102.0, 97.0, 104.9, 110.0, 113.0, 106.0] {
+            clock.advance(Duration::from_millis(1));
+            exchange.submit_recommendation(AgentRecommendation {
+                timestamp_us: clock.now_us(),
+                target_speed_rpm: Some(target),
+                ramp_rate_rpm_per_s: None,
+                confidence: 1.0,
+                reasoning_hash: [0u8; 32].into(),
+            });
+            iron.step();
+        }
+        assert_eq!(iron.stats().safety_rejections, 0);
+        assert_trajectory(&writes.lock().unwrap(), &[100.0, 110.0]);
+
+        exchange.request_emergency_stop();
+        clock.advance(Duration::from_millis(1));
+        iron.step();
+        assert_trajectory(&writes.lock().unwrap(), &[100.0, 110.0, 0.0]);
+    }
+
+    #[test]
+    fn test_operational_cap_clamps_but_hard_limit_rejects() {
+        use crate::safety::SafetyViolation;
+        use crate::timebase::LogicalClock;
+
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let config = ControlConfig {
+            operational_max_speed_rpm: Some(2000.0),
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(
+            TrackingIo {
+                speed: 1990.0,
+                writes: Arc::clone(&writes),
+            },
+            config,
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+        let recommend = |iron: &mut IronThread<TrackingIo, LogicalClock>, target: f64| {
+            clock.advance(Duration::from_millis(1));
+            exchange.submit_recommendation(AgentRecommendation {
+                timestamp_us: clock.now_us(),
+                target_speed_rpm: Some(target),
+                ramp_rate_rpm_per_s: None,
+                confidence: 1.0,
+                reasoning_hash: [0u8; 32].into(),
+            });
+            iron.step();
+        };
+
+        // Soft: within the hard limit, clamped to the cap and applied.
+        recommend(&mut iron, 2900.0);
+        assert_eq!(iron.stats().safety_rejections, 0);
+        assert_trajectory(&writes.lock().unwrap(), &[2000.0]);
+
+        // Hard: above max_speed_rpm, rejected rather than clamped.
+        recommend(&mut iron, 3500.0);
+        assert_eq!(iron.stats().safety_rejections, 1);
+        assert!(matches!(
+            exchange.last_rejection().map(|r| r.violation),
+            Some(SafetyViolation::ExceedsMaxSpeed { .. })
+        ));
+        assert_ne!(writes.lock().unwrap().last(), Some(&3500.0));
+    }
+
+    #[test]
+    fn test_ramped_estop_decelerates_to_zero() {
+        use crate::timebase::LogicalClock;
+
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let config = ControlConfig {
+            // 10 000 rpm/s: 10 rpm over a 1 ms cycle
+            estop_profile: EStopProfile::Ramp {
+                decel_rpm_per_s: 10_000.0,
+            },
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            config,
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+        let run = |iron: &mut IronThread<SimulatedMotor, LogicalClock>| {
+            clock.advance(Duration::from_millis(1));
+            exchange.submit_recommendation(AgentRecommendation {
+                timestamp_us: clock.now_us(),
+                target_speed_rpm: Some(40.0),
+                ramp_rate_rpm_per_s: None,
+                confidence: 1.0,
+                reasoning_hash: [0u8; 32].into(),
+            });
+            iron.step();
+            iron.io().target_speed()
+        };
+
+        for _ in 0..5 {
+            run(&mut iron);
+        }
+        assert_eq!(iron.io().target_speed(), 40.0);
+
+        // The agent keeps asking for 40 rpm throughout the stop.
+        exchange.request_emergency_stop();
+        let speeds: Vec<f64> = (0..6).map(|_| run(&mut iron)).collect();
+        assert_eq!(speeds, [30.0, 20.0, 10.0, 0.0, 0.0, 0.0]);
+        assert_eq!(iron.stats().safety_state, SafetyState::Safe);
+    }
+
+    #[test]
+    fn test_estop_profile_parse() {
+        assert_eq!(
+            EStopProfile::parse("immediate"),
+            Some(EStopProfile::Immediate)
+        );
+        assert_eq!(
+            EStopProfile::parse("ramp:500"),
+            Some(EStopProfile::Ramp {
+                decel_rpm_per_s: 500.0
+            })
+        );
+        for bad in [
+            "ramp",
+            "ramp:0",
+            "ramp:-5",
+            "ramp:inf",
+            "immediate:1",
+            "stop",
+        ] {
+            assert_eq!(EStopProfile::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_transition_hook_sees_each_state_change_once() {
+        use crate::timebase::LogicalClock;
+
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let hook_seen = Arc::clone(&seen);
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        )
+        .with_transition_hook(move |timestamp_us, transition| {
+            hook_seen.lock().unwrap().push((
+                timestamp_us,
+                transition.from,
+                transition.to,
+                transition.cause,
+            ));
+        });
+        let run = |iron: &mut IronThread<SimulatedMotor, LogicalClock>, cycles: usize| {
+            for _ in 0..cycles {
+                clock.advance(Duration::from_millis(1));
+                exchange.submit_recommendation(AgentRecommendation {
+                    timestamp_us: clock.now_us(),
+                    target_speed_rpm: Some(40.0),
+                    ramp_rate_rpm_per_s: None,
+                    confidence: 1.0,
+                    reasoning_hash: [0u8; 32].into(),
+                });
+                iron.step();
+            }
+        };
+
+        run(&mut iron, 5);
+        assert!(seen.lock().unwrap().is_empty());
+
+        // A latching alarm trips and latches in the same cycle; holding it
+        // for more cycles reports nothing further.
+        iron.io_mut().set_alarms(AlarmFlags::DOOR_OPEN);
+        run(&mut iron, 5);
+        let trip_us = 6_000;
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                (
+                    trip_us,
+                    SafetyState::Normal,
+                    SafetyState::Trip,
+                    TransitionCause::Forced
+                ),
+                (
+                    trip_us,
+                    SafetyState::Trip,
+                    SafetyState::Safe,
+                    TransitionCause::Latched
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_latching_alarm_forces_setpoint_to_zero() {
+        use crate::timebase::LogicalClock;
+
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let config = ControlConfig {
+            latching_alarms: AlarmFlags::DOOR_OPEN | AlarmFlags::OVERCURRENT,
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            config,
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+        let run = |iron: &mut IronThread<SimulatedMotor, LogicalClock>, cycles: usize| {
+            for _ in 0..cycles {
+                clock.advance(Duration::from_millis(1));
+                exchange.submit_recommendation(AgentRecommendation {
+                    timestamp_us: clock.now_us(),
+                    target_speed_rpm: Some(40.0),
+                    ramp_rate_rpm_per_s: None,
+                    confidence: 1.0,
+                    reasoning_hash: [0u8; 32].into(),
+                });
+                iron.step();
+            }
+        };
+
+        run(&mut iron, 5);
+        assert_eq!(iron.io().target_speed(), 40.0);
+
+        // Not latching: logged, but the motor keeps running.
+        iron.io_mut().set_alarms(AlarmFlags::DRIVE_FAULT);
+        run(&mut iron, 5);
+        assert_eq!(iron.stats().safety_state, SafetyState::Normal);
+
+        iron.io_mut().set_alarms(AlarmFlags::DOOR_OPEN);
+        run(&mut iron, 1);
+        assert_eq!(iron.io().target_speed(), 0.0);
+        assert_eq!(iron.stats().safety_state, SafetyState::Safe);
+
+        // Latched: clearing the alarm does not restart the motor.
+        iron.io_mut().set_alarms(AlarmFlags::empty());
+        run(&mut iron, 5);
+        assert_eq!(iron.io().target_speed(), 0.0);
+        assert_eq!(iron.stats().safety_state, SafetyState::Safe);
+    }
+
+    #[test]
+    fn test_runtime_limits_tighten_but_never_loosen() {
+        use crate::safety::SafetyViolation;
+        use crate::timebase::LogicalClock;
+
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let configured = ControlConfig::default().safety_limits;
+        let mut iron = IronThread::new(
+            TrackingIo {
+                speed: 1990.0,
+                writes: Arc::clone(&writes),
+            },
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+        let recommend = |iron: &mut IronThread<TrackingIo, LogicalClock>, target: f64| {
+            clock.advance(Duration::from_millis(1));
+            exchange.submit_recommendation(AgentRecommendation {
+                timestamp_us: clock.now_us(),
+                target_speed_rpm: Some(target),
+                ramp_rate_rpm_per_s: None,
+                confidence: 1.0,
+                reasoning_hash: [0u8; 32].into(),
+            });
+            iron.step();
+        };
+        let rejected_limit = || match exchange.last_rejection().map(|r| r.violation) {
+            Some(SafetyViolation::ExceedsMaxSpeed { limit, .. }) => Some(limit),
+            _ => None,
+        };
+
+        exchange.request_safety_limits(SafetyLimits {
+            max_speed_rpm: 1500.0,
+            ..configured
+        });
+        iron.step();
+        // Submitted past the bridge's check: the loop still refuses it and
+        // keeps the tightened limit.
+        exchange.request_safety_limits(SafetyLimits {
+            max_speed_rpm: 5000.0,
+            ..configured
+        });
+        recommend(&mut iron, 2000.0);
+        assert_eq!(iron.stats().safety_rejections, 1);
+        assert_eq!(rejected_limit(), Some(1500.0));
+    }
+
+    #[test]
+    fn test_agent_timeout_policy_parse() {
+        assert_eq!(
+            AgentTimeoutPolicy::parse("hold"),
+            Some(AgentTimeoutPolicy::HoldLast)
+        );
+        assert_eq!(
+            AgentTimeoutPolicy::parse("ramp-to-zero:500"),
+            Some(AgentTimeoutPolicy::RampToZero { rate: 500.0 })
+        );
+        assert_eq!(
+            AgentTimeoutPolicy::parse("ramp-to-safe:300:100"),
+            Some(AgentTimeoutPolicy::RampToSafe {
+                speed: 300.0,
+                rate: 100.0
+            })
+        );
+        for bad in [
+            "",
+            "ramp-to-zero",
+            "ramp-to-zero:0",
+            "ramp-to-safe:300:-1",
+            "stop",
+        ] {
+            assert_eq!(AgentTimeoutPolicy::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_ramped_recommendation_follows_linear_profile() {
+        use crate::timebase::LogicalClock;
+
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let mut iron = IronThread::new(
+            TrackingIo {
+                speed: 0.0,
+                writes: Arc::clone(&writes),
+            },
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+
+        // 200 rpm in one 1 ms step would trip the 50 000 rpm/s limit;
+        // ramped at 20 000 rpm/s it arrives in ten cycles.
+        clock.advance(Duration::from_millis(1));
+        exchange.submit_recommendation(AgentRecommendation {
+            timestamp_us: clock.now_us(),
+            target_speed_rpm: Some(200.0),
+            ramp_rate_rpm_per_s: Some(20_000.0),
+            confidence: 1.0,
+            reasoning_hash: [0u8; 32].into(),
+        });
+        for _ in 0..12 {
+            iron.step();
+            clock.advance(Duration::from_millis(1));
+        }
+
+        let expected: Vec<f64> = (1..=12).map(|n| (20.0 * n as f64).min(200.0)).collect();
+        let writes = writes.lock().unwrap();
+        assert_eq!(writes.len(), expected.len());
+        for (got, want) in writes.iter().zip(&expected) {
+            assert!((got - want).abs() < 1e-9, "got {got}, want {want}");
+        }
+        assert_eq!(iron.stats().safety_rejections, 0);
+        assert_eq!(iron.stats().safety_state, SafetyState::Normal);
+    }
 }
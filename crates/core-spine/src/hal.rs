@@ -5,6 +5,22 @@ pub struct CycleStats {
     pub missed_cycles: u64,
 }
 
+bitflags::bitflags! {
+    /// Boolean machine inputs such as interlock switches and drive trips.
+    /// Alarms in `ControlConfig::latching_alarms` latch the loop into `Safe`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct AlarmFlags: u16 {
+        /// Guard door open
+        const DOOR_OPEN = 1 << 0;
+        /// Drive tripped on overcurrent
+        const OVERCURRENT = 1 << 1;
+        /// Hardwired emergency stop pressed
+        const ESTOP_BUTTON = 1 << 2;
+        /// Drive reports a fault
+        const DRIVE_FAULT = 1 << 3;
+    }
+}
+
 pub trait MachineIO: Send {
     fn step(&mut self, dt_s: f64);
     fn read_speed(&self) -> f64;
@@ -13,4 +29,44 @@ pub trait MachineIO: Send {
     fn write_speed(&mut self, rpm: f64);
     fn cycle_stats(&self) -> CycleStats;
     fn is_healthy(&self) -> bool;
+
+    /// Alarm inputs as of the last `step`. Backends without any report none.
+    fn read_alarms(&self) -> AlarmFlags {
+        AlarmFlags::empty()
+    }
+}
+
+/// Lets the control loop drive a backend chosen at runtime.
+impl<T: MachineIO + ?Sized> MachineIO for Box<T> {
+    fn step(&mut self, dt_s: f64) {
+        (**self).step(dt_s)
+    }
+
+    fn read_speed(&self) -> f64 {
+        (**self).read_speed()
+    }
+
+    fn read_temperature(&self) -> f64 {
+        (**self).read_temperature()
+    }
+
+    fn read_pressure(&self) -> f64 {
+        (**self).read_pressure()
+    }
+
+    fn write_speed(&mut self, rpm: f64) {
+        (**self).write_speed(rpm)
+    }
+
+    fn cycle_stats(&self) -> CycleStats {
+        (**self).cycle_stats()
+    }
+
+    fn is_healthy(&self) -> bool {
+        (**self).is_healthy()
+    }
+
+    fn read_alarms(&self) -> AlarmFlags {
+        (**self).read_alarms()
+    }
 }
@@ -0,0 +1,254 @@
+//! Failover from a faulted HAL backend to a standby.
+//!
+//! A backend that loses its link (e.g. a Modbus drive that stops answering)
+//! keeps returning its last readings while reporting unhealthy. Once the
+//! primary has been unhealthy for longer than the fault timeout,
+//! [`FailoverIO`] stops driving it and, if a standby is given, serves the
+//! control loop from the standby so cycles stay deterministic. It reports
+//! unhealthy from then on so the fault stays visible; bringing the machine
+//! to a stop is left to the control loop's `hal_fault_timeout`.
+
+use crate::hal::{AlarmFlags, CycleStats, MachineIO};
+use std::time::Duration;
+
+/// Called once when failing over, with how long the primary was unhealthy.
+pub type FailoverHook = Box<dyn FnOnce(Duration) + Send>;
+
+pub struct FailoverIO<P: MachineIO, S: MachineIO> {
+    primary: P,
+    standby: Option<S>,
+    fault_timeout: Duration,
+    unhealthy_for: Duration,
+    failed_over: bool,
+    on_failover: Option<FailoverHook>,
+}
+
+impl<P: MachineIO, S: MachineIO> FailoverIO<P, S> {
+    /// Without a standby the primary stays in use after the fault.
+    pub fn new(primary: P, standby: Option<S>, fault_timeout: Duration) -> Self {
+        Self {
+            primary,
+            standby,
+            fault_timeout,
+            unhealthy_for: Duration::ZERO,
+            failed_over: false,
+            on_failover: None,
+        }
+    }
+
+    pub fn with_failover_hook(self, hook: impl FnOnce(Duration) + Send + 'static) -> Self {
+        Self {
+            on_failover: Some(Box::new(hook)),
+            ..self
+        }
+    }
+
+    /// True once the primary exceeded the fault timeout; never resets.
+    pub fn failed_over(&self) -> bool {
+        self.failed_over
+    }
+
+    fn active(&self) -> &dyn MachineIO {
+        match &self.standby {
+            Some(standby) if self.failed_over => standby,
+            _ => &self.primary,
+        }
+    }
+
+    fn active_mut(&mut self) -> &mut dyn MachineIO {
+        match &mut self.standby {
+            Some(standby) if self.failed_over => standby,
+            _ => &mut self.primary,
+        }
+    }
+}
+
+impl<P: MachineIO, S: MachineIO> MachineIO for FailoverIO<P, S> {
+    fn step(&mut self, dt_s: f64) {
+        if self.failed_over {
+            self.active_mut().step(dt_s);
+            return;
+        }
+        self.primary.step(dt_s);
+        if self.primary.is_healthy() {
+            self.unhealthy_for = Duration::ZERO;
+            return;
+        }
+        self.unhealthy_for += Duration::from_secs_f64(dt_s);
+        if self.unhealthy_for >= self.fault_timeout {
+            self.failed_over = true;
+            // Best effort: the link is likely down.
+            self.primary.write_speed(0.0);
+            if let Some(hook) = self.on_failover.take() {
+                hook(self.unhealthy_for);
+            }
+        }
+    }
+
+    fn read_speed(&self) -> f64 {
+        self.active().read_speed()
+    }
+
+    fn read_temperature(&self) -> f64 {
+        self.active().read_temperature()
+    }
+
+    fn read_pressure(&self) -> f64 {
+        self.active().read_pressure()
+    }
+
+    fn write_speed(&mut self, rpm: f64) {
+        self.active_mut().write_speed(rpm)
+    }
+
+    fn cycle_stats(&self) -> CycleStats {
+        self.active().cycle_stats()
+    }
+
+    fn is_healthy(&self) -> bool {
+        !self.failed_over && self.primary.is_healthy()
+    }
+
+    fn read_alarms(&self) -> AlarmFlags {
+        self.active().read_alarms()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control_loop::{ControlConfig, IronThread};
+    use crate::hal_sim::SimulatedMotor;
+    use crate::safety_supervisor::SafetyState;
+    use crate::sync::StateExchange;
+    use crate::timebase::LogicalClock;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// Reads a fixed speed and answers until `responding` is cleared, then
+    /// keeps returning its last readings like a dead fieldbus link.
+    struct FlakyIO {
+        responding: Arc<AtomicBool>,
+        speed: f64,
+    }
+
+    impl MachineIO for FlakyIO {
+        fn step(&mut self, _dt_s: f64) {}
+        fn read_speed(&self) -> f64 {
+            self.speed
+        }
+        fn read_temperature(&self) -> f64 {
+            30.0
+        }
+        fn read_pressure(&self) -> f64 {
+            1.0
+        }
+        fn write_speed(&mut self, rpm: f64) {
+            if self.responding.load(Ordering::Relaxed) {
+                self.speed = rpm;
+            }
+        }
+        fn cycle_stats(&self) -> CycleStats {
+            CycleStats::default()
+        }
+        fn is_healthy(&self) -> bool {
+            self.responding.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn test_failover_after_fault_timeout() {
+        let responding = Arc::new(AtomicBool::new(true));
+        let failovers = Arc::new(AtomicU32::new(0));
+        let hook_count = Arc::clone(&failovers);
+        let io = FailoverIO::new(
+            FlakyIO {
+                responding: Arc::clone(&responding),
+                speed: 0.0,
+            },
+            Some(SimulatedMotor::new()),
+            Duration::from_millis(5),
+        )
+        .with_failover_hook(move |unhealthy_for| {
+            assert!(unhealthy_for >= Duration::from_millis(5));
+            hook_count.fetch_add(1, Ordering::Relaxed);
+        });
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let config = ControlConfig {
+            hal_fault_timeout: Some(Duration::from_millis(5)),
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(io, config, Arc::clone(&exchange), clock.clone());
+
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(1));
+            iron.step();
+        }
+        assert_eq!(iron.stats().safety_state, SafetyState::Degraded);
+        assert!(exchange.hal_healthy());
+
+        // The link dies: the loop keeps going on the standby and goes Safe.
+        responding.store(false, Ordering::Relaxed);
+        for _ in 0..4 {
+            clock.advance(Duration::from_millis(1));
+            iron.step();
+        }
+        assert!(!iron.io().failed_over());
+        assert_ne!(iron.stats().safety_state, SafetyState::Safe);
+
+        for _ in 0..5 {
+            clock.advance(Duration::from_millis(1));
+            iron.step();
+        }
+        assert!(iron.io().failed_over());
+        assert_eq!(iron.stats().safety_state, SafetyState::Safe);
+        assert!(!exchange.hal_healthy());
+        assert_eq!(failovers.load(Ordering::Relaxed), 1);
+
+        // Recovery of the primary does not undo the failover.
+        responding.store(true, Ordering::Relaxed);
+        clock.advance(Duration::from_millis(1));
+        iron.step();
+        assert!(iron.io().failed_over());
+        assert_eq!(iron.stats().safety_state, SafetyState::Safe);
+        assert_eq!(exchange.read_state().motor_speed_rpm, 0.0);
+    }
+
+    #[test]
+    fn test_brief_unhealthy_blip_is_tolerated() {
+        let responding = Arc::new(AtomicBool::new(true));
+        let mut io = FailoverIO::new(
+            FlakyIO {
+                responding: Arc::clone(&responding),
+                speed: 0.0,
+            },
+            Some(SimulatedMotor::new()),
+            Duration::from_millis(5),
+        );
+        for cycle in 0..20 {
+            responding.store(cycle % 4 != 0, Ordering::Relaxed);
+            io.step(0.001);
+        }
+        assert!(!io.failed_over());
+    }
+
+    #[test]
+    fn test_without_standby_primary_stays_active() {
+        let responding = Arc::new(AtomicBool::new(false));
+        let mut io: FailoverIO<_, SimulatedMotor> = FailoverIO::new(
+            FlakyIO {
+                responding,
+                speed: 42.0,
+            },
+            None,
+            Duration::from_millis(2),
+        );
+        for _ in 0..3 {
+            io.step(0.001);
+        }
+        assert!(io.failed_over());
+        assert!(!io.is_healthy());
+        assert_eq!(io.read_speed(), 42.0);
+    }
+}
@@ -1,4 +1,208 @@
-use crate::hal::{CycleStats, MachineIO};
+use crate::hal::{AlarmFlags, CycleStats, MachineIO};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Physical parameters of a [`SimulatedMotor`]. Speed follows the setpoint
+/// with time constant `inertia / friction_coeff`; temperature starts at
+/// ambient.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulatedMotorConfig {
+    pub inertia: f64,
+    pub friction_coeff: f64,
+    pub thermal_mass: f64,
+    /// Heat input per (rad/s)^2 of shaft speed
+    pub heat_generation: f64,
+    /// Heat loss per degree above ambient
+    pub cooling_rate: f64,
+    pub ambient_temp_c: f64,
+    /// Pressure at standstill
+    pub base_pressure_bar: f64,
+    /// Pressure added per rpm^2
+    pub pressure_coeff: f64,
+    /// Perturbations applied to the readings only; off by default
+    pub faults: SensorFaults,
+}
+
+/// Sensor faults injected by a [`SimulatedMotor`]. They change what the
+/// `read_*` methods report, never the simulated physics, and are drawn from
+/// a generator seeded with `seed` so a run can be reproduced exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensorFaults {
+    pub seed: u64,
+    /// Standard deviation of the Gaussian noise added to each reading
+    pub speed_noise_rpm: f64,
+    pub temp_noise_c: f64,
+    pub pressure_noise_bar: f64,
+    /// Chance per cycle and reading of a spike
+    pub spike_probability: f64,
+    /// A spiking reading is multiplied by this; 10 unless set
+    pub spike_factor: f64,
+    /// Stuck-at faults: the sensor reports this value whatever the state
+    pub stuck_speed_rpm: Option<f64>,
+    pub stuck_temp_c: Option<f64>,
+    pub stuck_pressure_bar: Option<f64>,
+}
+
+impl Default for SimulatedMotorConfig {
+    fn default() -> Self {
+        Self {
+            inertia: 0.5,
+            friction_coeff: 0.01,
+            thermal_mass: 500.0,
+            heat_generation: 0.001,
+            cooling_rate: 10.0,
+            ambient_temp_c: 25.0,
+            base_pressure_bar: 1.0,
+            pressure_coeff: 0.0001,
+            faults: SensorFaults::default(),
+        }
+    }
+}
+
+impl Default for SensorFaults {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            speed_noise_rpm: 0.0,
+            temp_noise_c: 0.0,
+            pressure_noise_bar: 0.0,
+            spike_probability: 0.0,
+            // Setting only `spike_probability` must still spike.
+            spike_factor: 10.0,
+            stuck_speed_rpm: None,
+            stuck_temp_c: None,
+            stuck_pressure_bar: None,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum SimulatedMotorConfigError {
+    #[error("simulated motor {name} must be positive, got {value}")]
+    NotPositive { name: &'static str, value: f64 },
+    #[error("simulated motor {name} must be finite, got {value}")]
+    NotFinite { name: &'static str, value: f64 },
+    #[error("simulated sensor fault {name} is out of range, got {value}")]
+    FaultOutOfRange { name: &'static str, value: f64 },
+}
+
+impl SimulatedMotorConfig {
+    /// Check that every physical constant is finite and that the ones the
+    /// dynamics divide by or scale with are positive.
+    pub fn validate(&self) -> Result<(), SimulatedMotorConfigError> {
+        let positive = [
+            ("inertia", self.inertia),
+            ("friction_coeff", self.friction_coeff),
+            ("thermal_mass", self.thermal_mass),
+            ("heat_generation", self.heat_generation),
+            ("cooling_rate", self.cooling_rate),
+            ("base_pressure_bar", self.base_pressure_bar),
+            ("pressure_coeff", self.pressure_coeff),
+        ];
+        for (name, value) in positive {
+            if !value.is_finite() {
+                return Err(SimulatedMotorConfigError::NotFinite { name, value });
+            }
+            if value <= 0.0 {
+                return Err(SimulatedMotorConfigError::NotPositive { name, value });
+            }
+        }
+        if !self.ambient_temp_c.is_finite() {
+            return Err(SimulatedMotorConfigError::NotFinite {
+                name: "ambient_temp_c",
+                value: self.ambient_temp_c,
+            });
+        }
+        self.faults.validate()
+    }
+}
+
+impl SensorFaults {
+    fn validate(&self) -> Result<(), SimulatedMotorConfigError> {
+        let stuck = [
+            ("stuck_speed_rpm", self.stuck_speed_rpm),
+            ("stuck_temp_c", self.stuck_temp_c),
+            ("stuck_pressure_bar", self.stuck_pressure_bar),
+        ];
+        let in_range = [
+            ("speed_noise_rpm", self.speed_noise_rpm, 0.0..=f64::MAX),
+            ("temp_noise_c", self.temp_noise_c, 0.0..=f64::MAX),
+            (
+                "pressure_noise_bar",
+                self.pressure_noise_bar,
+                0.0..=f64::MAX,
+            ),
+            ("spike_probability", self.spike_probability, 0.0..=1.0),
+            ("spike_factor", self.spike_factor, f64::MIN..=f64::MAX),
+        ]
+        .into_iter()
+        .chain(
+            stuck
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value?, f64::MIN..=f64::MAX))),
+        );
+        for (name, value, range) in in_range {
+            if !range.contains(&value) {
+                return Err(SimulatedMotorConfigError::FaultOutOfRange { name, value });
+            }
+        }
+        Ok(())
+    }
+
+    /// `value` as a faulty sensor reports it this cycle
+    fn perturb(&self, value: f64, noise_sd: f64, rng: &mut SplitMix64) -> f64 {
+        let mut reading = value;
+        if noise_sd > 0.0 {
+            reading += noise_sd * rng.next_gaussian();
+        }
+        if self.spike_probability > 0.0 && rng.next_f64() < self.spike_probability {
+            reading *= self.spike_factor;
+        }
+        reading
+    }
+}
+
+/// Small seeded generator for fault injection; not for cryptography.
+#[derive(Debug, Clone)]
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal, by the Box-Muller transform
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+/// Values the sensors report, after any injected noise and spikes
+#[derive(Debug, Clone, Copy)]
+struct Readings {
+    speed_rpm: f64,
+    temperature_c: f64,
+    pressure_bar: f64,
+}
 
 /// Simulated motor with thermal dynamics.
 #[derive(Debug, Clone)]
@@ -7,34 +211,59 @@ pub struct SimulatedMotor {
     temperature_c: f64,
     pressure_bar: f64,
 
-    inertia: f64,
-    friction_coeff: f64,
-    thermal_mass: f64,
-    heat_generation: f64,
-    cooling_rate: f64,
-    ambient_temp: f64,
+    config: SimulatedMotorConfig,
+    readings: Readings,
+    rng: SplitMix64,
 
     target_speed: f64,
+    alarms: AlarmFlags,
     stats: CycleStats,
 }
 
 impl SimulatedMotor {
     pub fn new() -> Self {
+        Self::build(SimulatedMotorConfig::default())
+    }
+
+    /// A motor with the given physical parameters, once they validate
+    pub fn with_config(config: SimulatedMotorConfig) -> Result<Self, SimulatedMotorConfigError> {
+        config.validate()?;
+        Ok(Self::build(config))
+    }
+
+    fn build(config: SimulatedMotorConfig) -> Self {
         Self {
             speed_rpm: 0.0,
-            temperature_c: 25.0,
-            pressure_bar: 1.0,
-            inertia: 0.5,
-            friction_coeff: 0.01,
-            thermal_mass: 500.0,
-            heat_generation: 0.001,
-            cooling_rate: 10.0,
-            ambient_temp: 25.0,
+            temperature_c: config.ambient_temp_c,
+            pressure_bar: config.base_pressure_bar,
+            config,
+            readings: Readings {
+                speed_rpm: 0.0,
+                temperature_c: config.ambient_temp_c,
+                pressure_bar: config.base_pressure_bar,
+            },
+            rng: SplitMix64::new(config.faults.seed),
             target_speed: 0.0,
+            alarms: AlarmFlags::empty(),
             stats: CycleStats::default(),
         }
     }
 
+    pub fn config(&self) -> &SimulatedMotorConfig {
+        &self.config
+    }
+
+    /// Setpoint last written by the control loop
+    pub fn target_speed(&self) -> f64 {
+        self.target_speed
+    }
+
+    /// Drive the alarm inputs, e.g. to open the guard door in a test. They
+    /// stay as set until the next call.
+    pub fn set_alarms(&mut self, alarms: AlarmFlags) {
+        self.alarms = alarms;
+    }
+
     fn update_stats(&mut self, dt_s: f64) {
         let cycle_us = (dt_s * 1_000_000.0) as u64;
         self.stats.last_cycle_us = cycle_us;
@@ -50,34 +279,51 @@ impl Default for SimulatedMotor {
 
 impl MachineIO for SimulatedMotor {
     fn step(&mut self, dt_s: f64) {
+        let config = &self.config;
+
         // Motor speed response.
         let speed_error = self.target_speed - self.speed_rpm;
-        let time_constant = self.inertia / self.friction_coeff;
+        let time_constant = config.inertia / config.friction_coeff;
         self.speed_rpm += speed_error * (1.0 - (-dt_s / time_constant).exp());
 
         // Thermal dynamics.
         let speed_rad_s = self.speed_rpm * std::f64::consts::PI / 30.0;
-        let heat_in = self.heat_generation * speed_rad_s * speed_rad_s;
-        let heat_out = self.cooling_rate * (self.temperature_c - self.ambient_temp);
-        let delta_temp = (heat_in - heat_out) * dt_s / self.thermal_mass;
+        let heat_in = config.heat_generation * speed_rad_s * speed_rad_s;
+        let heat_out = config.cooling_rate * (self.temperature_c - config.ambient_temp_c);
+        let delta_temp = (heat_in - heat_out) * dt_s / config.thermal_mass;
         self.temperature_c += delta_temp;
 
         // Pressure model: proportional to speed squared.
-        self.pressure_bar = 1.0 + 0.0001 * self.speed_rpm * self.speed_rpm;
+        self.pressure_bar =
+            config.base_pressure_bar + config.pressure_coeff * self.speed_rpm * self.speed_rpm;
+
+        let faults = &config.faults;
+        self.readings = Readings {
+            speed_rpm: faults.perturb(self.speed_rpm, faults.speed_noise_rpm, &mut self.rng),
+            temperature_c: faults.perturb(self.temperature_c, faults.temp_noise_c, &mut self.rng),
+            pressure_bar: faults.perturb(
+                self.pressure_bar,
+                faults.pressure_noise_bar,
+                &mut self.rng,
+            ),
+        };
 
         self.update_stats(dt_s);
     }
 
     fn read_speed(&self) -> f64 {
-        self.speed_rpm
+        let stuck = self.config.faults.stuck_speed_rpm;
+        stuck.unwrap_or(self.readings.speed_rpm)
     }
 
     fn read_temperature(&self) -> f64 {
-        self.temperature_c
+        let stuck = self.config.faults.stuck_temp_c;
+        stuck.unwrap_or(self.readings.temperature_c)
     }
 
     fn read_pressure(&self) -> f64 {
-        self.pressure_bar
+        let stuck = self.config.faults.stuck_pressure_bar;
+        stuck.unwrap_or(self.readings.pressure_bar)
     }
 
     fn write_speed(&mut self, rpm: f64) {
@@ -91,4 +337,171 @@ impl MachineIO for SimulatedMotor {
     fn is_healthy(&self) -> bool {
         self.temperature_c.is_finite() && self.temperature_c < 120.0 && self.speed_rpm >= 0.0
     }
+
+    fn read_alarms(&self) -> AlarmFlags {
+        self.alarms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Speed after `steps` 1 ms cycles toward a 1000 rpm setpoint
+    fn speed_after_step(mut motor: SimulatedMotor, steps: usize) -> f64 {
+        motor.write_speed(1000.0);
+        for _ in 0..steps {
+            motor.step(0.001);
+        }
+        motor.read_speed()
+    }
+
+    #[test]
+    fn test_higher_inertia_responds_more_slowly() {
+        let heavy = SimulatedMotor::with_config(SimulatedMotorConfig {
+            inertia: 5.0,
+            ..SimulatedMotorConfig::default()
+        })
+        .unwrap();
+        let default_speed = speed_after_step(SimulatedMotor::new(), 100);
+        let heavy_speed = speed_after_step(heavy, 100);
+        assert!(heavy_speed > 0.0);
+        assert!(
+            heavy_speed < default_speed,
+            "heavy {heavy_speed} rpm, default {default_speed} rpm"
+        );
+    }
+
+    fn with_faults(faults: SensorFaults) -> SimulatedMotor {
+        SimulatedMotor::with_config(SimulatedMotorConfig {
+            faults,
+            ..SimulatedMotorConfig::default()
+        })
+        .unwrap()
+    }
+
+    /// Speed readings over `steps` cycles toward 1000 rpm
+    fn speed_readings(mut motor: SimulatedMotor, steps: usize) -> Vec<f64> {
+        motor.write_speed(1000.0);
+        (0..steps)
+            .map(|_| {
+                motor.step(0.001);
+                motor.read_speed()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_noise_is_deterministic() {
+        let noisy = |seed| SensorFaults {
+            seed,
+            speed_noise_rpm: 5.0,
+            ..SensorFaults::default()
+        };
+        let first = speed_readings(with_faults(noisy(42)), 200);
+        assert_eq!(first, speed_readings(with_faults(noisy(42)), 200));
+        assert_ne!(first, speed_readings(with_faults(noisy(43)), 200));
+
+        // The physics underneath is untouched: readings scatter around the
+        // noiseless trajectory.
+        let clean = speed_readings(SimulatedMotor::new(), 200);
+        let deviations: Vec<f64> = first.iter().zip(&clean).map(|(n, c)| n - c).collect();
+        assert!(deviations.iter().any(|d| d.abs() > 1.0));
+        assert!(deviations.iter().all(|d| d.abs() < 5.0 * 6.0));
+        let mean = deviations.iter().sum::<f64>() / deviations.len() as f64;
+        assert!(mean.abs() < 1.5, "mean deviation {mean}");
+    }
+
+    #[test]
+    fn test_spikes_and_stuck_sensors() {
+        // The factor is left at its default.
+        let spiky = with_faults(SensorFaults {
+            spike_probability: 1.0,
+            ..SensorFaults::default()
+        });
+        let clean = speed_readings(SimulatedMotor::new(), 10);
+        let spiked = speed_readings(spiky, 10);
+        for (s, c) in spiked.iter().zip(&clean) {
+            assert!((s - c * 10.0).abs() < 1e-9);
+        }
+
+        let mut stuck = with_faults(SensorFaults {
+            stuck_temp_c: Some(500.0),
+            ..SensorFaults::default()
+        });
+        stuck.step(0.001);
+        assert_eq!(stuck.read_temperature(), 500.0);
+        assert!(stuck.is_healthy(), "physical temperature is still normal");
+    }
+
+    #[test]
+    fn test_voting_outvotes_a_stuck_channel() {
+        use crate::hal_voting::{VotingSensor, VotingTolerance};
+
+        let stuck = with_faults(SensorFaults {
+            stuck_speed_rpm: Some(2500.0),
+            ..SensorFaults::default()
+        });
+        let mut voted = VotingSensor::new(
+            [SimulatedMotor::new(), stuck, SimulatedMotor::new()],
+            VotingTolerance::default(),
+        )
+        .with_mirrored_writes();
+        voted.write_speed(100.0);
+        voted.step(0.001);
+        assert!(voted.read_speed() < 100.0);
+        assert_eq!(voted.disagreeing_channels(), vec![1]);
+        assert!(!voted.is_healthy());
+    }
+
+    #[test]
+    fn test_non_positive_constants_are_rejected() {
+        for config in [
+            SimulatedMotorConfig {
+                inertia: 0.0,
+                ..SimulatedMotorConfig::default()
+            },
+            SimulatedMotorConfig {
+                friction_coeff: -0.01,
+                ..SimulatedMotorConfig::default()
+            },
+            SimulatedMotorConfig {
+                thermal_mass: f64::NAN,
+                ..SimulatedMotorConfig::default()
+            },
+        ] {
+            assert!(SimulatedMotor::with_config(config).is_err(), "{config:?}");
+        }
+        let faults = |faults| SimulatedMotorConfig {
+            faults,
+            ..SimulatedMotorConfig::default()
+        };
+        for bad in [
+            SensorFaults {
+                temp_noise_c: -1.0,
+                ..SensorFaults::default()
+            },
+            SensorFaults {
+                spike_probability: 1.5,
+                ..SensorFaults::default()
+            },
+            SensorFaults {
+                stuck_pressure_bar: Some(f64::INFINITY),
+                ..SensorFaults::default()
+            },
+        ] {
+            assert!(faults(bad).validate().is_err(), "{bad:?}");
+        }
+        assert_eq!(
+            SimulatedMotorConfig {
+                cooling_rate: 0.0,
+                ..SimulatedMotorConfig::default()
+            }
+            .validate(),
+            Err(SimulatedMotorConfigError::NotPositive {
+                name: "cooling_rate",
+                value: 0.0
+            })
+        );
+    }
 }
@@ -0,0 +1,242 @@
+use crate::hal::{AlarmFlags, CycleStats, MachineIO};
+
+/// Largest allowed deviation of a channel from the voted value.
+#[derive(Debug, Clone, Copy)]
+pub struct VotingTolerance {
+    pub speed_rpm: f64,
+    pub temp_c: f64,
+    pub pressure_bar: f64,
+}
+
+impl Default for VotingTolerance {
+    fn default() -> Self {
+        Self {
+            speed_rpm: 50.0,
+            temp_c: 2.0,
+            pressure_bar: 0.2,
+        }
+    }
+}
+
+/// 2-out-of-3 voting over three redundant channels.
+///
+/// Reads return the median of the three channels, so a single faulty sensor
+/// is outvoted. Any channel that deviates from the median by more than the
+/// tolerance is a sensor disagreement fault and makes `is_healthy` false.
+/// Speed commands go to the first channel, the one wired to the actuator;
+/// the others only sense.
+pub struct VotingSensor<IO: MachineIO> {
+    channels: [IO; 3],
+    tolerance: VotingTolerance,
+    mirror_writes: bool,
+}
+
+impl<IO: MachineIO> VotingSensor<IO> {
+    pub fn new(channels: [IO; 3], tolerance: VotingTolerance) -> Self {
+        Self {
+            channels,
+            tolerance,
+            mirror_writes: false,
+        }
+    }
+
+    /// Write speed commands to every channel, for channels that each model
+    /// the whole plant, such as three simulated motors
+    pub fn with_mirrored_writes(mut self) -> Self {
+        self.mirror_writes = true;
+        self
+    }
+
+    pub fn channels(&self) -> &[IO; 3] {
+        &self.channels
+    }
+
+    /// Indices of channels currently outside tolerance on any variable.
+    pub fn disagreeing_channels(&self) -> Vec<usize> {
+        let speed = self.vote(|io| io.read_speed());
+        let temp = self.vote(|io| io.read_temperature());
+        let pressure = self.vote(|io| io.read_pressure());
+        (0..3)
+            .filter(|&i| {
+                let io = &self.channels[i];
+                !within(io.read_speed(), speed, self.tolerance.speed_rpm)
+                    || !within(io.read_temperature(), temp, self.tolerance.temp_c)
+                    || !within(io.read_pressure(), pressure, self.tolerance.pressure_bar)
+            })
+            .collect()
+    }
+
+    fn vote(&self, read: impl Fn(&IO) -> f64) -> f64 {
+        median([
+            read(&self.channels[0]),
+            read(&self.channels[1]),
+            read(&self.channels[2]),
+        ])
+    }
+}
+
+fn within(value: f64, voted: f64, tolerance: f64) -> bool {
+    (value - voted).abs() <= tolerance
+}
+
+/// Median of three; a NaN channel sorts last so it cannot win the vote.
+fn median(mut values: [f64; 3]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    if values[1].is_nan() {
+        values[0]
+    } else {
+        values[1]
+    }
+}
+
+impl<IO: MachineIO> MachineIO for VotingSensor<IO> {
+    fn step(&mut self, dt_s: f64) {
+        for io in &mut self.channels {
+            io.step(dt_s);
+        }
+    }
+
+    fn read_speed(&self) -> f64 {
+        self.vote(|io| io.read_speed())
+    }
+
+    fn read_temperature(&self) -> f64 {
+        self.vote(|io| io.read_temperature())
+    }
+
+    fn read_pressure(&self) -> f64 {
+        self.vote(|io| io.read_pressure())
+    }
+
+    fn write_speed(&mut self, rpm: f64) {
+        let commanded = if self.mirror_writes { 3 } else { 1 };
+        for io in &mut self.channels[..commanded] {
+            io.write_speed(rpm);
+        }
+    }
+
+    fn cycle_stats(&self) -> CycleStats {
+        self.channels[0].cycle_stats()
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.channels.iter().all(|io| io.is_healthy()) && self.disagreeing_channels().is_empty()
+    }
+
+    /// Alarms are not voted: one channel raising an alarm is enough.
+    fn read_alarms(&self) -> AlarmFlags {
+        self.channels
+            .iter()
+            .fold(AlarmFlags::empty(), |alarms, io| alarms | io.read_alarms())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedIO {
+        speed: f64,
+        temp: f64,
+        pressure: f64,
+        written: Option<f64>,
+    }
+
+    impl FixedIO {
+        fn nominal() -> Self {
+            Self {
+                speed: 1000.0,
+                temp: 40.0,
+                pressure: 2.0,
+                written: None,
+            }
+        }
+    }
+
+    impl MachineIO for FixedIO {
+        fn step(&mut self, _dt_s: f64) {}
+        fn read_speed(&self) -> f64 {
+            self.speed
+        }
+        fn read_temperature(&self) -> f64 {
+            self.temp
+        }
+        fn read_pressure(&self) -> f64 {
+            self.pressure
+        }
+        fn write_speed(&mut self, rpm: f64) {
+            self.written = Some(rpm);
+        }
+        fn cycle_stats(&self) -> CycleStats {
+            CycleStats::default()
+        }
+        fn is_healthy(&self) -> bool {
+            true
+        }
+    }
+
+    fn voter(channels: [FixedIO; 3]) -> VotingSensor<FixedIO> {
+        VotingSensor::new(channels, VotingTolerance::default())
+    }
+
+    #[test]
+    fn test_agreeing_sensors_are_healthy() {
+        let v = voter([FixedIO::nominal(), FixedIO::nominal(), FixedIO::nominal()]);
+        assert_eq!(v.read_speed(), 1000.0);
+        assert!(v.is_healthy());
+    }
+
+    #[test]
+    fn test_one_faulty_high_sensor_is_outvoted() {
+        let mut high = FixedIO::nominal();
+        high.temp = 500.0;
+        let v = voter([FixedIO::nominal(), high, FixedIO::nominal()]);
+        assert_eq!(v.read_temperature(), 40.0);
+        assert_eq!(v.disagreeing_channels(), vec![1]);
+        assert!(!v.is_healthy());
+    }
+
+    #[test]
+    fn test_one_faulty_low_sensor_is_outvoted() {
+        let mut low = FixedIO::nominal();
+        low.speed = 0.0;
+        let v = voter([low, FixedIO::nominal(), FixedIO::nominal()]);
+        assert_eq!(v.read_speed(), 1000.0);
+        assert_eq!(v.disagreeing_channels(), vec![0]);
+        assert!(!v.is_healthy());
+    }
+
+    #[test]
+    fn test_two_sensor_disagreement_is_unhealthy() {
+        let mut high = FixedIO::nominal();
+        high.pressure = 5.0;
+        let mut low = FixedIO::nominal();
+        low.pressure = 0.5;
+        let v = voter([high, FixedIO::nominal(), low]);
+        assert_eq!(v.read_pressure(), 2.0);
+        assert_eq!(v.disagreeing_channels(), vec![0, 2]);
+        assert!(!v.is_healthy());
+    }
+
+    #[test]
+    fn test_nan_channel_never_wins_vote() {
+        let mut nan = FixedIO::nominal();
+        nan.speed = f64::NAN;
+        let v = voter([nan, FixedIO::nominal(), FixedIO::nominal()]);
+        assert_eq!(v.read_speed(), 1000.0);
+        assert!(!v.is_healthy());
+    }
+
+    #[test]
+    fn test_speed_is_written_to_the_primary_channel_only() {
+        let mut v = voter([FixedIO::nominal(), FixedIO::nominal(), FixedIO::nominal()]);
+        v.write_speed(500.0);
+        let written: Vec<_> = v.channels().iter().map(|io| io.written).collect();
+        assert_eq!(written, [Some(500.0), None, None]);
+
+        let mut v = voter([FixedIO::nominal(), FixedIO::nominal(), FixedIO::nominal()])
+            .with_mirrored_writes();
+        v.write_speed(500.0);
+        assert!(v.channels().iter().all(|io| io.written == Some(500.0)));
+    }
+}
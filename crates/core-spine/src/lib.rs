@@ -1,6 +1,11 @@
 pub mod control_loop;
 pub mod hal;
+pub mod hal_failover;
 pub mod hal_sim;
+pub mod hal_voting;
+pub mod ramp;
+pub mod reasoning;
+pub mod replay;
 pub mod safety;
 mod safety_proptest;
 pub mod safety_supervisor;
@@ -8,9 +13,23 @@ pub mod sync;
 pub mod tags;
 pub mod timebase;
 
-pub use control_loop::{ControlConfig, ExecutionStats, IronThread};
-pub use hal::{CycleStats, MachineIO};
-pub use hal_sim::SimulatedMotor;
-pub use safety::{SafetyLimits, SafetyViolation, Setpoint, Unvalidated, Validated};
-pub use sync::{AgentRecommendation, ProcessSnapshot, StateExchange};
-pub use timebase::TimeBase;
+pub use control_loop::{
+    AgentTimeoutPolicy, ControlConfig, EStopProfile, ExecutionStats, IronThread,
+    RecommendationAgeHistogram, TransitionHook, WaitStrategy, RECOMMENDATION_AGE_BUCKETS_US,
+};
+pub use hal::{AlarmFlags, CycleStats, MachineIO};
+pub use hal_failover::{FailoverHook, FailoverIO};
+pub use hal_sim::{SensorFaults, SimulatedMotor, SimulatedMotorConfig, SimulatedMotorConfigError};
+pub use hal_voting::{VotingSensor, VotingTolerance};
+pub use ramp::{RampGenerator, RampProfile};
+pub use reasoning::ReasoningHash;
+pub use replay::{replay, RecordedRecommendation, ReplayError, ReplaySource};
+pub use safety::{
+    LimitsChangeError, RateDirection, SafetyLimits, SafetyViolation, Setpoint, Unvalidated,
+    Validated,
+};
+pub use safety_supervisor::{SafetyState, SafetyTransition, ThermalDerate, TransitionCause};
+pub use sync::{
+    AgentRecommendation, ProcessSnapshot, RejectedRecommendation, Sequenced, StateExchange,
+};
+pub use timebase::{Clock, LogicalClock, MockClock, TimeBase};
@@ -0,0 +1,143 @@
+//! Explicit setpoint ramps.
+//!
+//! Without a ramp the agent's target is applied at once and anything beyond
+//! the per-cycle rate limits trips the supervisor. A [`RampProfile`] instead
+//! asks the loop to walk the effective setpoint toward the target at a fixed
+//! rate, giving a predictable linear trajectory.
+
+use crate::safety::SafetyLimits;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RampProfile {
+    pub target_rpm: f64,
+    pub rate_rpm_per_s: f64,
+}
+
+impl RampProfile {
+    /// `None` unless the target is finite and the rate finite and positive.
+    pub fn new(target_rpm: f64, rate_rpm_per_s: f64) -> Option<Self> {
+        let valid = target_rpm.is_finite() && rate_rpm_per_s.is_finite() && rate_rpm_per_s > 0.0;
+        valid.then_some(Self {
+            target_rpm,
+            rate_rpm_per_s,
+        })
+    }
+}
+
+/// Advances an effective setpoint toward the active profile's target.
+#[derive(Debug, Clone, Default)]
+pub struct RampGenerator {
+    profile: Option<RampProfile>,
+    setpoint: Option<f64>,
+}
+
+impl RampGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn profile(&self) -> Option<RampProfile> {
+        self.profile
+    }
+
+    /// Follow `profile`. The ramp continues from the current effective
+    /// setpoint, so re-sending the same or a new target never jumps.
+    pub fn set_profile(&mut self, profile: RampProfile) {
+        self.profile = Some(profile);
+    }
+
+    /// Drop the active ramp; the next profile starts from the measured speed.
+    pub fn clear(&mut self) {
+        self.profile = None;
+        self.setpoint = None;
+    }
+
+    /// Step the setpoint by at most `rate * dt_s` toward the target, also
+    /// keeping it within the accel/decel limit over `dt_s` of `current_speed` so
+    /// the result passes the supervisor's rate check. Returns `None` when no
+    /// ramp is active.
+    ///
+    /// `max`/`min` rather than `clamp`: a NaN speed reading or inverted
+    /// limits must not panic the control loop. A NaN bound is skipped, and
+    /// the supervisor rejects the cycle's non-finite reading anyway.
+    pub fn advance(&mut self, dt_s: f64, current_speed: f64, limits: &SafetyLimits) -> Option<f64> {
+        let profile = self.profile?;
+        let from = self.setpoint.unwrap_or(current_speed);
+        let max_step = profile.rate_rpm_per_s * dt_s;
+        let stepped = from + (profile.target_rpm - from).max(-max_step).min(max_step);
+        let next = stepped
+            .max(current_speed - limits.max_decel_rpm_per_s * dt_s)
+            .min(current_speed + limits.max_accel_rpm_per_s * dt_s);
+        // Restart from the measurement once it is finite again.
+        self.setpoint = next.is_finite().then_some(next);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> SafetyLimits {
+        SafetyLimits::symmetric(3000.0, 0.0, 50_000.0, 80.0, 10.0)
+    }
+
+    #[test]
+    fn test_setpoint_follows_linear_profile() {
+        let mut ramp = RampGenerator::new();
+        ramp.set_profile(RampProfile::new(100.0, 10_000.0).unwrap());
+
+        // 10 000 rpm/s is 10 rpm a 1 ms step, with the plant tracking the setpoint.
+        let mut speed = 0.0;
+        for cycle in 1..=10 {
+            speed = ramp.advance(0.001, speed, &limits()).unwrap();
+            assert!((speed - 10.0 * cycle as f64).abs() < 1e-9);
+        }
+        assert_eq!(ramp.advance(0.001, speed, &limits()), Some(100.0));
+    }
+
+    #[test]
+    fn test_ramp_down_and_retarget_continue_from_setpoint() {
+        let mut ramp = RampGenerator::new();
+        ramp.set_profile(RampProfile::new(0.0, 20_000.0).unwrap());
+        assert_eq!(ramp.advance(0.001, 100.0, &limits()), Some(80.0));
+
+        ramp.set_profile(RampProfile::new(200.0, 5_000.0).unwrap());
+        assert_eq!(ramp.advance(0.001, 80.0, &limits()), Some(85.0));
+    }
+
+    #[test]
+    fn test_step_clamped_by_safety_limits() {
+        let mut ramp = RampGenerator::new();
+        ramp.set_profile(RampProfile::new(1000.0, 1_000_000.0).unwrap());
+        // A lagging plant holds each step within 50 rpm of the measurement.
+        assert_eq!(ramp.advance(0.001, 0.0, &limits()), Some(50.0));
+        assert_eq!(ramp.advance(0.001, 10.0, &limits()), Some(60.0));
+    }
+
+    #[test]
+    fn test_invalid_rate_has_no_profile() {
+        assert!(RampProfile::new(100.0, 0.0).is_none());
+        assert!(RampProfile::new(100.0, -5.0).is_none());
+        assert!(RampProfile::new(100.0, f64::NAN).is_none());
+        assert!(RampProfile::new(f64::NAN, 10.0).is_none());
+        assert!(RampProfile::new(f64::INFINITY, 10.0).is_none());
+        assert!(RampGenerator::new()
+            .advance(0.001, 0.0, &limits())
+            .is_none());
+    }
+
+    #[test]
+    fn test_nan_speed_and_inverted_limits_do_not_panic() {
+        let mut ramp = RampGenerator::new();
+        ramp.set_profile(RampProfile::new(100.0, 10_000.0).unwrap());
+        assert!(ramp.advance(0.001, f64::NAN, &limits()).is_some());
+        assert_eq!(ramp.advance(0.001, 0.0, &limits()), Some(10.0));
+
+        let mut ramp = RampGenerator::new();
+        ramp.set_profile(RampProfile::new(100.0, 10_000.0).unwrap());
+        let mut inverted = limits();
+        inverted.max_accel_rpm_per_s = -100_000.0;
+        assert!(ramp.advance(0.001, 0.0, &inverted).unwrap().is_finite());
+    }
+}
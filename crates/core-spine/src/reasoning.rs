@@ -0,0 +1,97 @@
+//! Digest of the agent reasoning behind a recommendation.
+//!
+//! Agents hash their reasoning with SHA-256 by default; deployments that
+//! moved to a 64-byte digest such as SHA-512 configure the bridge for it.
+//! Both sizes are held inline so recommendations and snapshots stay `Copy`
+//! for the lock-free exchange.
+
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReasoningHash {
+    /// 32-byte digest, e.g. SHA-256
+    Bytes32([u8; 32]),
+    /// 64-byte digest, e.g. SHA-512
+    Bytes64([u8; 64]),
+}
+
+impl ReasoningHash {
+    /// All-zero 32-byte hash
+    pub const ZERO: Self = Self::Bytes32([0; 32]);
+
+    /// Digest lengths accepted, in bytes
+    pub const LENGTHS: [usize; 2] = [32, 64];
+
+    /// `None` unless `bytes` is 32 or 64 bytes long
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        if let Ok(bytes) = <[u8; 32]>::try_from(bytes) {
+            return Some(Self::Bytes32(bytes));
+        }
+        <[u8; 64]>::try_from(bytes).ok().map(Self::Bytes64)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Bytes32(bytes) => bytes,
+            Self::Bytes64(bytes) => bytes,
+        }
+    }
+}
+
+impl Default for ReasoningHash {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl From<[u8; 32]> for ReasoningHash {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self::Bytes32(bytes)
+    }
+}
+
+impl From<[u8; 64]> for ReasoningHash {
+    fn from(bytes: [u8; 64]) -> Self {
+        Self::Bytes64(bytes)
+    }
+}
+
+impl PartialEq<[u8; 32]> for ReasoningHash {
+    fn eq(&self, other: &[u8; 32]) -> bool {
+        self.as_bytes() == other
+    }
+}
+
+impl PartialEq<[u8; 64]> for ReasoningHash {
+    fn eq(&self, other: &[u8; 64]) -> bool {
+        self.as_bytes() == other
+    }
+}
+
+/// Serialized as a plain byte sequence, as the 32-byte array it replaced.
+impl Serialize for ReasoningHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_slice_accepts_only_known_lengths() {
+        assert_eq!(
+            ReasoningHash::from_slice(&[0xab; 32]),
+            Some(ReasoningHash::Bytes32([0xab; 32]))
+        );
+        assert_eq!(
+            ReasoningHash::from_slice(&[0xcd; 64]),
+            Some(ReasoningHash::Bytes64([0xcd; 64]))
+        );
+        for len in [0, 16, 31, 33, 48, 63, 65] {
+            assert_eq!(ReasoningHash::from_slice(&vec![0; len]), None, "{len}");
+        }
+        assert_ne!(ReasoningHash::from([0u8; 64]), ReasoningHash::ZERO);
+    }
+}
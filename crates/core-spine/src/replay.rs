@@ -0,0 +1,224 @@
+//! Deterministic replay of recorded recommendation sessions.
+//!
+//! A session file is JSONL, one recommendation per line, timestamped in
+//! control-loop microseconds (`TimeBase::now_us` at capture time):
+//!
+//! ```text
+//! {"timestamp_us": 1000, "target_speed_rpm": 500.0, "confidence": 0.9}
+//! ```
+//!
+//! Replays step the control loop at a fixed `dt` against a [`LogicalClock`],
+//! so the same session always produces the same state sequence.
+
+use crate::control_loop::{ControlConfig, IronThread};
+use crate::hal::MachineIO;
+use crate::reasoning::ReasoningHash;
+use crate::sync::{AgentRecommendation, ProcessSnapshot, StateExchange};
+use crate::timebase::{Clock, LogicalClock};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("Failed to read replay file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Line {line}: invalid recommendation: {source}")]
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+/// One recommendation as captured from a live session
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct RecordedRecommendation {
+    pub timestamp_us: u64,
+    pub target_speed_rpm: Option<f64>,
+    #[serde(default)]
+    pub ramp_rate_rpm_per_s: Option<f64>,
+    #[serde(default = "default_confidence")]
+    pub confidence: f32,
+}
+
+fn default_confidence() -> f32 {
+    1.0
+}
+
+impl From<RecordedRecommendation> for AgentRecommendation {
+    fn from(rec: RecordedRecommendation) -> Self {
+        AgentRecommendation {
+            timestamp_us: rec.timestamp_us,
+            target_speed_rpm: rec.target_speed_rpm,
+            ramp_rate_rpm_per_s: rec.ramp_rate_rpm_per_s,
+            confidence: rec.confidence,
+            reasoning_hash: ReasoningHash::ZERO,
+        }
+    }
+}
+
+/// Recorded recommendations, released to the control loop by timestamp
+#[derive(Debug, Clone)]
+pub struct ReplaySource {
+    recommendations: Vec<RecordedRecommendation>,
+    next: usize,
+}
+
+impl ReplaySource {
+    pub fn new(mut recommendations: Vec<RecordedRecommendation>) -> Self {
+        // Stable sort keeps file order for equal timestamps
+        recommendations.sort_by_key(|rec| rec.timestamp_us);
+        Self {
+            recommendations,
+            next: 0,
+        }
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, ReplayError> {
+        let mut recommendations = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let rec = serde_json::from_str(&line).map_err(|source| ReplayError::Parse {
+                line: index + 1,
+                source,
+            })?;
+            recommendations.push(rec);
+        }
+        Ok(Self::new(recommendations))
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, ReplayError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// The latest recommendation due at `now_us` that has not been released
+    /// yet; earlier ones it supersedes are skipped, as they would be live.
+    pub fn take_due(&mut self, now_us: u64) -> Option<RecordedRecommendation> {
+        let mut due = None;
+        while let Some(rec) = self.recommendations.get(self.next) {
+            if rec.timestamp_us > now_us {
+                break;
+            }
+            due = Some(*rec);
+            self.next += 1;
+        }
+        due
+    }
+
+    /// Timestamp of the last recommendation in the session
+    pub fn end_us(&self) -> u64 {
+        self.recommendations
+            .last()
+            .map(|rec| rec.timestamp_us)
+            .unwrap_or(0)
+    }
+}
+
+/// Replay `source` against `io` for `cycles` control cycles of
+/// `config.cycle_time`, returning the published state after every cycle.
+pub fn replay<IO: MachineIO>(
+    io: IO,
+    config: ControlConfig,
+    mut source: ReplaySource,
+    cycles: u64,
+) -> Vec<ProcessSnapshot> {
+    let clock = LogicalClock::new();
+    let exchange = Arc::new(StateExchange::new(
+        config.recommendation_timeout.as_micros() as u64,
+    ));
+    let cycle_time = config.cycle_time;
+    let mut iron = IronThread::new(io, config, Arc::clone(&exchange), clock.clone());
+
+    let mut states = Vec::with_capacity(cycles as usize);
+    for _ in 0..cycles {
+        clock.advance(cycle_time);
+        if let Some(rec) = source.take_due(clock.now_us()) {
+            exchange.submit_recommendation(rec.into());
+        }
+        iron.step();
+        states.push(exchange.read_state());
+    }
+    states
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hal_sim::SimulatedMotor;
+    use crate::safety_supervisor::SafetyState;
+    use std::io::Write;
+
+    const SESSION: &str = r#"{"timestamp_us": 1000, "target_speed_rpm": 30.0, "confidence": 0.9}
+{"timestamp_us": 50000, "target_speed_rpm": 45.0}
+{"timestamp_us": 120000, "target_speed_rpm": 5000.0, "confidence": 0.8}
+
+{"timestamp_us": 200000, "target_speed_rpm": null, "confidence": 0.1}
+"#;
+
+    fn to_jsonl(states: &[ProcessSnapshot]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for state in states {
+            serde_json::to_writer(&mut out, state).unwrap();
+            out.write_all(b"\n").unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_replays_are_byte_identical() {
+        let run = || {
+            let source = ReplaySource::from_reader(SESSION.as_bytes()).unwrap();
+            let cycles = source.end_us() / 1000 + 100;
+            to_jsonl(&replay(
+                SimulatedMotor::new(),
+                ControlConfig::default(),
+                source,
+                cycles,
+            ))
+        };
+
+        let first = run();
+        let second = run();
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_replay_applies_recommendations_by_timestamp() {
+        let source = ReplaySource::from_reader(SESSION.as_bytes()).unwrap();
+        let states = replay(SimulatedMotor::new(), ControlConfig::default(), source, 120);
+
+        assert_eq!(states[0].timestamp_us, 1000);
+        assert_eq!(states[100].safety_state, SafetyState::Normal);
+        assert!(states[100].motor_speed_rpm > 0.0);
+        // The over-limit recommendation at 120ms trips the supervisor
+        let last = states.last().unwrap();
+        assert_eq!(last.timestamp_us, 120_000);
+        assert_eq!(last.safety_state, SafetyState::Trip);
+    }
+
+    #[test]
+    fn test_take_due_skips_superseded() {
+        let mut source = ReplaySource::from_reader(SESSION.as_bytes()).unwrap();
+        assert!(source.take_due(500).is_none());
+        let rec = source.take_due(60_000).unwrap();
+        assert_eq!(rec.target_speed_rpm, Some(45.0));
+        assert!(source.take_due(60_000).is_none());
+    }
+
+    #[test]
+    fn test_parse_error_reports_line() {
+        let input = "{\"timestamp_us\": 1}\nnot json\n";
+        assert!(matches!(
+            ReplaySource::from_reader(input.as_bytes()),
+            Err(ReplayError::Parse { line: 2, .. })
+        ));
+    }
+}
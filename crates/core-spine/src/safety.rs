@@ -1,4 +1,6 @@
+use serde::Serialize;
 use std::marker::PhantomData;
+use thiserror::Error;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Unvalidated;
@@ -21,12 +23,143 @@ pub struct Setpoint<State = Unvalidated> {
     _state: PhantomData<State>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub struct SafetyLimits {
     pub max_speed_rpm: f64,
     pub min_speed_rpm: f64,
-    pub max_rate_of_change: f64,
+    /// Largest allowed speed increase per second, scaled by the cycle time
+    pub max_accel_rpm_per_s: f64,
+    /// Largest allowed speed decrease per second, scaled by the cycle time
+    pub max_decel_rpm_per_s: f64,
+    /// Below this temperature speed may only be held or reduced
+    pub min_temp_c: f64,
     pub max_temp_c: f64,
+    pub max_pressure_bar: f64,
+}
+
+impl SafetyLimits {
+    /// A `min_temp_c` that never engages the low-temperature interlock
+    pub const NO_MIN_TEMP_C: f64 = -273.15;
+
+    /// Limits with the same acceleration and deceleration bound, matching
+    /// the former single `max_rate_of_change`, and no minimum temperature.
+    pub fn symmetric(
+        max_speed_rpm: f64,
+        min_speed_rpm: f64,
+        max_rate_rpm_per_s: f64,
+        max_temp_c: f64,
+        max_pressure_bar: f64,
+    ) -> Self {
+        Self {
+            max_speed_rpm,
+            min_speed_rpm,
+            max_accel_rpm_per_s: max_rate_rpm_per_s,
+            max_decel_rpm_per_s: max_rate_rpm_per_s,
+            min_temp_c: Self::NO_MIN_TEMP_C,
+            max_temp_c,
+            max_pressure_bar,
+        }
+    }
+
+    /// `Ok` if these limits are at least as restrictive as `bounds`: no
+    /// upper limit above its bound, no lower limit below it, positive rate
+    /// limits and non-empty speed and temperature ranges. Runtime limit
+    /// changes must pass this against the configured limits.
+    pub fn check_within(&self, bounds: &SafetyLimits) -> Result<(), LimitsChangeError> {
+        let upper = [
+            ("max_speed_rpm", self.max_speed_rpm, bounds.max_speed_rpm),
+            (
+                "max_accel_rpm_per_s",
+                self.max_accel_rpm_per_s,
+                bounds.max_accel_rpm_per_s,
+            ),
+            (
+                "max_decel_rpm_per_s",
+                self.max_decel_rpm_per_s,
+                bounds.max_decel_rpm_per_s,
+            ),
+            ("max_temp_c", self.max_temp_c, bounds.max_temp_c),
+            (
+                "max_pressure_bar",
+                self.max_pressure_bar,
+                bounds.max_pressure_bar,
+            ),
+        ];
+        let lower = [
+            ("min_speed_rpm", self.min_speed_rpm, bounds.min_speed_rpm),
+            ("min_temp_c", self.min_temp_c, bounds.min_temp_c),
+        ];
+        for (field, requested, _) in upper.iter().chain(&lower) {
+            if !requested.is_finite() {
+                return Err(LimitsChangeError::NonFinite { field });
+            }
+        }
+        for &(field, requested, bound) in &upper {
+            if requested > bound {
+                return Err(LimitsChangeError::Loosens {
+                    field,
+                    requested,
+                    bound,
+                });
+            }
+        }
+        for &(field, requested, bound) in &lower {
+            if requested < bound {
+                return Err(LimitsChangeError::Loosens {
+                    field,
+                    requested,
+                    bound,
+                });
+            }
+        }
+        for (field, requested) in [
+            ("max_accel_rpm_per_s", self.max_accel_rpm_per_s),
+            ("max_decel_rpm_per_s", self.max_decel_rpm_per_s),
+        ] {
+            if requested <= 0.0 {
+                return Err(LimitsChangeError::NotPositive { field, requested });
+            }
+        }
+        if self.min_speed_rpm > self.max_speed_rpm {
+            return Err(LimitsChangeError::EmptyRange {
+                min_field: "min_speed_rpm",
+                max_field: "max_speed_rpm",
+            });
+        }
+        if self.min_temp_c >= self.max_temp_c {
+            return Err(LimitsChangeError::EmptyRange {
+                min_field: "min_temp_c",
+                max_field: "max_temp_c",
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Why a runtime change of [`SafetyLimits`] was refused
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum LimitsChangeError {
+    #[error("{field} is not finite")]
+    NonFinite { field: &'static str },
+    #[error("{field} {requested} is looser than the configured {bound}")]
+    Loosens {
+        field: &'static str,
+        requested: f64,
+        bound: f64,
+    },
+    #[error("{field} must be positive, got {requested}")]
+    NotPositive { field: &'static str, requested: f64 },
+    #[error("{min_field} must be below {max_field}")]
+    EmptyRange {
+        min_field: &'static str,
+        max_field: &'static str,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDirection {
+    Accelerating,
+    Decelerating,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -37,6 +170,7 @@ pub enum SafetyViolation {
     NonFiniteSensor {
         current_speed: f64,
         current_temp: f64,
+        current_pressure: f64,
     },
     ExceedsMaxSpeed {
         requested: f64,
@@ -46,14 +180,57 @@ pub enum SafetyViolation {
         requested: f64,
         limit: f64,
     },
+    /// `delta` is signed: positive when speeding up. Both `delta` and
+    /// `limit` are in rpm for the cycle being validated.
     RateOfChangeTooHigh {
         delta: f64,
         limit: f64,
+        direction: RateDirection,
     },
     TemperatureInterlock {
         current_temp: f64,
         limit: f64,
     },
+    BelowMinTemperature {
+        current_temp: f64,
+        limit: f64,
+    },
+    ExceedsMaxPressure {
+        current: f64,
+        limit: f64,
+    },
+}
+
+impl SafetyViolation {
+    /// Metric label of each variant, indexed by [`Self::kind_index`]
+    pub const KINDS: [&'static str; 8] = [
+        "non_finite_setpoint",
+        "non_finite_sensor",
+        "exceeds_max_speed",
+        "below_min_speed",
+        "rate_of_change",
+        "temperature_interlock",
+        "below_min_temperature",
+        "exceeds_max_pressure",
+    ];
+
+    pub fn kind_index(&self) -> usize {
+        match self {
+            Self::NonFiniteSetpoint { .. } => 0,
+            Self::NonFiniteSensor { .. } => 1,
+            Self::ExceedsMaxSpeed { .. } => 2,
+            Self::BelowMinSpeed { .. } => 3,
+            Self::RateOfChangeTooHigh { .. } => 4,
+            Self::TemperatureInterlock { .. } => 5,
+            Self::BelowMinTemperature { .. } => 6,
+            Self::ExceedsMaxPressure { .. } => 7,
+        }
+    }
+
+    /// Which limit was hit, e.g. `temperature_interlock`
+    pub fn kind(&self) -> &'static str {
+        Self::KINDS[self.kind_index()]
+    }
 }
 
 impl Setpoint<Unvalidated> {
@@ -69,16 +246,19 @@ impl Setpoint<Unvalidated> {
         limits: &SafetyLimits,
         current_speed: f64,
         current_temp: f64,
+        current_pressure: f64,
     ) -> Result<Setpoint<BoundsChecked>, SafetyViolation> {
         if !self.value.is_finite() {
             return Err(SafetyViolation::NonFiniteSetpoint {
                 requested: self.value,
             });
         }
-        if !current_speed.is_finite() || !current_temp.is_finite() {
+        if !current_speed.is_finite() || !current_temp.is_finite() || !current_pressure.is_finite()
+        {
             return Err(SafetyViolation::NonFiniteSensor {
                 current_speed,
                 current_temp,
+                current_pressure,
             });
         }
 
@@ -101,30 +281,44 @@ impl Setpoint<Unvalidated> {
         })
     }
 
+    /// Run every check; `dt_s` is the cycle time the rate limits are scaled by
     pub fn validate(
         self,
         limits: &SafetyLimits,
         current_speed: f64,
         current_temp: f64,
+        current_pressure: f64,
+        dt_s: f64,
     ) -> Result<Setpoint<Validated>, SafetyViolation> {
-        self.bounds_check(limits, current_speed, current_temp)?
-            .rate_check(limits, current_speed)?
-            .interlock_check(limits, current_temp)?
+        self.bounds_check(limits, current_speed, current_temp, current_pressure)?
+            .rate_check(limits, current_speed, dt_s)?
+            .interlock_check(limits, current_speed, current_temp, current_pressure)?
             .finalize()
     }
 }
 
 impl Setpoint<BoundsChecked> {
+    /// Hold the change from `current_speed` to what the rate limits allow
+    /// over `dt_s`. A non-positive or non-finite `dt_s` allows no change.
     pub fn rate_check(
         self,
         limits: &SafetyLimits,
         current_speed: f64,
+        dt_s: f64,
     ) -> Result<Setpoint<RateChecked>, SafetyViolation> {
-        let delta = (self.value - current_speed).abs();
-        if delta > limits.max_rate_of_change {
+        let delta = self.value - current_speed;
+        let (direction, rate) = if delta >= 0.0 {
+            (RateDirection::Accelerating, limits.max_accel_rpm_per_s)
+        } else {
+            (RateDirection::Decelerating, limits.max_decel_rpm_per_s)
+        };
+        let dt_s = if dt_s.is_finite() { dt_s.max(0.0) } else { 0.0 };
+        let limit = rate * dt_s;
+        if delta.abs() > limit {
             return Err(SafetyViolation::RateOfChangeTooHigh {
                 delta,
-                limit: limits.max_rate_of_change,
+                limit,
+                direction,
             });
         }
 
@@ -139,7 +333,9 @@ impl Setpoint<RateChecked> {
     pub fn interlock_check(
         self,
         limits: &SafetyLimits,
+        current_speed: f64,
         current_temp: f64,
+        current_pressure: f64,
     ) -> Result<Setpoint<InterlockCleared>, SafetyViolation> {
         if current_temp > limits.max_temp_c {
             return Err(SafetyViolation::TemperatureInterlock {
@@ -147,6 +343,21 @@ impl Setpoint<RateChecked> {
                 limit: limits.max_temp_c,
             });
         }
+        // Too cold to speed up (viscous fluid, condensation); holding or
+        // slowing down stays allowed, as with overpressure.
+        if current_temp < limits.min_temp_c && self.value > current_speed {
+            return Err(SafetyViolation::BelowMinTemperature {
+                current_temp,
+                limit: limits.min_temp_c,
+            });
+        }
+        // Overpressure only blocks increases so the agent can still shed load.
+        if current_pressure > limits.max_pressure_bar && self.value > current_speed {
+            return Err(SafetyViolation::ExceedsMaxPressure {
+                current: current_pressure,
+                limit: limits.max_pressure_bar,
+            });
+        }
 
         Ok(Setpoint {
             value: self.value,
@@ -174,18 +385,86 @@ impl Setpoint<Validated> {
 mod tests {
     use super::*;
 
+    /// 1 ms cycle
+    const DT_S: f64 = 0.001;
+
     fn limits() -> SafetyLimits {
         SafetyLimits {
             max_speed_rpm: 3000.0,
             min_speed_rpm: 0.0,
-            max_rate_of_change: 100.0,
+            max_accel_rpm_per_s: 100_000.0,
+            max_decel_rpm_per_s: 250_000.0,
+            min_temp_c: -40.0,
             max_temp_c: 80.0,
+            max_pressure_bar: 10.0,
         }
     }
 
+    #[test]
+    fn limits_may_only_tighten_within_bounds() {
+        let bounds = limits();
+        let tighter = SafetyLimits {
+            max_speed_rpm: 1500.0,
+            min_speed_rpm: 100.0,
+            max_temp_c: 60.0,
+            ..bounds
+        };
+        assert_eq!(tighter.check_within(&bounds), Ok(()));
+        assert_eq!(bounds.check_within(&bounds), Ok(()));
+
+        let looser = SafetyLimits {
+            max_pressure_bar: 12.0,
+            ..bounds
+        };
+        assert_eq!(
+            looser.check_within(&bounds),
+            Err(LimitsChangeError::Loosens {
+                field: "max_pressure_bar",
+                requested: 12.0,
+                bound: 10.0,
+            })
+        );
+        let lower_min_temp = SafetyLimits {
+            min_temp_c: -50.0,
+            ..bounds
+        };
+        assert!(matches!(
+            lower_min_temp.check_within(&bounds),
+            Err(LimitsChangeError::Loosens {
+                field: "min_temp_c",
+                ..
+            })
+        ));
+        let nan = SafetyLimits {
+            max_temp_c: f64::NAN,
+            ..bounds
+        };
+        assert!(matches!(
+            nan.check_within(&bounds),
+            Err(LimitsChangeError::NonFinite { .. })
+        ));
+        let frozen = SafetyLimits {
+            max_decel_rpm_per_s: 0.0,
+            ..bounds
+        };
+        assert!(matches!(
+            frozen.check_within(&bounds),
+            Err(LimitsChangeError::NotPositive { .. })
+        ));
+        let empty = SafetyLimits {
+            min_speed_rpm: 2000.0,
+            max_speed_rpm: 1000.0,
+            ..bounds
+        };
+        assert!(matches!(
+            empty.check_within(&bounds),
+            Err(LimitsChangeError::EmptyRange { .. })
+        ));
+    }
+
     #[test]
     fn rejects_nan_setpoint() {
-        let res = Setpoint::new(f64::NAN).validate(&limits(), 0.0, 25.0);
+        let res = Setpoint::new(f64::NAN).validate(&limits(), 0.0, 25.0, 1.0, DT_S);
         assert!(matches!(
             res,
             Err(SafetyViolation::NonFiniteSetpoint { .. })
@@ -194,31 +473,133 @@ mod tests {
 
     #[test]
     fn rejects_nonfinite_sensor() {
-        let res = Setpoint::new(10.0).validate(&limits(), f64::INFINITY, 25.0);
+        let res = Setpoint::new(10.0).validate(&limits(), f64::INFINITY, 25.0, 1.0, DT_S);
         assert!(matches!(res, Err(SafetyViolation::NonFiniteSensor { .. })));
     }
 
     #[test]
     fn accepts_valid_setpoint() {
-        let res = Setpoint::new(100.0).validate(&limits(), 50.0, 25.0);
+        let res = Setpoint::new(100.0).validate(&limits(), 50.0, 25.0, 1.0, DT_S);
         assert!(res.is_ok());
     }
 
     #[test]
     fn rejects_rate_limit() {
-        let res = Setpoint::new(500.0).validate(&limits(), 0.0, 25.0);
+        let res = Setpoint::new(500.0).validate(&limits(), 0.0, 25.0, 1.0, DT_S);
         assert!(matches!(
             res,
-            Err(SafetyViolation::RateOfChangeTooHigh { .. })
+            Err(SafetyViolation::RateOfChangeTooHigh {
+                direction: RateDirection::Accelerating,
+                ..
+            })
         ));
     }
 
+    #[test]
+    fn deceleration_has_its_own_limit() {
+        // 200 rpm down is within the decel limit but over the accel limit.
+        assert!(Setpoint::new(300.0)
+            .validate(&limits(), 500.0, 25.0, 1.0, DT_S)
+            .is_ok());
+        assert!(Setpoint::new(700.0)
+            .validate(&limits(), 500.0, 25.0, 1.0, DT_S)
+            .is_err());
+
+        let res = Setpoint::new(200.0).validate(&limits(), 500.0, 25.0, 1.0, DT_S);
+        assert_eq!(
+            res.err(),
+            Some(SafetyViolation::RateOfChangeTooHigh {
+                delta: -300.0,
+                limit: 250.0,
+                direction: RateDirection::Decelerating,
+            })
+        );
+    }
+
+    #[test]
+    fn rate_limit_scales_with_cycle_time() {
+        // 100 000 rpm/s allows 100 rpm in 1 ms but 1000 rpm in 10 ms.
+        assert!(Setpoint::new(600.0)
+            .validate(&limits(), 0.0, 25.0, 1.0, 0.01)
+            .is_ok());
+        assert!(Setpoint::new(600.0)
+            .validate(&limits(), 0.0, 25.0, 1.0, DT_S)
+            .is_err());
+        assert_eq!(
+            Setpoint::new(1200.0)
+                .validate(&limits(), 0.0, 25.0, 1.0, 0.01)
+                .err(),
+            Some(SafetyViolation::RateOfChangeTooHigh {
+                delta: 1200.0,
+                limit: 1000.0,
+                direction: RateDirection::Accelerating,
+            })
+        );
+
+        // Without a usable cycle time only holding is allowed.
+        for dt_s in [0.0, -1.0, f64::NAN] {
+            assert!(Setpoint::new(50.0)
+                .validate(&limits(), 50.0, 25.0, 1.0, dt_s)
+                .is_ok());
+            assert!(Setpoint::new(51.0)
+                .validate(&limits(), 50.0, 25.0, 1.0, dt_s)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn symmetric_limits_set_both_rates() {
+        let limits = SafetyLimits::symmetric(3000.0, 0.0, 75.0, 80.0, 10.0);
+        assert_eq!(limits.max_accel_rpm_per_s, 75.0);
+        assert_eq!(limits.max_decel_rpm_per_s, 75.0);
+    }
+
+    #[test]
+    fn rejects_increase_over_max_pressure() {
+        let res = Setpoint::new(100.0).validate(&limits(), 50.0, 25.0, 12.0, DT_S);
+        assert!(matches!(
+            res,
+            Err(SafetyViolation::ExceedsMaxPressure { .. })
+        ));
+    }
+
+    #[test]
+    fn allows_decrease_over_max_pressure() {
+        let res = Setpoint::new(25.0).validate(&limits(), 50.0, 25.0, 12.0, DT_S);
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn rejects_increase_below_min_temperature() {
+        let res = Setpoint::new(100.0).validate(&limits(), 50.0, -45.0, 1.0, DT_S);
+        assert_eq!(
+            res.err(),
+            Some(SafetyViolation::BelowMinTemperature {
+                current_temp: -45.0,
+                limit: -40.0,
+            })
+        );
+        assert!(Setpoint::new(50.0)
+            .validate(&limits(), 50.0, -45.0, 1.0, DT_S)
+            .is_ok());
+        assert!(Setpoint::new(25.0)
+            .validate(&limits(), 50.0, -45.0, 1.0, DT_S)
+            .is_ok());
+    }
+
+    #[test]
+    fn symmetric_limits_have_no_min_temperature() {
+        let limits = SafetyLimits::symmetric(3000.0, 0.0, 75.0, 80.0, 10.0);
+        assert_eq!(limits.min_temp_c, SafetyLimits::NO_MIN_TEMP_C);
+    }
+
     #[test]
     fn rejects_temp_interlock() {
-        let res = Setpoint::new(100.0).validate(&limits(), 50.0, 100.0);
+        let res = Setpoint::new(100.0).validate(&limits(), 50.0, 100.0, 1.0, DT_S);
         assert!(matches!(
             res,
             Err(SafetyViolation::TemperatureInterlock { .. })
         ));
+        assert_eq!(res.unwrap_err().kind(), "temperature_interlock");
     }
 }
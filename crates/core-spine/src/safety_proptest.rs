@@ -3,12 +3,18 @@ mod proptest_safety {
     use crate::safety::*;
     use proptest::prelude::*;
 
+    /// 1 ms cycle: the rate limits below allow 100 rpm up and 250 rpm down
+    const DT_S: f64 = 0.001;
+
     fn safety_limits() -> SafetyLimits {
         SafetyLimits {
             max_speed_rpm: 3000.0,
             min_speed_rpm: 0.0,
-            max_rate_of_change: 100.0,
+            max_accel_rpm_per_s: 100_000.0,
+            max_decel_rpm_per_s: 250_000.0,
+            min_temp_c: -40.0,
             max_temp_c: 80.0,
+            max_pressure_bar: 10.0,
         }
     }
 
@@ -19,7 +25,7 @@ mod proptest_safety {
         #[test]
         fn valid_setpoints_accepted(
             current_speed in 0.0f64..=3000.0,
-            delta in -100.0f64..=100.0, // Generate delta directly to ensure it respects rate limit
+            delta in -250.0f64..=100.0, // Generate delta directly to ensure it respects both rate limits
             current_temp in -40.0f64..=80.0,
         ) {
             let limits = safety_limits();
@@ -28,15 +34,67 @@ mod proptest_safety {
 
             // Re-verify delta due to clamping (clamping might reduce delta, which is fine,
             // but if clamping INCREASES delta it would be an issue - but clamping range only reduces delta magnitude)
-            // Actually, clamping to 0..3000 when current is 0..3000 and delta is -100..100
-            // is always safe regarding rate limit because |clamped - current| <= |(current+delta) - current| = |delta| <= 100
+            // Actually, clamping to 0..3000 when current is 0..3000 and delta is -250..100
+            // is always safe regarding rate limits because clamping keeps the sign of delta
+            // and |clamped - current| <= |delta|
 
             let result = Setpoint::<Unvalidated>::new(setpoint)
-                .validate(&limits, current_speed, current_temp);
+                .validate(&limits, current_speed, current_temp, 1.0, DT_S);
 
             prop_assert!(result.is_ok(), "Failed for speed={}, delta={}, temp={}, result={:?}", current_speed, delta, current_temp, result);
         }
 
+        // Property: Each direction is held to its own rate limit
+        #[test]
+        fn rate_limit_respects_direction(
+            // Leaves room for either step without leaving the speed bounds
+            current_speed in 750.0f64..=2000.0,
+            excess in 0.01f64..=500.0,
+            accelerating in any::<bool>(),
+        ) {
+            let limits = safety_limits();
+            let setpoint = if accelerating {
+                current_speed + limits.max_accel_rpm_per_s * DT_S + excess
+            } else {
+                current_speed - limits.max_decel_rpm_per_s * DT_S - excess
+            };
+
+            let result = Setpoint::<Unvalidated>::new(setpoint)
+                .validate(&limits, current_speed, 25.0, 1.0, DT_S);
+            let expected = if accelerating {
+                RateDirection::Accelerating
+            } else {
+                RateDirection::Decelerating
+            };
+            let matches_direction = matches!(
+                result,
+                Err(SafetyViolation::RateOfChangeTooHigh { direction, .. }) if direction == expected
+            );
+            prop_assert!(matches_direction, "Expected {:?} rate violation, got {:?}", expected, result);
+        }
+
+        // Property: The same rpm/s limit holds at any cycle time
+        #[test]
+        fn rate_limit_is_cycle_time_independent(
+            dt_s in 0.0001f64..=0.1,
+            fraction in 0.0f64..=0.999,
+            excess in 1.001f64..=2.0,
+        ) {
+            let limits = SafetyLimits {
+                max_speed_rpm: 1.0e6,
+                ..safety_limits()
+            };
+            let allowed = limits.max_accel_rpm_per_s * dt_s;
+            let within = Setpoint::<Unvalidated>::new(allowed * fraction)
+                .validate(&limits, 0.0, 25.0, 1.0, dt_s);
+            prop_assert!(within.is_ok(), "{} rpm in {} s rejected: {:?}", allowed * fraction, dt_s, within);
+
+            let beyond = Setpoint::<Unvalidated>::new(allowed * excess)
+                .validate(&limits, 0.0, 25.0, 1.0, dt_s);
+            let is_rate = matches!(beyond, Err(SafetyViolation::RateOfChangeTooHigh { .. }));
+            prop_assert!(is_rate, "{} rpm in {} s: {:?}", allowed * excess, dt_s, beyond);
+        }
+
         // Property: Setpoints above max are always rejected
         #[test]
         fn overspeed_always_rejected(
@@ -46,7 +104,7 @@ mod proptest_safety {
         ) {
             let limits = safety_limits();
             let result = Setpoint::<Unvalidated>::new(setpoint)
-                .validate(&limits, current_speed, current_temp);
+                .validate(&limits, current_speed, current_temp, 1.0, DT_S);
 
             let is_overspeed = matches!(result, Err(SafetyViolation::ExceedsMaxSpeed { .. }));
             prop_assert!(is_overspeed, "Expected ExceedsMaxSpeed, got {:?}", result);
@@ -62,13 +120,13 @@ mod proptest_safety {
 
             // Test NaN
             let nan_result = Setpoint::<Unvalidated>::new(f64::NAN)
-                .validate(&limits, current_speed, current_temp);
+                .validate(&limits, current_speed, current_temp, 1.0, DT_S);
             let is_nan_err = matches!(nan_result, Err(SafetyViolation::NonFiniteSetpoint { .. }));
             prop_assert!(is_nan_err, "Expected NonFiniteSetpoint for NaN, got {:?}", nan_result);
 
             // Test Infinity
             let inf_result = Setpoint::<Unvalidated>::new(f64::INFINITY)
-                .validate(&limits, current_speed, current_temp);
+                .validate(&limits, current_speed, current_temp, 1.0, DT_S);
             let is_inf_valid = matches!(
                 inf_result,
                 Err(SafetyViolation::NonFiniteSetpoint { .. }) |
@@ -91,11 +149,66 @@ mod proptest_safety {
             // Only test if we are actually trying to increase (clamping might make it equal)
             if setpoint > current_speed {
                 let result = Setpoint::<Unvalidated>::new(setpoint)
-                    .validate(&limits, current_speed, current_temp);
+                    .validate(&limits, current_speed, current_temp, 1.0, DT_S);
 
                  let is_interlock = matches!(result, Err(SafetyViolation::TemperatureInterlock { .. }));
                  prop_assert!(is_interlock, "Expected TemperatureInterlock, got {:?}", result);
             }
         }
+
+        // Property: Below the minimum temperature no increase is accepted
+        #[test]
+        fn low_temp_interlock_blocks_increase(
+            current_speed in 0.0f64..=3000.0,
+            delta in 0.001f64..=100.0,
+            current_temp in -273.0f64..-40.0,
+        ) {
+            let limits = safety_limits();
+            let setpoint = (current_speed + delta).min(limits.max_speed_rpm);
+
+            if setpoint > current_speed {
+                let result = Setpoint::<Unvalidated>::new(setpoint)
+                    .validate(&limits, current_speed, current_temp, 1.0, DT_S);
+
+                let is_interlock = matches!(result, Err(SafetyViolation::BelowMinTemperature { .. }));
+                prop_assert!(is_interlock, "Expected BelowMinTemperature, got {:?}", result);
+            }
+        }
+
+        // Property: Below the minimum temperature holding or slowing down is
+        // still accepted
+        #[test]
+        fn low_temp_interlock_allows_decrease(
+            current_speed in 0.0f64..=3000.0,
+            delta in 0.0f64..=250.0,
+            current_temp in -273.0f64..-40.0,
+        ) {
+            let limits = safety_limits();
+            let setpoint = (current_speed - delta).max(limits.min_speed_rpm);
+            let result = Setpoint::<Unvalidated>::new(setpoint)
+                .validate(&limits, current_speed, current_temp, 1.0, DT_S);
+
+            prop_assert!(result.is_ok(), "Failed for speed={}, delta={}, temp={}, result={:?}", current_speed, delta, current_temp, result);
+        }
+
+        // Property: Overpressure prevents all increases
+        #[test]
+        fn pressure_interlock_blocks_increase(
+            current_speed in 0.0f64..=3000.0,
+            delta in 0.001f64..=100.0,
+            current_temp in -40.0f64..=80.0,
+            current_pressure in 10.01f64..=1000.0,
+        ) {
+            let limits = safety_limits();
+            let setpoint = (current_speed + delta).min(limits.max_speed_rpm);
+
+            if setpoint > current_speed {
+                let result = Setpoint::<Unvalidated>::new(setpoint)
+                    .validate(&limits, current_speed, current_temp, current_pressure, DT_S);
+
+                let is_interlock = matches!(result, Err(SafetyViolation::ExceedsMaxPressure { .. }));
+                prop_assert!(is_interlock, "Expected ExceedsMaxPressure, got {:?}", result);
+            }
+        }
     }
 }
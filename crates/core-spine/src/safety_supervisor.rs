@@ -1,6 +1,8 @@
 use crate::safety::{SafetyLimits, SafetyViolation, Setpoint};
+use serde::Serialize;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SafetyState {
     #[default]
     Normal,
@@ -9,11 +11,83 @@ pub enum SafetyState {
     Safe,
 }
 
+/// Why the supervisor changed state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionCause {
+    /// A setpoint validated after running degraded
+    Recovered,
+    /// No target this cycle, so the last safe setpoint is held
+    MissingRecommendation,
+    /// A setpoint failed validation
+    Violation(SafetyViolation),
+    /// Cycle jitter above the configured maximum
+    TimingJitter { jitter_us: u64 },
+    /// [`SafetySupervisor::trip`], e.g. an e-stop, watchdog or HAL fault
+    Forced,
+    /// A tripped supervisor latched into `Safe`
+    Latched,
+}
+
+impl TransitionCause {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TransitionCause::Recovered => "recovered",
+            TransitionCause::MissingRecommendation => "missing_recommendation",
+            TransitionCause::Violation(_) => "violation",
+            TransitionCause::TimingJitter { .. } => "timing_jitter",
+            TransitionCause::Forced => "forced",
+            TransitionCause::Latched => "latched",
+        }
+    }
+}
+
+/// One change of [`SafetyState`], recorded only when the state differs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafetyTransition {
+    pub from: SafetyState,
+    pub to: SafetyState,
+    pub cause: TransitionCause,
+}
+
+/// Thermal load shedding: above `soft_limit_c` every target is scaled down
+/// by `gain_per_c` of itself per degree, so the machine slows to shed heat
+/// and only trips at the hard `max_temp_c`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalDerate {
+    pub soft_limit_c: f64,
+    /// Fraction of the target shed per degree above the soft limit
+    pub gain_per_c: f64,
+}
+
+impl ThermalDerate {
+    /// `None` unless the soft limit is finite and the gain finite and positive.
+    pub fn new(soft_limit_c: f64, gain_per_c: f64) -> Option<Self> {
+        (soft_limit_c.is_finite() && gain_per_c.is_finite() && gain_per_c > 0.0).then_some(Self {
+            soft_limit_c,
+            gain_per_c,
+        })
+    }
+
+    /// Share of the target allowed at `temp_c`, from 1 at or below the soft
+    /// limit down to 0
+    pub fn factor(&self, temp_c: f64) -> f64 {
+        let excess = temp_c - self.soft_limit_c;
+        if excess > 0.0 {
+            (1.0 - self.gain_per_c * excess).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
 pub struct SafetySupervisor {
     state: SafetyState,
     last_safe_setpoint: f64,
     limits: SafetyLimits,
+    thermal_derate: Option<ThermalDerate>,
     timing_violation_count: u32,
+    /// Transitions not yet taken by [`Self::take_transitions`]
+    transitions: Vec<SafetyTransition>,
 }
 
 impl SafetySupervisor {
@@ -22,7 +96,17 @@ impl SafetySupervisor {
             state: SafetyState::Normal,
             last_safe_setpoint: 0.0,
             limits,
+            thermal_derate: None,
             timing_violation_count: 0,
+            transitions: Vec::with_capacity(4),
+        }
+    }
+
+    /// Scale targets down above a soft temperature limit, see [`ThermalDerate`]
+    pub fn with_thermal_derate(self, thermal_derate: Option<ThermalDerate>) -> Self {
+        Self {
+            thermal_derate,
+            ..self
         }
     }
 
@@ -30,45 +114,164 @@ impl SafetySupervisor {
         self.state
     }
 
+    /// Transitions since the last call, oldest first. Callers drain this
+    /// every cycle; it grows until they do.
+    pub fn take_transitions(&mut self) -> std::vec::Drain<'_, SafetyTransition> {
+        self.transitions.drain(..)
+    }
+
+    fn set_state(&mut self, to: SafetyState, cause: TransitionCause) {
+        if self.state != to {
+            self.transitions.push(SafetyTransition {
+                from: self.state,
+                to,
+                cause,
+            });
+            self.state = to;
+        }
+    }
+
+    /// Validate `target_speed` for a cycle of `dt_s` seconds
     pub fn apply_recommendation(
         &mut self,
         target_speed: Option<f64>,
         current_speed: f64,
         current_temp: f64,
+        current_pressure: f64,
+        dt_s: f64,
+    ) -> (f64, Option<SafetyViolation>) {
+        self.apply(
+            target_speed,
+            current_speed,
+            current_temp,
+            current_pressure,
+            dt_s,
+            false,
+        )
+    }
+
+    /// Validate a `target_speed` chosen by the agent-timeout policy rather
+    /// than the agent. It is checked like a recommendation, but a valid step
+    /// leaves the supervisor `Degraded`, since the agent is still missing.
+    pub fn apply_timeout_target(
+        &mut self,
+        target_speed: Option<f64>,
+        current_speed: f64,
+        current_temp: f64,
+        current_pressure: f64,
+        dt_s: f64,
+    ) -> (f64, Option<SafetyViolation>) {
+        self.apply(
+            target_speed,
+            current_speed,
+            current_temp,
+            current_pressure,
+            dt_s,
+            true,
+        )
+    }
+
+    fn apply(
+        &mut self,
+        target_speed: Option<f64>,
+        current_speed: f64,
+        current_temp: f64,
+        current_pressure: f64,
+        dt_s: f64,
+        agent_timed_out: bool,
     ) -> (f64, Option<SafetyViolation>) {
         if matches!(self.state, SafetyState::Trip | SafetyState::Safe) {
-            self.state = SafetyState::Safe;
+            self.set_state(SafetyState::Safe, TransitionCause::Latched);
             self.last_safe_setpoint = 0.0;
             return (0.0, None);
         }
 
         let target_speed = match target_speed {
-            Some(value) => value,
+            Some(value) => self.derate(value, current_speed, current_temp, dt_s),
             None => {
-                self.state = SafetyState::Degraded;
+                self.set_state(
+                    SafetyState::Degraded,
+                    TransitionCause::MissingRecommendation,
+                );
+                self.last_safe_setpoint =
+                    self.derate(self.last_safe_setpoint, current_speed, current_temp, dt_s);
                 return (self.last_safe_setpoint, None);
             }
         };
 
         let raw_setpoint = Setpoint::new(target_speed);
-        let validated = raw_setpoint.validate(&self.limits, current_speed, current_temp);
+        let validated = raw_setpoint.validate(
+            &self.limits,
+            current_speed,
+            current_temp,
+            current_pressure,
+            dt_s,
+        );
 
         match validated {
             Ok(safe_setpoint) => {
                 let speed = safe_setpoint.value();
                 self.last_safe_setpoint = speed;
-                self.state = SafetyState::Normal;
-                self.timing_violation_count = 0;
+                if agent_timed_out {
+                    self.set_state(
+                        SafetyState::Degraded,
+                        TransitionCause::MissingRecommendation,
+                    );
+                } else {
+                    self.set_state(SafetyState::Normal, TransitionCause::Recovered);
+                    self.timing_violation_count = 0;
+                }
                 (speed, None)
             }
             Err(violation) => {
-                self.state = SafetyState::Trip;
+                self.set_state(SafetyState::Trip, TransitionCause::Violation(violation));
                 self.last_safe_setpoint = 0.0;
                 (0.0, Some(violation))
             }
         }
     }
 
+    /// `target` scaled down for heat above the soft limit. The reduction is
+    /// held within the deceleration limit and above `min_speed_rpm`, so a
+    /// derated target never fails validation where the original passed.
+    fn derate(&self, target: f64, current_speed: f64, current_temp: f64, dt_s: f64) -> f64 {
+        let Some(derate) = self.thermal_derate else {
+            return target;
+        };
+        let factor = derate.factor(current_temp);
+        if factor >= 1.0 {
+            return target;
+        }
+        let dt_s = if dt_s.is_finite() { dt_s.max(0.0) } else { 0.0 };
+        let floor =
+            (current_speed - self.limits.max_decel_rpm_per_s * dt_s).max(self.limits.min_speed_rpm);
+        (target * factor).max(floor).min(target)
+    }
+
+    /// Record that `speed` stays commanded after the control loop skipped a
+    /// validated setpoint inside its deadband, so a later hold resumes from
+    /// what the actuator was actually told.
+    pub fn hold_setpoint(&mut self, speed: f64) {
+        if matches!(self.state, SafetyState::Normal | SafetyState::Degraded) {
+            self.last_safe_setpoint = speed;
+        }
+    }
+
+    /// Validate later setpoints against `limits`. Callers check runtime
+    /// changes with [`SafetyLimits::check_within`] first.
+    pub fn set_limits(&mut self, limits: SafetyLimits) {
+        self.limits = limits;
+    }
+
+    /// Force the supervisor into `Trip`, e.g. on a watchdog overrun. A
+    /// supervisor already latched in `Safe` stays there.
+    pub fn trip(&mut self) {
+        if self.state != SafetyState::Safe {
+            self.set_state(SafetyState::Trip, TransitionCause::Forced);
+        }
+        self.last_safe_setpoint = 0.0;
+    }
+
     pub fn note_timing_jitter(
         &mut self,
         jitter_us: u64,
@@ -81,11 +284,14 @@ impl SafetySupervisor {
         }
 
         self.timing_violation_count = self.timing_violation_count.saturating_add(1);
-        if self.timing_violation_count >= trip_after.max(1) {
-            self.state = SafetyState::Trip;
+        let cause = TransitionCause::TimingJitter { jitter_us };
+        if matches!(self.state, SafetyState::Trip | SafetyState::Safe) {
+            // Already latched; jitter must not lift it back to `Degraded`.
+        } else if self.timing_violation_count >= trip_after.max(1) {
+            self.set_state(SafetyState::Trip, cause);
             self.last_safe_setpoint = 0.0;
         } else {
-            self.state = SafetyState::Degraded;
+            self.set_state(SafetyState::Degraded, cause);
         }
         true
     }
@@ -109,4 +315,226 @@ impl SafetyState {
             SafetyState::Safe => 3,
         }
     }
+
+    /// Inverse of `as_u8`; unknown values map to `Trip`
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => SafetyState::Normal,
+            1 => SafetyState::Degraded,
+            3 => SafetyState::Safe,
+            _ => SafetyState::Trip,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT_S: f64 = 0.001;
+
+    fn limits() -> SafetyLimits {
+        SafetyLimits {
+            max_speed_rpm: 3000.0,
+            min_speed_rpm: 0.0,
+            max_accel_rpm_per_s: 100_000.0,
+            max_decel_rpm_per_s: 100_000.0,
+            min_temp_c: SafetyLimits::NO_MIN_TEMP_C,
+            max_temp_c: 80.0,
+            max_pressure_bar: 10.0,
+        }
+    }
+
+    #[test]
+    fn missing_recommendation_degrades_and_holds_last_safe() {
+        let mut supervisor = SafetySupervisor::new(limits());
+        let (speed, violation) = supervisor.apply_recommendation(Some(50.0), 0.0, 25.0, 1.0, DT_S);
+        assert_eq!(speed, 50.0);
+        assert!(violation.is_none());
+        assert_eq!(supervisor.state(), SafetyState::Normal);
+
+        let (speed, violation) = supervisor.apply_recommendation(None, 50.0, 25.0, 1.0, DT_S);
+        assert_eq!(speed, 50.0);
+        assert!(violation.is_none());
+        assert_eq!(supervisor.state(), SafetyState::Degraded);
+    }
+
+    #[test]
+    fn held_setpoint_is_what_a_later_hold_resumes() {
+        let mut supervisor = SafetySupervisor::new(limits());
+        supervisor.apply_recommendation(Some(50.0), 0.0, 25.0, 1.0, DT_S);
+        supervisor.apply_recommendation(Some(52.0), 50.0, 25.0, 1.0, DT_S);
+        supervisor.hold_setpoint(50.0);
+        let (speed, _) = supervisor.apply_recommendation(None, 50.0, 25.0, 1.0, DT_S);
+        assert_eq!(speed, 50.0);
+
+        // Holding never revives a tripped supervisor.
+        supervisor.trip();
+        supervisor.hold_setpoint(50.0);
+        let (speed, _) = supervisor.apply_recommendation(None, 50.0, 25.0, 1.0, DT_S);
+        assert_eq!(speed, 0.0);
+    }
+
+    #[test]
+    fn violation_trips_then_latches_safe() {
+        let mut supervisor = SafetySupervisor::new(limits());
+        let (speed, violation) =
+            supervisor.apply_recommendation(Some(5000.0), 0.0, 25.0, 1.0, DT_S);
+        assert_eq!(speed, 0.0);
+        assert!(matches!(
+            violation,
+            Some(SafetyViolation::ExceedsMaxSpeed { .. })
+        ));
+        assert_eq!(supervisor.state(), SafetyState::Trip);
+
+        let (speed, _) = supervisor.apply_recommendation(Some(50.0), 0.0, 25.0, 1.0, DT_S);
+        assert_eq!(speed, 0.0);
+        assert_eq!(supervisor.state(), SafetyState::Safe);
+    }
+
+    #[test]
+    fn sustained_jitter_trips() {
+        let mut supervisor = SafetySupervisor::new(limits());
+        assert!(supervisor.note_timing_jitter(600, 500, 3));
+        assert_eq!(supervisor.state(), SafetyState::Degraded);
+        assert!(supervisor.note_timing_jitter(600, 500, 3));
+        assert!(supervisor.note_timing_jitter(600, 500, 3));
+        assert_eq!(supervisor.state(), SafetyState::Trip);
+    }
+
+    #[test]
+    fn each_state_change_is_recorded_once() {
+        let mut supervisor = SafetySupervisor::new(limits());
+        let states = |supervisor: &mut SafetySupervisor| {
+            supervisor
+                .take_transitions()
+                .map(|t| (t.from, t.to, t.cause.as_str()))
+                .collect::<Vec<_>>()
+        };
+
+        // Staying in a state records nothing.
+        for _ in 0..3 {
+            supervisor.apply_recommendation(Some(50.0), 0.0, 25.0, 1.0, DT_S);
+        }
+        assert!(states(&mut supervisor).is_empty());
+
+        for _ in 0..3 {
+            supervisor.apply_recommendation(None, 50.0, 25.0, 1.0, DT_S);
+        }
+        supervisor.apply_recommendation(Some(50.0), 50.0, 25.0, 1.0, DT_S);
+        assert_eq!(
+            states(&mut supervisor),
+            [
+                (
+                    SafetyState::Normal,
+                    SafetyState::Degraded,
+                    "missing_recommendation"
+                ),
+                (SafetyState::Degraded, SafetyState::Normal, "recovered"),
+            ]
+        );
+
+        supervisor.note_timing_jitter(600, 500, 2);
+        supervisor.note_timing_jitter(700, 500, 2);
+        supervisor.note_timing_jitter(800, 500, 2);
+        for _ in 0..3 {
+            supervisor.apply_recommendation(Some(50.0), 0.0, 25.0, 1.0, DT_S);
+        }
+        supervisor.note_timing_jitter(900, 500, 2);
+        supervisor.trip();
+        let transitions: Vec<_> = supervisor.take_transitions().collect();
+        assert_eq!(
+            transitions,
+            [
+                SafetyTransition {
+                    from: SafetyState::Normal,
+                    to: SafetyState::Degraded,
+                    cause: TransitionCause::TimingJitter { jitter_us: 600 },
+                },
+                SafetyTransition {
+                    from: SafetyState::Degraded,
+                    to: SafetyState::Trip,
+                    cause: TransitionCause::TimingJitter { jitter_us: 700 },
+                },
+                SafetyTransition {
+                    from: SafetyState::Trip,
+                    to: SafetyState::Safe,
+                    cause: TransitionCause::Latched,
+                },
+            ]
+        );
+        assert_eq!(supervisor.state(), SafetyState::Safe);
+    }
+
+    #[test]
+    fn violation_transition_carries_the_violation() {
+        let mut supervisor = SafetySupervisor::new(limits());
+        supervisor.apply_recommendation(Some(5000.0), 0.0, 25.0, 1.0, DT_S);
+        let transitions: Vec<_> = supervisor.take_transitions().collect();
+        assert_eq!(transitions.len(), 1);
+        assert!(matches!(
+            transitions[0].cause,
+            TransitionCause::Violation(SafetyViolation::ExceedsMaxSpeed { .. })
+        ));
+    }
+
+    #[test]
+    fn speed_reduces_as_temperature_climbs_past_soft_limit() {
+        let derate = ThermalDerate::new(60.0, 0.01).unwrap();
+        let mut supervisor = SafetySupervisor::new(limits()).with_thermal_derate(Some(derate));
+
+        let (speed, _) = supervisor.apply_recommendation(Some(1000.0), 1000.0, 55.0, 1.0, DT_S);
+        assert_eq!(speed, 1000.0);
+
+        // These limits leave plenty of deceleration headroom over DT_S.
+        let mut last = speed;
+        for temp in [62.0, 66.0, 70.0, 75.0] {
+            let (speed, violation) =
+                supervisor.apply_recommendation(Some(1000.0), last, temp, 1.0, DT_S);
+            assert!(violation.is_none());
+            assert!(speed < last, "{speed} at {temp} C");
+            assert!((speed - 1000.0 * derate.factor(temp)).abs() < 1e-9);
+            last = speed;
+        }
+        assert_eq!(supervisor.state(), SafetyState::Normal);
+
+        // Holding the last setpoint while hot still sheds load.
+        let (held, _) = supervisor.apply_recommendation(None, last, 78.0, 1.0, DT_S);
+        assert!(held < last);
+
+        // The hard limit still trips.
+        let (speed, violation) =
+            supervisor.apply_recommendation(Some(1000.0), held, 81.0, 1.0, DT_S);
+        assert_eq!(speed, 0.0);
+        assert!(matches!(
+            violation,
+            Some(SafetyViolation::TemperatureInterlock { .. })
+        ));
+    }
+
+    #[test]
+    fn thermal_derate_respects_deceleration_limit() {
+        let slow = SafetyLimits {
+            max_decel_rpm_per_s: 10_000.0,
+            ..limits()
+        };
+        let derate = ThermalDerate::new(60.0, 0.05).unwrap();
+        let mut supervisor = SafetySupervisor::new(slow).with_thermal_derate(Some(derate));
+        // Halving 2000 rpm at once would exceed 10 000 rpm/s, 10 rpm over DT_S.
+        let (speed, violation) =
+            supervisor.apply_recommendation(Some(2000.0), 2000.0, 70.0, 1.0, DT_S);
+        assert!(violation.is_none());
+        assert!((speed - 1990.0).abs() < 1e-9);
+        assert_eq!(ThermalDerate::new(60.0, 0.0), None);
+    }
+
+    #[test]
+    fn explicit_trip_forces_zero_output() {
+        let mut supervisor = SafetySupervisor::new(limits());
+        supervisor.apply_recommendation(Some(50.0), 0.0, 25.0, 1.0, DT_S);
+        supervisor.trip();
+        assert_eq!(supervisor.state(), SafetyState::Trip);
+        let (speed, _) = supervisor.apply_recommendation(None, 50.0, 25.0, 1.0, DT_S);
+        assert_eq!(speed, 0.0);
+    }
 }
@@ -1,8 +1,16 @@
+use crate::control_loop::{
+    ExecutionStats, RecommendationAgeHistogram, RECOMMENDATION_AGE_BUCKETS_US,
+};
+use crate::reasoning::ReasoningHash;
+use crate::safety::{SafetyLimits, SafetyViolation};
 use crate::safety_supervisor::SafetyState;
+use serde::Serialize;
 use std::cell::UnsafeCell;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::Mutex;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize)]
 pub struct ProcessSnapshot {
     pub timestamp_us: u64,
     pub cycle_count: u64,
@@ -11,14 +19,25 @@ pub struct ProcessSnapshot {
     pub motor_temp_c: f64,
     pub pressure_bar: f64,
     pub cycle_jitter_us: u32,
+    /// `reasoning_hash` of the recommendation that set this cycle's output,
+    /// or [`ProcessSnapshot::HOLDING_LAST_SAFE`] when none did
+    pub applied_reasoning_hash: ReasoningHash,
+}
+
+impl ProcessSnapshot {
+    /// Applied hash while the loop holds its last safe setpoint: no fresh
+    /// recommendation, a rejected one, or an emergency stop.
+    pub const HOLDING_LAST_SAFE: ReasoningHash = ReasoningHash::ZERO;
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct AgentRecommendation {
     pub timestamp_us: u64,
     pub target_speed_rpm: Option<f64>,
+    /// Ramp toward the target at this rate instead of applying it at once
+    pub ramp_rate_rpm_per_s: Option<f64>,
     pub confidence: f32,
-    pub reasoning_hash: [u8; 32],
+    pub reasoning_hash: ReasoningHash,
 }
 
 impl Default for AgentRecommendation {
@@ -26,15 +45,49 @@ impl Default for AgentRecommendation {
         Self {
             timestamp_us: 0,
             target_speed_rpm: None,
+            ramp_rate_rpm_per_s: None,
             confidence: 0.0,
-            reasoning_hash: [0u8; 32],
+            reasoning_hash: ReasoningHash::ZERO,
         }
     }
 }
 
+/// A recommendation the safety supervisor refused, published by the Iron
+/// Thread so the bridge can audit it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RejectedRecommendation {
+    /// `timestamp_us` of the rejected [`AgentRecommendation`]
+    pub recommendation_us: u64,
+    /// Setpoint the supervisor was asked for this cycle (a ramp step when ramping)
+    pub requested_rpm: Option<f64>,
+    pub current_speed_rpm: f64,
+    pub current_temp_c: f64,
+    pub violation: SafetyViolation,
+    pub reasoning_hash: ReasoningHash,
+}
+
+/// A value read from the exchange with the number of writes published so
+/// far, starting at 1 for the first write and 0 before any. A periodic
+/// reader that sees the sequence jump by more than one between reads missed
+/// the states in between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sequenced<T> {
+    pub sequence: u64,
+    pub value: T,
+}
+
+impl<T> Sequenced<T> {
+    /// Writes overwritten unread since a read that saw `previous`
+    pub fn skipped_since(&self, previous: u64) -> u64 {
+        self.sequence.saturating_sub(previous).saturating_sub(1)
+    }
+}
+
+/// The write sequence doubles as the slot index (`sequence % 3`), so the
+/// value and its sequence are published by one atomic store.
 struct TripleBuffer<T: Copy + Default> {
     slots: [UnsafeCell<T>; 3],
-    index: AtomicUsize,
+    sequence: AtomicU64,
 }
 
 unsafe impl<T: Copy + Default + Send> Send for TripleBuffer<T> {}
@@ -45,22 +98,156 @@ impl<T: Copy + Default> TripleBuffer<T> {
         let slots = std::array::from_fn(|_| UnsafeCell::new(T::default()));
         Self {
             slots,
-            index: AtomicUsize::new(0),
+            sequence: AtomicU64::new(0),
         }
     }
 
     fn write(&self, value: T) {
-        let current = self.index.load(Ordering::Relaxed);
-        let next = (current + 1) % 3;
+        let next = self.sequence.load(Ordering::Relaxed) + 1;
         unsafe {
-            *self.slots[next].get() = value;
+            *self.slots[(next % 3) as usize].get() = value;
+        }
+        self.sequence.store(next, Ordering::Release);
+    }
+
+    fn read(&self) -> Sequenced<T> {
+        let sequence = self.sequence.load(Ordering::Acquire);
+        let value = unsafe { *self.slots[(sequence % 3) as usize].get() };
+        Sequenced { sequence, value }
+    }
+}
+
+/// Iron Thread execution counters, mirrored every cycle so other threads can
+/// read them while the loop runs. Individual fields are consistent; a
+/// snapshot may mix values from adjacent cycles.
+#[derive(Default)]
+struct SharedStats {
+    cycles_executed: AtomicU64,
+    cycles_missed: AtomicU64,
+    max_jitter_us: AtomicU64,
+    safety_rejections: AtomicU64,
+    safety_violations: [AtomicU64; SafetyViolation::KINDS.len()],
+    agent_timeouts: AtomicU64,
+    last_recommendation_age_us: AtomicU64,
+    recommendation_age_counts: [AtomicU64; RECOMMENDATION_AGE_BUCKETS_US.len() + 1],
+    recommendation_age_sums_us: [AtomicU64; RECOMMENDATION_AGE_BUCKETS_US.len() + 1],
+    timing_violations: AtomicU64,
+    jitter_warn_cycles: AtomicU64,
+    jitter_crit_cycles: AtomicU64,
+    safety_state: AtomicU8,
+    // f64 high-water marks stored as bits
+    max_speed_rpm: AtomicU64,
+    max_temp_c: AtomicU64,
+    max_pressure_bar: AtomicU64,
+}
+
+impl SharedStats {
+    fn store(&self, stats: &ExecutionStats) {
+        self.cycles_executed
+            .store(stats.cycles_executed, Ordering::Relaxed);
+        self.cycles_missed
+            .store(stats.cycles_missed, Ordering::Relaxed);
+        self.max_jitter_us
+            .store(stats.max_jitter_us, Ordering::Relaxed);
+        self.safety_rejections
+            .store(stats.safety_rejections, Ordering::Relaxed);
+        for (shared, value) in self.safety_violations.iter().zip(stats.safety_violations) {
+            shared.store(value, Ordering::Relaxed);
+        }
+        self.agent_timeouts
+            .store(stats.agent_timeouts, Ordering::Relaxed);
+        self.last_recommendation_age_us
+            .store(stats.last_recommendation_age_us, Ordering::Relaxed);
+        let age = &stats.recommendation_age;
+        for (shared, value) in self.recommendation_age_counts.iter().zip(age.counts) {
+            shared.store(value, Ordering::Relaxed);
+        }
+        for (shared, value) in self.recommendation_age_sums_us.iter().zip(age.sums_us) {
+            shared.store(value, Ordering::Relaxed);
+        }
+        self.timing_violations
+            .store(stats.timing_violations, Ordering::Relaxed);
+        self.jitter_warn_cycles
+            .store(stats.jitter_warn_cycles, Ordering::Relaxed);
+        self.jitter_crit_cycles
+            .store(stats.jitter_crit_cycles, Ordering::Relaxed);
+        self.safety_state
+            .store(stats.safety_state.as_u8(), Ordering::Relaxed);
+        self.max_speed_rpm
+            .store(stats.max_speed_rpm.to_bits(), Ordering::Relaxed);
+        self.max_temp_c
+            .store(stats.max_temp_c.to_bits(), Ordering::Relaxed);
+        self.max_pressure_bar
+            .store(stats.max_pressure_bar.to_bits(), Ordering::Relaxed);
+    }
+
+    fn load(&self) -> ExecutionStats {
+        ExecutionStats {
+            cycles_executed: self.cycles_executed.load(Ordering::Relaxed),
+            cycles_missed: self.cycles_missed.load(Ordering::Relaxed),
+            max_jitter_us: self.max_jitter_us.load(Ordering::Relaxed),
+            safety_rejections: self.safety_rejections.load(Ordering::Relaxed),
+            safety_violations: self
+                .safety_violations
+                .each_ref()
+                .map(|count| count.load(Ordering::Relaxed)),
+            agent_timeouts: self.agent_timeouts.load(Ordering::Relaxed),
+            last_recommendation_age_us: self.last_recommendation_age_us.load(Ordering::Relaxed),
+            recommendation_age: RecommendationAgeHistogram {
+                counts: self
+                    .recommendation_age_counts
+                    .each_ref()
+                    .map(|count| count.load(Ordering::Relaxed)),
+                sums_us: self
+                    .recommendation_age_sums_us
+                    .each_ref()
+                    .map(|sum| sum.load(Ordering::Relaxed)),
+            },
+            safety_state: SafetyState::from_u8(self.safety_state.load(Ordering::Relaxed)),
+            timing_violations: self.timing_violations.load(Ordering::Relaxed),
+            jitter_warn_cycles: self.jitter_warn_cycles.load(Ordering::Relaxed),
+            jitter_crit_cycles: self.jitter_crit_cycles.load(Ordering::Relaxed),
+            max_speed_rpm: f64::from_bits(self.max_speed_rpm.load(Ordering::Relaxed)),
+            max_temp_c: f64::from_bits(self.max_temp_c.load(Ordering::Relaxed)),
+            max_pressure_bar: f64::from_bits(self.max_pressure_bar.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Bounded ring of the most recent recommendations, kept for diagnostics.
+/// Only touched by submitters and readers of the history, never by the
+/// Iron Thread.
+struct RecommendationHistory {
+    capacity: usize,
+    entries: Mutex<VecDeque<AgentRecommendation>>,
+}
+
+impl RecommendationHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
         }
-        self.index.store(next, Ordering::Release);
     }
 
-    fn read(&self) -> T {
-        let idx = self.index.load(Ordering::Acquire);
-        unsafe { *self.slots[idx].get() }
+    fn push(&self, rec: AgentRecommendation) {
+        let mut entries = match self.entries.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(rec);
+    }
+
+    fn recent(&self, n: usize) -> Vec<AgentRecommendation> {
+        let entries = match self.entries.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let skip = entries.len().saturating_sub(n);
+        entries.iter().skip(skip).copied().collect()
     }
 }
 
@@ -68,6 +255,14 @@ pub struct StateExchange {
     process_state: TripleBuffer<ProcessSnapshot>,
     agent_recommendation: TripleBuffer<AgentRecommendation>,
     max_recommendation_age_us: u64,
+    emergency_stop: AtomicBool,
+    session_reset: AtomicBool,
+    history: Option<RecommendationHistory>,
+    last_rejection: TripleBuffer<Option<RejectedRecommendation>>,
+    stats: SharedStats,
+    hal_healthy: AtomicBool,
+    safety_limits: TripleBuffer<Option<SafetyLimits>>,
+    safety_limits_pending: AtomicBool,
 }
 
 impl StateExchange {
@@ -76,6 +271,24 @@ impl StateExchange {
             process_state: TripleBuffer::new(),
             agent_recommendation: TripleBuffer::new(),
             max_recommendation_age_us: max_age_us,
+            emergency_stop: AtomicBool::new(false),
+            session_reset: AtomicBool::new(false),
+            history: None,
+            last_rejection: TripleBuffer::new(),
+            stats: SharedStats::default(),
+            hal_healthy: AtomicBool::new(false),
+            safety_limits: TripleBuffer::new(),
+            safety_limits_pending: AtomicBool::new(false),
+        }
+    }
+
+    /// Like `new`, but also retains the last `capacity` submitted
+    /// recommendations for `recent_recommendations`. A capacity of zero
+    /// disables the history.
+    pub fn with_history(max_age_us: u64, capacity: usize) -> Self {
+        Self {
+            history: (capacity > 0).then(|| RecommendationHistory::new(capacity)),
+            ..Self::new(max_age_us)
         }
     }
 
@@ -86,9 +299,18 @@ impl StateExchange {
 
     /// Called by Iron Thread to get latest recommendation
     pub fn get_recommendation(&self, current_time_us: u64) -> Option<AgentRecommendation> {
+        self.get_recommendation_sequenced(current_time_us)
+            .map(|rec| rec.value)
+    }
+
+    /// `get_recommendation` with the number of recommendations submitted so far
+    pub fn get_recommendation_sequenced(
+        &self,
+        current_time_us: u64,
+    ) -> Option<Sequenced<AgentRecommendation>> {
         let rec = self.agent_recommendation.read();
-        let age = current_time_us.saturating_sub(rec.timestamp_us);
-        if rec.timestamp_us == 0 || age > self.max_recommendation_age_us {
+        let age = current_time_us.saturating_sub(rec.value.timestamp_us);
+        if rec.value.timestamp_us == 0 || age > self.max_recommendation_age_us {
             None
         } else {
             Some(rec)
@@ -98,10 +320,183 @@ impl StateExchange {
     /// Called by Bridge Thread
     pub fn submit_recommendation(&self, rec: AgentRecommendation) {
         self.agent_recommendation.write(rec);
+        if let Some(history) = &self.history {
+            history.push(rec);
+        }
+    }
+
+    /// Up to `n` of the most recently submitted recommendations, oldest
+    /// first. Empty when the exchange was built without history.
+    /// Diagnostics only; takes a lock, so never call from the Iron Thread.
+    pub fn recent_recommendations(&self, n: usize) -> Vec<AgentRecommendation> {
+        match &self.history {
+            Some(history) => history.recent(n),
+            None => Vec::new(),
+        }
     }
 
     /// Called by Bridge Thread
     pub fn read_state(&self) -> ProcessSnapshot {
+        self.process_state.read().value
+    }
+
+    /// `read_state` with the number of states published so far
+    pub fn read_state_sequenced(&self) -> Sequenced<ProcessSnapshot> {
         self.process_state.read()
     }
+
+    /// Called by Iron Thread whenever the supervisor rejects a recommendation
+    pub fn publish_rejection(&self, rejection: RejectedRecommendation) {
+        self.last_rejection.write(Some(rejection));
+    }
+
+    /// Most recent rejected recommendation, `None` until the first rejection
+    pub fn last_rejection(&self) -> Option<RejectedRecommendation> {
+        self.last_rejection.read().value
+    }
+
+    /// Called by Iron Thread at the end of every cycle (lock-free)
+    pub fn publish_stats(&self, stats: &ExecutionStats) {
+        self.stats.store(stats);
+    }
+
+    /// Called by Iron Thread every cycle with `MachineIO::is_healthy`
+    pub fn publish_hal_health(&self, healthy: bool) {
+        self.hal_healthy.store(healthy, Ordering::Release);
+    }
+
+    /// HAL health as of the last cycle; false before the first cycle
+    pub fn hal_healthy(&self) -> bool {
+        self.hal_healthy.load(Ordering::Acquire)
+    }
+
+    /// Latest Iron Thread execution stats, readable while the loop runs
+    pub fn execution_stats(&self) -> ExecutionStats {
+        self.stats.load()
+    }
+
+    /// Called by operator interfaces (OPC UA, bridge) to command a stop.
+    /// The Iron Thread trips the safety supervisor on its next cycle.
+    pub fn request_emergency_stop(&self) {
+        self.emergency_stop.store(true, Ordering::Release);
+    }
+
+    /// Called by Iron Thread: consume a pending emergency stop request
+    pub fn take_emergency_stop(&self) -> bool {
+        self.emergency_stop.swap(false, Ordering::AcqRel)
+    }
+
+    /// Ask the Iron Thread to clear its session high-water marks (jitter,
+    /// speed, temperature, pressure) on its next cycle. Counters are kept.
+    pub fn request_session_reset(&self) {
+        self.session_reset.store(true, Ordering::Release);
+    }
+
+    /// Called by Iron Thread: consume a pending session reset request
+    pub fn take_session_reset(&self) -> bool {
+        self.session_reset.swap(false, Ordering::AcqRel)
+    }
+
+    /// Called by operator interfaces with limits already checked against
+    /// the configured ones. The Iron Thread adopts them on its next cycle.
+    pub fn request_safety_limits(&self, limits: SafetyLimits) {
+        self.safety_limits.write(Some(limits));
+        self.safety_limits_pending.store(true, Ordering::Release);
+    }
+
+    /// Limits last passed to `request_safety_limits`, `None` if never
+    pub fn requested_safety_limits(&self) -> Option<SafetyLimits> {
+        self.safety_limits.read().value
+    }
+
+    /// Called by Iron Thread: consume a pending safety limits change
+    pub fn take_safety_limits(&self) -> Option<SafetyLimits> {
+        if self.safety_limits_pending.swap(false, Ordering::AcqRel) {
+            self.safety_limits.read().value
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rec(timestamp_us: u64, rpm: f64) -> AgentRecommendation {
+        AgentRecommendation {
+            timestamp_us,
+            target_speed_rpm: Some(rpm),
+            ramp_rate_rpm_per_s: None,
+            confidence: 0.9,
+            reasoning_hash: [0u8; 32].into(),
+        }
+    }
+
+    #[test]
+    fn test_history_keeps_last_n_in_order() {
+        let exchange = StateExchange::with_history(1_000_000, 3);
+        for i in 1..=5u64 {
+            exchange.submit_recommendation(rec(i, i as f64 * 100.0));
+        }
+
+        let recent = exchange.recent_recommendations(10);
+        let stamps: Vec<u64> = recent.iter().map(|r| r.timestamp_us).collect();
+        assert_eq!(stamps, vec![3, 4, 5]);
+
+        let last_two = exchange.recent_recommendations(2);
+        assert_eq!(last_two.len(), 2);
+        assert_eq!(last_two[1].target_speed_rpm, Some(500.0));
+
+        // Hot-path read still sees only the latest value.
+        let latest = exchange.get_recommendation(5).expect("fresh");
+        assert_eq!(latest.timestamp_us, 5);
+    }
+
+    #[test]
+    fn test_sequence_reveals_states_overwritten_unread() {
+        let exchange = StateExchange::new(1_000_000);
+        assert_eq!(exchange.read_state_sequenced().sequence, 0);
+
+        let publish = |cycle_count| {
+            exchange.publish_state(ProcessSnapshot {
+                cycle_count,
+                ..ProcessSnapshot::default()
+            })
+        };
+        publish(1);
+        let first = exchange.read_state_sequenced();
+        assert_eq!(first.sequence, 1);
+        assert_eq!(first.skipped_since(0), 0);
+
+        // The writer outpaces the reader: three publishes between reads.
+        (2..=4).for_each(publish);
+        let second = exchange.read_state_sequenced();
+        assert_eq!(second.value.cycle_count, 4);
+        assert!(second.sequence - first.sequence > 1);
+        assert_eq!(second.skipped_since(first.sequence), 2);
+        assert_eq!(
+            exchange
+                .read_state_sequenced()
+                .skipped_since(second.sequence),
+            0
+        );
+
+        exchange.submit_recommendation(rec(10, 100.0));
+        exchange.submit_recommendation(rec(11, 200.0));
+        let latest = exchange.get_recommendation_sequenced(12).unwrap();
+        assert_eq!(latest.sequence, 2);
+        assert_eq!(latest.value.target_speed_rpm, Some(200.0));
+    }
+
+    #[test]
+    fn test_history_disabled_by_default() {
+        let exchange = StateExchange::new(1_000_000);
+        exchange.submit_recommendation(rec(1, 100.0));
+        assert!(exchange.recent_recommendations(5).is_empty());
+
+        let zero = StateExchange::with_history(1_000_000, 0);
+        zero.submit_recommendation(rec(1, 100.0));
+        assert!(zero.recent_recommendations(5).is_empty());
+    }
 }
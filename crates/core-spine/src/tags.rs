@@ -61,3 +61,20 @@ pub const AGENT_CONFIDENCE: Tag = Tag {
     opcua_node: "AgentConfidence",
     rerun_path: "motor/agent/confidence",
 };
+
+/// Every published tag, in OPC UA browse order.
+pub const ALL: [Tag; 8] = [
+    MOTOR_SPEED_RPM,
+    MOTOR_TEMP_C,
+    PRESSURE_BAR,
+    CYCLE_JITTER_US,
+    TIMESTAMP_US,
+    SAFETY_STATE,
+    AGENT_TARGET_RPM,
+    AGENT_CONFIDENCE,
+];
+
+/// Look up a tag by its `key`.
+pub fn by_key(key: &str) -> Option<Tag> {
+    ALL.iter().copied().find(|tag| tag.key == key)
+}
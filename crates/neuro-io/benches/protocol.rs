@@ -0,0 +1,106 @@
+//! Wire protocol hot path: parsing JSON lines from agents and, with the
+//! `proto` feature, protobuf encode/decode of recommendations and state.
+//!
+//! Run with `cargo bench -p neuro-io --bench protocol`, adding
+//! `--features proto` for the protobuf benches.
+
+use core_spine::ProcessSnapshot;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use neuro_io::protocol::{IncomingMessage, StateMsg};
+use std::time::Duration;
+
+const HELLO: &str = r#"{"type":"hello","protocol_version":{"major":1,"minor":0},"capabilities":["recommendation.v1","auth.hmac-sha256"],"client_id":"bench-client"}"#;
+
+const RECOMMENDATION: &str = r#"{"type":"recommendation","protocol_version":{"major":1,"minor":0},"sequence":42,"issued_at_unix_us":1700000000000000,"ttl_ms":1000,"target_speed_rpm":1500.0,"confidence":0.92,"reasoning_hash":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa","client_unix_us":1700000000000000}"#;
+
+fn snapshot() -> ProcessSnapshot {
+    ProcessSnapshot {
+        timestamp_us: 1_000_000,
+        cycle_count: 1_000,
+        motor_speed_rpm: 1500.0,
+        motor_temp_c: 45.0,
+        pressure_bar: 2.0,
+        cycle_jitter_us: 12,
+        ..ProcessSnapshot::default()
+    }
+}
+
+fn bench_json(c: &mut Criterion) {
+    let mut group = c.benchmark_group("json");
+    group.bench_function("parse_hello", |b| {
+        b.iter(|| IncomingMessage::parse(black_box(HELLO)))
+    });
+    group.bench_function("parse_recommendation", |b| {
+        b.iter(|| IncomingMessage::parse(black_box(RECOMMENDATION)))
+    });
+    let snapshot = snapshot();
+    group.bench_function("encode_state", |b| {
+        b.iter(|| {
+            let msg = StateMsg::from_snapshot(black_box(&snapshot), 7, 1_700_000_000_000_000);
+            serde_json::to_string(&msg)
+        })
+    });
+    group.finish();
+}
+
+#[cfg(feature = "proto")]
+fn bench_proto(c: &mut Criterion) {
+    use neuro_io::protocol_proto::proto;
+    use prost::Message;
+
+    let IncomingMessage::Recommendation(rec) = IncomingMessage::parse(RECOMMENDATION).unwrap()
+    else {
+        unreachable!("RECOMMENDATION is a recommendation");
+    };
+    let recommendation = proto::WireMessage {
+        payload: Some(proto::wire_message::Payload::Recommendation(rec.into())),
+    };
+    let encoded = recommendation.encode_to_vec();
+
+    let snapshot = snapshot();
+    let state = proto::WireMessage {
+        payload: Some(proto::wire_message::Payload::State(proto::State {
+            protocol_version: Some(proto::ProtocolVersion { major: 1, minor: 0 }),
+            sequence: 7,
+            timestamp_us: snapshot.timestamp_us,
+            cycle_count: snapshot.cycle_count,
+            safety_state: snapshot.safety_state.as_str().to_string(),
+            motor_speed_rpm: snapshot.motor_speed_rpm,
+            motor_temp_c: snapshot.motor_temp_c,
+            pressure_bar: snapshot.pressure_bar,
+            cycle_jitter_us: snapshot.cycle_jitter_us,
+            ..proto::State::default()
+        })),
+    };
+
+    let mut group = c.benchmark_group("proto");
+    group.bench_function("encode_recommendation", |b| {
+        b.iter(|| black_box(&recommendation).encode_to_vec())
+    });
+    group.bench_function("decode_recommendation", |b| {
+        b.iter(|| {
+            let wire = proto::WireMessage::decode(black_box(encoded.as_slice())).unwrap();
+            IncomingMessage::try_from(wire)
+        })
+    });
+    group.bench_function("encode_state", |b| {
+        b.iter(|| black_box(&state).encode_to_vec())
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "proto"))]
+fn bench_proto(_: &mut Criterion) {}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = bench_json, bench_proto
+}
+criterion_main!(benches);
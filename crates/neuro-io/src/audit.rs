@@ -0,0 +1,877 @@
+//! Audit logging for safety-critical events.
+//!
+//! This module provides persistent logging of all safety-relevant events
+//! including recommendations, rejections, and system state changes.
+//! Files can optionally be rotated by size; the hash chain continues across
+//! rotated files. In background mode, hashing and disk I/O happen on a
+//! dedicated writer thread so callers never block on the filesystem.
+
+use core_spine::{RejectedRecommendation, SafetyTransition, SafetyViolation, TransitionCause};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use thiserror::Error;
+use tracing::warn;
+
+/// Types of events that are logged in the audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    /// AI recommendation received from agent
+    RecommendationReceived,
+    /// Recommendation was applied to actuator
+    RecommendationApplied,
+    /// Recommendation was replaced before the control loop applied or
+    /// rejected it
+    RecommendationSuperseded,
+    /// Recommendation was rejected by safety firewall
+    SafetyRejection,
+    /// Agent client connected to bridge
+    ClientConnected,
+    /// Agent client disconnected
+    ClientDisconnected,
+    /// Emergency stop triggered
+    EmergencyStop,
+    /// Configuration change applied
+    ConfigChange,
+    /// System startup
+    SystemStart,
+    /// System shutdown
+    SystemShutdown,
+    /// Watchdog timeout occurred
+    WatchdogTimeout,
+    /// Configured HAL failed to start; running on a fallback backend
+    HalFallback,
+    /// HAL stayed unhealthy past the fault timeout at runtime
+    HalFailover,
+    /// Power-on self-test result
+    SelfTest,
+    /// Safety supervisor changed state
+    SafetyTransition,
+}
+
+/// A single audit log entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Monotonic timestamp in microseconds
+    pub timestamp_us: u64,
+    /// Wall-clock Unix timestamp in microseconds
+    pub unix_us: u64,
+    /// Type of event being logged
+    pub event_type: AuditEventType,
+    /// Additional event-specific details
+    pub details: serde_json::Value,
+}
+
+/// A record with hash chaining for tamper evidence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub entry: AuditEntry,
+    pub prev_hash: String,
+    pub entry_hash: String,
+}
+
+struct AuditState {
+    writer: BufWriter<File>,
+    last_hash: String,
+    bytes_written: u64,
+    rotation: Option<Rotation>,
+}
+
+impl AuditState {
+    /// Hash-chain and buffer one entry, rotating the file if it grew too large
+    fn append(&mut self, entry: AuditEntry) -> std::io::Result<()> {
+        let prev_hash = self.last_hash.clone();
+        let entry_hash = hash_entry(&entry, &prev_hash);
+        let record = AuditRecord {
+            entry,
+            prev_hash,
+            entry_hash: entry_hash.clone(),
+        };
+
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.last_hash = entry_hash;
+        self.bytes_written += line.len() as u64;
+
+        if let Some(rotation) = &self.rotation {
+            if self.bytes_written >= rotation.max_bytes {
+                self.writer.flush()?;
+                rotation.shift_files()?;
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&rotation.path)?;
+                // last_hash is kept, so the first record of the new file
+                // chains to the last record of the rotated one.
+                self.writer = BufWriter::with_capacity(8192, file);
+                self.bytes_written = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Result of a successful chain verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Number of records checked
+    pub records: usize,
+    /// `prev_hash` of the first record: `"0"` for a fresh log, otherwise the
+    /// last hash of the rotated file that precedes this one
+    pub first_prev_hash: String,
+    /// `entry_hash` of the last record
+    pub last_hash: String,
+}
+
+/// Errors reported by [`AuditLogger::verify`]; line numbers are 1-based
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("Failed to read audit log: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Line {line}: malformed record: {message}")]
+    Malformed { line: usize, message: String },
+
+    #[error("Line {line}: entry hash does not match its contents")]
+    HashMismatch { line: usize },
+
+    #[error("Line {line}: prev_hash does not match the previous entry")]
+    BrokenLink { line: usize },
+}
+
+/// Size-based rotation settings
+struct Rotation {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl Rotation {
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        rotated_path(&self.path, index)
+    }
+
+    /// Shift `audit.jsonl.N` -> `.N+1` (dropping the oldest) and move the
+    /// active file to `.1`.
+    fn shift_files(&self) -> std::io::Result<()> {
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)?;
+        }
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated_path(1))
+    }
+}
+
+/// `path` with `.index` appended, as rotation names its files
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.to_path_buf().into_os_string();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+/// Handle to the background writer thread; joins it when dropped
+struct BackgroundWriter {
+    sender: Mutex<Option<SyncSender<AuditEntry>>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BackgroundWriter {
+    fn spawn(mut state: AuditState, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<AuditEntry>(capacity);
+        let handle = thread::Builder::new()
+            .name("audit-writer".to_string())
+            .spawn(move || {
+                while let Ok(first) = receiver.recv() {
+                    // Drain whatever is queued, then flush once per batch
+                    let mut next = Some(first);
+                    while let Some(entry) = next {
+                        if let Err(e) = state.append(entry) {
+                            warn!(error = %e, "Audit write failed");
+                        }
+                        next = receiver.try_recv().ok();
+                    }
+                    if let Err(e) = state.flush() {
+                        warn!(error = %e, "Audit flush failed");
+                    }
+                }
+            })
+            .expect("Failed to spawn audit writer thread");
+        Self {
+            sender: Mutex::new(Some(sender)),
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+
+    fn flush_and_join(&self) {
+        // Dropping the sender ends the writer loop once the queue is drained
+        self.sender.lock().unwrap().take();
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for BackgroundWriter {
+    fn drop(&mut self) {
+        self.flush_and_join();
+    }
+}
+
+enum AuditSink {
+    /// Hash, write, and flush on the calling thread
+    Sync(Mutex<AuditState>),
+    /// Queue entries for the writer thread
+    Background(BackgroundWriter),
+}
+
+/// Thread-safe audit logger that writes to a JSONL file
+pub struct AuditLogger {
+    sink: AuditSink,
+    dropped: AtomicU64,
+}
+
+impl AuditLogger {
+    /// Create a new audit logger writing to the specified path.
+    /// The file is opened in append mode to preserve existing logs.
+    pub fn new(path: &Path) -> std::io::Result<Self> {
+        Self::open(path, None)
+    }
+
+    /// Create an audit logger that rotates the file once it exceeds `max_bytes`,
+    /// keeping up to `max_files` rotated files (`audit.jsonl.1` is the newest).
+    /// At least one rotated file is always kept, so rotation never deletes
+    /// the records just written.
+    pub fn with_rotation(path: &Path, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        Self::open(
+            path,
+            Some(Rotation {
+                path: path.to_path_buf(),
+                max_bytes,
+                max_files: max_files.max(1),
+            }),
+        )
+    }
+
+    fn open(path: &Path, rotation: Option<Rotation>) -> std::io::Result<Self> {
+        // Create parent directories if they don't exist
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // An empty log right after a rotation continues from the newest
+        // rotated file, so the chain never restarts at "0" mid-history.
+        let last_hash = match last_entry_hash(path)? {
+            Some(hash) => hash,
+            None => last_entry_hash(&rotated_path(path, 1))?.unwrap_or_else(|| String::from("0")),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let bytes_written = file.metadata()?.len();
+
+        Ok(Self {
+            sink: AuditSink::Sync(Mutex::new(AuditState {
+                writer: BufWriter::with_capacity(8192, file),
+                last_hash,
+                bytes_written,
+                rotation,
+            })),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Move writing to a background thread fed by a queue of `capacity` entries.
+    /// When the queue is full, new entries are dropped and counted rather than
+    /// blocking the caller; see [`AuditLogger::dropped_count`].
+    pub fn into_background(self, capacity: usize) -> Self {
+        match self.sink {
+            AuditSink::Sync(state) => Self {
+                sink: AuditSink::Background(BackgroundWriter::spawn(
+                    state.into_inner().unwrap(),
+                    capacity,
+                )),
+                dropped: self.dropped,
+            },
+            AuditSink::Background(_) => self,
+        }
+    }
+
+    /// Log an audit entry. This is thread-safe and can be called from any thread.
+    pub fn log(&self, entry: AuditEntry) -> std::io::Result<()> {
+        match &self.sink {
+            AuditSink::Sync(state) => {
+                let mut state = state.lock().unwrap();
+                state.append(entry)?;
+                state.flush()
+            }
+            AuditSink::Background(writer) => {
+                let sent = writer
+                    .sender
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|sender| sender.try_send(entry).is_ok());
+                if !sent && self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                    warn!("Audit queue full or closed, dropping entries");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Number of entries dropped because the background queue was full or closed
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Write out everything queued and stop the background writer.
+    /// Entries logged afterwards are dropped. No-op in synchronous mode.
+    pub fn flush_and_join(&self) {
+        if let AuditSink::Background(writer) = &self.sink {
+            writer.flush_and_join();
+        }
+    }
+
+    /// Verify the hash chain of an audit log file.
+    ///
+    /// Every record's `entry_hash` is recomputed and each `prev_hash` must match
+    /// the previous record's `entry_hash`. The first record's `prev_hash` is
+    /// taken as the anchor, so rotated files can be verified on their own.
+    pub fn verify(path: &Path) -> Result<VerifyReport, VerifyError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut report: Option<VerifyReport> = None;
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: AuditRecord =
+                serde_json::from_str(&line).map_err(|e| VerifyError::Malformed {
+                    line: line_no,
+                    message: e.to_string(),
+                })?;
+            if hash_entry(&record.entry, &record.prev_hash) != record.entry_hash {
+                return Err(VerifyError::HashMismatch { line: line_no });
+            }
+            match report.as_mut() {
+                Some(report) => {
+                    if record.prev_hash != report.last_hash {
+                        return Err(VerifyError::BrokenLink { line: line_no });
+                    }
+                    report.records += 1;
+                    report.last_hash = record.entry_hash;
+                }
+                None => {
+                    report = Some(VerifyReport {
+                        records: 1,
+                        first_prev_hash: record.prev_hash,
+                        last_hash: record.entry_hash,
+                    });
+                }
+            }
+        }
+
+        Ok(report.unwrap_or_else(|| VerifyReport {
+            records: 0,
+            first_prev_hash: String::from("0"),
+            last_hash: String::from("0"),
+        }))
+    }
+
+    /// Read records in timeline order for offline analysis. Unlike
+    /// [`AuditLogger::verify`] this is lenient: malformed lines and records
+    /// whose `timestamp_us` goes backwards are skipped with a warning.
+    pub fn read_timeline(path: &Path) -> std::io::Result<Vec<AuditRecord>> {
+        timeline_from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Convenience method to log with just event type and details
+    pub fn log_event(
+        &self,
+        timestamp_us: u64,
+        unix_us: u64,
+        event_type: AuditEventType,
+        details: serde_json::Value,
+    ) -> std::io::Result<()> {
+        self.log(AuditEntry {
+            timestamp_us,
+            unix_us,
+            event_type,
+            details,
+        })
+    }
+}
+
+fn timeline_from_reader(reader: impl BufRead) -> std::io::Result<Vec<AuditRecord>> {
+    let mut records: Vec<AuditRecord> = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditRecord = match serde_json::from_str(&line) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!(line = index + 1, error = %e, "Skipping malformed audit record");
+                continue;
+            }
+        };
+        if let Some(last) = records.last() {
+            if record.entry.timestamp_us < last.entry.timestamp_us {
+                warn!(
+                    line = index + 1,
+                    timestamp_us = record.entry.timestamp_us,
+                    previous_us = last.entry.timestamp_us,
+                    "Skipping out-of-order audit record"
+                );
+                continue;
+            }
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Hash of the last record in an existing log, so a restarted logger
+/// continues the chain instead of starting a new one.
+fn last_entry_hash(path: &Path) -> std::io::Result<Option<String>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut last = None;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if let Ok(record) = serde_json::from_str::<AuditRecord>(&line) {
+            last = Some(record.entry_hash);
+        }
+    }
+    Ok(last)
+}
+
+pub fn hash_entry(entry: &AuditEntry, prev_hash: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    let entry_bytes = serde_json::to_vec(entry).unwrap_or_default();
+    hasher.update(&entry_bytes);
+    to_hex(&hasher.finalize())
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    to_hex(&hasher.finalize())
+}
+
+pub fn hash_str(value: &str) -> String {
+    hash_bytes(value.as_bytes())
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        use std::fmt::Write;
+        let _ = write!(&mut out, "{:02x}", byte);
+    }
+    out
+}
+
+/// Details for a safety rejection event
+#[derive(Debug, Clone, Serialize)]
+pub struct SafetyRejectionDetails {
+    /// Matches the `RecommendationReceived` entry of the rejected recommendation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    pub requested_speed: f64,
+    pub current_speed: f64,
+    pub current_temp: f64,
+    pub violation_type: String,
+    pub limit_value: f64,
+    pub reasoning_hash: String,
+}
+
+impl From<&RejectedRecommendation> for SafetyRejectionDetails {
+    fn from(rejection: &RejectedRecommendation) -> Self {
+        let (violation_type, limit_value) = match rejection.violation {
+            SafetyViolation::NonFiniteSetpoint { .. } => ("non_finite_setpoint", f64::NAN),
+            SafetyViolation::NonFiniteSensor { .. } => ("non_finite_sensor", f64::NAN),
+            SafetyViolation::ExceedsMaxSpeed { limit, .. } => ("exceeds_max_speed", limit),
+            SafetyViolation::BelowMinSpeed { limit, .. } => ("below_min_speed", limit),
+            SafetyViolation::RateOfChangeTooHigh { limit, .. } => ("rate_of_change", limit),
+            SafetyViolation::TemperatureInterlock { limit, .. } => ("temperature_interlock", limit),
+            SafetyViolation::BelowMinTemperature { limit, .. } => ("below_min_temperature", limit),
+            SafetyViolation::ExceedsMaxPressure { limit, .. } => ("exceeds_max_pressure", limit),
+        };
+        Self {
+            correlation_id: None,
+            requested_speed: rejection.requested_rpm.unwrap_or(f64::NAN),
+            current_speed: rejection.current_speed_rpm,
+            current_temp: rejection.current_temp_c,
+            violation_type: violation_type.to_string(),
+            limit_value,
+            reasoning_hash: to_hex(rejection.reasoning_hash.as_bytes()),
+        }
+    }
+}
+
+/// Details for a safety state transition event
+#[derive(Debug, Clone, Serialize)]
+pub struct SafetyTransitionDetails {
+    pub from: String,
+    pub to: String,
+    pub cause: String,
+    /// Violation kind when `cause` is `violation`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub violation_type: Option<String>,
+    /// Cycle jitter when `cause` is `timing_jitter`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jitter_us: Option<u64>,
+}
+
+impl From<&SafetyTransition> for SafetyTransitionDetails {
+    fn from(transition: &SafetyTransition) -> Self {
+        let (violation_type, jitter_us) = match transition.cause {
+            TransitionCause::Violation(violation) => (Some(violation.kind().to_string()), None),
+            TransitionCause::TimingJitter { jitter_us } => (None, Some(jitter_us)),
+            _ => (None, None),
+        };
+        Self {
+            from: transition.from.as_str().to_string(),
+            to: transition.to.as_str().to_string(),
+            cause: transition.cause.as_str().to_string(),
+            violation_type,
+            jitter_us,
+        }
+    }
+}
+
+/// Details for a recommendation received event
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationReceivedDetails {
+    /// `<client>/<sequence>`, repeated on the entry recording the outcome
+    pub correlation_id: String,
+    pub target_speed: Option<f64>,
+    pub confidence: f32,
+    pub reasoning_hash: String,
+    pub client_addr: Option<String>,
+    pub client_id: Option<String>,
+}
+
+/// Details for a recommendation applied event
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationAppliedDetails {
+    pub correlation_id: String,
+    pub target_speed: f64,
+    pub confidence: f32,
+    /// Motor speed when the recommendation was submitted
+    pub previous_speed: f64,
+    pub reasoning_hash: String,
+}
+
+/// Details for a recommendation superseded event
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationSupersededDetails {
+    pub correlation_id: String,
+    /// Correlation id of the recommendation that replaced it
+    pub superseded_by: String,
+    pub target_speed: f64,
+    pub reasoning_hash: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_audit_logger_writes_jsonl() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        let logger = AuditLogger::new(&path).unwrap();
+
+        logger
+            .log_event(
+                1000,
+                1704067200000000,
+                AuditEventType::SystemStart,
+                serde_json::json!({"version": "0.1.0"}),
+            )
+            .unwrap();
+
+        logger
+            .log_event(
+                2000,
+                1704067201000000,
+                AuditEventType::RecommendationReceived,
+                serde_json::json!({"target_speed": 500.0, "confidence": 0.9}),
+            )
+            .unwrap();
+
+        // Read back and verify
+        let mut file = File::open(&path).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let first: AuditRecord = serde_json::from_str(lines[0]).unwrap();
+        let second: AuditRecord = serde_json::from_str(lines[1]).unwrap();
+
+        assert_eq!(first.entry.timestamp_us, 1000);
+        assert_eq!(second.entry.timestamp_us, 2000);
+        assert_eq!(second.prev_hash, first.entry_hash);
+    }
+
+    #[test]
+    fn test_rotation_preserves_chain() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        let logger = AuditLogger::with_rotation(&path, 512, 3).unwrap();
+        let rotated_path = dir.path().join("audit.jsonl.1");
+        let mut i = 0u64;
+        // Keep writing until a rotation happened and the new file has records
+        while !rotated_path.exists() || std::fs::metadata(&path).unwrap().len() == 0 {
+            assert!(i < 100, "rotation never triggered");
+            logger
+                .log_event(
+                    i,
+                    1704067200000000 + i,
+                    AuditEventType::RecommendationReceived,
+                    serde_json::json!({"target_speed": 500.0, "seq": i}),
+                )
+                .unwrap();
+            i += 1;
+        }
+
+        let read_records = |p: &Path| -> Vec<AuditRecord> {
+            std::fs::read_to_string(p)
+                .unwrap()
+                .lines()
+                .map(|l| serde_json::from_str(l).unwrap())
+                .collect()
+        };
+
+        let rotated = read_records(&rotated_path);
+        let current = read_records(&path);
+        assert!(!rotated.is_empty());
+
+        // The chain continues from the rotated file into the active one
+        assert_eq!(current[0].prev_hash, rotated.last().unwrap().entry_hash);
+        for pair in rotated
+            .iter()
+            .chain(current.iter())
+            .collect::<Vec<_>>()
+            .windows(2)
+        {
+            assert_eq!(pair[1].prev_hash, pair[0].entry_hash);
+            assert_eq!(
+                hash_entry(&pair[1].entry, &pair[1].prev_hash),
+                pair[1].entry_hash
+            );
+        }
+    }
+
+    #[test]
+    fn test_rotation_without_kept_files_still_keeps_one() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        let logger = AuditLogger::with_rotation(&path, 256, 0).unwrap();
+        for i in 0..20u64 {
+            logger
+                .log_event(
+                    i,
+                    1704067200000000 + i,
+                    AuditEventType::RecommendationReceived,
+                    serde_json::json!({"target_speed": 500.0, "seq": i}),
+                )
+                .unwrap();
+        }
+
+        // The last rotation moved records to `.1` instead of deleting them.
+        let rotated = dir.path().join("audit.jsonl.1");
+        assert!(std::fs::metadata(&rotated).unwrap().len() > 0);
+        assert!(!dir.path().join("audit.jsonl.2").exists());
+        let last = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .chain(std::fs::read_to_string(&rotated).unwrap().lines())
+            .any(|line| line.contains("\"seq\":19"));
+        assert!(last, "the newest record was lost");
+    }
+
+    #[test]
+    fn test_reopen_after_rotation_continues_chain() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        write_entries(&path, 3);
+        let rotated_path = dir.path().join("audit.jsonl.1");
+        // A restart right after rotation finds an empty active file.
+        std::fs::rename(&path, &rotated_path).unwrap();
+        std::fs::File::create(&path).unwrap();
+
+        write_entries(&path, 1);
+        let last_rotated: AuditRecord = serde_json::from_str(
+            std::fs::read_to_string(&rotated_path)
+                .unwrap()
+                .lines()
+                .last()
+                .unwrap(),
+        )
+        .unwrap();
+        let first: AuditRecord = serde_json::from_str(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .next()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(first.prev_hash, last_rotated.entry_hash);
+    }
+
+    fn write_entries(path: &Path, count: u64) {
+        let logger = AuditLogger::new(path).unwrap();
+        for i in 0..count {
+            logger
+                .log_event(
+                    i,
+                    1704067200000000 + i,
+                    AuditEventType::RecommendationReceived,
+                    serde_json::json!({"target_speed": 500.0 + i as f64}),
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_verify_valid_chain() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        write_entries(&path, 3);
+        // A restarted logger continues the existing chain
+        write_entries(&path, 2);
+
+        let report = AuditLogger::verify(&path).unwrap();
+        assert_eq!(report.records, 5);
+        assert_eq!(report.first_prev_hash, "0");
+    }
+
+    #[test]
+    fn test_verify_detects_mutated_entry() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        write_entries(&path, 3);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let tampered = contents.replacen("501.0", "9001.0", 1);
+        assert_ne!(contents, tampered);
+        std::fs::write(&path, tampered).unwrap();
+
+        assert!(matches!(
+            AuditLogger::verify(&path),
+            Err(VerifyError::HashMismatch { line: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_verify_detects_deleted_line() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        write_entries(&path, 3);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+
+        assert!(matches!(
+            AuditLogger::verify(&path),
+            Err(VerifyError::BrokenLink { line: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_background_writer_flushes_on_join() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        let logger = AuditLogger::new(&path).unwrap().into_background(64);
+        for i in 0..50u64 {
+            logger
+                .log_event(
+                    i,
+                    1704067200000000 + i,
+                    AuditEventType::RecommendationReceived,
+                    serde_json::json!({"seq": i}),
+                )
+                .unwrap();
+        }
+        logger.flush_and_join();
+
+        let report = AuditLogger::verify(&path).unwrap();
+        assert_eq!(report.records as u64 + logger.dropped_count(), 50);
+        assert_eq!(logger.dropped_count(), 0);
+
+        // Writer is gone: further entries are counted, not written
+        logger
+            .log_event(99, 0, AuditEventType::SystemShutdown, serde_json::json!({}))
+            .unwrap();
+        assert_eq!(logger.dropped_count(), 1);
+        assert_eq!(AuditLogger::verify(&path).unwrap().records, 50);
+    }
+
+    #[test]
+    fn test_timeline_skips_malformed_and_out_of_order_lines() {
+        let record = |timestamp_us: u64| {
+            let entry = AuditEntry {
+                timestamp_us,
+                unix_us: timestamp_us,
+                event_type: AuditEventType::RecommendationReceived,
+                details: serde_json::json!({ "target_speed": 100.0 }),
+            };
+            serde_json::to_string(&AuditRecord {
+                entry_hash: hash_entry(&entry, "0"),
+                entry,
+                prev_hash: "0".to_string(),
+            })
+            .unwrap()
+        };
+        let log = [
+            record(10),
+            "{not json".to_string(),
+            record(30),
+            record(20),
+            String::new(),
+            record(30),
+            record(40),
+        ]
+        .join("\n");
+
+        let timeline = timeline_from_reader(log.as_bytes()).unwrap();
+        let times: Vec<u64> = timeline.iter().map(|r| r.entry.timestamp_us).collect();
+        assert_eq!(times, vec![10, 30, 30, 40]);
+    }
+}
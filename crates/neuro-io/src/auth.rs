@@ -1,17 +1,33 @@
 //! Authentication and authorization for the bridge.
 //!
-//! This module provides HMAC-based token validation for agent recommendations.
+//! This module provides token validation for agent recommendations, signed
+//! either with a shared HMAC-SHA256 secret or with the cortex's Ed25519 private
+//! key (so agents holding only the public key cannot forge tokens).
+//! Tokens are standard JWTs (`header.payload.signature`, `alg: HS256|EdDSA`);
+//! the legacy two-part `payload.signature` format can be enabled for older agents.
 
+use core_spine::{Clock, TimeBase};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
 use std::sync::Mutex;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use thiserror::Error;
+use tracing::{info, warn};
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo; the raw key follows it.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
 /// Errors that can occur during authentication
 #[derive(Debug, Error)]
 pub enum AuthError {
@@ -36,12 +52,21 @@ pub enum AuthError {
     #[error("Token signature verification failed")]
     InvalidSignature,
 
+    #[error("Unsupported token algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("Invalid verification key: {0}")]
+    InvalidKey(String),
+
     #[error("Token replay detected")]
     ReplayDetected,
 
     #[error("Token nonce missing")]
     MissingNonce,
 
+    #[error("Replay window is full of unexpired tokens")]
+    ReplayWindowFull,
+
     #[error("Token decode error: {0}")]
     DecodeError(String),
 
@@ -49,11 +74,35 @@ pub enum AuthError {
     InvalidClaims(String),
 }
 
+/// Signature algorithm used for bridge tokens
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AuthAlgorithm {
+    /// Shared-secret HMAC-SHA256 (`HS256`)
+    #[default]
+    HmacSha256,
+    /// Asymmetric Ed25519 (`EdDSA`); the spine only holds the public key
+    Ed25519,
+}
+
+impl AuthAlgorithm {
+    /// JOSE `alg` header value for this algorithm
+    pub const fn jose_alg(&self) -> &'static str {
+        match self {
+            AuthAlgorithm::HmacSha256 => "HS256",
+            AuthAlgorithm::Ed25519 => "EdDSA",
+        }
+    }
+}
+
 /// Configuration for token validation
 #[derive(Clone, Debug)]
 pub struct AuthConfig {
+    /// Signature algorithm expected on tokens
+    pub algorithm: AuthAlgorithm,
     /// Shared secret for HMAC signing
     pub secret: Vec<u8>,
+    /// Raw 32-byte Ed25519 public key (when `algorithm` is `Ed25519`)
+    pub public_key: Vec<u8>,
     /// Maximum token age in seconds
     pub max_age_secs: u64,
     /// Whether authentication is required
@@ -64,27 +113,51 @@ pub struct AuthConfig {
     pub audience: String,
     /// Optional required scope
     pub required_scope: Option<String>,
-    /// Number of nonces to keep for replay protection
+    /// Scope required per action (message type such as `recommendation`, or
+    /// command capability such as `command.estop`), in place of
+    /// `required_scope` for that action
+    pub action_scopes: BTreeMap<String, String>,
+    /// Maximum number of unexpired nonces held for replay protection.
+    /// Nonces are dropped once their token is older than `max_age_secs`
+    /// plus `max_clock_skew_secs`; a full window rejects new tokens.
     pub replay_window: usize,
     /// Allowed clock skew in seconds
     pub max_clock_skew_secs: u64,
+    /// Accept the legacy two-part `payload.signature` token format
+    pub allow_legacy_format: bool,
+    /// Snapshot the replay window here so it survives restarts
+    pub replay_state_path: Option<PathBuf>,
+    /// Minimum interval between replay window snapshots
+    pub replay_snapshot_interval: Duration,
 }
 
 impl Default for AuthConfig {
     fn default() -> Self {
         Self {
+            algorithm: AuthAlgorithm::HmacSha256,
             secret: Vec::new(),
+            public_key: Vec::new(),
             max_age_secs: 300, // 5 minutes
             enabled: false,
             issuer: "neuroplc".to_string(),
             audience: "neuroplc-spine".to_string(),
             required_scope: None,
-            replay_window: 1024,
+            action_scopes: BTreeMap::new(),
+            replay_window: DEFAULT_REPLAY_WINDOW,
             max_clock_skew_secs: 5,
+            allow_legacy_format: false,
+            replay_state_path: None,
+            replay_snapshot_interval: DEFAULT_REPLAY_SNAPSHOT_INTERVAL,
         }
     }
 }
 
+/// Default cap on held nonces: 100 tokens/s over the default 5 minute max age
+pub const DEFAULT_REPLAY_WINDOW: usize = 32_768;
+
+/// Default interval between replay window snapshots
+pub const DEFAULT_REPLAY_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Claims carried in a token
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenClaims {
@@ -99,8 +172,51 @@ pub struct TokenClaims {
     pub nonce: String,
 }
 
+/// JOSE header carried in the first segment of a JWT
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoseHeader {
+    pub alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+}
+
+impl JoseHeader {
+    fn for_algorithm(algorithm: AuthAlgorithm) -> Self {
+        Self {
+            alg: algorithm.jose_alg().to_string(),
+            typ: Some("JWT".to_string()),
+        }
+    }
+}
+
+enum VerificationKey {
+    Hmac(Vec<u8>),
+    /// `None` when the configured key was not a valid point: fail closed.
+    Ed25519(Option<VerifyingKey>),
+}
+
+impl VerificationKey {
+    fn algorithm(&self) -> AuthAlgorithm {
+        match self {
+            VerificationKey::Hmac(_) => AuthAlgorithm::HmacSha256,
+            VerificationKey::Ed25519(_) => AuthAlgorithm::Ed25519,
+        }
+    }
+}
+
+/// A nonce accepted by the validator and the `iat` of its token
+#[derive(Clone, Serialize, Deserialize)]
+struct ReplayEntry {
+    nonce: String,
+    iat: u64,
+}
+
+/// Nonces of recently accepted tokens. Entries are kept until their token
+/// can no longer pass the age check, so every still-valid token stays
+/// protected; `capacity` only bounds memory, and a window full of live
+/// entries refuses new tokens rather than forgetting one.
 struct ReplayWindow {
-    order: VecDeque<String>,
+    order: VecDeque<ReplayEntry>,
     set: HashSet<String>,
     capacity: usize,
 }
@@ -114,101 +230,395 @@ impl ReplayWindow {
         }
     }
 
-    fn insert(&mut self, nonce: String) -> Result<(), AuthError> {
+    /// Record `nonce`, first dropping entries issued before `oldest_valid_iat`
+    fn insert(&mut self, nonce: String, iat: u64, oldest_valid_iat: u64) -> Result<(), AuthError> {
         if nonce.is_empty() {
             return Err(AuthError::MissingNonce);
         }
         if self.set.contains(&nonce) {
             return Err(AuthError::ReplayDetected);
         }
-        self.order.push_back(nonce.clone());
-        self.set.insert(nonce);
+        self.evict_expired(oldest_valid_iat);
+        if self.order.len() >= self.capacity {
+            return Err(AuthError::ReplayWindowFull);
+        }
+        self.set.insert(nonce.clone());
+        self.order.push_back(ReplayEntry { nonce, iat });
+        Ok(())
+    }
+
+    fn evict_expired(&mut self, oldest_valid_iat: u64) {
+        // Entries arrive roughly in iat order, so the front is usually enough.
+        while let Some(front) = self.order.front() {
+            if front.iat >= oldest_valid_iat {
+                break;
+            }
+            if let Some(old) = self.order.pop_front() {
+                self.set.remove(&old.nonce);
+            }
+        }
+        if self.order.len() >= self.capacity {
+            let set = &mut self.set;
+            self.order.retain(|entry| {
+                let live = entry.iat >= oldest_valid_iat;
+                if !live {
+                    set.remove(&entry.nonce);
+                }
+                live
+            });
+        }
+    }
+
+    /// Replace the window contents with entries loaded from a snapshot
+    fn restore(&mut self, entries: Vec<ReplayEntry>) {
+        self.order.clear();
+        self.set.clear();
+        for entry in entries {
+            if !entry.nonce.is_empty() && self.set.insert(entry.nonce.clone()) {
+                self.order.push_back(entry);
+            }
+        }
         while self.order.len() > self.capacity {
             if let Some(old) = self.order.pop_front() {
-                self.set.remove(&old);
+                self.set.remove(&old.nonce);
+            }
+        }
+    }
+}
+
+/// On-disk form of the replay window
+#[derive(Serialize, Deserialize)]
+struct ReplaySnapshot {
+    nonces: Vec<ReplayEntry>,
+}
+
+/// Where and how often the replay window is written to disk
+struct ReplayPersistence {
+    path: PathBuf,
+    interval_us: u64,
+    last_snapshot_us: AtomicU64,
+    /// Writes periodic snapshots off the validating thread
+    writer: Option<SnapshotWriter>,
+}
+
+/// Background thread that serializes and writes copied replay windows
+struct SnapshotWriter {
+    sender: SyncSender<Vec<ReplayEntry>>,
+    handle: JoinHandle<()>,
+}
+
+impl SnapshotWriter {
+    fn spawn(path: PathBuf) -> std::io::Result<Self> {
+        // One slot: a snapshot still waiting to be written is recent enough.
+        let (sender, receiver) = mpsc::sync_channel::<Vec<ReplayEntry>>(1);
+        let handle = thread::Builder::new()
+            .name("replay-snapshot".to_string())
+            .spawn(move || {
+                for entries in receiver {
+                    if let Err(e) = ReplayPersistence::write(&path, entries) {
+                        warn!(path = %path.display(), error = %e, "Failed to write replay snapshot");
+                    }
+                }
+            })?;
+        Ok(Self { sender, handle })
+    }
+
+    /// Drain pending snapshots and stop the thread
+    fn finish(self) {
+        drop(self.sender);
+        let _ = self.handle.join();
+    }
+}
+
+impl ReplayPersistence {
+    fn load(path: &Path) -> Vec<ReplayEntry> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read replay snapshot; starting empty");
+                return Vec::new();
+            }
+        };
+        match serde_json::from_slice::<ReplaySnapshot>(&bytes) {
+            Ok(snapshot) => {
+                info!(path = %path.display(), nonces = snapshot.nonces.len(), "Restored replay window");
+                snapshot.nonces
+            }
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Corrupt replay snapshot; starting empty");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Whether `interval_us` has passed since the last snapshot; claims the
+    /// next one if so
+    fn due(&self, now_us: u64) -> bool {
+        let last = self.last_snapshot_us.load(Ordering::Relaxed);
+        now_us.saturating_sub(last) >= self.interval_us
+            && self
+                .last_snapshot_us
+                .compare_exchange(last, now_us, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+    }
+
+    /// Hand `entries` to the writer thread, or write them here without one
+    fn submit(&self, entries: Vec<ReplayEntry>) {
+        match &self.writer {
+            Some(writer) => {
+                if let Err(TrySendError::Disconnected(_)) = writer.sender.try_send(entries) {
+                    warn!(path = %self.path.display(), "Replay snapshot writer stopped");
+                }
+            }
+            None => {
+                if let Err(e) = Self::write(&self.path, entries) {
+                    warn!(path = %self.path.display(), error = %e, "Failed to write replay snapshot");
+                }
             }
         }
-        Ok(())
+    }
+
+    /// Write via a temporary file and rename so a crash never leaves a torn snapshot
+    fn write(path: &Path, nonces: Vec<ReplayEntry>) -> std::io::Result<()> {
+        let bytes = serde_json::to_vec(&ReplaySnapshot { nonces })?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, path)
     }
 }
 
-/// Token validator using HMAC-SHA256
+/// Token validator using HMAC-SHA256 or Ed25519
 pub struct TokenValidator {
-    secret: Vec<u8>,
+    key: VerificationKey,
     max_age_secs: u64,
     issuer: String,
     audience: String,
     required_scope: Option<String>,
+    action_scopes: BTreeMap<String, String>,
     max_clock_skew_secs: u64,
+    allow_legacy_format: bool,
     replay: Mutex<ReplayWindow>,
+    persistence: Option<ReplayPersistence>,
+    clock: Box<dyn Clock>,
 }
 
 impl TokenValidator {
     /// Create a new token validator with the given secret and max age
     pub fn new(secret: Vec<u8>, max_age_secs: u64) -> Self {
+        Self::with_key(VerificationKey::Hmac(secret), max_age_secs)
+    }
+
+    /// Create a validator that verifies Ed25519 signatures with a raw public key
+    pub fn with_ed25519_key(public_key: &[u8], max_age_secs: u64) -> Self {
+        Self::with_key(
+            VerificationKey::Ed25519(parse_verifying_key(public_key)),
+            max_age_secs,
+        )
+    }
+
+    fn with_key(key: VerificationKey, max_age_secs: u64) -> Self {
         Self {
-            secret,
+            key,
             max_age_secs,
             issuer: "neuroplc".to_string(),
             audience: "neuroplc-spine".to_string(),
             required_scope: None,
+            action_scopes: BTreeMap::new(),
             max_clock_skew_secs: 5,
-            replay: Mutex::new(ReplayWindow::new(1024)),
+            allow_legacy_format: false,
+            replay: Mutex::new(ReplayWindow::new(DEFAULT_REPLAY_WINDOW)),
+            persistence: None,
+            clock: Box::new(TimeBase::new()),
         }
     }
 
     /// Create from an AuthConfig
     pub fn from_config(config: &AuthConfig) -> Self {
-        Self {
-            secret: config.secret.clone(),
+        let key = match config.algorithm {
+            AuthAlgorithm::HmacSha256 => VerificationKey::Hmac(config.secret.clone()),
+            AuthAlgorithm::Ed25519 => {
+                VerificationKey::Ed25519(parse_verifying_key(&config.public_key))
+            }
+        };
+        let validator = Self {
+            key,
             max_age_secs: config.max_age_secs,
             issuer: config.issuer.clone(),
             audience: config.audience.clone(),
             required_scope: config.required_scope.clone(),
+            action_scopes: config.action_scopes.clone(),
             max_clock_skew_secs: config.max_clock_skew_secs,
+            allow_legacy_format: config.allow_legacy_format,
             replay: Mutex::new(ReplayWindow::new(config.replay_window)),
+            persistence: None,
+            clock: Box::new(TimeBase::new()),
+        };
+        match &config.replay_state_path {
+            Some(path) => validator
+                .with_persistence(path)
+                .with_snapshot_interval(config.replay_snapshot_interval),
+            None => validator,
         }
     }
 
-    /// Validate a token string
-    /// Token format: base64url(payload).base64url(signature)
+    /// Validate a token string.
+    /// Token format: base64url(header).base64url(payload).base64url(signature),
+    /// or base64url(payload).base64url(signature) when legacy tokens are allowed.
     pub fn validate(&self, token: &str) -> Result<TokenClaims, AuthError> {
-        use base64::Engine;
-        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
-
-        let mut parts = token.split('.');
-        let payload_b64 = parts.next().ok_or(AuthError::InvalidFormat)?;
-        let sig_b64 = parts.next().ok_or(AuthError::InvalidFormat)?;
-        if parts.next().is_some() {
-            return Err(AuthError::InvalidFormat);
-        }
+        self.validate_scoped(token, self.required_scope.as_deref())
+    }
 
-        let payload = engine
-            .decode(payload_b64)
-            .map_err(|e| AuthError::DecodeError(e.to_string()))?;
-        let signature = engine
-            .decode(sig_b64)
-            .map_err(|e| AuthError::DecodeError(e.to_string()))?;
+    /// Validate a token for `action`, requiring the scope configured for it
+    /// or else the global required scope
+    pub fn validate_action(&self, token: &str, action: &str) -> Result<TokenClaims, AuthError> {
+        let required = self
+            .action_scopes
+            .get(action)
+            .or(self.required_scope.as_ref());
+        self.validate_scoped(token, required.map(String::as_str))
+    }
 
-        let mut mac =
-            HmacSha256::new_from_slice(&self.secret).expect("HMAC can take key of any size");
-        mac.update(&payload);
-        mac.verify_slice(&signature)
-            .map_err(|_| AuthError::InvalidSignature)?;
+    fn validate_scoped(
+        &self,
+        token: &str,
+        required_scope: Option<&str>,
+    ) -> Result<TokenClaims, AuthError> {
+        let parts: Vec<&str> = token.split('.').collect();
+        let payload = match parts.as_slice() {
+            [header_b64, payload_b64, sig_b64] => {
+                let header: JoseHeader = serde_json::from_slice(&decode_segment(header_b64)?)
+                    .map_err(|e| AuthError::DecodeError(e.to_string()))?;
+                // Pin the algorithm to prevent alg-confusion downgrades (e.g. "none").
+                if header.alg != self.key.algorithm().jose_alg() {
+                    return Err(AuthError::UnsupportedAlgorithm(header.alg));
+                }
+                let signing_input = &token[..header_b64.len() + 1 + payload_b64.len()];
+                self.verify_signature(signing_input.as_bytes(), &decode_segment(sig_b64)?)?;
+                decode_segment(payload_b64)?
+            }
+            [payload_b64, sig_b64] if self.allow_legacy_format => {
+                let payload = decode_segment(payload_b64)?;
+                self.verify_signature(&payload, &decode_segment(sig_b64)?)?;
+                payload
+            }
+            _ => return Err(AuthError::InvalidFormat),
+        };
 
         let claims: TokenClaims = serde_json::from_slice(&payload)
             .map_err(|e| AuthError::InvalidClaims(e.to_string()))?;
 
-        self.validate_claims(&claims)?;
+        self.validate_claims(&claims, required_scope)?;
 
         Ok(claims)
     }
 
-    fn validate_claims(&self, claims: &TokenClaims) -> Result<(), AuthError> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+    fn verify_signature(&self, message: &[u8], signature: &[u8]) -> Result<(), AuthError> {
+        match &self.key {
+            VerificationKey::Hmac(secret) => {
+                let mut mac =
+                    HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+                mac.update(message);
+                mac.verify_slice(signature)
+                    .map_err(|_| AuthError::InvalidSignature)
+            }
+            VerificationKey::Ed25519(key) => {
+                let key = key.as_ref().ok_or(AuthError::InvalidSignature)?;
+                let signature =
+                    Signature::from_slice(signature).map_err(|_| AuthError::InvalidSignature)?;
+                key.verify(message, &signature)
+                    .map_err(|_| AuthError::InvalidSignature)
+            }
+        }
+    }
+
+    /// Verify a detached base64url `signature` over `message` with the token
+    /// key. Used for per-message integrity, independent of token claims.
+    pub fn verify_detached(&self, message: &[u8], signature: &str) -> Result<(), AuthError> {
+        self.verify_signature(message, &decode_segment(signature)?)
+    }
+
+    /// Require `scope` for `action` instead of the global required scope
+    pub fn with_action_scope(
+        mut self,
+        action: impl Into<String>,
+        scope: impl Into<String>,
+    ) -> Self {
+        self.action_scopes.insert(action.into(), scope.into());
+        self
+    }
+
+    /// Use `clock` for expiry checks instead of the system clock
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Restore the replay window from `path` and keep snapshotting it there,
+    /// so a restart does not reopen the window for already-accepted tokens.
+    /// A missing or corrupt snapshot starts the window empty.
+    ///
+    /// Snapshots are written at most once per snapshot interval and on drop.
+    /// After a crash, nonces accepted since the last snapshot are lost, so
+    /// tokens accepted in that interval can be replayed until they expire;
+    /// a shorter interval narrows that window at the cost of more writes.
+    pub fn with_persistence(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = ReplayPersistence::load(&path);
+        self.replay
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .restore(entries);
+        let writer = match SnapshotWriter::spawn(path.clone()) {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                warn!(error = %e, "Failed to start replay snapshot writer; writing inline");
+                None
+            }
+        };
+        self.persistence = Some(ReplayPersistence {
+            path,
+            interval_us: DEFAULT_REPLAY_SNAPSHOT_INTERVAL.as_micros() as u64,
+            last_snapshot_us: AtomicU64::new(0),
+            writer,
+        });
+        self
+    }
+
+    /// Set the minimum interval between replay window snapshots
+    pub fn with_snapshot_interval(mut self, interval: Duration) -> Self {
+        if let Some(persistence) = self.persistence.as_mut() {
+            persistence.interval_us = interval.as_micros() as u64;
+        }
+        self
+    }
+
+    /// Write the replay window to disk now, if persistence is enabled
+    pub fn snapshot(&self) {
+        if let Some(persistence) = &self.persistence {
+            let entries = self.replay_entries();
+            if let Err(e) = ReplayPersistence::write(&persistence.path, entries) {
+                warn!(path = %persistence.path.display(), error = %e, "Failed to write replay snapshot");
+            }
+        }
+    }
+
+    /// Copy of the replay window, taken under its lock
+    fn replay_entries(&self) -> Vec<ReplayEntry> {
+        let replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        replay.order.iter().cloned().collect()
+    }
+
+    fn now_secs(&self) -> u64 {
+        self.clock.unix_us() / 1_000_000
+    }
+
+    fn validate_claims(
+        &self,
+        claims: &TokenClaims,
+        required_scope: Option<&str>,
+    ) -> Result<(), AuthError> {
+        let now = self.now_secs();
         let skew = self.max_clock_skew_secs;
 
         if claims.iss != self.issuer {
@@ -217,7 +627,7 @@ impl TokenValidator {
         if claims.aud != self.audience {
             return Err(AuthError::InvalidAudience);
         }
-        if let Some(required) = &self.required_scope {
+        if let Some(required) = required_scope {
             if !claims.scope.iter().any(|scope| scope == required) {
                 return Err(AuthError::MissingScope);
             }
@@ -245,8 +655,18 @@ impl TokenValidator {
             });
         }
 
-        let mut replay = self.replay.lock().unwrap();
-        replay.insert(claims.nonce.clone())?;
+        let oldest_valid_iat = now.saturating_sub(self.max_age_secs.saturating_add(skew));
+        self.replay
+            .lock()
+            .unwrap()
+            .insert(claims.nonce.clone(), claims.iat, oldest_valid_iat)?;
+
+        // Only the copy happens here; the writer thread serializes and writes it.
+        if let Some(persistence) = &self.persistence {
+            if persistence.due(self.clock.now_us()) {
+                persistence.submit(self.replay_entries());
+            }
+        }
 
         Ok(())
     }
@@ -254,10 +674,7 @@ impl TokenValidator {
     /// Generate a new token for testing/development
     #[allow(dead_code)]
     pub fn generate_token(&self) -> String {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = self.now_secs();
 
         let claims = TokenClaims {
             iss: self.issuer.clone(),
@@ -273,35 +690,134 @@ impl TokenValidator {
         self.generate_token_with_claims(&claims)
     }
 
-    /// Generate a token with custom claims (for tests)
+    /// Generate a token with custom claims (for tests).
+    /// Ed25519 validators only hold a public key; use [`generate_ed25519_token`].
     #[allow(dead_code)]
     pub fn generate_token_with_claims(&self, claims: &TokenClaims) -> String {
-        use base64::Engine;
-        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
-        let payload = serde_json::to_vec(claims).expect("failed to serialize claims");
-        let payload_b64 = engine.encode(&payload);
-        let mut mac =
-            HmacSha256::new_from_slice(&self.secret).expect("HMAC can take key of any size");
-        mac.update(&payload);
-        let signature = mac.finalize().into_bytes();
-        let sig_b64 = engine.encode(signature);
-        format!("{}.{}", payload_b64, sig_b64)
+        match &self.key {
+            VerificationKey::Hmac(secret) => {
+                encode_token(AuthAlgorithm::HmacSha256, claims, |input| {
+                    let mut mac =
+                        HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+                    mac.update(input);
+                    mac.finalize().into_bytes().to_vec()
+                })
+            }
+            VerificationKey::Ed25519(_) => {
+                panic!("cannot sign tokens with an Ed25519 public key")
+            }
+        }
+    }
+}
+
+impl TokenValidator {
+    /// Detached HMAC signature over `message`, for tests.
+    /// Ed25519 validators only hold a public key; use [`sign_ed25519_detached`].
+    #[cfg(test)]
+    pub(crate) fn sign_detached(&self, message: &[u8]) -> String {
+        match &self.key {
+            VerificationKey::Hmac(secret) => {
+                let mut mac =
+                    HmacSha256::new_from_slice(secret).expect("HMAC can take key of any size");
+                mac.update(message);
+                encode_segment(&mac.finalize().into_bytes())
+            }
+            VerificationKey::Ed25519(_) => {
+                panic!("cannot sign messages with an Ed25519 public key")
+            }
+        }
+    }
+}
+
+impl Drop for TokenValidator {
+    fn drop(&mut self) {
+        // Let a pending periodic snapshot land before the final one replaces it.
+        if let Some(writer) = self.persistence.as_mut().and_then(|p| p.writer.take()) {
+            writer.finish();
+        }
+        self.snapshot();
     }
 }
 
+/// Generate an Ed25519-signed token (the cortex side of asymmetric auth)
+pub fn generate_ed25519_token(signing_key: &SigningKey, claims: &TokenClaims) -> String {
+    encode_token(AuthAlgorithm::Ed25519, claims, |input| {
+        signing_key.sign(input).to_bytes().to_vec()
+    })
+}
+
+/// Detached Ed25519 signature over `message`, as checked by
+/// [`TokenValidator::verify_detached`]
+pub fn sign_ed25519_detached(signing_key: &SigningKey, message: &[u8]) -> String {
+    encode_segment(&signing_key.sign(message).to_bytes())
+}
+
+/// Load an Ed25519 public key from a PEM (`PUBLIC KEY`) file or a raw 32-byte file
+pub fn load_ed25519_public_key(path: &Path) -> Result<Vec<u8>, AuthError> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| AuthError::InvalidKey(format!("{}: {}", path.display(), e)))?;
+    let key = match std::str::from_utf8(&bytes) {
+        Ok(text) if text.contains("-----BEGIN PUBLIC KEY-----") => {
+            use base64::Engine;
+            let body: String = text
+                .lines()
+                .filter(|line| !line.starts_with("-----"))
+                .collect();
+            let der = base64::engine::general_purpose::STANDARD
+                .decode(body.trim())
+                .map_err(|e| AuthError::InvalidKey(e.to_string()))?;
+            der.strip_prefix(&ED25519_SPKI_PREFIX[..])
+                .ok_or_else(|| AuthError::InvalidKey("not an Ed25519 public key".to_string()))?
+                .to_vec()
+        }
+        _ => bytes,
+    };
+    parse_verifying_key(&key)
+        .map(|k| k.to_bytes().to_vec())
+        .ok_or_else(|| AuthError::InvalidKey("expected a 32-byte Ed25519 key".to_string()))
+}
+
+fn parse_verifying_key(bytes: &[u8]) -> Option<VerifyingKey> {
+    let bytes: &[u8; 32] = bytes.try_into().ok()?;
+    VerifyingKey::from_bytes(bytes).ok()
+}
+
+fn encode_token(
+    algorithm: AuthAlgorithm,
+    claims: &TokenClaims,
+    sign: impl FnOnce(&[u8]) -> Vec<u8>,
+) -> String {
+    let header = serde_json::to_vec(&JoseHeader::for_algorithm(algorithm))
+        .expect("failed to serialize header");
+    let payload = serde_json::to_vec(claims).expect("failed to serialize claims");
+    let signing_input = format!("{}.{}", encode_segment(&header), encode_segment(&payload));
+    let signature = sign(signing_input.as_bytes());
+    format!("{}.{}", signing_input, encode_segment(&signature))
+}
+
+fn encode_segment(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn decode_segment(segment: &str) -> Result<Vec<u8>, AuthError> {
+    use base64::Engine;
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|e| AuthError::DecodeError(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core_spine::MockClock;
 
     fn test_secret() -> Vec<u8> {
         b"test-secret-key-for-hmac".to_vec()
     }
 
     fn base_claims(validator: &TokenValidator) -> TokenClaims {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = validator.now_secs();
         TokenClaims {
             iss: validator.issuer.clone(),
             sub: "neuroplc-test".to_string(),
@@ -337,18 +853,223 @@ mod tests {
 
     #[test]
     fn test_expired_token_rejected() {
-        let validator = TokenValidator::new(test_secret(), 1);
+        let clock = MockClock::starting_now();
+        let validator = TokenValidator::new(test_secret(), 1).with_clock(clock.clone());
         let mut claims = base_claims(&validator);
-        claims.iat = claims.iat.saturating_sub(10);
         claims.exp = claims.iat + 1;
 
         let token = validator.generate_token_with_claims(&claims);
-        std::thread::sleep(std::time::Duration::from_millis(1100));
+        clock.advance(std::time::Duration::from_secs(10));
         let result = validator.validate(&token);
 
         assert!(matches!(result, Err(AuthError::TokenExpired { .. })));
     }
 
+    #[test]
+    fn test_replay_window_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("replay.json");
+
+        let clock = MockClock::starting_now();
+        let validator = TokenValidator::new(test_secret(), 300)
+            .with_clock(clock.clone())
+            .with_persistence(&path);
+        let token = validator.generate_token_with_claims(&base_claims(&validator));
+        validator.validate(&token).unwrap();
+        drop(validator);
+
+        let restarted = TokenValidator::new(test_secret(), 300)
+            .with_clock(clock)
+            .with_persistence(&path);
+        assert!(matches!(
+            restarted.validate(&token),
+            Err(AuthError::ReplayDetected)
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_replay_snapshot_starts_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("replay.json");
+        std::fs::write(&path, b"{not json").unwrap();
+
+        let validator = TokenValidator::new(test_secret(), 300).with_persistence(&path);
+        let token = validator.generate_token();
+        assert!(validator.validate(&token).is_ok());
+        drop(validator);
+
+        // The next snapshot replaces the corrupt file with a readable one.
+        let bytes = std::fs::read(&path).unwrap();
+        let snapshot: ReplaySnapshot = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(snapshot.nonces.len(), 1);
+    }
+
+    #[test]
+    fn test_periodic_replay_snapshot_written_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("replay.json");
+
+        let validator = TokenValidator::new(test_secret(), 300)
+            .with_persistence(&path)
+            .with_snapshot_interval(Duration::ZERO);
+        validator.validate(&validator.generate_token()).unwrap();
+
+        // Written by the snapshot thread while the validator is still live
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let snapshot = loop {
+            if let Some(snapshot) = std::fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<ReplaySnapshot>(&bytes).ok())
+            {
+                break snapshot;
+            }
+            assert!(std::time::Instant::now() < deadline, "no replay snapshot");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(snapshot.nonces.len(), 1);
+
+        drop(validator);
+    }
+
+    #[test]
+    fn test_replay_detected_after_many_intervening_nonces() {
+        let clock = MockClock::starting_now();
+        let config = AuthConfig {
+            secret: test_secret(),
+            max_age_secs: 60,
+            enabled: true,
+            replay_window: 64,
+            ..Default::default()
+        };
+        let validator = TokenValidator::from_config(&config).with_clock(clock.clone());
+
+        let mut first = base_claims(&validator);
+        first.nonce = "first".to_string();
+        let replayed = validator.generate_token_with_claims(&first);
+        validator.validate(&replayed).unwrap();
+
+        // Fill the window: once full of live nonces, new tokens are refused
+        // rather than evicting a nonce whose token is still valid.
+        let mut refused = 0;
+        for i in 0..200 {
+            clock.advance(std::time::Duration::from_millis(250));
+            let mut claims = base_claims(&validator);
+            claims.nonce = format!("n-{i}");
+            let token = validator.generate_token_with_claims(&claims);
+            if let Err(AuthError::ReplayWindowFull) = validator.validate(&token) {
+                refused += 1;
+            }
+        }
+        assert!(refused > 0);
+
+        // Just under max_age the first token is still replay-protected.
+        assert!(validator.now_secs() - first.iat < config.max_age_secs);
+        assert!(matches!(
+            validator.validate(&replayed),
+            Err(AuthError::ReplayDetected)
+        ));
+
+        // Once it is past max_age + skew it is evicted and rejected as expired.
+        clock.advance(std::time::Duration::from_secs(
+            config.max_age_secs + config.max_clock_skew_secs,
+        ));
+        let mut fresh = base_claims(&validator);
+        fresh.nonce = "after-expiry".to_string();
+        let token = validator.generate_token_with_claims(&fresh);
+        validator.validate(&token).unwrap();
+        assert!(matches!(
+            validator.validate(&replayed),
+            Err(AuthError::TokenExpired { .. })
+        ));
+    }
+
+    fn legacy_token(claims: &TokenClaims) -> String {
+        let payload = serde_json::to_vec(claims).unwrap();
+        let mut mac = HmacSha256::new_from_slice(&test_secret()).unwrap();
+        mac.update(&payload);
+        let signature = mac.finalize().into_bytes();
+        format!(
+            "{}.{}",
+            encode_segment(&payload),
+            encode_segment(&signature)
+        )
+    }
+
+    #[test]
+    fn test_token_has_jose_header() {
+        let validator = TokenValidator::new(test_secret(), 300);
+        let token = validator.generate_token();
+        let header_b64 = token.split('.').next().unwrap();
+        let header: JoseHeader =
+            serde_json::from_slice(&decode_segment(header_b64).unwrap()).unwrap();
+        assert_eq!(header.alg, "HS256");
+        assert_eq!(token.split('.').count(), 3);
+    }
+
+    #[test]
+    fn test_alg_none_rejected() {
+        let validator = TokenValidator::new(test_secret(), 300);
+        let claims = base_claims(&validator);
+        let header = encode_segment(br#"{"alg":"none","typ":"JWT"}"#);
+        let payload = encode_segment(&serde_json::to_vec(&claims).unwrap());
+        let token = format!("{header}.{payload}.");
+
+        assert!(matches!(
+            validator.validate(&token),
+            Err(AuthError::UnsupportedAlgorithm(alg)) if alg == "none"
+        ));
+    }
+
+    #[test]
+    fn test_legacy_format_requires_flag() {
+        let strict = TokenValidator::new(test_secret(), 300);
+        let claims = base_claims(&strict);
+        let token = legacy_token(&claims);
+        assert!(matches!(
+            strict.validate(&token),
+            Err(AuthError::InvalidFormat)
+        ));
+
+        let config = AuthConfig {
+            secret: test_secret(),
+            allow_legacy_format: true,
+            ..Default::default()
+        };
+        let legacy = TokenValidator::from_config(&config);
+        assert!(legacy.validate(&token).is_ok());
+    }
+
+    #[test]
+    fn test_action_scope_replaces_required_scope() {
+        let config = AuthConfig {
+            secret: test_secret(),
+            required_scope: Some("cortex:recommend".to_string()),
+            action_scopes: [("command.estop".to_string(), "operator:estop".to_string())].into(),
+            ..Default::default()
+        };
+        let validator = TokenValidator::from_config(&config);
+        let token = |nonce: &str, scope: &str| {
+            let mut claims = base_claims(&validator);
+            claims.nonce = nonce.to_string();
+            claims.scope = vec![scope.to_string()];
+            validator.generate_token_with_claims(&claims)
+        };
+
+        assert!(matches!(
+            validator.validate_action(&token("a", "cortex:recommend"), "command.estop"),
+            Err(AuthError::MissingScope)
+        ));
+        assert!(validator
+            .validate_action(&token("b", "operator:estop"), "command.estop")
+            .is_ok());
+        // Actions without their own scope fall back to the global one.
+        assert!(matches!(
+            validator.validate_action(&token("c", "operator:estop"), "recommendation"),
+            Err(AuthError::MissingScope)
+        ));
+        assert!(validator.validate(&token("d", "cortex:recommend")).is_ok());
+    }
+
     #[test]
     fn test_invalid_format_rejected() {
         let validator = TokenValidator::new(test_secret(), 300);
@@ -358,4 +1079,61 @@ mod tests {
             Err(AuthError::DecodeError(_)) | Err(AuthError::InvalidFormat)
         ));
     }
+
+    #[test]
+    fn test_ed25519_token_accepted() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let validator =
+            TokenValidator::with_ed25519_key(signing_key.verifying_key().as_bytes(), 300);
+        let token = generate_ed25519_token(&signing_key, &base_claims(&validator));
+
+        assert!(validator.validate(&token).is_ok());
+    }
+
+    #[test]
+    fn test_ed25519_wrong_key_rejected() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let other_key = SigningKey::from_bytes(&[9u8; 32]);
+        let validator = TokenValidator::with_ed25519_key(other_key.verifying_key().as_bytes(), 300);
+        let token = generate_ed25519_token(&signing_key, &base_claims(&validator));
+
+        assert!(matches!(
+            validator.validate(&token),
+            Err(AuthError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_hmac_token_rejected_by_ed25519_validator() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let hmac = TokenValidator::new(signing_key.verifying_key().as_bytes().to_vec(), 300);
+        let validator =
+            TokenValidator::with_ed25519_key(signing_key.verifying_key().as_bytes(), 300);
+        let token = hmac.generate_token();
+
+        assert!(matches!(
+            validator.validate(&token),
+            Err(AuthError::UnsupportedAlgorithm(alg)) if alg == "HS256"
+        ));
+    }
+
+    #[test]
+    fn test_load_ed25519_public_key_pem() {
+        use base64::Engine;
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut der = ED25519_SPKI_PREFIX.to_vec();
+        der.extend_from_slice(signing_key.verifying_key().as_bytes());
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            base64::engine::general_purpose::STANDARD.encode(&der)
+        );
+        let dir = std::env::temp_dir().join(format!("neuroplc-auth-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cortex.pub.pem");
+        std::fs::write(&path, pem).unwrap();
+
+        let key = load_ed25519_public_key(&path).unwrap();
+        assert_eq!(key, signing_key.verifying_key().as_bytes().to_vec());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -1,41 +1,236 @@
-use crate::auth::{AuthConfig, TokenValidator};
+use crate::audit::{
+    AuditEventType, AuditLogger, RecommendationAppliedDetails, RecommendationReceivedDetails,
+    RecommendationSupersededDetails, SafetyRejectionDetails,
+};
+use crate::auth::AuthAlgorithm;
+use crate::auth::{AuthConfig, TokenClaims, TokenValidator};
 use crate::metrics::{
-    AGENT_CONFIDENCE, AGENT_TARGET_RPM, AUTH_FAILURES, AUTH_MISSING, BRIDGE_CONNECTED,
-    RECOMMENDATION_EXPIRED, RECOMMENDATION_OUT_OF_ORDER,
+    AGENT_CLOCK_OFFSET_MS, AGENT_CONFIDENCE, AGENT_TARGET_RPM, AUTH_FAILURES, AUTH_MISSING,
+    BRIDGE_CONNECTED, BRIDGE_REJECTS, BRIDGE_SLOW_CLIENT_DROPS, DUPLICATE_REASONING,
+    FRAME_CHECKSUM_FAILURES, HEALTH, LOW_CONFIDENCE_DROPPED, PARSE_ERRORS,
+    RECOMMENDATIONS_ACCEPTED, RECOMMENDATIONS_RATE_LIMITED, RECOMMENDATION_EXPIRED,
+    RECOMMENDATION_OUT_OF_ORDER, SIGNATURE_FAILURES, STATE_GAP, TLS_HANDSHAKE_FAILURES,
+};
+use crate::protocol::{
+    BatchMember, CommandMsg, ConfigMsg, ErrorMsg, HelloAckMsg, HelloMsg, IncomingMessage, PingMsg,
+    ProtocolVersion, RecommendationMsg, RejectMsg, RejectReason, StateMsg,
 };
-use crate::protocol::{HelloMsg, IncomingMessage, StateMsg};
 #[cfg(feature = "proto")]
 use crate::protocol_proto::proto;
-use crate::tls::{build_server_config, TlsConfig};
-use core_spine::{AgentRecommendation, StateExchange, TimeBase};
+use crate::tls::{handshake_failure_reason, ReloadableServerConfig, TlsConfig, TlsError};
+use core_spine::{
+    AgentRecommendation, Clock, ProcessSnapshot, ReasoningHash, SafetyLimits, Sequenced,
+    StateExchange,
+};
 #[cfg(feature = "proto")]
 use prost::Message;
 use rustls::{ServerConnection, StreamOwned};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::sync::{atomic::AtomicBool, Arc};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
+use thiserror::Error;
 use tracing::{debug, error, info, instrument, trace, warn, Span};
 
+/// Longest JSON line accepted before a client is dropped for never sending
+/// a newline.
+pub(crate) const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Largest protobuf payload accepted, measured after decompression.
+#[cfg(feature = "proto")]
+const MAX_FRAME_BYTES: usize = 256 * 1024;
+
+/// Capability listed in `hello`/`hello_ack` by peers that accept
+/// zstd-compressed protobuf frames.
+pub const ZSTD_CAPABILITY: &str = "compression.zstd";
+
+/// Capability a client must list in `hello` to send `ramp_rate_rpm_per_s`.
+pub const RAMP_CAPABILITY: &str = "recommendation.ramp";
+
+/// Capability a client must list in `hello` to send an `estop` command.
+pub const ESTOP_CAPABILITY: &str = "command.estop";
+
+/// Capability a client must list in `hello` to send a `set_limits` command.
+pub const SET_LIMITS_CAPABILITY: &str = "command.set_limits";
+
+/// Capability a client must list in `hello` to send a `get_config` command.
+pub const GET_CONFIG_CAPABILITY: &str = "command.get_config";
+
+/// Capability a client must list in `hello` to send a `batch`.
+pub const BATCH_CAPABILITY: &str = "recommendation.batch";
+
+/// Capability listed in `hello` by JSON-lines clients that want every line
+/// after the `hello_ack`, in both directions, to end in a tab and the
+/// CRC-32 of the JSON as 8 hex digits.
+pub const CRC32_FRAMING_CAPABILITY: &str = "framing.crc32";
+
+/// Actions that can be given their own required scope: the message types
+/// that carry a token and the authenticated commands
+pub const SCOPED_ACTIONS: &[&str] = &[
+    "recommendation",
+    "batch",
+    ESTOP_CAPABILITY,
+    SET_LIMITS_CAPABILITY,
+    GET_CONFIG_CAPABILITY,
+];
+
+/// Most recommendations a `batch` may carry. The exchange holds a single
+/// setpoint, so it cannot apply several members together.
+pub const MAX_BATCH_MEMBERS: usize = 1;
+
+/// Set in a protobuf frame's length prefix when the payload is
+/// zstd-compressed; the remaining bits are the compressed length.
+#[cfg(feature = "proto")]
+const COMPRESSED_FLAG: u32 = 1 << 31;
+
+#[cfg(feature = "proto")]
+const ZSTD_LEVEL: i32 = 3;
+
+/// Errors that stop the bridge before it starts serving
+#[derive(Debug, Error)]
+pub enum BridgeError {
+    #[error("failed to bind {addr}: {source}")]
+    Bind {
+        addr: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to configure listener: {0}")]
+    Listener(#[source] std::io::Error),
+    #[error("failed to configure TLS: {0}")]
+    Tls(#[from] TlsError),
+}
+
 pub struct BridgeConfig {
     pub bind_addr: String,
-    pub publish_interval: Duration,
+    /// Further addresses to listen on besides `bind_addr`, e.g. loopback plus
+    /// a management interface, or IPv4 plus IPv6. Clients are still served
+    /// one at a time across all listeners.
+    pub extra_bind_addrs: Vec<String>,
+    pub publish_mode: PublishMode,
     pub tls: TlsConfig,
     pub auth: AuthConfig,
     pub require_handshake: bool,
     pub wire_protocol: WireProtocol,
+    /// Number of consecutive publish intervals a client may leave the
+    /// previous state frame undrained before it is disconnected. Zero
+    /// disables slow-client detection.
+    pub max_stalled_publishes: u32,
+    /// Drop the client if no bytes are received for this long. Catches
+    /// peers that vanished without closing the connection. `None` disables.
+    pub idle_timeout: Option<Duration>,
+    /// Send a `ping` frame at this interval so clients with nothing else to
+    /// say can answer with `pong` and stay inside the idle timeout. JSON
+    /// lines only. `None` disables.
+    pub ping_interval: Option<Duration>,
+    /// Sustained recommendations per second accepted from one client, with a
+    /// burst of the same size. Excess recommendations are rejected with
+    /// `rate_limited`. `None` disables the limiter.
+    pub max_recommendations_per_sec: Option<u32>,
+    /// Recommendations with a lower confidence are dropped without reply
+    /// and never reach the control loop. Zero accepts everything.
+    pub min_confidence: f32,
+    /// Compress protobuf state frames larger than this many bytes with zstd
+    /// for clients that list [`ZSTD_CAPABILITY`] in `hello`. `None` disables
+    /// compression in both directions.
+    pub compress_frames_over: Option<usize>,
+    /// What to do when a client keeps sending the `reasoning_hash` of its
+    /// last accepted recommendation.
+    pub duplicate_reasoning: DuplicateReasoning,
+    /// Recommendations issued further than this ahead of the spine's wall
+    /// clock are rejected as `expired`. A warning is logged once the mean
+    /// agent offset passes half of it.
+    pub max_clock_skew: Duration,
+    /// Reject recommendations and batches without a valid detached
+    /// `signature`. Signatures that are present are always checked when auth
+    /// is configured.
+    pub require_signature: bool,
+    /// Length in bytes of the digest agents send as `reasoning_hash`: 32
+    /// for SHA-256, 64 for SHA-512. Hashes of any other length are rejected
+    /// as malformed.
+    pub reasoning_hash_bytes: usize,
+    /// Configured safety limits. `set_limits` commands may tighten the
+    /// control loop's limits within these but never loosen them. Requires
+    /// auth; `None` refuses every `set_limits`.
+    pub safety_limits: Option<SafetyLimits>,
+    /// Highest accepted sequence per `client_id`, kept across reconnects;
+    /// `None` lets every connection start its sequence afresh.
+    pub sequence_floors: Option<Arc<SequenceFloors>>,
+    /// Validator shared by every bridge serving the same spine, so a token
+    /// accepted on one transport is a replay on another and one replay
+    /// snapshot is written. `None` builds a private one from `auth`.
+    pub token_validator: Option<Arc<Mutex<TokenValidator>>>,
+    /// Redacted effective configuration returned by `get_config` commands.
+    /// Requires auth; `None` refuses every `get_config`.
+    pub effective_config: Option<Arc<serde_json::Value>>,
+    /// Pause between passes of the serving loop. It is the floor on reply
+    /// and state-frame latency, and sets what an idle bridge costs in CPU:
+    /// 1 ms for low-latency agents, 50 ms where CPU matters more. Values
+    /// below [`MIN_POLL_INTERVAL`] are raised to it.
+    pub poll_interval: Duration,
 }
 
+/// Shortest [`BridgeConfig::poll_interval`]; keeps a zero from busy-looping
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
 impl Default for BridgeConfig {
     fn default() -> Self {
         Self {
             bind_addr: "127.0.0.1:7000".to_string(),
-            publish_interval: Duration::from_millis(100),
+            extra_bind_addrs: Vec::new(),
+            publish_mode: PublishMode::default(),
             tls: TlsConfig::default(),
             auth: AuthConfig::default(),
             require_handshake: false,
             wire_protocol: WireProtocol::JsonLines,
+            max_stalled_publishes: 50,
+            idle_timeout: Some(Duration::from_secs(30)),
+            ping_interval: None,
+            max_recommendations_per_sec: Some(100),
+            min_confidence: 0.0,
+            compress_frames_over: None,
+            duplicate_reasoning: DuplicateReasoning::Off,
+            max_clock_skew: Duration::from_secs(5),
+            require_signature: false,
+            reasoning_hash_bytes: 32,
+            safety_limits: None,
+            sequence_floors: None,
+            token_validator: None,
+            effective_config: None,
+            poll_interval: Duration::from_millis(5),
+        }
+    }
+}
+
+impl BridgeConfig {
+    /// `bind_addr` followed by `extra_bind_addrs`
+    pub fn bind_addrs(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.bind_addr.as_str())
+            .chain(self.extra_bind_addrs.iter().map(String::as_str))
+    }
+
+    /// `poll_interval`, raised to at least [`MIN_POLL_INTERVAL`]
+    pub fn effective_poll_interval(&self) -> Duration {
+        self.poll_interval.max(MIN_POLL_INTERVAL)
+    }
+
+    /// The shared `token_validator`, or a private one built from `auth`;
+    /// `None` without auth
+    pub(crate) fn token_validator(&self) -> Option<Arc<Mutex<TokenValidator>>> {
+        if !self.auth.enabled {
+            return None;
         }
+        Some(
+            self.token_validator
+                .clone()
+                .unwrap_or_else(|| Arc::new(Mutex::new(TokenValidator::from_config(&self.auth)))),
+        )
     }
 }
 
@@ -62,6 +257,152 @@ impl WireProtocol {
     }
 }
 
+/// Tracking of recommendations that reuse the previous `reasoning_hash`
+/// under a new sequence number. An agent may legitimately repeat a decision,
+/// e.g. re-sending an unchanged target as a keepalive without re-running its
+/// reasoning, so `max_repeats` consecutive repeats are allowed before a
+/// recommendation counts as a duplicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateReasoning {
+    /// No tracking.
+    #[default]
+    Off,
+    /// Count duplicates in `neuroplc_duplicate_reasoning_total` but accept them.
+    Count { max_repeats: u32 },
+    /// Count duplicates and reject them with `duplicate_reasoning`.
+    Reject { max_repeats: u32 },
+}
+
+impl DuplicateReasoning {
+    /// `off`, `count:<REPEATS>` or `reject:<REPEATS>`
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(':');
+        let policy = match (parts.next()?, parts.next()) {
+            ("off", None) => DuplicateReasoning::Off,
+            ("count", Some(repeats)) => DuplicateReasoning::Count {
+                max_repeats: repeats.parse().ok()?,
+            },
+            ("reject", Some(repeats)) => DuplicateReasoning::Reject {
+                max_repeats: repeats.parse().ok()?,
+            },
+            _ => return None,
+        };
+        parts.next().is_none().then_some(policy)
+    }
+}
+
+/// When state frames are sent to a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishMode {
+    /// One frame every interval, changed or not.
+    FixedRate(Duration),
+    /// A frame as soon as the process moves beyond the change deadband, but
+    /// no more often than `min_interval`, and a keepalive frame whenever
+    /// `max_interval` passes without one.
+    OnChange {
+        min_interval: Duration,
+        max_interval: Duration,
+    },
+}
+
+impl Default for PublishMode {
+    fn default() -> Self {
+        PublishMode::FixedRate(Duration::from_millis(100))
+    }
+}
+
+impl PublishMode {
+    /// `fixed:<MS>` or `on-change:<MIN_MS>:<MAX_MS>`
+    pub fn parse(value: &str) -> Option<Self> {
+        let ms = |part: &str| part.parse::<u64>().ok().map(Duration::from_millis);
+        let mut parts = value.split(':');
+        let mode = match (parts.next()?, parts.next(), parts.next()) {
+            ("fixed", Some(interval), None) => PublishMode::FixedRate(ms(interval)?),
+            ("on-change", Some(min), Some(max)) => PublishMode::OnChange {
+                min_interval: ms(min)?,
+                max_interval: ms(max)?,
+            },
+            _ => return None,
+        };
+        let valid = match mode {
+            PublishMode::FixedRate(interval) => !interval.is_zero(),
+            PublishMode::OnChange {
+                min_interval,
+                max_interval,
+            } => !max_interval.is_zero() && min_interval <= max_interval,
+        };
+        (parts.next().is_none() && valid).then_some(mode)
+    }
+}
+
+/// Changes smaller than these are not worth an on-change frame; a change
+/// of safety state always is.
+const DEADBAND_SPEED_RPM: f64 = 1.0;
+const DEADBAND_TEMP_C: f64 = 0.1;
+const DEADBAND_PRESSURE_BAR: f64 = 0.1;
+
+fn changed_beyond_deadband(previous: &ProcessSnapshot, current: &ProcessSnapshot) -> bool {
+    previous.safety_state != current.safety_state
+        || (current.motor_speed_rpm - previous.motor_speed_rpm).abs() > DEADBAND_SPEED_RPM
+        || (current.motor_temp_c - previous.motor_temp_c).abs() > DEADBAND_TEMP_C
+        || (current.pressure_bar - previous.pressure_bar).abs() > DEADBAND_PRESSURE_BAR
+}
+
+/// Decides, per client, when the next state frame is due.
+#[derive(Debug)]
+pub(crate) struct PublishSchedule {
+    mode: PublishMode,
+    last_attempt: Option<Instant>,
+    last_sent: Option<ProcessSnapshot>,
+}
+
+impl PublishSchedule {
+    pub(crate) fn new(mode: PublishMode) -> Self {
+        Self {
+            mode,
+            last_attempt: None,
+            last_sent: None,
+        }
+    }
+
+    /// Whether `snapshot` should be sent at `now`. The first check after
+    /// construction or [`PublishSchedule::reset`] is always due.
+    pub(crate) fn due(&self, snapshot: &ProcessSnapshot, now: Instant) -> bool {
+        let Some(last_attempt) = self.last_attempt else {
+            return true;
+        };
+        let elapsed = now.saturating_duration_since(last_attempt);
+        match self.mode {
+            PublishMode::FixedRate(interval) => elapsed >= interval,
+            PublishMode::OnChange {
+                min_interval,
+                max_interval,
+            } => {
+                elapsed >= max_interval
+                    || (elapsed >= min_interval
+                        && self
+                            .last_sent
+                            .is_none_or(|sent| changed_beyond_deadband(&sent, snapshot)))
+            }
+        }
+    }
+
+    /// A due frame was skipped because the previous one is still unsent.
+    pub(crate) fn note_skipped(&mut self, now: Instant) {
+        self.last_attempt = Some(now);
+    }
+
+    pub(crate) fn note_sent(&mut self, snapshot: ProcessSnapshot, now: Instant) {
+        self.last_attempt = Some(now);
+        self.last_sent = Some(snapshot);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.last_attempt = None;
+        self.last_sent = None;
+    }
+}
+
 enum BridgeStream {
     Plain(TcpStream),
     Tls(Box<StreamOwned<ServerConnection, TcpStream>>),
@@ -92,37 +433,423 @@ impl Write for BridgeStream {
     }
 }
 
+impl BridgeStream {
+    /// A TLS stream that has not yet completed its handshake
+    fn is_handshaking(&self) -> bool {
+        match self {
+            BridgeStream::Plain(_) => false,
+            BridgeStream::Tls(s) => s.conn.is_handshaking(),
+        }
+    }
+}
+
+/// Token bucket in micro-tokens so refill is exact integer arithmetic: a
+/// rate of N tokens/s is N micro-tokens per microsecond.
+#[derive(Debug)]
+struct TokenBucket {
+    rate_per_sec: u64,
+    capacity: u64,
+    available: u64,
+    last_refill_us: Option<u64>,
+}
+
+impl TokenBucket {
+    const TOKEN: u64 = 1_000_000;
+
+    fn new(rate_per_sec: u32) -> Self {
+        let capacity = rate_per_sec.max(1) as u64 * Self::TOKEN;
+        Self {
+            rate_per_sec: rate_per_sec.max(1) as u64,
+            capacity,
+            available: capacity,
+            last_refill_us: None,
+        }
+    }
+
+    fn try_take(&mut self, now_us: u64) -> bool {
+        if let Some(last) = self.last_refill_us {
+            let elapsed = now_us.saturating_sub(last);
+            let refill = elapsed.saturating_mul(self.rate_per_sec);
+            self.available = self.available.saturating_add(refill).min(self.capacity);
+        }
+        self.last_refill_us = Some(now_us);
+        if self.available >= Self::TOKEN {
+            self.available -= Self::TOKEN;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn reset(&mut self) {
+        self.available = self.capacity;
+        self.last_refill_us = None;
+    }
+}
+
+/// Sliding window of `issued_at_unix_us - unix_us` for one client. Positive
+/// means the agent's clock runs ahead; transit time and agent-side queueing
+/// pull it negative.
+#[derive(Debug, Default)]
+struct ClockOffsetWindow {
+    samples_us: VecDeque<i64>,
+    sum_us: i64,
+    warned: bool,
+}
+
+impl ClockOffsetWindow {
+    const LEN: usize = 32;
+
+    /// Add a sample and return the window mean
+    fn push(&mut self, offset_us: i64) -> i64 {
+        if self.samples_us.len() == Self::LEN {
+            let oldest = self.samples_us.pop_front().unwrap_or_default();
+            self.sum_us = self.sum_us.saturating_sub(oldest);
+        }
+        self.samples_us.push_back(offset_us);
+        self.sum_us = self.sum_us.saturating_add(offset_us);
+        self.sum_us / self.samples_us.len() as i64
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Most clients [`SequenceFloors`] tracks; new ones get no floor beyond it
+pub const MAX_SEQUENCE_FLOORS: usize = 4096;
+
+/// Minimum interval between writes of persisted sequence floors
+pub const SEQUENCE_FLOORS_PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Highest recommendation sequence accepted from each client, shared by
+/// every connection. A client that reconnects under the same identity must
+/// continue above its floor, so it cannot replay sequences it already used.
+/// With auth the identity is the token `sub`; without it, the hello
+/// `client_id`, which `hello_ack` then reports the floor for. Clients with
+/// neither get none.
+#[derive(Debug, Default)]
+pub struct SequenceFloors {
+    floors: Mutex<BTreeMap<String, u64>>,
+    /// Written at most every [`SEQUENCE_FLOORS_PERSIST_INTERVAL`] when set
+    persistence: Option<FloorPersistence>,
+}
+
+/// Where persisted floors go and the thread that writes them
+#[derive(Debug)]
+struct FloorPersistence {
+    path: PathBuf,
+    last_write: Mutex<Option<Instant>>,
+    /// Raised since the last write
+    dirty: AtomicBool,
+    writer: Option<FloorWriter>,
+}
+
+/// Background thread that writes copied floors, off the bridge thread
+#[derive(Debug)]
+struct FloorWriter {
+    sender: SyncSender<BTreeMap<String, u64>>,
+    handle: JoinHandle<()>,
+}
+
+impl FloorWriter {
+    fn spawn(path: PathBuf) -> std::io::Result<Self> {
+        // One slot: floors still waiting to be written are recent enough.
+        let (sender, receiver) = mpsc::sync_channel::<BTreeMap<String, u64>>(1);
+        let handle = std::thread::Builder::new()
+            .name("sequence-floors".to_string())
+            .spawn(move || {
+                for floors in receiver {
+                    if let Err(e) = SequenceFloors::write(&path, &floors) {
+                        warn!(path = %path.display(), error = %e, "Failed to persist sequence floors");
+                    }
+                }
+            })?;
+        Ok(Self { sender, handle })
+    }
+}
+
+impl SequenceFloors {
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Floors persisted at `path`, which need not exist yet
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let floors = match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        let writer = match FloorWriter::spawn(path.to_path_buf()) {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                warn!(error = %e, "Failed to start sequence floor writer; writing inline");
+                None
+            }
+        };
+        Ok(Self {
+            floors: Mutex::new(floors),
+            persistence: Some(FloorPersistence {
+                path: path.to_path_buf(),
+                last_write: Mutex::new(None),
+                dirty: AtomicBool::new(false),
+                writer,
+            }),
+        })
+    }
+
+    pub fn floor(&self, client: &str) -> Option<u64> {
+        self.floors.lock().unwrap().get(client).copied()
+    }
+
+    fn raise(&self, client: &str, sequence: u64) {
+        let mut floors = self.floors.lock().unwrap();
+        let tracked = floors.len();
+        match floors.get_mut(client) {
+            Some(floor) if sequence <= *floor => return,
+            Some(floor) => *floor = sequence,
+            None if tracked >= MAX_SEQUENCE_FLOORS => {
+                warn!(
+                    client,
+                    max_clients = MAX_SEQUENCE_FLOORS,
+                    "Sequence floor table full; client gets no floor"
+                );
+                return;
+            }
+            None => {
+                floors.insert(client.to_string(), sequence);
+            }
+        }
+        let Some(persistence) = &self.persistence else {
+            return;
+        };
+        persistence.dirty.store(true, Ordering::Relaxed);
+        let mut last_write = persistence.last_write.lock().unwrap();
+        if last_write.is_some_and(|at| at.elapsed() < SEQUENCE_FLOORS_PERSIST_INTERVAL) {
+            return;
+        }
+        *last_write = Some(Instant::now());
+        persistence.dirty.store(false, Ordering::Relaxed);
+        // Copy under the lock; serializing and writing happen on the writer.
+        let copy = floors.clone();
+        drop(floors);
+        match &persistence.writer {
+            Some(writer) => {
+                if let Err(TrySendError::Disconnected(_)) = writer.sender.try_send(copy) {
+                    warn!(path = %persistence.path.display(), "Sequence floor writer stopped");
+                }
+            }
+            None => {
+                if let Err(e) = Self::write(&persistence.path, &copy) {
+                    warn!(path = %persistence.path.display(), error = %e, "Failed to persist sequence floors");
+                }
+            }
+        }
+    }
+
+    /// Write the floors to disk now, if they are persisted
+    pub fn snapshot(&self) {
+        if let Some(persistence) = &self.persistence {
+            persistence.dirty.store(false, Ordering::Relaxed);
+            let copy = self
+                .floors
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone();
+            if let Err(e) = Self::write(&persistence.path, &copy) {
+                warn!(path = %persistence.path.display(), error = %e, "Failed to persist sequence floors");
+            }
+        }
+    }
+
+    /// Replace the file atomically so a crash never leaves it truncated
+    fn write(path: &Path, floors: &BTreeMap<String, u64>) -> std::io::Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(floors)?)?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+impl Drop for SequenceFloors {
+    fn drop(&mut self) {
+        let Some(persistence) = self.persistence.as_mut() else {
+            return;
+        };
+        // Let a pending write land before the final one replaces it.
+        if let Some(writer) = persistence.writer.take() {
+            drop(writer.sender);
+            let _ = writer.handle.join();
+        }
+        if persistence.dirty.load(Ordering::Relaxed) {
+            self.snapshot();
+        }
+    }
+}
+
 #[derive(Debug)]
-struct InboundState {
+pub(crate) struct InboundState {
     last_sequence: Option<u64>,
     handshake_seen: bool,
     capabilities: Vec<String>,
     client_id: Option<String>,
+    peer_addr: Option<String>,
+    rate_limiter: Option<TokenBucket>,
+    min_confidence: f32,
+    duplicate_reasoning: DuplicateReasoning,
+    last_reasoning_hash: Option<ReasoningHash>,
+    /// Consecutive accepted recommendations that repeated `last_reasoning_hash`
+    reasoning_repeats: u32,
+    max_clock_skew_us: u64,
+    clock_offsets: ClockOffsetWindow,
+    require_signature: bool,
+    reasoning_hash_bytes: usize,
+    safety_limits: Option<SafetyLimits>,
+    sequence_floors: Option<Arc<SequenceFloors>>,
+    effective_config: Option<Arc<serde_json::Value>>,
+    /// Last recommendation submitted whose outcome is not yet audited; kept
+    /// across reconnects so late outcomes are still logged.
+    unaudited_submission: Option<UnauditedSubmission>,
+}
+
+/// A recommendation handed to the control loop, awaiting its applied or
+/// rejected audit entry
+#[derive(Debug, Clone)]
+struct UnauditedSubmission {
+    /// `timestamp_us` stamped on submission
+    submitted_us: u64,
+    reasoning_hash: ReasoningHash,
+    /// `reasoning_hash` as the agent sent it
+    reasoning_hash_hex: String,
+    target_speed: f64,
+    confidence: f32,
+    correlation_id: String,
+    /// Motor speed published when it was submitted
+    previous_speed: f64,
 }
 
 impl InboundState {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             last_sequence: None,
             handshake_seen: false,
             capabilities: Vec::new(),
             client_id: None,
+            peer_addr: None,
+            rate_limiter: None,
+            min_confidence: 0.0,
+            duplicate_reasoning: DuplicateReasoning::Off,
+            last_reasoning_hash: None,
+            reasoning_repeats: 0,
+            max_clock_skew_us: 5_000_000,
+            clock_offsets: ClockOffsetWindow::default(),
+            require_signature: false,
+            reasoning_hash_bytes: 32,
+            safety_limits: None,
+            sequence_floors: None,
+            effective_config: None,
+            unaudited_submission: None,
+        }
+    }
+
+    pub(crate) fn with_rate_limit(max_per_sec: Option<u32>) -> Self {
+        Self {
+            rate_limiter: max_per_sec.map(TokenBucket::new),
+            ..Self::new()
+        }
+    }
+
+    pub(crate) fn with_min_confidence(self, min_confidence: f32) -> Self {
+        Self {
+            min_confidence,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_duplicate_reasoning(self, duplicate_reasoning: DuplicateReasoning) -> Self {
+        Self {
+            duplicate_reasoning,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_max_clock_skew(self, max_clock_skew: Duration) -> Self {
+        Self {
+            max_clock_skew_us: max_clock_skew.as_micros() as u64,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_require_signature(self, require_signature: bool) -> Self {
+        Self {
+            require_signature,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_reasoning_hash_bytes(self, reasoning_hash_bytes: usize) -> Self {
+        Self {
+            reasoning_hash_bytes,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_safety_limits(self, safety_limits: Option<SafetyLimits>) -> Self {
+        Self {
+            safety_limits,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_sequence_floors(self, sequence_floors: Option<Arc<SequenceFloors>>) -> Self {
+        Self {
+            sequence_floors,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_effective_config(
+        self,
+        effective_config: Option<Arc<serde_json::Value>>,
+    ) -> Self {
+        Self {
+            effective_config,
+            ..self
         }
     }
 
     fn reset(&mut self) {
         self.last_sequence = None;
+        self.clock_offsets.clear();
+        self.last_reasoning_hash = None;
+        self.reasoning_repeats = 0;
         self.handshake_seen = false;
         self.capabilities.clear();
         self.client_id = None;
+        self.peer_addr = None;
+        if let Some(limiter) = self.rate_limiter.as_mut() {
+            limiter.reset();
+        }
+    }
+
+    fn allow_recommendation(&mut self, now_us: u64) -> bool {
+        match self.rate_limiter.as_mut() {
+            Some(limiter) => limiter.try_take(now_us),
+            None => true,
+        }
     }
 
-    fn accept_sequence(&mut self, sequence: u64) -> bool {
+    /// Whether `sequence` is above both this connection's last accepted one
+    /// and the floor kept under `floor_key`. Nothing is recorded until
+    /// [`Self::commit_sequence`], so unaccepted messages never move either.
+    fn check_sequence(&self, sequence: u64, floor_key: Option<&str>) -> bool {
         if sequence == 0 {
             warn!("Recommendation sequence missing or zero");
             return false;
         }
-        if let Some(last) = self.last_sequence {
+        let floor = floor_key.and_then(|key| self.sequence_floors.as_ref()?.floor(key));
+        if let Some(last) = self.last_sequence.max(floor) {
             if sequence <= last {
                 warn!(
                     sequence,
@@ -132,10 +859,105 @@ impl InboundState {
                 return false;
             }
         }
-        self.last_sequence = Some(sequence);
         true
     }
 
+    /// Record the sequence of a message that was accepted
+    fn commit_sequence(&mut self, envelope: &AcceptedEnvelope) {
+        self.last_sequence = Some(envelope.sequence);
+        if let (Some(floors), Some(key)) = (&self.sequence_floors, &envelope.floor_key) {
+            floors.raise(key, envelope.sequence);
+        }
+    }
+
+    /// Floor for the hello `client_id`; only used without auth, where that
+    /// id is what floors are kept under
+    fn sequence_floor(&self) -> Option<u64> {
+        let floors = self.sequence_floors.as_ref()?;
+        floors.floor(self.client_id.as_deref()?)
+    }
+
+    /// Record the agent's clock offset and warn, once per excursion, while
+    /// its mean sits past half the skew that gets recommendations rejected.
+    fn note_clock_offset(&mut self, issued_at_unix_us: u64, now_unix_us: u64) {
+        let offset_us = (issued_at_unix_us as i64).saturating_sub(now_unix_us as i64);
+        let mean_us = self.clock_offsets.push(offset_us);
+        AGENT_CLOCK_OFFSET_MS.set(mean_us as f64 / 1_000.0);
+
+        // Only running ahead is bounded; lag is covered by the TTL check.
+        let ahead_us = mean_us.max(0) as u64;
+        if ahead_us >= self.max_clock_skew_us / 2 {
+            if !self.clock_offsets.warned {
+                self.clock_offsets.warned = true;
+                warn!(
+                    offset_ms = mean_us / 1_000,
+                    max_skew_ms = self.max_clock_skew_us / 1_000,
+                    client_id = ?self.client_id,
+                    "Agent clock offset is approaching the rejection limit"
+                );
+            }
+        } else if ahead_us < self.max_clock_skew_us / 4 {
+            self.clock_offsets.warned = false;
+        }
+    }
+
+    /// Track `hash` against the last accepted one; false if the recommendation
+    /// should be rejected as a duplicate.
+    fn accept_reasoning(&mut self, hash: ReasoningHash) -> bool {
+        let (max_repeats, reject) = match self.duplicate_reasoning {
+            DuplicateReasoning::Off => return true,
+            DuplicateReasoning::Count { max_repeats } => (max_repeats, false),
+            DuplicateReasoning::Reject { max_repeats } => (max_repeats, true),
+        };
+        if self.last_reasoning_hash != Some(hash) {
+            self.last_reasoning_hash = Some(hash);
+            self.reasoning_repeats = 0;
+            return true;
+        }
+        if self.reasoning_repeats >= max_repeats {
+            warn!(
+                repeats = self.reasoning_repeats,
+                max_repeats, "Recommendation repeats the previous reasoning_hash"
+            );
+            DUPLICATE_REASONING.inc();
+            if reject {
+                return false;
+            }
+        }
+        self.reasoning_repeats = self.reasoning_repeats.saturating_add(1);
+        true
+    }
+
+    fn declared(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    /// The client asked for [`CRC32_FRAMING_CAPABILITY`]
+    pub(crate) fn crc32_framing(&self) -> bool {
+        self.declared(CRC32_FRAMING_CAPABILITY)
+    }
+
+    #[cfg(feature = "proto")]
+    fn accepts_zstd(&self) -> bool {
+        self.declared(ZSTD_CAPABILITY)
+    }
+
+    pub(crate) fn note_peer(&mut self, addr: SocketAddr) {
+        self.peer_addr = Some(addr.to_string());
+    }
+
+    /// `<client>/<sequence>`, naming the client by its hello `client_id`,
+    /// else its address. Sequences never repeat for a client, so this
+    /// identifies one decision across logs and the audit trail.
+    fn correlation_id(&self, sequence: u64) -> String {
+        let client = self
+            .client_id
+            .as_deref()
+            .or(self.peer_addr.as_deref())
+            .unwrap_or("unknown");
+        format!("{client}/{sequence}")
+    }
+
     fn note_handshake(&mut self, hello: &HelloMsg) {
         self.handshake_seen = true;
         self.capabilities = hello.capabilities.clone();
@@ -143,66 +965,212 @@ impl InboundState {
     }
 }
 
-pub fn run_bridge(
-    exchange: Arc<StateExchange>,
-    timebase: TimeBase,
-    config: BridgeConfig,
-    stop: Arc<AtomicBool>,
-) {
-    let listener = TcpListener::bind(&config.bind_addr)
-        .unwrap_or_else(|e| panic!("Failed to bind {}: {}", config.bind_addr, e));
-    listener
-        .set_nonblocking(true)
-        .expect("Failed to set nonblocking");
+/// Longest backlog of unsent bytes behind which a `reject` is still queued.
+/// Rejections are best-effort and are dropped rather than delay state frames.
+const MAX_REJECT_BACKLOG_BYTES: usize = 16 * 1024;
 
-    info!(
-        addr = %config.bind_addr,
-        tls = config.tls.enabled,
-        auth = config.auth.enabled,
-        protocol = %config.wire_protocol.as_str(),
-        "Bridge listening"
-    );
+/// Outbound reply produced while handling an inbound message.
+#[derive(Debug)]
+pub(crate) enum BridgeReply {
+    HelloAck { sequence_floor: Option<u64> },
+    VersionError(ProtocolVersion),
+    Reject(RejectMsg),
+    Config(ConfigMsg),
+}
 
-    let tls_config = if config.tls.enabled {
-        match build_server_config(&config.tls) {
-            Ok(c) => Some(c),
-            Err(e) => {
-                error!(error = %e, "Failed to configure TLS");
-                return;
+/// Capabilities advertised in `hello_ack`, derived from the bridge config.
+pub(crate) fn server_capabilities(config: &BridgeConfig) -> Vec<String> {
+    let mut caps = vec!["recommendation.v1".to_string(), RAMP_CAPABILITY.to_string()];
+    if config.wire_protocol == WireProtocol::JsonLines {
+        caps.push(ESTOP_CAPABILITY.to_string());
+        caps.push(CRC32_FRAMING_CAPABILITY.to_string());
+        if config.auth.enabled && config.safety_limits.is_some() {
+            caps.push(SET_LIMITS_CAPABILITY.to_string());
+        }
+        if config.auth.enabled && config.effective_config.is_some() {
+            caps.push(GET_CONFIG_CAPABILITY.to_string());
+        }
+    }
+    if config.auth.enabled {
+        caps.push(match config.auth.algorithm {
+            AuthAlgorithm::HmacSha256 => "auth.hmac-sha256".to_string(),
+            AuthAlgorithm::Ed25519 => "auth.ed25519".to_string(),
+        });
+    }
+    if config.ping_interval.is_some() && config.wire_protocol == WireProtocol::JsonLines {
+        caps.push("ping.v1".to_string());
+    }
+    if cfg!(feature = "proto")
+        && config.wire_protocol == WireProtocol::Protobuf
+        && config.compress_frames_over.is_some()
+    {
+        caps.push(ZSTD_CAPABILITY.to_string());
+    }
+    caps
+}
+
+/// Encode a reply as a complete frame for the given wire protocol.
+pub(crate) fn encode_reply(
+    reply: &BridgeReply,
+    wire_protocol: WireProtocol,
+    capabilities: &[String],
+) -> Option<Vec<u8>> {
+    match wire_protocol {
+        WireProtocol::JsonLines => {
+            let line = match reply {
+                BridgeReply::HelloAck { sequence_floor } => serde_json::to_string(
+                    &HelloAckMsg::new(capabilities.to_vec(), wire_protocol.as_str())
+                        .with_sequence_floor(*sequence_floor),
+                ),
+                BridgeReply::VersionError(requested) => {
+                    serde_json::to_string(&ErrorMsg::unsupported_version(*requested))
+                }
+                BridgeReply::Reject(reject) => serde_json::to_string(reject),
+                BridgeReply::Config(config) => serde_json::to_string(config),
+            };
+            let mut frame = line.ok()?.into_bytes();
+            frame.push(b'\n');
+            Some(frame)
+        }
+        WireProtocol::Protobuf => {
+            #[cfg(feature = "proto")]
+            {
+                let payload = match reply {
+                    BridgeReply::HelloAck { sequence_floor } => {
+                        proto::wire_message::Payload::HelloAck(
+                            HelloAckMsg::new(capabilities.to_vec(), wire_protocol.as_str())
+                                .with_sequence_floor(*sequence_floor)
+                                .into(),
+                        )
+                    }
+                    BridgeReply::VersionError(requested) => proto::wire_message::Payload::Error(
+                        ErrorMsg::unsupported_version(*requested).into(),
+                    ),
+                    BridgeReply::Reject(reject) => {
+                        proto::wire_message::Payload::Reject(reject.into())
+                    }
+                    // Commands are JSON-lines only.
+                    BridgeReply::Config(_) => return None,
+                };
+                let wire = proto::WireMessage {
+                    payload: Some(payload),
+                };
+                let mut body = Vec::new();
+                wire.encode(&mut body).ok()?;
+                Some(binary_frame(body, None))
+            }
+            #[cfg(not(feature = "proto"))]
+            {
+                let _ = (reply, capabilities);
+                None
             }
         }
-    } else {
-        None
-    };
+    }
+}
 
-    let validator = if config.auth.enabled {
-        Some(TokenValidator::from_config(&config.auth))
-    } else {
-        None
+/// Counts publish intervals missed because the client has not drained the
+/// previous frame.
+#[derive(Debug)]
+struct SlowClientMonitor {
+    limit: u32,
+    stalled: u32,
+}
+
+impl SlowClientMonitor {
+    fn new(limit: u32) -> Self {
+        Self { limit, stalled: 0 }
+    }
+
+    /// Record a publish interval. Returns true once the client has stalled
+    /// for `limit` consecutive intervals and should be dropped.
+    fn on_interval(&mut self, frame_pending: bool) -> bool {
+        if !frame_pending {
+            self.stalled = 0;
+            return false;
+        }
+        self.stalled = self.stalled.saturating_add(1);
+        self.limit > 0 && self.stalled >= self.limit
+    }
+
+    fn reset(&mut self) {
+        self.stalled = 0;
+    }
+}
+
+/// Serve one agent connection at a time until `stop` is set. Returns an
+/// error instead of serving when the listener or TLS cannot be set up.
+/// With `audit`, accepted recommendations and their safety rejections are
+/// written to the audit trail.
+pub fn run_bridge<C: Clock>(
+    exchange: Arc<StateExchange>,
+    clock: C,
+    config: BridgeConfig,
+    stop: Arc<AtomicBool>,
+    audit: Option<Arc<AuditLogger>>,
+) -> Result<(), BridgeError> {
+    let listeners = config
+        .bind_addrs()
+        .map(bind_listener)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut next_listener = 0;
+
+    info!(
+        addrs = ?config.bind_addrs().collect::<Vec<_>>(),
+        tls = config.tls.enabled,
+        auth = config.auth.enabled,
+        protocol = %config.wire_protocol.as_str(),
+        "Bridge listening"
+    );
+
+    let mut tls_config = if config.tls.enabled {
+        Some(ReloadableServerConfig::new(&config.tls)?)
+    } else {
+        None
     };
 
+    let validator = config.token_validator();
+
+    HEALTH.set_bridge_listening(true);
+
     let mut client: Option<BridgeStream> = None;
     let mut recv_buf: Vec<u8> = Vec::with_capacity(4096);
     let mut send_buf: Vec<u8> = Vec::new();
     let mut send_offset: usize = 0;
-    let mut last_publish = Instant::now();
+    let mut publish = PublishSchedule::new(config.publish_mode);
     let mut state_sequence: u64 = 0;
-    let mut inbound_state = InboundState::new();
+    let mut inbound_state = InboundState::with_rate_limit(config.max_recommendations_per_sec)
+        .with_min_confidence(config.min_confidence)
+        .with_duplicate_reasoning(config.duplicate_reasoning)
+        .with_max_clock_skew(config.max_clock_skew)
+        .with_require_signature(config.require_signature)
+        .with_reasoning_hash_bytes(config.reasoning_hash_bytes)
+        .with_safety_limits(config.safety_limits)
+        .with_sequence_floors(config.sequence_floors.clone())
+        .with_effective_config(config.effective_config.clone());
+    let mut slow_client = SlowClientMonitor::new(config.max_stalled_publishes);
+    let mut last_activity = Instant::now();
+    let mut last_ping = Instant::now();
+    let mut ping_sequence: u64 = 0;
+    let capabilities = server_capabilities(&config);
+    let poll_interval = config.effective_poll_interval();
+    // Set after queueing an error frame; the client is closed once it drains.
+    let mut close_after_send = false;
 
     loop {
         if stop.load(std::sync::atomic::Ordering::Relaxed) {
             break;
         }
         if client.is_none() {
-            match listener.accept() {
+            match accept_any(&listeners, &mut next_listener) {
                 Ok((stream, addr)) => {
                     info!(client_addr = %addr, "Bridge client connected");
+                    inbound_state.note_peer(addr);
                     stream
                         .set_nonblocking(true)
                         .expect("Failed to set nonblocking on client");
 
-                    if let Some(tls_cfg) = &tls_config {
-                        match ServerConnection::new(tls_cfg.clone()) {
+                    if let Some(tls_cfg) = tls_config.as_mut() {
+                        match ServerConnection::new(tls_cfg.current()) {
                             Ok(conn) => {
                                 client = Some(BridgeStream::Tls(Box::new(StreamOwned::new(
                                     conn, stream,
@@ -216,6 +1184,8 @@ pub fn run_bridge(
                         client = Some(BridgeStream::Plain(stream));
                     }
                     BRIDGE_CONNECTED.set(1.0);
+                    last_activity = Instant::now();
+                    last_ping = Instant::now();
                 }
                 Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
                 Err(err) => {
@@ -235,43 +1205,72 @@ pub fn run_bridge(
                     BRIDGE_CONNECTED.set(0.0);
                 }
                 Ok(n) => {
+                    last_activity = Instant::now();
                     recv_buf.extend_from_slice(&temp[..n]);
                     match config.wire_protocol {
                         WireProtocol::JsonLines => {
                             while let Some(pos) = recv_buf.iter().position(|b| *b == b'\n') {
+                                if close_after_send {
+                                    recv_buf.clear();
+                                    break;
+                                }
                                 let line = recv_buf.drain(..=pos).collect::<Vec<u8>>();
                                 if let Ok(text) = std::str::from_utf8(&line) {
-                                    let trimmed = text.trim();
-                                    if trimmed.is_empty() {
+                                    if text.trim().is_empty() {
                                         continue;
                                     }
-                                    if let Some(msg) = IncomingMessage::parse(trimmed) {
-                                        handle_incoming(
+                                    let Some(trimmed) = checked_line(text, &inbound_state) else {
+                                        continue;
+                                    };
+                                    if let Some(msg) = parse_incoming(trimmed) {
+                                        let validator =
+                                            validator.as_ref().map(|v| v.lock().unwrap());
+                                        let reply = handle_incoming(
                                             msg,
                                             &exchange,
-                                            &timebase,
-                                            &validator,
+                                            &clock,
+                                            validator.as_deref(),
                                             config.require_handshake,
                                             &mut inbound_state,
+                                            audit.as_deref(),
                                         );
+                                        if let Some(reply) = reply {
+                                            close_after_send |= queue_reply(
+                                                &mut send_buf,
+                                                &reply,
+                                                config.wire_protocol,
+                                                &capabilities,
+                                                inbound_state.crc32_framing(),
+                                            );
+                                        }
                                     }
                                 }
                             }
+                            if recv_buf.len() > MAX_LINE_BYTES {
+                                warn!(len = recv_buf.len(), "Dropping client with oversized line");
+                                drop_client = true;
+                                BRIDGE_CONNECTED.set(0.0);
+                            }
                         }
                         WireProtocol::Protobuf => {
                             #[cfg(feature = "proto")]
                             {
-                                const MAX_FRAME_BYTES: usize = 256 * 1024;
                                 loop {
+                                    if close_after_send {
+                                        recv_buf.clear();
+                                        break;
+                                    }
                                     if recv_buf.len() < 4 {
                                         break;
                                     }
-                                    let len = u32::from_be_bytes([
+                                    let prefix = u32::from_be_bytes([
                                         recv_buf[0],
                                         recv_buf[1],
                                         recv_buf[2],
                                         recv_buf[3],
-                                    ]) as usize;
+                                    ]);
+                                    let compressed = prefix & COMPRESSED_FLAG != 0;
+                                    let len = (prefix & !COMPRESSED_FLAG) as usize;
                                     if len > MAX_FRAME_BYTES {
                                         warn!(len, "Dropping client with oversized frame");
                                         drop_client = true;
@@ -282,19 +1281,47 @@ pub fn run_bridge(
                                     }
                                     let payload = recv_buf[4..4 + len].to_vec();
                                     recv_buf.drain(..4 + len);
+                                    let payload = if !compressed {
+                                        payload
+                                    } else if config.compress_frames_over.is_none() {
+                                        warn!("Dropping client sending unnegotiated compressed frames");
+                                        drop_client = true;
+                                        break;
+                                    } else {
+                                        match decompress_frame(&payload) {
+                                            Some(payload) => payload,
+                                            None => {
+                                                warn!("Dropping client with undecodable compressed frame");
+                                                drop_client = true;
+                                                break;
+                                            }
+                                        }
+                                    };
                                     match proto::WireMessage::decode(payload.as_slice())
                                         .ok()
                                         .and_then(|msg| IncomingMessage::try_from(msg).ok())
                                     {
                                         Some(msg) => {
-                                            handle_incoming(
+                                            let validator =
+                                                validator.as_ref().map(|v| v.lock().unwrap());
+                                            let reply = handle_incoming(
                                                 msg,
                                                 &exchange,
-                                                &timebase,
-                                                &validator,
+                                                &clock,
+                                                validator.as_deref(),
                                                 config.require_handshake,
                                                 &mut inbound_state,
+                                                audit.as_deref(),
                                             );
+                                            if let Some(reply) = reply {
+                                                close_after_send |= queue_reply(
+                                                    &mut send_buf,
+                                                    &reply,
+                                                    config.wire_protocol,
+                                                    &capabilities,
+                                                    false,
+                                                );
+                                            }
                                         }
                                         None => {
                                             warn!("Failed to decode protobuf message");
@@ -311,6 +1338,13 @@ pub fn run_bridge(
                     }
                 }
                 Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(err) if stream.is_handshaking() => {
+                    let reason = handshake_failure_reason(&err);
+                    warn!(error = %err, reason, "Bridge TLS handshake failed");
+                    TLS_HANDSHAKE_FAILURES.with_label_values(&[reason]).inc();
+                    drop_client = true;
+                    BRIDGE_CONNECTED.set(0.0);
+                }
                 Err(err) => {
                     warn!(error = %err, "Bridge read error");
                     drop_client = true;
@@ -318,28 +1352,48 @@ pub fn run_bridge(
                 }
             }
 
+            if let Some(idle_timeout) = config.idle_timeout {
+                if !drop_client && last_activity.elapsed() >= idle_timeout {
+                    warn!(
+                        idle_ms = last_activity.elapsed().as_millis() as u64,
+                        "Dropping idle bridge client"
+                    );
+                    drop_client = true;
+                    BRIDGE_CONNECTED.set(0.0);
+                }
+            }
+
             // Publish state
-            if send_buf.is_empty() && last_publish.elapsed() >= config.publish_interval {
+            let Sequenced {
+                sequence: snapshot_sequence,
+                value: snapshot,
+            } = exchange.read_state_sequenced();
+            let now = Instant::now();
+            let publish_due = publish.due(&snapshot, now);
+            if publish_due && slow_client.on_interval(!send_buf.is_empty()) {
+                warn!(
+                    stalled_intervals = slow_client.stalled,
+                    pending_bytes = send_buf.len() - send_offset,
+                    "Dropping slow bridge client"
+                );
+                BRIDGE_SLOW_CLIENT_DROPS.inc();
+                drop_client = true;
+                BRIDGE_CONNECTED.set(0.0);
+            } else if publish_due && !send_buf.is_empty() {
+                publish.note_skipped(now);
+            } else if publish_due {
                 state_sequence = state_sequence.wrapping_add(1);
-                let snapshot = exchange.read_state();
                 match config.wire_protocol {
                     WireProtocol::JsonLines => {
-                        let msg = StateMsg {
-                            msg_type: "state",
-                            protocol_version: crate::protocol::ProtocolVersion::v1(),
-                            sequence: state_sequence,
-                            timestamp_us: snapshot.timestamp_us,
-                            cycle_count: snapshot.cycle_count,
-                            safety_state: snapshot.safety_state.as_str(),
-                            unix_us: timebase.unix_us(),
-                            motor_speed_rpm: snapshot.motor_speed_rpm,
-                            motor_temp_c: snapshot.motor_temp_c,
-                            pressure_bar: snapshot.pressure_bar,
-                            cycle_jitter_us: snapshot.cycle_jitter_us,
-                        };
+                        let msg =
+                            StateMsg::from_snapshot(&snapshot, state_sequence, clock.unix_us())
+                                .with_snapshot_sequence(snapshot_sequence);
                         if let Ok(line) = serde_json::to_string(&msg) {
                             send_buf = line.into_bytes();
                             send_buf.push(b'\n');
+                            if inbound_state.crc32_framing() {
+                                append_crc32(&mut send_buf);
+                            }
                             send_offset = 0;
                             trace!(sequence = state_sequence, "Bridge queued state frame");
                         }
@@ -356,20 +1410,25 @@ pub fn run_bridge(
                                 timestamp_us: snapshot.timestamp_us,
                                 cycle_count: snapshot.cycle_count,
                                 safety_state: snapshot.safety_state.as_str().to_string(),
-                                unix_us: timebase.unix_us(),
+                                unix_us: clock.unix_us(),
                                 motor_speed_rpm: snapshot.motor_speed_rpm,
                                 motor_temp_c: snapshot.motor_temp_c,
                                 pressure_bar: snapshot.pressure_bar,
                                 cycle_jitter_us: snapshot.cycle_jitter_us,
+                                applied_reasoning_hash: crate::audit::to_hex(
+                                    snapshot.applied_reasoning_hash.as_bytes(),
+                                ),
+                                snapshot_sequence,
                             };
                             let wire = proto::WireMessage {
                                 payload: Some(proto::wire_message::Payload::State(msg)),
                             };
                             let mut frame = Vec::new();
                             if wire.encode(&mut frame).is_ok() {
-                                let len = frame.len() as u32;
-                                send_buf = len.to_be_bytes().to_vec();
-                                send_buf.extend_from_slice(&frame);
+                                let compress_over = config
+                                    .compress_frames_over
+                                    .filter(|_| inbound_state.accepts_zstd());
+                                send_buf = binary_frame(frame, compress_over);
                                 send_offset = 0;
                             }
                         }
@@ -381,10 +1440,35 @@ pub fn run_bridge(
                         }
                     }
                 }
-                last_publish = Instant::now();
+                publish.note_sent(snapshot, now);
+            }
+
+            if let Some(ping_interval) = config.ping_interval {
+                if send_buf.is_empty()
+                    && config.wire_protocol == WireProtocol::JsonLines
+                    && last_ping.elapsed() >= ping_interval
+                {
+                    ping_sequence = ping_sequence.wrapping_add(1);
+                    let msg = PingMsg {
+                        msg_type: "ping",
+                        protocol_version: crate::protocol::ProtocolVersion::v1(),
+                        sequence: ping_sequence,
+                        unix_us: clock.unix_us(),
+                    };
+                    if let Ok(line) = serde_json::to_string(&msg) {
+                        send_buf = line.into_bytes();
+                        send_buf.push(b'\n');
+                        if inbound_state.crc32_framing() {
+                            append_crc32(&mut send_buf);
+                        }
+                        send_offset = 0;
+                        trace!(sequence = ping_sequence, "Bridge queued ping frame");
+                    }
+                    last_ping = Instant::now();
+                }
             }
 
-            if !send_buf.is_empty() {
+            if !drop_client && !send_buf.is_empty() {
                 match stream.write(&send_buf[send_offset..]) {
                     Ok(0) => {
                         info!("Bridge client disconnected");
@@ -407,6 +1491,16 @@ pub fn run_bridge(
                     }
                 }
             }
+
+            if close_after_send && send_buf.is_empty() && !drop_client {
+                info!("Closing bridge client after handshake rejection");
+                drop_client = true;
+                BRIDGE_CONNECTED.set(0.0);
+            }
+        }
+
+        if let Some(audit) = audit.as_deref() {
+            audit_outcome(&exchange, &clock, audit, &mut inbound_state);
         }
 
         if drop_client {
@@ -415,21 +1509,172 @@ pub fn run_bridge(
             send_buf.clear();
             send_offset = 0;
             inbound_state.reset();
+            slow_client.reset();
+            publish.reset();
+            close_after_send = false;
+        }
+
+        std::thread::sleep(poll_interval);
+    }
+    HEALTH.set_bridge_listening(false);
+    Ok(())
+}
+
+/// Bind a nonblocking listener on `addr`.
+pub(crate) fn bind_listener(addr: &str) -> Result<TcpListener, BridgeError> {
+    let listener = TcpListener::bind(addr).map_err(|source| BridgeError::Bind {
+        addr: addr.to_string(),
+        source,
+    })?;
+    listener
+        .set_nonblocking(true)
+        .map_err(BridgeError::Listener)?;
+    Ok(listener)
+}
+
+/// Accept from the first listener with a pending connection, starting after
+/// the one that accepted last so no address starves the others. Fails with
+/// `WouldBlock` when none has a connection waiting.
+fn accept_any(
+    listeners: &[TcpListener],
+    next: &mut usize,
+) -> std::io::Result<(TcpStream, SocketAddr)> {
+    for offset in 0..listeners.len() {
+        let index = (*next + offset) % listeners.len();
+        match listeners[index].accept() {
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+            result => {
+                *next = index + 1;
+                return result;
+            }
+        }
+    }
+    Err(std::io::ErrorKind::WouldBlock.into())
+}
+
+/// Append a reply frame behind any partially written frame, with a CRC-32
+/// suffix if `crc32` is set. The `hello_ack` is always sent without one so
+/// a client can read it before knowing whether framing was accepted.
+/// Returns true when the connection should be closed once the frame is
+/// flushed.
+fn queue_reply(
+    send_buf: &mut Vec<u8>,
+    reply: &BridgeReply,
+    wire_protocol: WireProtocol,
+    capabilities: &[String],
+    crc32: bool,
+) -> bool {
+    if let BridgeReply::Reject(reject) = reply {
+        if send_buf.len() > MAX_REJECT_BACKLOG_BYTES {
+            debug!(
+                sequence = reject.sequence,
+                backlog = send_buf.len(),
+                "Skipping reject frame for backlogged client"
+            );
+            return false;
         }
+    }
+    match encode_reply(reply, wire_protocol, capabilities) {
+        Some(mut frame) => {
+            if crc32 && !matches!(reply, BridgeReply::HelloAck { .. }) {
+                append_crc32(&mut frame);
+            }
+            send_buf.extend_from_slice(&frame);
+        }
+        None => warn!("Failed to encode bridge reply"),
+    }
+    matches!(reply, BridgeReply::VersionError(_))
+}
+
+/// Put a tab and the CRC-32 of the JSON, as 8 hex digits, before the
+/// trailing newline of a JSON-lines frame.
+pub(crate) fn append_crc32(frame: &mut Vec<u8>) {
+    if frame.last() == Some(&b'\n') {
+        frame.pop();
+    }
+    let crc = crc32fast::hash(frame);
+    frame.extend_from_slice(format!("\t{crc:08x}\n").as_bytes());
+}
+
+/// The JSON of an inbound line, after checking and removing its CRC-32
+/// suffix when the client asked for framing. Lines with a missing or wrong
+/// checksum are counted and dropped, never parsed.
+pub(crate) fn checked_line<'a>(line: &'a str, inbound_state: &InboundState) -> Option<&'a str> {
+    if !inbound_state.crc32_framing() {
+        return Some(line.trim());
+    }
+    let framed = line.trim_end_matches(['\r', '\n']);
+    let verified = framed.rsplit_once('\t').and_then(|(json, checksum)| {
+        let expected = u32::from_str_radix(checksum, 16).ok();
+        (checksum.len() == 8 && expected == Some(crc32fast::hash(json.as_bytes())))
+            .then_some(json.trim())
+    });
+    if verified.is_none() {
+        FRAME_CHECKSUM_FAILURES.inc();
+        warn!(len = line.len(), "Dropping JSON line with a bad checksum");
+    }
+    verified
+}
 
-        std::thread::sleep(Duration::from_millis(5));
+/// States published after `last_seen` up to `current`. State is latest-value
+/// only, so these cannot be replayed, only counted. `None` when the client
+/// is ahead of the spine, e.g. after a spine restart.
+pub(crate) fn state_gap(last_seen: u64, current: u64) -> Option<u64> {
+    current.checked_sub(last_seen)
+}
+
+/// Parse one inbound JSON message, counting and logging why it was dropped
+pub(crate) fn parse_incoming(text: &str) -> Option<IncomingMessage> {
+    match IncomingMessage::parse(text) {
+        Ok(msg) => Some(msg),
+        Err(e) => {
+            PARSE_ERRORS.with_label_values(&[e.reason()]).inc();
+            debug!(error = %e, "Ignoring unparseable bridge message");
+            None
+        }
     }
 }
 
-#[instrument(skip(exchange, timebase, validator), fields(reasoning_hash))]
-fn handle_incoming(
+/// Handle one inbound message, counting any reject by reason
+#[instrument(
+    skip(exchange, clock, validator, audit),
+    fields(reasoning_hash, correlation_id)
+)]
+pub(crate) fn handle_incoming<C: Clock>(
     msg: IncomingMessage,
     exchange: &StateExchange,
-    timebase: &TimeBase,
-    validator: &Option<TokenValidator>,
+    clock: &C,
+    validator: Option<&TokenValidator>,
     require_handshake: bool,
     inbound_state: &mut InboundState,
-) {
+    audit: Option<&AuditLogger>,
+) -> Option<BridgeReply> {
+    let reply = handle_message(
+        msg,
+        exchange,
+        clock,
+        validator,
+        require_handshake,
+        inbound_state,
+        audit,
+    );
+    if let Some(BridgeReply::Reject(reject)) = &reply {
+        BRIDGE_REJECTS
+            .with_label_values(&[reject.reason.as_str()])
+            .inc();
+    }
+    reply
+}
+
+fn handle_message<C: Clock>(
+    msg: IncomingMessage,
+    exchange: &StateExchange,
+    clock: &C,
+    validator: Option<&TokenValidator>,
+    require_handshake: bool,
+    inbound_state: &mut InboundState,
+    audit: Option<&AuditLogger>,
+) -> Option<BridgeReply> {
     match msg {
         IncomingMessage::Hello(hello) => {
             if !hello.protocol_version.is_supported() {
@@ -438,150 +1683,2593 @@ fn handle_incoming(
                     minor = hello.protocol_version.minor,
                     "Unsupported protocol version"
                 );
-                return;
+                return Some(BridgeReply::VersionError(hello.protocol_version));
             }
             inbound_state.note_handshake(&hello);
+            if let Some(last_seen) = hello.last_snapshot_sequence {
+                let current = exchange.read_state_sequenced().sequence;
+                match state_gap(last_seen, current) {
+                    Some(gap) => {
+                        STATE_GAP.set(gap as f64);
+                        info!(
+                            client_id = ?hello.client_id,
+                            last_seen,
+                            gap,
+                            "Bridge client resumed"
+                        );
+                    }
+                    None => warn!(
+                        client_id = ?hello.client_id,
+                        last_seen,
+                        current,
+                        "Resuming client saw a later state than the spine has published"
+                    ),
+                }
+            }
             info!(
                 client_id = ?hello.client_id,
                 capabilities = ?hello.capabilities,
                 "Bridge handshake received"
             );
+            // With auth, floors belong to token subjects, which an
+            // unauthenticated hello cannot name.
+            Some(BridgeReply::HelloAck {
+                sequence_floor: validator
+                    .is_none()
+                    .then(|| inbound_state.sequence_floor())
+                    .flatten(),
+            })
+        }
+        IncomingMessage::Pong(pong) => {
+            trace!(sequence = pong.sequence, "Bridge pong received");
+            None
         }
         IncomingMessage::Recommendation(rec) => {
             Span::current().record("reasoning_hash", rec.reasoning_hash.as_str());
+            let reject = |reason: RejectReason| {
+                Some(BridgeReply::Reject(RejectMsg::new(
+                    reason,
+                    rec.sequence,
+                    rec.reasoning_hash.clone(),
+                )))
+            };
 
-            if !rec.protocol_version.is_supported() {
-                warn!(
-                    major = rec.protocol_version.major,
-                    minor = rec.protocol_version.minor,
-                    "Unsupported protocol version"
-                );
-                return;
+            let envelope = Envelope {
+                protocol_version: rec.protocol_version,
+                sequence: rec.sequence,
+                issued_at_unix_us: rec.issued_at_unix_us,
+                ttl_ms: rec.ttl_ms,
+                expires_at_unix_us: rec.expires_at_unix_us,
+                auth_token: &rec.auth_token,
+                action: "recommendation",
+            };
+            let accepted = match accept_envelope(
+                &envelope,
+                clock,
+                validator,
+                require_handshake,
+                inbound_state,
+            ) {
+                Ok(accepted) => accepted,
+                Err(reason) => return reject(reason),
+            };
+            if let Err(reason) = verify_signature(
+                &rec.signature,
+                || rec.signing_input(),
+                validator,
+                inbound_state,
+            ) {
+                return reject(reason);
             }
 
-            if require_handshake && !inbound_state.handshake_seen {
-                warn!("Recommendation received before handshake");
-                return;
+            let member = Member::from(&rec);
+            let hash = match check_member(&member, inbound_state) {
+                MemberCheck::Valid(hash) => hash,
+                MemberCheck::LowConfidence => return None,
+                MemberCheck::Invalid(reason) => return reject(reason),
+            };
+            if !inbound_state.accept_reasoning(hash) {
+                return reject(RejectReason::DuplicateReasoning);
             }
 
-            if !inbound_state.accept_sequence(rec.sequence) {
-                RECOMMENDATION_OUT_OF_ORDER.inc();
-                return;
-            }
+            inbound_state.commit_sequence(&accepted);
+            submit_member(
+                &member,
+                hash,
+                rec.sequence,
+                exchange,
+                clock,
+                inbound_state,
+                audit,
+            );
+            None
+        }
+        IncomingMessage::Batch(batch) => {
+            let reject = |reason: RejectReason, reasoning_hash: &str| {
+                Some(BridgeReply::Reject(RejectMsg::new(
+                    reason,
+                    batch.sequence,
+                    reasoning_hash.to_string(),
+                )))
+            };
 
-            if rec.ttl_ms == 0 {
-                warn!("Missing recommendation TTL");
-                return;
-            }
-            if rec.issued_at_unix_us == 0 {
-                warn!("Missing recommendation issued_at_unix_us");
-                return;
+            let envelope = Envelope {
+                protocol_version: batch.protocol_version,
+                sequence: batch.sequence,
+                issued_at_unix_us: batch.issued_at_unix_us,
+                ttl_ms: batch.ttl_ms,
+                expires_at_unix_us: batch.expires_at_unix_us,
+                auth_token: &batch.auth_token,
+                action: "batch",
+            };
+            let accepted = match accept_envelope(
+                &envelope,
+                clock,
+                validator,
+                require_handshake,
+                inbound_state,
+            ) {
+                Ok(accepted) => accepted,
+                Err(reason) => return reject(reason, ""),
+            };
+            if let Err(reason) = verify_signature(
+                &batch.signature,
+                || batch.signing_input(),
+                validator,
+                inbound_state,
+            ) {
+                return reject(reason, "");
             }
-            let now_unix_us = timebase.unix_us();
-            const MAX_CLOCK_SKEW_MS: u64 = 5_000;
-            let max_skew_us = MAX_CLOCK_SKEW_MS * 1_000;
-            if rec.issued_at_unix_us > now_unix_us.saturating_add(max_skew_us) {
+            if !inbound_state.declared(BATCH_CAPABILITY) {
                 warn!(
-                    issued_at_unix_us = rec.issued_at_unix_us,
-                    now_unix_us, "Recommendation timestamp is too far in the future"
+                    capability = BATCH_CAPABILITY,
+                    "Batch uses an undeclared capability"
                 );
-                return;
+                return reject(RejectReason::CapabilityNotDeclared, "");
             }
-            let age_ms = now_unix_us
-                .saturating_sub(rec.issued_at_unix_us)
-                .saturating_div(1_000);
-            if age_ms > rec.ttl_ms {
-                warn!(age_ms, ttl_ms = rec.ttl_ms, "Recommendation expired");
-                RECOMMENDATION_EXPIRED.inc();
-                return;
+            if batch.recommendations.is_empty() || batch.recommendations.len() > MAX_BATCH_MEMBERS {
+                warn!(
+                    members = batch.recommendations.len(),
+                    max_members = MAX_BATCH_MEMBERS,
+                    "Batch must carry exactly one recommendation"
+                );
+                return reject(RejectReason::Malformed, "");
             }
 
-            // Check authentication
-            if let Some(val) = validator {
-                match &rec.auth_token {
-                    Some(token) => {
-                        if let Err(e) = val.validate(token) {
-                            warn!(error = %e, "Invalid auth token");
-                            AUTH_FAILURES.inc();
-                            return;
-                        }
+            // All-or-nothing: every member must pass before any is applied.
+            let mut checked = Vec::with_capacity(batch.recommendations.len());
+            for member in batch.recommendations.iter().map(Member::from) {
+                let reason = match check_member(&member, inbound_state) {
+                    MemberCheck::Valid(hash) => {
+                        checked.push((member, hash));
+                        continue;
                     }
-                    None => {
-                        warn!("Missing auth token");
-                        AUTH_MISSING.inc();
-                        return;
-                    }
-                }
+                    MemberCheck::LowConfidence => RejectReason::LowConfidence,
+                    MemberCheck::Invalid(reason) => reason,
+                };
+                warn!(
+                    sequence = batch.sequence,
+                    reasoning_hash = member.reasoning_hash,
+                    reason = reason.as_str(),
+                    "Rejecting batch with an invalid member"
+                );
+                return reject(reason, member.reasoning_hash);
+            }
+            let (member, hash) = checked.pop()?;
+            Span::current().record("reasoning_hash", member.reasoning_hash);
+            if !inbound_state.accept_reasoning(hash) {
+                return reject(RejectReason::DuplicateReasoning, member.reasoning_hash);
             }
 
-            let hash = match hex_to_32(&rec.reasoning_hash) {
-                Some(h) => h,
-                None => {
-                    warn!(hash = %rec.reasoning_hash, "Invalid reasoning_hash hex length");
-                    return;
+            inbound_state.commit_sequence(&accepted);
+            submit_member(
+                &member,
+                hash,
+                batch.sequence,
+                exchange,
+                clock,
+                inbound_state,
+                audit,
+            );
+            None
+        }
+        IncomingMessage::Command(cmd) => {
+            let reject = |reason: RejectReason| {
+                Some(BridgeReply::Reject(RejectMsg::new(
+                    reason,
+                    cmd.sequence,
+                    String::new(),
+                )))
+            };
+            let capability = match cmd.command.as_str() {
+                "estop" => ESTOP_CAPABILITY,
+                "set_limits" => SET_LIMITS_CAPABILITY,
+                "get_config" => GET_CONFIG_CAPABILITY,
+                _ => {
+                    warn!(command = %cmd.command, "Unknown bridge command");
+                    return reject(RejectReason::Malformed);
                 }
             };
-
-            let target = rec.target_speed_rpm;
-            if let Some(val) = target {
-                if !val.is_finite() {
-                    warn!(value = %val, "Ignoring non-finite recommendation");
-                    return;
+            if require_handshake && !inbound_state.handshake_seen {
+                warn!("Command received before handshake");
+                return reject(RejectReason::BadVersion);
+            }
+            if !inbound_state.declared(capability) {
+                warn!(capability, "Command uses an undeclared capability");
+                return reject(RejectReason::CapabilityNotDeclared);
+            }
+            if !authorized(validator, &cmd.auth_token, capability) {
+                return reject(RejectReason::AuthFailed);
+            }
+            if capability == SET_LIMITS_CAPABILITY {
+                if validator.is_none() {
+                    warn!("set_limits refused: auth is not configured");
+                    return reject(RejectReason::AuthFailed);
                 }
+                return set_limits(&cmd, exchange, clock, inbound_state, audit)
+                    .err()
+                    .and_then(reject);
             }
-            if !(0.0..=1.0).contains(&rec.confidence) {
-                warn!(
-                    confidence = rec.confidence,
-                    "Ignoring recommendation with invalid confidence"
-                );
-                return;
+            if capability == GET_CONFIG_CAPABILITY {
+                if validator.is_none() {
+                    warn!("get_config refused: auth is not configured");
+                    return reject(RejectReason::AuthFailed);
+                }
+                let Some(config) = inbound_state.effective_config.as_deref() else {
+                    warn!("get_config refused: no configuration to serve");
+                    return reject(RejectReason::Malformed);
+                };
+                info!(client_id = ?inbound_state.client_id, "Effective configuration queried via bridge");
+                return Some(BridgeReply::Config(ConfigMsg::new(
+                    cmd.sequence,
+                    config.clone(),
+                )));
             }
 
-            // Update metrics
-            if let Some(target_val) = target {
-                AGENT_TARGET_RPM.set(target_val);
+            warn!(client_id = ?inbound_state.client_id, "Emergency stop commanded via bridge");
+            exchange.request_emergency_stop();
+            if let Some(audit) = audit {
+                let details = serde_json::json!({
+                    "source": "bridge",
+                    "client_addr": inbound_state.peer_addr,
+                    "client_id": inbound_state.client_id,
+                });
+                log_audit(audit, clock, AuditEventType::EmergencyStop, &details);
             }
-            AGENT_CONFIDENCE.set(rec.confidence as f64);
+            None
+        }
+    }
+}
 
-            debug!(
-                target_speed = ?target,
-                confidence = rec.confidence,
-                "Recommendation received"
+/// Apply an authorized `set_limits` command if the resulting limits are
+/// within the configured ones. Applied and refused changes are both audited.
+fn set_limits<C: Clock>(
+    cmd: &CommandMsg,
+    exchange: &StateExchange,
+    clock: &C,
+    inbound_state: &InboundState,
+    audit: Option<&AuditLogger>,
+) -> Result<(), RejectReason> {
+    let Some(configured) = inbound_state.safety_limits else {
+        warn!("set_limits refused: runtime limit changes are disabled");
+        return Err(RejectReason::Malformed);
+    };
+    let Some(patch) = &cmd.limits else {
+        warn!("set_limits without limits");
+        return Err(RejectReason::Malformed);
+    };
+    let previous = exchange.requested_safety_limits().unwrap_or(configured);
+    let requested = patch.apply(previous);
+    let outcome = requested.check_within(&configured);
+    if let Some(audit) = audit {
+        let details = serde_json::json!({
+            "source": "bridge",
+            "client_addr": inbound_state.peer_addr,
+            "client_id": inbound_state.client_id,
+            "setting": "safety_limits",
+            "outcome": if outcome.is_ok() { "applied" } else { "denied" },
+            "error": outcome.err().map(|e| e.to_string()),
+            "previous": previous,
+            "requested": requested,
+        });
+        log_audit(audit, clock, AuditEventType::ConfigChange, &details);
+    }
+    match outcome {
+        Ok(()) => {
+            info!(client_id = ?inbound_state.client_id, limits = ?requested, "Safety limits tightened via bridge");
+            exchange.request_safety_limits(requested);
+            Ok(())
+        }
+        Err(e) => {
+            warn!(error = %e, "Refusing to loosen safety limits");
+            Err(RejectReason::Unsafe)
+        }
+    }
+}
+
+/// Ordering, freshness and auth fields shared by `recommendation` and `batch`
+struct Envelope<'a> {
+    protocol_version: ProtocolVersion,
+    sequence: u64,
+    issued_at_unix_us: u64,
+    /// Zero when `expires_at_unix_us` is used instead
+    ttl_ms: u64,
+    expires_at_unix_us: Option<u64>,
+    auth_token: &'a Option<String>,
+    /// Message type, for its required scope
+    action: &'static str,
+}
+
+/// The setpoint part of a `recommendation` or one `batch` member
+struct Member<'a> {
+    target_speed_rpm: Option<f64>,
+    ramp_rate_rpm_per_s: Option<f64>,
+    confidence: f32,
+    reasoning_hash: &'a str,
+}
+
+impl<'a> From<&'a RecommendationMsg> for Member<'a> {
+    fn from(rec: &'a RecommendationMsg) -> Self {
+        Self {
+            target_speed_rpm: rec.target_speed_rpm,
+            ramp_rate_rpm_per_s: rec.ramp_rate_rpm_per_s,
+            confidence: rec.confidence,
+            reasoning_hash: &rec.reasoning_hash,
+        }
+    }
+}
+
+impl<'a> From<&'a BatchMember> for Member<'a> {
+    fn from(member: &'a BatchMember) -> Self {
+        Self {
+            target_speed_rpm: member.target_speed_rpm,
+            ramp_rate_rpm_per_s: member.ramp_rate_rpm_per_s,
+            confidence: member.confidence,
+            reasoning_hash: &member.reasoning_hash,
+        }
+    }
+}
+
+enum MemberCheck {
+    Valid(ReasoningHash),
+    /// Below `min_confidence`: dropped without a reply
+    LowConfidence,
+    Invalid(RejectReason),
+}
+
+/// An envelope that passed [`accept_envelope`]. Its sequence is recorded
+/// with [`InboundState::commit_sequence`] once the message is accepted.
+struct AcceptedEnvelope {
+    sequence: u64,
+    /// Token subject with auth, else the hello `client_id`
+    floor_key: Option<String>,
+}
+
+/// Rate limit, version, handshake, TTL, clock skew, auth and then sequence
+/// checks. The sequence comes last so only authenticated, fresh messages
+/// are measured against the sequence floor.
+fn accept_envelope<C: Clock>(
+    envelope: &Envelope<'_>,
+    clock: &C,
+    validator: Option<&TokenValidator>,
+    require_handshake: bool,
+    inbound_state: &mut InboundState,
+) -> Result<AcceptedEnvelope, RejectReason> {
+    if !inbound_state.allow_recommendation(clock.now_us()) {
+        debug!(
+            sequence = envelope.sequence,
+            "Recommendation rate limit exceeded"
+        );
+        RECOMMENDATIONS_RATE_LIMITED.inc();
+        return Err(RejectReason::RateLimited);
+    }
+
+    if !envelope.protocol_version.is_supported() {
+        warn!(
+            major = envelope.protocol_version.major,
+            minor = envelope.protocol_version.minor,
+            "Unsupported protocol version"
+        );
+        return Err(RejectReason::BadVersion);
+    }
+
+    if require_handshake && !inbound_state.handshake_seen {
+        warn!("Recommendation received before handshake");
+        return Err(RejectReason::BadVersion);
+    }
+
+    match (envelope.ttl_ms, envelope.expires_at_unix_us) {
+        (0, None) => {
+            warn!("Missing recommendation TTL");
+            return Err(RejectReason::Malformed);
+        }
+        (1.., Some(_)) => {
+            warn!("Recommendation carries both ttl_ms and expires_at_unix_us");
+            return Err(RejectReason::Malformed);
+        }
+        _ => {}
+    }
+    if envelope.issued_at_unix_us == 0 {
+        warn!("Missing recommendation issued_at_unix_us");
+        return Err(RejectReason::Malformed);
+    }
+    let now_unix_us = clock.unix_us();
+    inbound_state.note_clock_offset(envelope.issued_at_unix_us, now_unix_us);
+    let max_skew_us = inbound_state.max_clock_skew_us;
+    if envelope.issued_at_unix_us > now_unix_us.saturating_add(max_skew_us) {
+        warn!(
+            issued_at_unix_us = envelope.issued_at_unix_us,
+            now_unix_us, "Recommendation timestamp is too far in the future"
+        );
+        return Err(RejectReason::Expired);
+    }
+    // An absolute deadline is compared directly, so the agent's clock
+    // offset does not also shift it by the transit time.
+    if let Some(expires_at_unix_us) = envelope.expires_at_unix_us {
+        if now_unix_us > expires_at_unix_us {
+            warn!(expires_at_unix_us, now_unix_us, "Recommendation expired");
+            RECOMMENDATION_EXPIRED.inc();
+            return Err(RejectReason::Expired);
+        }
+    } else {
+        let age_ms = now_unix_us
+            .saturating_sub(envelope.issued_at_unix_us)
+            .saturating_div(1_000);
+        if age_ms > envelope.ttl_ms {
+            warn!(age_ms, ttl_ms = envelope.ttl_ms, "Recommendation expired");
+            RECOMMENDATION_EXPIRED.inc();
+            return Err(RejectReason::Expired);
+        }
+    }
+
+    let floor_key = match authenticate(validator, envelope.auth_token, envelope.action) {
+        Ok(Some(claims)) => Some(claims.sub),
+        Ok(None) => inbound_state.client_id.clone(),
+        Err(()) => return Err(RejectReason::AuthFailed),
+    };
+
+    if !inbound_state.check_sequence(envelope.sequence, floor_key.as_deref()) {
+        RECOMMENDATION_OUT_OF_ORDER.inc();
+        return Err(RejectReason::OutOfOrder);
+    }
+    Ok(AcceptedEnvelope {
+        sequence: envelope.sequence,
+        floor_key,
+    })
+}
+
+/// Hash, value range, declared capability and confidence checks
+fn check_member(member: &Member<'_>, inbound_state: &InboundState) -> MemberCheck {
+    let expected_bytes = inbound_state.reasoning_hash_bytes;
+    let hash = match hex_to_bytes(member.reasoning_hash, expected_bytes)
+        .and_then(|bytes| ReasoningHash::from_slice(&bytes))
+    {
+        Some(h) => h,
+        None => {
+            warn!(
+                hash = %member.reasoning_hash,
+                expected_bytes, "Invalid reasoning_hash hex length"
+            );
+            return MemberCheck::Invalid(RejectReason::Malformed);
+        }
+    };
+
+    if let Some(val) = member.target_speed_rpm {
+        if !val.is_finite() {
+            warn!(value = %val, "Ignoring non-finite recommendation");
+            return MemberCheck::Invalid(RejectReason::Unsafe);
+        }
+    }
+    if let Some(rate) = member.ramp_rate_rpm_per_s {
+        if !inbound_state.declared(RAMP_CAPABILITY) {
+            warn!(
+                capability = RAMP_CAPABILITY,
+                "Recommendation uses an undeclared capability"
             );
+            return MemberCheck::Invalid(RejectReason::CapabilityNotDeclared);
+        }
+        if !rate.is_finite() || rate <= 0.0 {
+            warn!(rate = %rate, "Ignoring recommendation with invalid ramp rate");
+            return MemberCheck::Invalid(RejectReason::Unsafe);
+        }
+    }
+    if !(0.0..=1.0).contains(&member.confidence) {
+        warn!(
+            confidence = member.confidence,
+            "Ignoring recommendation with invalid confidence"
+        );
+        return MemberCheck::Invalid(RejectReason::Unsafe);
+    }
+    if member.confidence < inbound_state.min_confidence {
+        debug!(
+            confidence = member.confidence,
+            min_confidence = inbound_state.min_confidence,
+            "Dropping low-confidence recommendation"
+        );
+        LOW_CONFIDENCE_DROPPED.inc();
+        return MemberCheck::LowConfidence;
+    }
+    MemberCheck::Valid(hash)
+}
+
+/// Stamp a validated setpoint, hand it to the control loop and audit it
+/// under a correlation id derived from the message `sequence`
+fn submit_member<C: Clock>(
+    member: &Member<'_>,
+    hash: ReasoningHash,
+    sequence: u64,
+    exchange: &StateExchange,
+    clock: &C,
+    inbound_state: &mut InboundState,
+    audit: Option<&AuditLogger>,
+) {
+    let target = member.target_speed_rpm;
+
+    // Update metrics
+    if let Some(target_val) = target {
+        AGENT_TARGET_RPM.set(target_val);
+    }
+    AGENT_CONFIDENCE.set(member.confidence as f64);
+
+    let correlation_id = inbound_state.correlation_id(sequence);
+    Span::current().record("correlation_id", correlation_id.as_str());
+    debug!(
+        target_speed = ?target,
+        confidence = member.confidence,
+        "Recommendation received"
+    );
+
+    let stamped = AgentRecommendation {
+        timestamp_us: clock.now_us(),
+        target_speed_rpm: target,
+        ramp_rate_rpm_per_s: member.ramp_rate_rpm_per_s,
+        confidence: member.confidence,
+        reasoning_hash: hash,
+    };
 
-            let stamped = AgentRecommendation {
-                timestamp_us: timebase.now_us(),
-                target_speed_rpm: target,
-                confidence: rec.confidence,
-                reasoning_hash: hash,
+    // The submission replaces any earlier one, which can then never be
+    // applied or rejected: settle its outcome now, or audit it as superseded.
+    if let Some(audit) = audit {
+        audit_outcome(exchange, clock, audit, inbound_state);
+        if let Some(superseded) = inbound_state.unaudited_submission.take() {
+            let details = RecommendationSupersededDetails {
+                correlation_id: superseded.correlation_id,
+                superseded_by: correlation_id.clone(),
+                target_speed: superseded.target_speed,
+                reasoning_hash: superseded.reasoning_hash_hex,
             };
+            log_audit(
+                audit,
+                clock,
+                AuditEventType::RecommendationSuperseded,
+                &details,
+            );
+        }
+    }
+    let previous_speed = exchange.read_state().motor_speed_rpm;
+    exchange.submit_recommendation(stamped);
+    RECOMMENDATIONS_ACCEPTED.inc();
+    // Without a target there is nothing for the loop to apply or reject.
+    inbound_state.unaudited_submission = target.map(|target_speed| UnauditedSubmission {
+        submitted_us: stamped.timestamp_us,
+        reasoning_hash: hash,
+        reasoning_hash_hex: member.reasoning_hash.to_string(),
+        target_speed,
+        confidence: member.confidence,
+        correlation_id: correlation_id.clone(),
+        previous_speed,
+    });
+    if let Some(audit) = audit {
+        let details = RecommendationReceivedDetails {
+            correlation_id,
+            target_speed: target,
+            confidence: member.confidence,
+            reasoning_hash: member.reasoning_hash.to_string(),
+            client_addr: inbound_state.peer_addr.clone(),
+            client_id: inbound_state.client_id.clone(),
+        };
+        log_audit(
+            audit,
+            clock,
+            AuditEventType::RecommendationReceived,
+            &details,
+        );
+    }
+}
 
-            exchange.submit_recommendation(stamped);
+/// Validate `token` for `action` when auth is enabled, counting failures.
+fn authorized(validator: Option<&TokenValidator>, token: &Option<String>, action: &str) -> bool {
+    authenticate(validator, token, action).is_ok()
+}
+
+/// Like [`authorized`], returning the token's claims; `None` without auth
+fn authenticate(
+    validator: Option<&TokenValidator>,
+    token: &Option<String>,
+    action: &str,
+) -> Result<Option<TokenClaims>, ()> {
+    let Some(val) = validator else {
+        return Ok(None);
+    };
+    match token {
+        Some(token) => match val.validate_action(token, action) {
+            Ok(claims) => Ok(Some(claims)),
+            Err(e) => {
+                warn!(error = %e, action, "Invalid auth token");
+                AUTH_FAILURES.inc();
+                Err(())
+            }
+        },
+        None => {
+            warn!("Missing auth token");
+            AUTH_MISSING.inc();
+            Err(())
         }
     }
 }
 
-fn hex_to_32(input: &str) -> Option<[u8; 32]> {
-    if input.len() != 64 {
-        return None;
+/// Check the detached `signature` over `signing_input` with the auth key.
+/// Without auth there is no key, so signatures cannot be checked and are
+/// ignored; the CLI refuses to require them in that case.
+fn verify_signature(
+    signature: &Option<String>,
+    signing_input: impl FnOnce() -> Vec<u8>,
+    validator: Option<&TokenValidator>,
+    inbound_state: &InboundState,
+) -> Result<(), RejectReason> {
+    let Some(val) = validator else {
+        return Ok(());
+    };
+    match signature {
+        Some(signature) => val
+            .verify_detached(&signing_input(), signature)
+            .map_err(|e| {
+                warn!(error = %e, "Invalid recommendation signature");
+                SIGNATURE_FAILURES.inc();
+                RejectReason::BadSignature
+            }),
+        None if inbound_state.require_signature => {
+            warn!("Missing recommendation signature");
+            SIGNATURE_FAILURES.inc();
+            Err(RejectReason::BadSignature)
+        }
+        None => Ok(()),
     }
-    let mut out = [0u8; 32];
-    let bytes = input.as_bytes();
-    let mut i = 0;
-    while i < 32 {
-        let hi = from_hex_digit(bytes[i * 2])?;
-        let lo = from_hex_digit(bytes[i * 2 + 1])?;
-        out[i] = (hi << 4) | lo;
-        i += 1;
+}
+
+/// Audit whether the control loop applied or rejected the last
+/// recommendation submitted through `inbound_state`, once per
+/// recommendation, under the correlation id of its received entry. One
+/// replaced before either is audited as superseded on submission instead.
+pub(crate) fn audit_outcome<C: Clock>(
+    exchange: &StateExchange,
+    clock: &C,
+    audit: &AuditLogger,
+    inbound_state: &mut InboundState,
+) {
+    let Some(pending) = &inbound_state.unaudited_submission else {
+        return;
+    };
+    match exchange.last_rejection() {
+        Some(rejection) if rejection.recommendation_us == pending.submitted_us => {
+            let details = SafetyRejectionDetails {
+                correlation_id: Some(pending.correlation_id.clone()),
+                ..SafetyRejectionDetails::from(&rejection)
+            };
+            log_audit(audit, clock, AuditEventType::SafetyRejection, &details);
+        }
+        _ => {
+            let state = exchange.read_state();
+            if state.timestamp_us < pending.submitted_us
+                || state.applied_reasoning_hash != pending.reasoning_hash
+            {
+                return;
+            }
+            debug!(
+                correlation_id = %pending.correlation_id,
+                motor_speed = state.motor_speed_rpm,
+                "Recommendation applied"
+            );
+            let details = RecommendationAppliedDetails {
+                correlation_id: pending.correlation_id.clone(),
+                target_speed: pending.target_speed,
+                confidence: pending.confidence,
+                previous_speed: pending.previous_speed,
+                reasoning_hash: pending.reasoning_hash_hex.clone(),
+            };
+            log_audit(
+                audit,
+                clock,
+                AuditEventType::RecommendationApplied,
+                &details,
+            );
+        }
     }
-    Some(out)
+    inbound_state.unaudited_submission = None;
 }
 
-fn from_hex_digit(b: u8) -> Option<u8> {
-    match b {
-        b'0'..=b'9' => Some(b - b'0'),
-        b'a'..=b'f' => Some(b - b'a' + 10),
-        b'A'..=b'F' => Some(b - b'A' + 10),
+fn log_audit<C: Clock>(
+    audit: &AuditLogger,
+    clock: &C,
+    event_type: AuditEventType,
+    details: &impl Serialize,
+) {
+    let details = serde_json::to_value(details).unwrap_or_default();
+    if let Err(e) = audit.log_event(clock.now_us(), clock.unix_us(), event_type, details) {
+        warn!(error = %e, "Failed to write audit entry");
+    }
+}
+
+/// Length-prefix a protobuf payload, zstd-compressing it when it is larger
+/// than `compress_over`.
+#[cfg(feature = "proto")]
+fn binary_frame(body: Vec<u8>, compress_over: Option<usize>) -> Vec<u8> {
+    let (flag, body) = match compress_over {
+        Some(threshold) if body.len() > threshold => {
+            match zstd::bulk::compress(&body, ZSTD_LEVEL) {
+                Ok(compressed) => (COMPRESSED_FLAG, compressed),
+                Err(e) => {
+                    warn!(error = %e, "zstd compression failed, sending frame uncompressed");
+                    (0, body)
+                }
+            }
+        }
+        _ => (0, body),
+    };
+    let mut frame = (body.len() as u32 | flag).to_be_bytes().to_vec();
+    frame.extend_from_slice(&body);
+    frame
+}
+
+/// Decompress a frame payload, refusing anything that would expand past
+/// `MAX_FRAME_BYTES`.
+#[cfg(feature = "proto")]
+fn decompress_frame(payload: &[u8]) -> Option<Vec<u8>> {
+    zstd::bulk::decompress(payload, MAX_FRAME_BYTES).ok()
+}
+
+/// Decode exactly `len` bytes of hex
+fn hex_to_bytes(input: &str, len: usize) -> Option<Vec<u8>> {
+    if input.len() != len * 2 {
+        return None;
+    }
+    input
+        .as_bytes()
+        .chunks_exact(2)
+        .map(|pair| Some((from_hex_digit(pair[0])? << 4) | from_hex_digit(pair[1])?))
+        .collect()
+}
+
+fn from_hex_digit(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::to_hex;
+    use core_spine::MockClock;
+
+    #[test]
+    fn test_slow_client_dropped_after_limit() {
+        let mut monitor = SlowClientMonitor::new(3);
+        assert!(!monitor.on_interval(true));
+        assert!(!monitor.on_interval(true));
+        // Draining the frame clears the count.
+        assert!(!monitor.on_interval(false));
+        assert!(!monitor.on_interval(true));
+        assert!(!monitor.on_interval(true));
+        assert!(monitor.on_interval(true));
+
+        let mut disabled = SlowClientMonitor::new(0);
+        for _ in 0..100 {
+            assert!(!disabled.on_interval(true));
+        }
+    }
+
+    #[test]
+    fn test_hello_replies_with_ack_or_error() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+        let caps = server_capabilities(&BridgeConfig::default());
+
+        let hello =
+            IncomingMessage::parse(r#"{"type":"hello","protocol_version":{"major":1,"minor":0}}"#)
+                .unwrap();
+        let reply = handle_incoming(hello, &exchange, &clock, None, true, &mut inbound, None);
+        assert!(matches!(
+            reply,
+            Some(BridgeReply::HelloAck {
+                sequence_floor: None
+            })
+        ));
+        assert!(inbound.handshake_seen);
+
+        let mut send_buf = Vec::new();
+        let close = queue_reply(
+            &mut send_buf,
+            &BridgeReply::HelloAck {
+                sequence_floor: None,
+            },
+            WireProtocol::JsonLines,
+            &caps,
+            false,
+        );
+        assert!(!close);
+        let ack: serde_json::Value = serde_json::from_slice(&send_buf).unwrap();
+        assert_eq!(ack["type"], "hello_ack");
+        assert_eq!(ack["wire_protocol"], "json");
+
+        let hello =
+            IncomingMessage::parse(r#"{"type":"hello","protocol_version":{"major":2,"minor":0}}"#)
+                .unwrap();
+        let mut inbound = InboundState::new();
+        let reply = handle_incoming(hello, &exchange, &clock, None, true, &mut inbound, None);
+        let reply = reply.unwrap();
+        assert!(!inbound.handshake_seen);
+
+        let mut send_buf = Vec::new();
+        assert!(queue_reply(
+            &mut send_buf,
+            &reply,
+            WireProtocol::JsonLines,
+            &caps,
+            false
+        ));
+        let err: serde_json::Value = serde_json::from_slice(&send_buf).unwrap();
+        assert_eq!(err["type"], "error");
+        assert_eq!(err["code"], "unsupported_version");
+    }
+
+    fn recommendation(clock: &MockClock, sequence: u64, ttl_ms: u64) -> IncomingMessage {
+        let line = serde_json::json!({
+            "type": "recommendation",
+            "protocol_version": {"major": 1, "minor": 0},
+            "sequence": sequence,
+            "target_speed_rpm": 500.0,
+            "confidence": 0.9,
+            "reasoning_hash": "ab".repeat(32),
+            "issued_at_unix_us": clock.unix_us(),
+            "ttl_ms": ttl_ms,
+        })
+        .to_string();
+        IncomingMessage::parse(&line).unwrap()
+    }
+
+    fn hello_with(capabilities: &[&str]) -> IncomingMessage {
+        let line = serde_json::json!({
+            "type": "hello",
+            "protocol_version": {"major": 1, "minor": 0},
+            "capabilities": capabilities,
+        })
+        .to_string();
+        IncomingMessage::parse(&line).unwrap()
+    }
+
+    fn assert_rejected(reply: Option<BridgeReply>, reason: RejectReason) {
+        match reply {
+            Some(BridgeReply::Reject(reject)) => assert_eq!(reject.reason, reason),
+            other => panic!("expected {reason:?} reject, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reconnecting_client_continues_above_its_sequence_floor() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sequence-floors.json");
+        let floors = Arc::new(SequenceFloors::load(&path).unwrap());
+        let mut inbound = InboundState::new().with_sequence_floors(Some(Arc::clone(&floors)));
+        let hello = |client_id: Option<&str>| {
+            let line = serde_json::json!({
+                "type": "hello",
+                "protocol_version": {"major": 1, "minor": 0},
+                "client_id": client_id,
+            })
+            .to_string();
+            IncomingMessage::parse(&line).unwrap()
+        };
+        let handle = |msg, inbound: &mut InboundState| {
+            handle_incoming(msg, &exchange, &clock, None, false, inbound, None)
+        };
+
+        // First connect: no floor yet.
+        let reply = handle(hello(Some("agent-1")), &mut inbound);
+        assert!(matches!(
+            reply,
+            Some(BridgeReply::HelloAck {
+                sequence_floor: None
+            })
+        ));
+        assert!(handle(recommendation(&clock, 5, 1_000), &mut inbound).is_none());
+
+        // Reconnect: stale sequences are replays, the floor is advertised.
+        inbound.reset();
+        let reply = handle(hello(Some("agent-1")), &mut inbound);
+        assert!(matches!(
+            reply,
+            Some(BridgeReply::HelloAck {
+                sequence_floor: Some(5)
+            })
+        ));
+        let reply = handle(recommendation(&clock, 3, 1_000), &mut inbound);
+        assert_rejected(reply, RejectReason::OutOfOrder);
+        assert!(handle(recommendation(&clock, 6, 1_000), &mut inbound).is_none());
+
+        // Other ids and anonymous clients start afresh.
+        for client_id in [Some("agent-2"), None] {
+            inbound.reset();
+            handle(hello(client_id), &mut inbound);
+            assert!(handle(recommendation(&clock, 1, 1_000), &mut inbound).is_none());
+        }
+
+        // The floor survives a restart of the spine, which writes it on shutdown.
+        drop(inbound);
+        drop(floors);
+        let reloaded = SequenceFloors::load(&path).unwrap();
+        assert_eq!(reloaded.floor("agent-1"), Some(6));
+        assert_eq!(reloaded.floor("agent-2"), Some(1));
+
+        let ack = encode_reply(
+            &BridgeReply::HelloAck {
+                sequence_floor: Some(6),
+            },
+            WireProtocol::JsonLines,
+            &[],
+        )
+        .unwrap();
+        let ack: serde_json::Value = serde_json::from_slice(&ack).unwrap();
+        assert_eq!(ack["sequence_floor"], 6);
+    }
+
+    #[test]
+    fn test_unauthenticated_recommendation_leaves_sequence_floor() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let validator = Some(TokenValidator::new(b"secret".to_vec(), 60).with_clock(clock.clone()));
+        let floors = Arc::new(SequenceFloors::in_memory());
+        let mut inbound = InboundState::new().with_sequence_floors(Some(Arc::clone(&floors)));
+        let hello = IncomingMessage::parse(
+            &serde_json::json!({
+                "type": "hello",
+                "protocol_version": {"major": 1, "minor": 0},
+                "client_id": "test-agent",
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let reply = handle_incoming(
+            hello,
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None,
+        );
+        // The hello id is not what floors are kept under with auth.
+        assert!(matches!(
+            reply,
+            Some(BridgeReply::HelloAck {
+                sequence_floor: None
+            })
+        ));
+
+        // A forged token with a huge sequence must not lock the client out.
+        let mut forged = recommendation(&clock, u64::MAX - 1, 1_000);
+        if let IncomingMessage::Recommendation(rec) = &mut forged {
+            rec.auth_token = Some("forged.token.value".to_string());
+        }
+        let reply = handle_incoming(
+            forged,
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::AuthFailed);
+        assert_eq!(floors.floor("test-agent"), None);
+
+        let mut genuine = recommendation(&clock, 1, 1_000);
+        if let IncomingMessage::Recommendation(rec) = &mut genuine {
+            rec.auth_token = Some(token(validator.as_ref().unwrap(), &clock, 1));
+        }
+        let reply = handle_incoming(
+            genuine,
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None,
+        );
+        assert!(reply.is_none());
+        // Keyed by the token subject
+        assert_eq!(floors.floor("test-agent"), Some(1));
+    }
+
+    #[test]
+    fn test_sequence_floor_table_is_bounded() {
+        let floors = SequenceFloors::in_memory();
+        for i in 0..=MAX_SEQUENCE_FLOORS {
+            floors.raise(&format!("client-{i}"), 1);
+        }
+        assert_eq!(floors.floor("client-0"), Some(1));
+        assert_eq!(floors.floor(&format!("client-{MAX_SEQUENCE_FLOORS}")), None);
+        // Known clients still advance when the table is full.
+        floors.raise("client-0", 2);
+        assert_eq!(floors.floor("client-0"), Some(2));
+    }
+
+    #[test]
+    fn test_reasoning_hash_length_follows_config() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let hashed = |sequence, hash: String| {
+            let mut msg = recommendation(&clock, sequence, 1_000);
+            if let IncomingMessage::Recommendation(rec) = &mut msg {
+                rec.reasoning_hash = hash;
+            }
+            msg
+        };
+        let (sha256, sha512) = ("ab".repeat(32), "cd".repeat(64));
+
+        for (hash_bytes, accepted, mismatched) in [(32, &sha256, &sha512), (64, &sha512, &sha256)] {
+            let mut inbound = InboundState::new().with_reasoning_hash_bytes(hash_bytes);
+            let reply = handle_incoming(
+                hashed(1, accepted.clone()),
+                &exchange,
+                &clock,
+                None,
+                false,
+                &mut inbound,
+                None,
+            );
+            assert!(reply.is_none(), "{hash_bytes}: {reply:?}");
+            let applied = exchange.get_recommendation(clock.now_us()).unwrap();
+            assert_eq!(applied.reasoning_hash.as_bytes().len(), hash_bytes);
+            assert_eq!(to_hex(applied.reasoning_hash.as_bytes()), *accepted);
+
+            let reply = handle_incoming(
+                hashed(2, mismatched.clone()),
+                &exchange,
+                &clock,
+                None,
+                false,
+                &mut inbound,
+                None,
+            );
+            assert_rejected(reply, RejectReason::Malformed);
+        }
+    }
+
+    #[test]
+    fn test_ramp_requires_declared_capability() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let ramped = |sequence| {
+            let mut msg = recommendation(&clock, sequence, 1_000);
+            if let IncomingMessage::Recommendation(rec) = &mut msg {
+                rec.ramp_rate_rpm_per_s = Some(100.0);
+            }
+            msg
+        };
+
+        let mut inbound = InboundState::new();
+        let hello = hello_with(&["recommendation.v1"]);
+        handle_incoming(hello, &exchange, &clock, None, true, &mut inbound, None);
+        let reply = handle_incoming(ramped(1), &exchange, &clock, None, true, &mut inbound, None);
+        assert_rejected(reply, RejectReason::CapabilityNotDeclared);
+        // Plain recommendations stay allowed.
+        let plain = recommendation(&clock, 2, 1_000);
+        assert!(
+            handle_incoming(plain, &exchange, &clock, None, true, &mut inbound, None).is_none()
+        );
+
+        let mut inbound = InboundState::new();
+        let hello = hello_with(&["recommendation.v1", RAMP_CAPABILITY]);
+        handle_incoming(hello, &exchange, &clock, None, true, &mut inbound, None);
+        assert!(
+            handle_incoming(ramped(1), &exchange, &clock, None, true, &mut inbound, None).is_none()
+        );
+        assert_eq!(
+            exchange
+                .get_recommendation(clock.now_us())
+                .unwrap()
+                .ramp_rate_rpm_per_s,
+            Some(100.0)
+        );
+    }
+
+    #[test]
+    fn test_resume_measures_state_gap() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        for _ in 0..3 {
+            exchange.publish_state(ProcessSnapshot::default());
+        }
+        let last_seen = exchange.read_state_sequenced().sequence;
+
+        // The client drops while the control loop keeps publishing.
+        for _ in 0..5 {
+            exchange.publish_state(ProcessSnapshot::default());
+        }
+        let line = serde_json::json!({
+            "type": "hello",
+            "protocol_version": {"major": 1, "minor": 0},
+            "client_id": "agent-1",
+            "last_snapshot_sequence": last_seen,
+        })
+        .to_string();
+        let hello = IncomingMessage::parse(&line).unwrap();
+        let mut inbound = InboundState::new();
+        let reply = handle_incoming(hello, &exchange, &clock, None, true, &mut inbound, None);
+        assert!(matches!(reply, Some(BridgeReply::HelloAck { .. })));
+        assert_eq!(STATE_GAP.get(), 5.0);
+
+        assert_eq!(state_gap(last_seen, last_seen), Some(0));
+        // A client ahead of the spine saw a previous run; nothing to measure.
+        assert_eq!(state_gap(last_seen + 1, last_seen), None);
+    }
+
+    #[test]
+    fn test_crc32_framed_lines_drop_corruption() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let line = r#"{"type":"ping"}"#;
+
+        let mut inbound = InboundState::new();
+        // Unframed lines pass through untouched until the client opts in.
+        assert_eq!(checked_line(&format!("  {line}\r\n"), &inbound), Some(line));
+        let hello = hello_with(&["recommendation.v1", CRC32_FRAMING_CAPABILITY]);
+        handle_incoming(hello, &exchange, &clock, None, true, &mut inbound, None);
+        assert!(inbound.crc32_framing());
+
+        let mut frame = format!("{line}\n").into_bytes();
+        append_crc32(&mut frame);
+        let framed = String::from_utf8(frame).unwrap();
+        assert_eq!(
+            framed,
+            format!("{line}\t{:08x}\n", crc32fast::hash(line.as_bytes()))
+        );
+        assert_eq!(checked_line(&framed, &inbound), Some(line));
+
+        let failures = FRAME_CHECKSUM_FAILURES.get();
+        let truncated = framed.replacen("ping", "pin", 1);
+        assert_eq!(checked_line(&truncated, &inbound), None);
+        assert_eq!(checked_line(&format!("{line}\n"), &inbound), None);
+        assert_eq!(checked_line(&format!("{line}\tzz\n"), &inbound), None);
+        assert!(FRAME_CHECKSUM_FAILURES.get() >= failures + 3);
+    }
+
+    #[test]
+    fn test_estop_command_requires_declared_capability() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let estop = || {
+            IncomingMessage::parse(r#"{"type":"command","command":"estop","sequence":1}"#).unwrap()
+        };
+
+        let mut inbound = InboundState::new();
+        handle_incoming(
+            hello_with(&["recommendation.v1"]),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut inbound,
+            None,
+        );
+        let reply = handle_incoming(estop(), &exchange, &clock, None, false, &mut inbound, None);
+        assert_rejected(reply, RejectReason::CapabilityNotDeclared);
+        assert!(!exchange.take_emergency_stop());
+
+        handle_incoming(
+            hello_with(&[ESTOP_CAPABILITY]),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut inbound,
+            None,
+        );
+        assert!(
+            handle_incoming(estop(), &exchange, &clock, None, false, &mut inbound, None).is_none()
+        );
+        assert!(exchange.take_emergency_stop());
+
+        let unknown =
+            IncomingMessage::parse(r#"{"type":"command","command":"reboot","sequence":2}"#)
+                .unwrap();
+        let reply = handle_incoming(unknown, &exchange, &clock, None, false, &mut inbound, None);
+        assert_rejected(reply, RejectReason::Malformed);
+    }
+
+    #[test]
+    fn test_action_scopes_are_enforced_per_message_type() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let validator = Some(
+            TokenValidator::new(b"secret".to_vec(), 60)
+                .with_clock(clock.clone())
+                .with_action_scope("recommendation", "cortex:recommend")
+                .with_action_scope(ESTOP_CAPABILITY, "operator:estop"),
+        );
+        let val = validator.as_ref().unwrap();
+        let estop = |sequence: u64, scope: &str| {
+            let line = serde_json::json!({
+                "type": "command",
+                "command": "estop",
+                "sequence": sequence,
+                "auth_token": scoped_token(val, &clock, sequence, scope),
+            })
+            .to_string();
+            IncomingMessage::parse(&line).unwrap()
+        };
+        let rec = |sequence: u64, scope: &str| {
+            let mut msg = recommendation(&clock, sequence, 1_000);
+            if let IncomingMessage::Recommendation(rec) = &mut msg {
+                rec.auth_token = Some(scoped_token(val, &clock, 100 + sequence, scope));
+            }
+            msg
+        };
+
+        let mut inbound = InboundState::new();
+        handle_incoming(
+            hello_with(&["recommendation.v1", ESTOP_CAPABILITY]),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None,
+        );
+        let failures = AUTH_FAILURES.get();
+        let reply = handle_incoming(
+            estop(1, "cortex:recommend"),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::AuthFailed);
+        assert!(!exchange.take_emergency_stop());
+        assert!(AUTH_FAILURES.get() > failures);
+        assert!(handle_incoming(
+            estop(2, "operator:estop"),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None
+        )
+        .is_none());
+        assert!(exchange.take_emergency_stop());
+
+        let reply = handle_incoming(
+            rec(1, "operator:estop"),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::AuthFailed);
+        assert!(handle_incoming(
+            rec(2, "cortex:recommend"),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            true,
+            &mut inbound,
+            None
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_set_limits_tightens_and_audits_refusals() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let audit = AuditLogger::new(&path).unwrap();
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let validator = Some(TokenValidator::new(b"secret".to_vec(), 60).with_clock(clock.clone()));
+        let configured = core_spine::ControlConfig::default().safety_limits;
+        let mut inbound = InboundState::new().with_safety_limits(Some(configured));
+        handle_incoming(
+            hello_with(&[SET_LIMITS_CAPABILITY]),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None,
+        );
+        let mut set_limits = |sequence: u64, limits: serde_json::Value| {
+            let line = serde_json::json!({
+                "type": "command",
+                "command": "set_limits",
+                "sequence": sequence,
+                "auth_token": token(validator.as_ref().unwrap(), &clock, sequence),
+                "limits": limits,
+            });
+            let msg = IncomingMessage::parse(&line.to_string()).unwrap();
+            handle_incoming(
+                msg,
+                &exchange,
+                &clock,
+                validator.as_ref(),
+                false,
+                &mut inbound,
+                Some(&audit),
+            )
+        };
+
+        assert!(set_limits(1, serde_json::json!({"max_speed_rpm": 1500.0})).is_none());
+        assert_rejected(
+            set_limits(2, serde_json::json!({"max_temp_c": 95.0})),
+            RejectReason::Unsafe,
+        );
+        let applied = exchange.take_safety_limits().unwrap();
+        assert_eq!(applied.max_speed_rpm, 1500.0);
+        assert_eq!(applied.max_temp_c, configured.max_temp_c);
+        assert_eq!(exchange.take_safety_limits(), None);
+
+        let records = AuditLogger::read_timeline(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        for record in &records {
+            assert!(matches!(
+                record.entry.event_type,
+                AuditEventType::ConfigChange
+            ));
+        }
+        assert_eq!(records[0].entry.details["outcome"], "applied");
+        assert_eq!(
+            records[0].entry.details["requested"]["max_speed_rpm"],
+            1500.0
+        );
+        let denied = &records[1].entry.details;
+        assert_eq!(denied["outcome"], "denied");
+        assert_eq!(denied["previous"]["max_speed_rpm"], 1500.0);
+        assert_eq!(denied["requested"]["max_temp_c"], 95.0);
+        assert!(denied["error"].as_str().unwrap().contains("max_temp_c"));
+
+        // Without auth the command is refused outright.
+        let mut open = InboundState::new().with_safety_limits(Some(configured));
+        handle_incoming(
+            hello_with(&[SET_LIMITS_CAPABILITY]),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut open,
+            None,
+        );
+        let msg = IncomingMessage::parse(
+            r#"{"type":"command","command":"set_limits","sequence":1,"limits":{"max_speed_rpm":100.0}}"#,
+        )
+        .unwrap();
+        let reply = handle_incoming(msg, &exchange, &clock, None, false, &mut open, None);
+        assert_rejected(reply, RejectReason::AuthFailed);
+        assert_eq!(exchange.take_safety_limits(), None);
+    }
+
+    #[test]
+    fn test_get_config_requires_auth() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let validator = Some(TokenValidator::new(b"secret".to_vec(), 60).with_clock(clock.clone()));
+        let served = Arc::new(serde_json::json!({"bind_addr": "127.0.0.1:7000"}));
+        let get_config = |sequence: u64, auth_token: Option<String>| {
+            let line = serde_json::json!({
+                "type": "command",
+                "command": "get_config",
+                "sequence": sequence,
+                "auth_token": auth_token,
+            });
+            IncomingMessage::parse(&line.to_string()).unwrap()
+        };
+
+        let mut inbound = InboundState::new().with_effective_config(Some(Arc::clone(&served)));
+        handle_incoming(
+            hello_with(&[GET_CONFIG_CAPABILITY]),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None,
+        );
+        let reply = handle_incoming(
+            get_config(1, None),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::AuthFailed);
+
+        let auth_token = token(validator.as_ref().unwrap(), &clock, 2);
+        let reply = handle_incoming(
+            get_config(2, Some(auth_token)),
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None,
+        )
+        .unwrap();
+        let frame = encode_reply(&reply, WireProtocol::JsonLines, &[]).unwrap();
+        let sent: serde_json::Value = serde_json::from_slice(&frame).unwrap();
+        assert_eq!(sent["type"], "config");
+        assert_eq!(sent["sequence"], 2);
+        assert_eq!(sent["config"], *served);
+
+        // Without auth configured there is nothing to check the token against.
+        let mut open = InboundState::new().with_effective_config(Some(served));
+        handle_incoming(
+            hello_with(&[GET_CONFIG_CAPABILITY]),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut open,
+            None,
+        );
+        let reply = handle_incoming(
+            get_config(1, None),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut open,
+            None,
+        );
+        assert_rejected(reply, RejectReason::AuthFailed);
+    }
+
+    fn batch(clock: &MockClock, sequence: u64, members: serde_json::Value) -> IncomingMessage {
+        let line = serde_json::json!({
+            "type": "batch",
+            "protocol_version": {"major": 1, "minor": 0},
+            "sequence": sequence,
+            "issued_at_unix_us": clock.unix_us(),
+            "ttl_ms": 1_000,
+            "recommendations": members,
+        })
+        .to_string();
+        IncomingMessage::parse(&line).unwrap()
+    }
+
+    #[test]
+    fn test_batch_is_applied_all_or_nothing() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+        handle_incoming(
+            hello_with(&[BATCH_CAPABILITY]),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        );
+
+        // An invalid member rejects the batch with its hash.
+        let members = serde_json::json!([
+            {"target_speed_rpm": 200.0, "confidence": 0.9, "reasoning_hash": "zz"},
+        ]);
+        let reply = handle_incoming(
+            batch(&clock, 1, members),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        );
+        match reply {
+            Some(BridgeReply::Reject(reject)) => {
+                assert_eq!(reject.reason, RejectReason::Malformed);
+                assert_eq!(reject.sequence, 1);
+                assert_eq!(reject.reasoning_hash, "zz");
+            }
+            other => panic!("expected reject, got {other:?}"),
+        }
+        assert!(exchange.get_recommendation(clock.now_us()).is_none());
+
+        // A low-confidence member is rejected with the batch, not dropped.
+        let mut confident = InboundState::new().with_min_confidence(0.5);
+        handle_incoming(
+            hello_with(&[BATCH_CAPABILITY]),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut confident,
+            None,
+        );
+        let members = serde_json::json!([
+            {"target_speed_rpm": 200.0, "confidence": 0.1, "reasoning_hash": "cd".repeat(32)},
+        ]);
+        let reply = handle_incoming(
+            batch(&clock, 1, members),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut confident,
+            None,
+        );
+        match reply {
+            Some(BridgeReply::Reject(reject)) => {
+                assert_eq!(reject.reason, RejectReason::LowConfidence);
+                assert_eq!(reject.reasoning_hash, "cd".repeat(32));
+            }
+            other => panic!("expected reject, got {other:?}"),
+        }
+        assert!(exchange.get_recommendation(clock.now_us()).is_none());
+
+        // Valid members the single-setpoint exchange cannot apply together
+        // are refused, with no member blamed.
+        let members = serde_json::json!([
+            {"target_speed_rpm": 100.0, "confidence": 0.9, "reasoning_hash": "ab".repeat(32)},
+            {"target_speed_rpm": 200.0, "confidence": 0.8, "reasoning_hash": "cd".repeat(32)},
+        ]);
+        let reply = handle_incoming(
+            batch(&clock, 2, members),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        );
+        match reply {
+            Some(BridgeReply::Reject(reject)) => {
+                assert_eq!(reject.reason, RejectReason::Malformed);
+                assert_eq!(reject.reasoning_hash, "");
+            }
+            other => panic!("expected reject, got {other:?}"),
+        }
+        assert!(exchange.get_recommendation(clock.now_us()).is_none());
+
+        let members = serde_json::json!([
+            {"target_speed_rpm": 200.0, "confidence": 0.8, "reasoning_hash": "cd".repeat(32)},
+        ]);
+        assert!(handle_incoming(
+            batch(&clock, 2, members),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        )
+        .is_none());
+        let applied = exchange.get_recommendation(clock.now_us()).unwrap();
+        assert_eq!(applied.target_speed_rpm, Some(200.0));
+        assert_eq!(applied.reasoning_hash, [0xcd; 32]);
+
+        // The envelope is sequenced like a single recommendation.
+        let members = serde_json::json!([
+            {"target_speed_rpm": 50.0, "confidence": 0.9, "reasoning_hash": "ef".repeat(32)},
+        ]);
+        let reply = handle_incoming(
+            batch(&clock, 2, members),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::OutOfOrder);
+        let reply = handle_incoming(
+            batch(&clock, 3, serde_json::json!([])),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::Malformed);
+    }
+
+    #[test]
+    fn test_batch_requires_declared_capability() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+        handle_incoming(
+            hello_with(&["recommendation.v1"]),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        );
+        let members = serde_json::json!([
+            {"target_speed_rpm": 100.0, "confidence": 0.9, "reasoning_hash": "ab".repeat(32)},
+        ]);
+        let reply = handle_incoming(
+            batch(&clock, 1, members),
+            &exchange,
+            &clock,
+            None,
+            true,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::CapabilityNotDeclared);
+    }
+
+    /// A fresh auth token, unique per `sequence`
+    fn token(validator: &TokenValidator, clock: &MockClock, sequence: u64) -> String {
+        scoped_token(validator, clock, sequence, "cortex:recommend")
+    }
+
+    fn scoped_token(
+        validator: &TokenValidator,
+        clock: &MockClock,
+        sequence: u64,
+        scope: &str,
+    ) -> String {
+        let now = clock.unix_us() / 1_000_000;
+        let claims = crate::auth::TokenClaims {
+            iss: "neuroplc".to_string(),
+            sub: "test-agent".to_string(),
+            aud: "neuroplc-spine".to_string(),
+            scope: vec![scope.to_string()],
+            iat: now,
+            exp: now + 60,
+            nbf: None,
+            nonce: format!("nonce-{sequence}"),
+        };
+        validator.generate_token_with_claims(&claims)
+    }
+
+    /// A recommendation with a fresh auth token, signed, then `tamper`ed
+    fn signed(
+        validator: &TokenValidator,
+        clock: &MockClock,
+        sequence: u64,
+        tamper: impl FnOnce(&mut RecommendationMsg),
+    ) -> IncomingMessage {
+        let mut msg = recommendation(clock, sequence, 1_000);
+        if let IncomingMessage::Recommendation(rec) = &mut msg {
+            rec.auth_token = Some(token(validator, clock, sequence));
+            rec.signature = Some(validator.sign_detached(&rec.signing_input()));
+            tamper(rec);
+        }
+        msg
+    }
+
+    #[test]
+    fn test_signature_mismatch_is_rejected() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let validator = Some(TokenValidator::new(b"secret".to_vec(), 60).with_clock(clock.clone()));
+        let mut inbound = InboundState::new();
+        let failures = SIGNATURE_FAILURES.get();
+
+        let tampered = signed(validator.as_ref().unwrap(), &clock, 1, |rec| {
+            rec.target_speed_rpm = Some(2_900.0);
+        });
+        let reply = handle_incoming(
+            tampered,
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::BadSignature);
+        assert!(exchange.get_recommendation(clock.now_us()).is_none());
+        assert!(SIGNATURE_FAILURES.get() > failures);
+
+        let intact = signed(validator.as_ref().unwrap(), &clock, 2, |_| {});
+        assert!(handle_incoming(
+            intact,
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None
+        )
+        .is_none());
+        assert_eq!(
+            exchange
+                .get_recommendation(clock.now_us())
+                .unwrap()
+                .target_speed_rpm,
+            Some(500.0)
+        );
+
+        // Unsigned messages pass unless signatures are required.
+        let unsigned = signed(validator.as_ref().unwrap(), &clock, 3, |rec| {
+            rec.signature = None;
+        });
+        assert!(handle_incoming(
+            unsigned,
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None
+        )
+        .is_none());
+        let mut inbound = inbound.with_require_signature(true);
+        let unsigned = signed(validator.as_ref().unwrap(), &clock, 4, |rec| {
+            rec.signature = None;
+        });
+        let reply = handle_incoming(
+            unsigned,
+            &exchange,
+            &clock,
+            validator.as_ref(),
+            false,
+            &mut inbound,
+            None,
+        );
+        assert_rejected(reply, RejectReason::BadSignature);
+    }
+
+    #[test]
+    fn test_clock_offset_tracking_and_configurable_skew() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new().with_max_clock_skew(Duration::from_secs(2));
+        let skewed = |sequence, ahead_ms: u64| {
+            let mut msg = recommendation(&clock, sequence, 1_000);
+            if let IncomingMessage::Recommendation(rec) = &mut msg {
+                rec.issued_at_unix_us += ahead_ms * 1_000;
+            }
+            msg
+        };
+
+        // Inside the limit, but past half of it: accepted with a warning.
+        for seq in 1..=4 {
+            let msg = skewed(seq, 1_500);
+            assert!(
+                handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None).is_none()
+            );
+        }
+        assert!(inbound.clock_offsets.warned);
+
+        let msg = skewed(5, 2_500);
+        assert_rejected(
+            handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None),
+            RejectReason::Expired,
+        );
+
+        // The warning re-arms once the clocks agree again.
+        for seq in 6..6 + ClockOffsetWindow::LEN as u64 {
+            let msg = skewed(seq, 0);
+            handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None);
+        }
+        assert!(!inbound.clock_offsets.warned);
+        assert_eq!(inbound.clock_offsets.push(0), 0);
+    }
+
+    #[test]
+    fn test_rate_limiter_refills_over_time() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::with_rate_limit(Some(10));
+
+        for seq in 1..=10 {
+            let msg = recommendation(&clock, seq, 1_000);
+            assert!(
+                handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None).is_none()
+            );
+        }
+        let msg = recommendation(&clock, 11, 1_000);
+        match handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None) {
+            Some(BridgeReply::Reject(reject)) => {
+                assert_eq!(reject.reason, RejectReason::RateLimited)
+            }
+            other => panic!("expected rate limit reject, got {other:?}"),
+        }
+
+        // 100 ms at 10/s buys exactly one more token.
+        clock.advance(Duration::from_millis(100));
+        let msg = recommendation(&clock, 12, 1_000);
+        assert!(handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None).is_none());
+        let msg = recommendation(&clock, 13, 1_000);
+        assert!(handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None).is_some());
+    }
+
+    #[test]
+    fn test_rate_limiter_never_throttles_compliant_client() {
+        let mut bucket = TokenBucket::new(10);
+        let mut now_us = 1_000_000;
+        // Drain the burst first, then send at exactly the sustained rate.
+        for _ in 0..10 {
+            assert!(bucket.try_take(now_us));
+        }
+        for _ in 0..1_000 {
+            now_us += 100_000;
+            assert!(bucket.try_take(now_us));
+        }
+    }
+
+    #[test]
+    fn test_out_of_order_recommendation_rejected() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+
+        let msg = recommendation(&clock, 5, 1_000);
+        assert!(handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None).is_none());
+
+        let msg = recommendation(&clock, 4, 1_000);
+        let reply =
+            handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None).unwrap();
+        let mut send_buf = Vec::new();
+        assert!(!queue_reply(
+            &mut send_buf,
+            &reply,
+            WireProtocol::JsonLines,
+            &[],
+            false
+        ));
+        let frame: serde_json::Value = serde_json::from_slice(&send_buf).unwrap();
+        assert_eq!(frame["type"], "reject");
+        assert_eq!(frame["reason"], "out_of_order");
+        assert_eq!(frame["sequence"], 4);
+        assert_eq!(frame["reasoning_hash"], "ab".repeat(32));
+
+        // A backlogged client gets no reject rather than a growing buffer.
+        let mut backlog = vec![0u8; MAX_REJECT_BACKLOG_BYTES + 1];
+        queue_reply(&mut backlog, &reply, WireProtocol::JsonLines, &[], false);
+        assert_eq!(backlog.len(), MAX_REJECT_BACKLOG_BYTES + 1);
+    }
+
+    #[test]
+    fn test_low_confidence_recommendation_dropped() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new().with_min_confidence(0.5);
+        let with_confidence = |sequence, confidence| {
+            let mut msg = recommendation(&clock, sequence, 1_000);
+            if let IncomingMessage::Recommendation(rec) = &mut msg {
+                rec.confidence = confidence;
+            }
+            msg
+        };
+
+        let dropped = LOW_CONFIDENCE_DROPPED.get();
+        let reply = handle_incoming(
+            with_confidence(1, 0.3),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut inbound,
+            None,
+        );
+        assert!(reply.is_none());
+        assert!(exchange.get_recommendation(clock.now_us()).is_none());
+        assert!(LOW_CONFIDENCE_DROPPED.get() > dropped);
+
+        // Exactly at the threshold is accepted.
+        handle_incoming(
+            with_confidence(2, 0.5),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut inbound,
+            None,
+        );
+        let rec = exchange.get_recommendation(clock.now_us()).unwrap();
+        assert_eq!(rec.confidence, 0.5);
+    }
+
+    #[test]
+    fn test_repeated_reasoning_hash_beyond_allowance() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        let exchange = StateExchange::new(1_000_000);
+        let send = |inbound: &mut InboundState, sequence, hash: &str| {
+            let mut msg = recommendation(&clock, sequence, 1_000);
+            if let IncomingMessage::Recommendation(rec) = &mut msg {
+                rec.reasoning_hash = hash.to_string();
+            }
+            handle_incoming(msg, &exchange, &clock, None, false, inbound, None)
+        };
+        let same = "ab".repeat(32);
+        let other = "cd".repeat(32);
+
+        // Counting only: every recommendation is accepted.
+        let mut inbound = InboundState::new()
+            .with_duplicate_reasoning(DuplicateReasoning::Count { max_repeats: 1 });
+        let duplicates = DUPLICATE_REASONING.get();
+        for seq in 1..=3 {
+            assert!(send(&mut inbound, seq, &same).is_none());
+        }
+        assert!(DUPLICATE_REASONING.get() > duplicates);
+
+        // Strict: the first repeat is allowed, the second rejected, and a new
+        // hash starts over.
+        let mut inbound = InboundState::new()
+            .with_duplicate_reasoning(DuplicateReasoning::Reject { max_repeats: 1 });
+        assert!(send(&mut inbound, 1, &same).is_none());
+        assert!(send(&mut inbound, 2, &same).is_none());
+        match send(&mut inbound, 3, &same) {
+            Some(BridgeReply::Reject(reject)) => {
+                assert_eq!(reject.reason, RejectReason::DuplicateReasoning)
+            }
+            other => panic!("expected duplicate reasoning reject, got {other:?}"),
+        }
+        assert!(send(&mut inbound, 4, &other).is_none());
+        assert!(send(&mut inbound, 5, &same).is_none());
+
+        // Off by default.
+        let mut inbound = InboundState::new();
+        for seq in 1..=5 {
+            assert!(send(&mut inbound, seq, &same).is_none());
+        }
+    }
+
+    #[test]
+    fn test_duplicate_reasoning_parse() {
+        assert_eq!(
+            DuplicateReasoning::parse("off"),
+            Some(DuplicateReasoning::Off)
+        );
+        assert_eq!(
+            DuplicateReasoning::parse("count:3"),
+            Some(DuplicateReasoning::Count { max_repeats: 3 })
+        );
+        assert_eq!(
+            DuplicateReasoning::parse("reject:0"),
+            Some(DuplicateReasoning::Reject { max_repeats: 0 })
+        );
+        for bad in ["", "reject", "count:x", "reject:1:2", "on"] {
+            assert_eq!(DuplicateReasoning::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_recommendation_expires_after_ttl() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+
+        let msg = recommendation(&clock, 1, 100);
+        clock.advance(Duration::from_millis(150));
+        let reply = handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None);
+        assert!(exchange.get_recommendation(clock.now_us()).is_none());
+        match reply {
+            Some(BridgeReply::Reject(reject)) => {
+                assert_eq!(reject.reason, RejectReason::Expired);
+                assert_eq!(reject.sequence, 1);
+            }
+            other => panic!("expected reject, got {other:?}"),
+        }
+
+        let msg = recommendation(&clock, 2, 100);
+        clock.advance(Duration::from_millis(50));
+        let reply = handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None);
+        assert!(reply.is_none());
+        let rec = exchange.get_recommendation(clock.now_us()).unwrap();
+        assert_eq!(rec.target_speed_rpm, Some(500.0));
+        assert_eq!(rec.timestamp_us, clock.now_us());
+    }
+
+    #[test]
+    fn test_recommendation_expires_at_absolute_deadline() {
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+        let with_expiry = |sequence: u64, expiry: serde_json::Value| {
+            let mut line = serde_json::json!({
+                "type": "recommendation",
+                "protocol_version": {"major": 1, "minor": 0},
+                "sequence": sequence,
+                "target_speed_rpm": 500.0,
+                "confidence": 0.9,
+                "reasoning_hash": format!("{sequence:02x}").repeat(32),
+                "issued_at_unix_us": clock.unix_us(),
+            });
+            line.as_object_mut()
+                .unwrap()
+                .extend(expiry.as_object().unwrap().clone());
+            IncomingMessage::parse(&line.to_string()).unwrap()
+        };
+        let mut send =
+            |msg| handle_incoming(msg, &exchange, &clock, None, false, &mut inbound, None);
+
+        // Issued now, valid for 100 ms, received 50 ms later.
+        let deadline = clock.unix_us() + 100_000;
+        let msg = with_expiry(1, serde_json::json!({"expires_at_unix_us": deadline}));
+        clock.advance(Duration::from_millis(50));
+        assert!(send(msg).is_none());
+
+        let deadline = clock.unix_us() + 100_000;
+        let msg = with_expiry(2, serde_json::json!({"expires_at_unix_us": deadline}));
+        clock.advance(Duration::from_millis(150));
+        assert_rejected(send(msg), RejectReason::Expired);
+
+        let msg = with_expiry(3, serde_json::json!({"ttl_ms": 100}));
+        assert!(send(msg).is_none());
+
+        let deadline = clock.unix_us() + 100_000;
+        let both = serde_json::json!({"ttl_ms": 100, "expires_at_unix_us": deadline});
+        assert_rejected(send(with_expiry(4, both)), RejectReason::Malformed);
+        assert_rejected(
+            send(with_expiry(5, serde_json::json!({}))),
+            RejectReason::Malformed,
+        );
+    }
+
+    /// Instants at which `schedule` publishes over one second of 10 ms
+    /// polls, with `speed_at(ms)` as the process speed.
+    fn publish_times(schedule: &mut PublishSchedule, speed_at: impl Fn(u64) -> f64) -> Vec<u64> {
+        let start = Instant::now();
+        let mut sent = Vec::new();
+        for ms in (0..1_000).step_by(10) {
+            let now = start + Duration::from_millis(ms);
+            let snapshot = ProcessSnapshot {
+                motor_speed_rpm: speed_at(ms),
+                ..Default::default()
+            };
+            if schedule.due(&snapshot, now) {
+                schedule.note_sent(snapshot, now);
+                sent.push(ms);
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn test_on_change_publishes_keepalives_when_static() {
+        let mode = PublishMode::OnChange {
+            min_interval: Duration::from_millis(20),
+            max_interval: Duration::from_millis(250),
+        };
+
+        let static_times = publish_times(&mut PublishSchedule::new(mode), |_| 100.0);
+        assert_eq!(static_times, vec![0, 250, 500, 750]);
+
+        // A step at 400 ms goes out on the next poll, not at the keepalive.
+        let step_times = publish_times(&mut PublishSchedule::new(mode), |ms| {
+            if ms < 400 {
+                100.0
+            } else {
+                200.0
+            }
+        });
+        assert_eq!(step_times, vec![0, 250, 400, 650, 900]);
+
+        // A ramp changes every poll and is published every min_interval.
+        let ramp_times = publish_times(&mut PublishSchedule::new(mode), |ms| ms as f64);
+        assert_eq!(ramp_times.len(), 50);
+
+        let fixed = PublishMode::FixedRate(Duration::from_millis(100));
+        assert_eq!(
+            publish_times(&mut PublishSchedule::new(fixed), |_| 0.0).len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_publish_mode_parse() {
+        assert_eq!(
+            PublishMode::parse("fixed:50"),
+            Some(PublishMode::FixedRate(Duration::from_millis(50)))
+        );
+        assert_eq!(
+            PublishMode::parse("on-change:20:1000"),
+            Some(PublishMode::OnChange {
+                min_interval: Duration::from_millis(20),
+                max_interval: Duration::from_millis(1000),
+            })
+        );
+        for bad in [
+            "fixed",
+            "fixed:0",
+            "on-change:100:20",
+            "on-change:1:2:3",
+            "slow:10",
+        ] {
+            assert_eq!(PublishMode::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_received_and_rejected_recommendations_are_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let audit = AuditLogger::new(&path).unwrap();
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+        inbound.note_peer("127.0.0.1:40000".parse().unwrap());
+
+        let msg = recommendation(&clock, 1, 1_000);
+        handle_incoming(
+            msg,
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut inbound,
+            Some(&audit),
+        );
+        let rec = exchange.get_recommendation(clock.now_us()).unwrap();
+
+        exchange.publish_rejection(core_spine::RejectedRecommendation {
+            recommendation_us: rec.timestamp_us,
+            requested_rpm: rec.target_speed_rpm,
+            current_speed_rpm: 0.0,
+            current_temp_c: 25.0,
+            violation: core_spine::SafetyViolation::ExceedsMaxSpeed {
+                requested: 500.0,
+                limit: 300.0,
+            },
+            reasoning_hash: rec.reasoning_hash,
+        });
+        // Repeated cycles rejecting the same recommendation log it once.
+        audit_outcome(&exchange, &clock, &audit, &mut inbound);
+        audit_outcome(&exchange, &clock, &audit, &mut inbound);
+
+        let records = AuditLogger::read_timeline(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        let received = &records[0].entry;
+        assert!(matches!(
+            received.event_type,
+            AuditEventType::RecommendationReceived
+        ));
+        assert_eq!(received.details["target_speed"], 500.0);
+        assert_eq!(received.details["reasoning_hash"], "ab".repeat(32));
+        assert_eq!(received.details["client_addr"], "127.0.0.1:40000");
+        let rejected = &records[1].entry;
+        assert!(matches!(
+            rejected.event_type,
+            AuditEventType::SafetyRejection
+        ));
+        assert_eq!(rejected.details["violation_type"], "exceeds_max_speed");
+        assert_eq!(rejected.details["limit_value"], 300.0);
+        assert_eq!(received.details["correlation_id"], "127.0.0.1:40000/1");
+        assert_eq!(
+            rejected.details["correlation_id"],
+            received.details["correlation_id"]
+        );
+    }
+
+    #[test]
+    fn test_applied_recommendation_shares_received_correlation_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let audit = AuditLogger::new(&path).unwrap();
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+        inbound.note_peer("127.0.0.1:40000".parse().unwrap());
+        // A hello's client id names the client over its address.
+        inbound.client_id = Some("cortex-a".to_string());
+        exchange.publish_state(ProcessSnapshot {
+            timestamp_us: clock.now_us(),
+            motor_speed_rpm: 80.0,
+            ..ProcessSnapshot::default()
+        });
+
+        handle_incoming(
+            recommendation(&clock, 7, 1_000),
+            &exchange,
+            &clock,
+            None,
+            false,
+            &mut inbound,
+            Some(&audit),
+        );
+        let rec = exchange.get_recommendation(clock.now_us()).unwrap();
+        // Not applied yet: the loop still holds its last safe setpoint.
+        audit_outcome(&exchange, &clock, &audit, &mut inbound);
+
+        clock.advance(Duration::from_millis(1));
+        exchange.publish_state(ProcessSnapshot {
+            timestamp_us: clock.now_us(),
+            motor_speed_rpm: 120.0,
+            applied_reasoning_hash: rec.reasoning_hash,
+            ..ProcessSnapshot::default()
+        });
+        audit_outcome(&exchange, &clock, &audit, &mut inbound);
+        audit_outcome(&exchange, &clock, &audit, &mut inbound);
+
+        let records = AuditLogger::read_timeline(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        let received = &records[0].entry;
+        let applied = &records[1].entry;
+        assert!(matches!(
+            applied.event_type,
+            AuditEventType::RecommendationApplied
+        ));
+        assert_eq!(received.details["correlation_id"], "cortex-a/7");
+        assert_eq!(applied.details["correlation_id"], "cortex-a/7");
+        assert_eq!(applied.details["target_speed"], 500.0);
+        // The speed before the recommendation, not the one it produced.
+        assert_eq!(applied.details["previous_speed"], 80.0);
+        assert_eq!(applied.details["reasoning_hash"], "ab".repeat(32));
+    }
+
+    #[test]
+    fn test_replaced_recommendation_is_audited_as_superseded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let audit = AuditLogger::new(&path).unwrap();
+        let clock = MockClock::new(1_700_000_000_000_000);
+        clock.advance(Duration::from_millis(1));
+        let exchange = StateExchange::new(1_000_000);
+        let mut inbound = InboundState::new();
+        inbound.client_id = Some("cortex-a".to_string());
+
+        for sequence in [1, 2] {
+            handle_incoming(
+                recommendation(&clock, sequence, 1_000),
+                &exchange,
+                &clock,
+                None,
+                false,
+                &mut inbound,
+                Some(&audit),
+            );
+            clock.advance(Duration::from_millis(1));
+        }
+
+        // Received 1, superseded 1, received 2; 2 is still awaiting its outcome.
+        let records = AuditLogger::read_timeline(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        let superseded = &records[1].entry;
+        assert!(matches!(
+            superseded.event_type,
+            AuditEventType::RecommendationSuperseded
+        ));
+        assert_eq!(superseded.details["correlation_id"], "cortex-a/1");
+        assert_eq!(superseded.details["superseded_by"], "cortex-a/2");
+        assert_eq!(records[2].entry.details["correlation_id"], "cortex-a/2");
+    }
+
+    fn spawn_bridge(
+        config: BridgeConfig,
+    ) -> (String, Arc<AtomicBool>, std::thread::JoinHandle<()>) {
+        let bind_addr = {
+            let probe = TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().to_string()
+        };
+        let config = BridgeConfig {
+            bind_addr: bind_addr.clone(),
+            ..config
+        };
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                run_bridge(exchange, core_spine::TimeBase::new(), config, stop, None).unwrap()
+            })
+        };
+        (bind_addr, stop, handle)
+    }
+
+    fn connect(addr: &str) -> TcpStream {
+        let stream = loop {
+            match TcpStream::connect(addr) {
+                Ok(stream) => break stream,
+                Err(_) => std::thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream
+    }
+
+    /// Read until the bridge closes the connection; panics on timeout.
+    fn read_until_closed(stream: &mut TcpStream) {
+        let mut buf = [0u8; 1024];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) => break,
+                Ok(_) => continue,
+                Err(err) if err.kind() == std::io::ErrorKind::ConnectionReset => break,
+                Err(err) => panic!("client was not disconnected: {err}"),
+            }
+        }
+    }
+
+    #[cfg(feature = "dev-certs")]
+    #[test]
+    fn test_plaintext_client_counts_as_tls_handshake_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("cert.pem");
+        let key = dir.path().join("key.pem");
+        crate::tls::generate_dev_cert(&cert, &key).unwrap();
+        let failures = || {
+            TLS_HANDSHAKE_FAILURES
+                .with_label_values(&["protocol_mismatch"])
+                .get()
+        };
+        let before = failures();
+
+        let (addr, stop, handle) = spawn_bridge(BridgeConfig {
+            tls: TlsConfig {
+                enabled: true,
+                cert_path: cert.display().to_string(),
+                key_path: key.display().to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut stream = connect(&addr);
+        stream
+            .write_all(b"{\"type\":\"hello\",\"client_name\":\"plain\"}\n")
+            .unwrap();
+        read_until_closed(&mut stream);
+
+        assert_eq!(failures(), before + 1);
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_bind_failure_returns_error() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = BridgeConfig {
+            bind_addr: taken.local_addr().unwrap().to_string(),
+            ..Default::default()
+        };
+        let result = run_bridge(
+            Arc::new(StateExchange::new(1_000_000)),
+            core_spine::TimeBase::new(),
+            config,
+            Arc::new(AtomicBool::new(false)),
+            None,
+        );
+        assert!(matches!(result, Err(BridgeError::Bind { .. })));
+    }
+
+    #[cfg(feature = "proto")]
+    #[test]
+    fn test_binary_frame_compression_roundtrip() {
+        let body = vec![7u8; 4096];
+        let frame = binary_frame(body.clone(), Some(1024));
+        let prefix = u32::from_be_bytes(frame[..4].try_into().unwrap());
+        assert_ne!(prefix & COMPRESSED_FLAG, 0);
+        assert_eq!((prefix & !COMPRESSED_FLAG) as usize, frame.len() - 4);
+        assert_eq!(decompress_frame(&frame[4..]).unwrap(), body);
+
+        // At or under the threshold the frame stays plain.
+        let plain = binary_frame(vec![7u8; 1024], Some(1024));
+        assert_eq!(u32::from_be_bytes(plain[..4].try_into().unwrap()), 1024);
+    }
+
+    #[cfg(feature = "proto")]
+    #[test]
+    fn test_decompression_bomb_rejected() {
+        let bomb = zstd::bulk::compress(&vec![0u8; MAX_FRAME_BYTES + 1], ZSTD_LEVEL).unwrap();
+        assert!(bomb.len() < 1024);
+        assert!(decompress_frame(&bomb).is_none());
+    }
+
+    #[cfg(feature = "proto")]
+    #[test]
+    fn test_state_frames_compressed_after_negotiation() {
+        fn read_frame(stream: &mut TcpStream) -> (bool, proto::WireMessage) {
+            let mut prefix = [0u8; 4];
+            stream.read_exact(&mut prefix).unwrap();
+            let prefix = u32::from_be_bytes(prefix);
+            let compressed = prefix & COMPRESSED_FLAG != 0;
+            let mut payload = vec![0u8; (prefix & !COMPRESSED_FLAG) as usize];
+            stream.read_exact(&mut payload).unwrap();
+            if compressed {
+                payload = decompress_frame(&payload).unwrap();
+            }
+            (
+                compressed,
+                proto::WireMessage::decode(payload.as_slice()).unwrap(),
+            )
+        }
+
+        let (addr, stop, handle) = spawn_bridge(BridgeConfig {
+            wire_protocol: WireProtocol::Protobuf,
+            publish_mode: PublishMode::FixedRate(Duration::from_millis(20)),
+            compress_frames_over: Some(0),
+            ..Default::default()
+        });
+        let mut stream = connect(&addr);
+        let hello = proto::WireMessage {
+            payload: Some(proto::wire_message::Payload::Hello(proto::Hello {
+                protocol_version: Some(proto::ProtocolVersion { major: 1, minor: 0 }),
+                capabilities: vec![ZSTD_CAPABILITY.to_string()],
+                client_id: None,
+                last_snapshot_sequence: None,
+            })),
+        };
+        let mut body = Vec::new();
+        hello.encode(&mut body).unwrap();
+        stream.write_all(&binary_frame(body, None)).unwrap();
+
+        let mut saw_ack = false;
+        loop {
+            match read_frame(&mut stream) {
+                (
+                    _,
+                    proto::WireMessage {
+                        payload: Some(proto::wire_message::Payload::HelloAck(ack)),
+                    },
+                ) => {
+                    assert!(ack.capabilities.iter().any(|c| c == ZSTD_CAPABILITY));
+                    saw_ack = true;
+                }
+                (
+                    true,
+                    proto::WireMessage {
+                        payload: Some(proto::wire_message::Payload::State(_)),
+                    },
+                ) => break,
+                _ => {}
+            }
+        }
+        assert!(saw_ack);
+
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_silent_client_reaped_after_idle_timeout() {
+        let idle_timeout = Duration::from_millis(300);
+        let (addr, stop, handle) = spawn_bridge(BridgeConfig {
+            idle_timeout: Some(idle_timeout),
+            ..Default::default()
+        });
+
+        let mut stream = connect(&addr);
+        let connected_at = Instant::now();
+        // Drain state frames without ever writing until the bridge hangs up.
+        read_until_closed(&mut stream);
+        assert!(connected_at.elapsed() >= idle_timeout);
+
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_accepts_on_every_bind_address() {
+        use std::io::BufRead;
+
+        let extra = {
+            let probe = TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().to_string()
+        };
+        let (addr, stop, handle) = spawn_bridge(BridgeConfig {
+            extra_bind_addrs: vec![extra.clone()],
+            publish_mode: PublishMode::FixedRate(Duration::from_millis(10)),
+            ..Default::default()
+        });
+
+        for addr in [&addr, &extra, &addr] {
+            let stream = connect(addr);
+            let mut line = String::new();
+            std::io::BufReader::new(&stream)
+                .read_line(&mut line)
+                .unwrap();
+            let state: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(state["type"], "state");
+        }
+
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_shorter_poll_interval_delivers_state_sooner() {
+        use std::io::BufRead;
+
+        // Time to the first state frame and to the fifth; the bridge sends
+        // at most one frame per pass.
+        let time_frames = |poll_interval: Duration| {
+            let (addr, stop, handle) = spawn_bridge(BridgeConfig {
+                publish_mode: PublishMode::FixedRate(Duration::from_millis(1)),
+                poll_interval,
+                ..Default::default()
+            });
+            let stream = connect(&addr);
+            let connected_at = Instant::now();
+            let mut reader = std::io::BufReader::new(&stream);
+            let mut first = Duration::ZERO;
+            for frame in 0..5 {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if frame == 0 {
+                    first = connected_at.elapsed();
+                }
+            }
+            let fifth = connected_at.elapsed();
+            stop.store(true, std::sync::atomic::Ordering::Relaxed);
+            handle.join().unwrap();
+            (first, fifth)
+        };
+
+        let (fast_first, fast_fifth) = time_frames(Duration::ZERO);
+        let (_, slow_fifth) = time_frames(Duration::from_millis(100));
+        assert!(fast_first < Duration::from_millis(250), "{fast_first:?}");
+        assert!(slow_fifth >= Duration::from_millis(400), "{slow_fifth:?}");
+        assert!(fast_fifth < slow_fifth, "{fast_fifth:?} vs {slow_fifth:?}");
+    }
+
+    #[test]
+    fn test_newline_less_payload_disconnects_client() {
+        let (addr, stop, handle) = spawn_bridge(BridgeConfig::default());
+
+        let mut stream = connect(&addr);
+        let payload = vec![b'a'; 1024 * 1024];
+        // The bridge may hang up before the whole payload is written.
+        let _ = stream.write_all(&payload);
+        read_until_closed(&mut stream);
+
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_shared_token_validator_rejects_replays_across_bridges() {
+        let auth = AuthConfig {
+            enabled: true,
+            secret: b"secret".to_vec(),
+            ..AuthConfig::default()
+        };
+        let shared = Arc::new(Mutex::new(TokenValidator::from_config(&auth)));
+        let bridge_config = || BridgeConfig {
+            auth: auth.clone(),
+            token_validator: Some(Arc::clone(&shared)),
+            ..BridgeConfig::default()
+        };
+        let tcp = bridge_config().token_validator().unwrap();
+        let ws = bridge_config().token_validator().unwrap();
+        assert!(Arc::ptr_eq(&tcp, &ws));
+
+        let token = shared.lock().unwrap().generate_token();
+        assert!(tcp.lock().unwrap().validate(&token).is_ok());
+        assert!(matches!(
+            ws.lock().unwrap().validate(&token),
+            Err(crate::auth::AuthError::ReplayDetected)
+        ));
+    }
+}
@@ -0,0 +1,361 @@
+//! Tokio implementation of the TCP bridge for many concurrent agents.
+//!
+//! [`crate::bridge::run_bridge`] serves one client from a nonblocking loop
+//! that sleeps `poll_interval` between passes, which suits the single agent
+//! next to a real-time control loop. This variant accepts any number of
+//! clients, each on its own task, and handles inbound lines as soon as they
+//! arrive instead of on the next pass. Messages go through the same
+//! validation, auth, rate limiting and audit as the blocking bridge.
+//!
+//! State frames are still checked against the publish schedule once per
+//! `poll_interval`, because [`StateExchange`] has no change notification.
+
+use crate::audit::AuditLogger;
+use crate::auth::TokenValidator;
+use crate::bridge::{
+    append_crc32, audit_outcome, checked_line, encode_reply, handle_incoming, parse_incoming,
+    server_capabilities, BridgeConfig, BridgeError, BridgeReply, InboundState, PublishSchedule,
+    WireProtocol, MAX_LINE_BYTES,
+};
+use crate::metrics::{BRIDGE_CONNECTED, BRIDGE_SLOW_CLIENT_DROPS, HEALTH, TLS_HANDSHAKE_FAILURES};
+use crate::protocol::{PingMsg, ProtocolVersion, StateMsg};
+use crate::tls::{handshake_failure_reason, ReloadableServerConfig};
+use core_spine::{Clock, Sequenced, StateExchange};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tracing::{info, warn};
+
+/// A client that cannot take a frame within this long is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Upper bound on the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serve agents on every address in `config.bind_addrs()` until `stop` is
+/// set, one task per connection. `config.wire_protocol` is ignored; frames
+/// are always JSON lines. `stop` is checked every `config.poll_interval`.
+/// `audit` is used as in [`crate::bridge::run_bridge`].
+pub async fn run_bridge_async<C: Clock + Clone + Send + Sync + 'static>(
+    exchange: Arc<StateExchange>,
+    clock: C,
+    config: BridgeConfig,
+    stop: Arc<AtomicBool>,
+    audit: Option<Arc<AuditLogger>>,
+) -> Result<(), BridgeError> {
+    let mut listeners = Vec::new();
+    for addr in config.bind_addrs() {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|source| BridgeError::Bind {
+                addr: addr.to_string(),
+                source,
+            })?;
+        listeners.push(listener);
+    }
+    let tls_config = if config.tls.enabled {
+        Some(Arc::new(Mutex::new(ReloadableServerConfig::new(
+            &config.tls,
+        )?)))
+    } else {
+        None
+    };
+    let validator = config.token_validator();
+    let config = Arc::new(BridgeConfig {
+        wire_protocol: WireProtocol::JsonLines,
+        ..config
+    });
+
+    info!(
+        addrs = ?config.bind_addrs().collect::<Vec<_>>(),
+        tls = config.tls.enabled,
+        auth = config.auth.enabled,
+        "Async bridge listening"
+    );
+    HEALTH.set_bridge_listening(true);
+
+    let connected = Arc::new(AtomicUsize::new(0));
+    let mut accept_loops = JoinSet::new();
+    for listener in listeners {
+        let client = AsyncClient {
+            exchange: Arc::clone(&exchange),
+            clock: clock.clone(),
+            config: Arc::clone(&config),
+            validator: validator.clone(),
+            stop: Arc::clone(&stop),
+            audit: audit.clone(),
+            connected: Arc::clone(&connected),
+        };
+        accept_loops.spawn(accept_loop(listener, client, tls_config.clone()));
+    }
+    while accept_loops.join_next().await.is_some() {}
+
+    HEALTH.set_bridge_listening(false);
+    Ok(())
+}
+
+/// Accept on one listener until `stop` is set, then wait for its clients.
+async fn accept_loop<C: Clock + Clone + Send + Sync + 'static>(
+    listener: TcpListener,
+    client: AsyncClient<C>,
+    tls_config: Option<Arc<Mutex<ReloadableServerConfig>>>,
+) {
+    let poll_interval = client.config.effective_poll_interval();
+    let mut clients = JoinSet::new();
+    while !client.stop.load(Ordering::Relaxed) {
+        while clients.try_join_next().is_some() {}
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = tokio::time::sleep(poll_interval) => continue,
+        };
+        let (stream, addr) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(error = %e, "Async bridge accept error");
+                continue;
+            }
+        };
+        info!(client_addr = %addr, "Bridge client connected");
+        let _ = stream.set_nodelay(true);
+        let client = client.clone();
+        match tls_config.as_ref() {
+            Some(tls_config) => {
+                let acceptor = TlsAcceptor::from(tls_config.lock().unwrap().current());
+                clients.spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => client.serve(stream, addr).await,
+                        Ok(Err(e)) => {
+                            let reason = handshake_failure_reason(&e);
+                            warn!(error = %e, reason, "Bridge TLS handshake failed");
+                            TLS_HANDSHAKE_FAILURES.with_label_values(&[reason]).inc();
+                        }
+                        Err(_) => warn!(client_addr = %addr, "Bridge TLS handshake timed out"),
+                    }
+                });
+            }
+            None => {
+                clients.spawn(async move { client.serve(stream, addr).await });
+            }
+        }
+    }
+    while clients.join_next().await.is_some() {}
+}
+
+/// Per-connection state shared with the accept loop.
+#[derive(Clone)]
+struct AsyncClient<C> {
+    exchange: Arc<StateExchange>,
+    clock: C,
+    config: Arc<BridgeConfig>,
+    validator: Option<Arc<Mutex<TokenValidator>>>,
+    stop: Arc<AtomicBool>,
+    audit: Option<Arc<AuditLogger>>,
+    /// Clients currently served across all listeners
+    connected: Arc<AtomicUsize>,
+}
+
+impl<C: Clock> AsyncClient<C> {
+    async fn serve<S: AsyncRead + AsyncWrite + Unpin>(self, stream: S, peer_addr: SocketAddr) {
+        self.connected.fetch_add(1, Ordering::Relaxed);
+        BRIDGE_CONNECTED.set(1.0);
+        self.serve_connection(stream, peer_addr).await;
+        if self.connected.fetch_sub(1, Ordering::Relaxed) == 1 {
+            BRIDGE_CONNECTED.set(0.0);
+        }
+    }
+
+    async fn serve_connection<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: S,
+        peer_addr: SocketAddr,
+    ) {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let capabilities = server_capabilities(&self.config);
+        let mut inbound_state =
+            InboundState::with_rate_limit(self.config.max_recommendations_per_sec)
+                .with_min_confidence(self.config.min_confidence)
+                .with_duplicate_reasoning(self.config.duplicate_reasoning)
+                .with_max_clock_skew(self.config.max_clock_skew)
+                .with_require_signature(self.config.require_signature)
+                .with_reasoning_hash_bytes(self.config.reasoning_hash_bytes)
+                .with_safety_limits(self.config.safety_limits)
+                .with_sequence_floors(self.config.sequence_floors.clone())
+                .with_effective_config(self.config.effective_config.clone());
+        inbound_state.note_peer(peer_addr);
+        let mut publish = PublishSchedule::new(self.config.publish_mode);
+        let mut ticker = tokio::time::interval(self.config.effective_poll_interval());
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut recv_buf: Vec<u8> = Vec::with_capacity(4096);
+        let mut temp = [0u8; 1024];
+        let mut state_sequence: u64 = 0;
+        let mut ping_sequence: u64 = 0;
+        let mut last_activity = Instant::now();
+        let mut last_ping = Instant::now();
+
+        loop {
+            if self.stop.load(Ordering::Relaxed) {
+                let _ = writer.shutdown().await;
+                return;
+            }
+
+            tokio::select! {
+                read = reader.read(&mut temp) => match read {
+                    Ok(0) => {
+                        info!("Bridge client disconnected");
+                        return;
+                    }
+                    Ok(n) => {
+                        last_activity = Instant::now();
+                        recv_buf.extend_from_slice(&temp[..n]);
+                        while let Some(pos) = recv_buf.iter().position(|b| *b == b'\n') {
+                            let line = recv_buf.drain(..=pos).collect::<Vec<u8>>();
+                            let Ok(text) = std::str::from_utf8(&line) else {
+                                continue;
+                            };
+                            if text.trim().is_empty() {
+                                continue;
+                            }
+                            let Some(trimmed) = checked_line(text, &inbound_state) else {
+                                continue;
+                            };
+                            let reply = parse_incoming(trimmed).and_then(|msg| {
+                                let validator = self.validator.as_ref().map(|v| v.lock().unwrap());
+                                handle_incoming(
+                                    msg,
+                                    &self.exchange,
+                                    &self.clock,
+                                    validator.as_deref(),
+                                    self.config.require_handshake,
+                                    &mut inbound_state,
+                                    self.audit.as_deref(),
+                                )
+                            });
+                            if let Some(reply) = reply {
+                                let crc32 = inbound_state.crc32_framing();
+                                if !self.send_reply(&mut writer, &reply, &capabilities, crc32).await {
+                                    return;
+                                }
+                            }
+                        }
+                        if recv_buf.len() > MAX_LINE_BYTES {
+                            warn!(len = recv_buf.len(), "Dropping client with oversized line");
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        warn!(error = %e, "Bridge read error");
+                        return;
+                    }
+                },
+                _ = ticker.tick() => {}
+            }
+
+            if let Some(audit) = self.audit.as_deref() {
+                audit_outcome(&self.exchange, &self.clock, audit, &mut inbound_state);
+            }
+
+            if let Some(idle_timeout) = self.config.idle_timeout {
+                if last_activity.elapsed() >= idle_timeout {
+                    warn!(
+                        idle_ms = last_activity.elapsed().as_millis() as u64,
+                        "Dropping idle bridge client"
+                    );
+                    return;
+                }
+            }
+
+            let Sequenced {
+                sequence: snapshot_sequence,
+                value: snapshot,
+            } = self.exchange.read_state_sequenced();
+            if publish.due(&snapshot, Instant::now()) {
+                state_sequence = state_sequence.wrapping_add(1);
+                let msg = StateMsg::from_snapshot(&snapshot, state_sequence, self.clock.unix_us())
+                    .with_snapshot_sequence(snapshot_sequence);
+                if let Ok(line) = serde_json::to_string(&msg) {
+                    if !write_line(&mut writer, line, inbound_state.crc32_framing()).await {
+                        return;
+                    }
+                }
+                publish.note_sent(snapshot, Instant::now());
+            }
+
+            if let Some(ping_interval) = self.config.ping_interval {
+                if last_ping.elapsed() >= ping_interval {
+                    ping_sequence = ping_sequence.wrapping_add(1);
+                    let msg = PingMsg {
+                        msg_type: "ping",
+                        protocol_version: ProtocolVersion::v1(),
+                        sequence: ping_sequence,
+                        unix_us: self.clock.unix_us(),
+                    };
+                    if let Ok(line) = serde_json::to_string(&msg) {
+                        if !write_line(&mut writer, line, inbound_state.crc32_framing()).await {
+                            return;
+                        }
+                    }
+                    last_ping = Instant::now();
+                }
+            }
+        }
+    }
+
+    /// Send a reply frame, with a CRC-32 suffix if `crc32` is set and it is
+    /// not the `hello_ack`; returns false when the connection should end.
+    async fn send_reply<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        reply: &BridgeReply,
+        capabilities: &[String],
+        crc32: bool,
+    ) -> bool {
+        match encode_reply(reply, WireProtocol::JsonLines, capabilities) {
+            Some(mut frame) => {
+                if crc32 && !matches!(reply, BridgeReply::HelloAck { .. }) {
+                    append_crc32(&mut frame);
+                }
+                if !write_frame(writer, &frame).await {
+                    return false;
+                }
+            }
+            None => warn!("Failed to encode bridge reply"),
+        }
+        if matches!(reply, BridgeReply::VersionError(_)) {
+            info!("Closing bridge client after handshake rejection");
+            let _ = writer.shutdown().await;
+            return false;
+        }
+        true
+    }
+}
+
+async fn write_line<W: AsyncWrite + Unpin>(writer: &mut W, line: String, crc32: bool) -> bool {
+    let mut frame = line.into_bytes();
+    frame.push(b'\n');
+    if crc32 {
+        append_crc32(&mut frame);
+    }
+    write_frame(writer, &frame).await
+}
+
+/// Write a whole frame; returns false when the connection should end. A
+/// client that cannot take the frame within [`WRITE_TIMEOUT`] is dropped as
+/// slow.
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &[u8]) -> bool {
+    match tokio::time::timeout(WRITE_TIMEOUT, writer.write_all(frame)).await {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            warn!(error = %e, "Bridge write error");
+            false
+        }
+        Err(_) => {
+            warn!("Dropping slow bridge client");
+            BRIDGE_SLOW_CLIENT_DROPS.inc();
+            false
+        }
+    }
+}
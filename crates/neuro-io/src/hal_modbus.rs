@@ -1,18 +1,264 @@
-use core_spine::{CycleStats, MachineIO};
+use core_spine::{AlarmFlags, CycleStats, MachineIO};
+use std::net::{AddrParseError, SocketAddr};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use thiserror::Error;
 use tokio::runtime::Runtime;
 use tokio::time::interval;
+use tokio_modbus::client::Context;
 use tokio_modbus::prelude::*;
+use tokio_serial::{DataBits, Parity, SerialStream, StopBits};
 use tracing::{error, info, warn};
 
+/// Errors constructing a [`ModbusMotor`]. Connection failures happen later,
+/// in the background poller, and show up as an unhealthy HAL.
+#[derive(Debug, Error)]
+pub enum ModbusError {
+    #[error("invalid Modbus address {addr}: {source}")]
+    InvalidAddress {
+        addr: String,
+        #[source]
+        source: AddrParseError,
+    },
+    #[error("invalid Modbus serial spec {spec}: {reason}")]
+    InvalidSerialSpec { spec: String, reason: String },
+    #[error("failed to create Tokio runtime for Modbus client: {0}")]
+    Runtime(#[source] std::io::Error),
+}
+
+/// Serial line settings for Modbus RTU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialSpec {
+    pub path: String,
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    /// Modbus slave (unit) ID of the drive on the bus
+    pub slave: u8,
+}
+
+impl SerialSpec {
+    /// `<PATH>:<BAUD>[:<FRAMING>[:<SLAVE>]]`, e.g. `/dev/ttyUSB0:9600:8N1:1`.
+    /// Framing is data bits (5-8), parity (N, E or O) and stop bits (1 or 2),
+    /// default `8N1`; the slave ID defaults to 1.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.split(':');
+        let path = parts
+            .next()
+            .filter(|p| !p.is_empty())
+            .ok_or("missing device path")?;
+        let baud_rate = parts
+            .next()
+            .ok_or("missing baud rate")?
+            .parse::<u32>()
+            .ok()
+            .filter(|&baud| baud > 0)
+            .ok_or("invalid baud rate")?;
+        let (data_bits, parity, stop_bits) = match parts.next() {
+            Some(framing) => parse_framing(framing).ok_or("framing is not like 8N1")?,
+            None => (DataBits::Eight, Parity::None, StopBits::One),
+        };
+        let slave = match parts.next() {
+            Some(id) => id
+                .parse::<u8>()
+                .ok()
+                .filter(|&id| (1..=247).contains(&id))
+                .ok_or("slave ID must be 1-247")?,
+            None => 1,
+        };
+        if parts.next().is_some() {
+            return Err("too many fields".to_string());
+        }
+        Ok(Self {
+            path: path.to_string(),
+            baud_rate,
+            data_bits,
+            parity,
+            stop_bits,
+            slave,
+        })
+    }
+}
+
+fn parse_framing(framing: &str) -> Option<(DataBits, Parity, StopBits)> {
+    let bytes = framing.as_bytes();
+    if bytes.len() != 3 {
+        return None;
+    }
+    let data_bits = match bytes[0] {
+        b'5' => DataBits::Five,
+        b'6' => DataBits::Six,
+        b'7' => DataBits::Seven,
+        b'8' => DataBits::Eight,
+        _ => return None,
+    };
+    let parity = match bytes[1].to_ascii_uppercase() {
+        b'N' => Parity::None,
+        b'E' => Parity::Even,
+        b'O' => Parity::Odd,
+        _ => return None,
+    };
+    let stop_bits = match bytes[2] {
+        b'1' => StopBits::One,
+        b'2' => StopBits::Two,
+        _ => return None,
+    };
+    Some((data_bits, parity, stop_bits))
+}
+
+/// How [`ModbusMotor`] reaches the device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModbusTransport {
+    Tcp(SocketAddr),
+    Rtu(SerialSpec),
+}
+
+impl ModbusTransport {
+    /// A `host:port` socket address for Modbus TCP, or `serial:` followed
+    /// by a [`SerialSpec`] for Modbus RTU.
+    pub fn parse(addr: &str) -> Result<Self, ModbusError> {
+        match addr.strip_prefix("serial:") {
+            Some(spec) => SerialSpec::parse(spec)
+                .map(ModbusTransport::Rtu)
+                .map_err(|reason| ModbusError::InvalidSerialSpec {
+                    spec: addr.to_string(),
+                    reason,
+                }),
+            None => addr.parse().map(ModbusTransport::Tcp).map_err(|source| {
+                ModbusError::InvalidAddress {
+                    addr: addr.to_string(),
+                    source,
+                }
+            }),
+        }
+    }
+
+    async fn connect(&self) -> std::io::Result<Context> {
+        match self {
+            ModbusTransport::Tcp(addr) => tcp::connect(*addr).await,
+            ModbusTransport::Rtu(spec) => {
+                let builder = tokio_serial::new(spec.path.as_str(), spec.baud_rate)
+                    .data_bits(spec.data_bits)
+                    .parity(spec.parity)
+                    .stop_bits(spec.stop_bits);
+                let port = SerialStream::open(&builder)?;
+                Ok(rtu::attach_slave(port, Slave(spec.slave)))
+            }
+        }
+    }
+}
+
+/// Order of the two 16-bit words of a 32-bit register value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordOrder {
+    /// High word in the lower register address (Modbus convention).
+    HighFirst,
+    /// Low word first, as used by "word-swapped" drives.
+    LowFirst,
+}
+
+/// How the target speed is written to the holding registers. The raw value
+/// is `round(rpm * scale)`, clamped to what the registers can hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetEncoding {
+    /// One unsigned 16-bit register.
+    Scaled16 { scale: f64 },
+    /// One unsigned 32-bit value across two consecutive registers.
+    U32 { scale: f64, word_order: WordOrder },
+}
+
+impl Default for TargetEncoding {
+    fn default() -> Self {
+        TargetEncoding::Scaled16 { scale: 1.0 }
+    }
+}
+
+impl TargetEncoding {
+    /// `u16[:SCALE]`, `u32[:SCALE]` (high word first) or
+    /// `u32-swapped[:SCALE]` (low word first)
+    pub fn parse(value: &str) -> Option<Self> {
+        let (kind, scale) = match value.split_once(':') {
+            Some((kind, scale)) => (kind, scale.parse::<f64>().ok()?),
+            None => (value, 1.0),
+        };
+        if !(scale.is_finite() && scale > 0.0) {
+            return None;
+        }
+        match kind {
+            "u16" => Some(TargetEncoding::Scaled16 { scale }),
+            "u32" => Some(TargetEncoding::U32 {
+                scale,
+                word_order: WordOrder::HighFirst,
+            }),
+            "u32-swapped" => Some(TargetEncoding::U32 {
+                scale,
+                word_order: WordOrder::LowFirst,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Register words for `rpm`, and whether it had to be clamped to fit.
+    /// Negative and non-finite targets write zero.
+    pub fn encode(&self, rpm: f64) -> (Vec<u16>, bool) {
+        let (scale, max) = match self {
+            TargetEncoding::Scaled16 { scale } => (*scale, u16::MAX as f64),
+            TargetEncoding::U32 { scale, .. } => (*scale, u32::MAX as f64),
+        };
+        let scaled = (rpm * scale).round();
+        let raw = if scaled.is_nan() {
+            0.0
+        } else {
+            scaled.clamp(0.0, max)
+        };
+        let clamped = raw != scaled;
+        let words = match self {
+            TargetEncoding::Scaled16 { .. } => vec![raw as u16],
+            TargetEncoding::U32 { word_order, .. } => {
+                let raw = raw as u32;
+                let (high, low) = ((raw >> 16) as u16, raw as u16);
+                match word_order {
+                    WordOrder::HighFirst => vec![high, low],
+                    WordOrder::LowFirst => vec![low, high],
+                }
+            }
+        };
+        (words, clamped)
+    }
+}
+
+/// Where [`ModbusMotor`] writes its outputs and finds its alarm inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RegisterMap {
+    /// First holding register of the target speed
+    pub target_register: u16,
+    pub target_encoding: TargetEncoding,
+    /// First of the discrete inputs read as [`AlarmFlags`], one input per
+    /// flag in bit order. `None` reads no alarms.
+    pub alarm_inputs: Option<u16>,
+}
+
+/// Alarm flags from discrete input states, the first input being bit 0
+fn alarms_from_inputs(inputs: &[bool]) -> AlarmFlags {
+    let bits = inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, set)| **set)
+        .fold(0u16, |bits, (bit, _)| bits | 1 << bit);
+    AlarmFlags::from_bits_truncate(bits)
+}
+
 #[derive(Clone, Debug, Default)]
 struct SharedState {
     speed_rpm: f64,
     temp_c: f64,
     pressure_bar: f64,
     target_speed_rpm: f64,
+    alarms: AlarmFlags,
     connected: bool,
+    /// The last alarm input read failed; `alarms` may be stale
+    alarms_unknown: bool,
 }
 
 pub struct ModbusMotor {
@@ -23,36 +269,36 @@ pub struct ModbusMotor {
 }
 
 impl ModbusMotor {
-    pub fn new(addr: &str) -> Option<Self> {
+    /// Connect with the default [`RegisterMap`]
+    pub fn new(addr: &str) -> Result<Self, ModbusError> {
+        Self::with_register_map(addr, RegisterMap::default())
+    }
+
+    /// `addr` is parsed by [`ModbusTransport::parse`]
+    pub fn with_register_map(addr: &str, register_map: RegisterMap) -> Result<Self, ModbusError> {
+        let transport = ModbusTransport::parse(addr)?;
         let state = Arc::new(Mutex::new(SharedState::default()));
         let state_clone = state.clone();
         let addr = addr.to_string();
 
-        let runtime = Arc::new(Runtime::new().expect("Failed to create Tokio runtime"));
+        let runtime = Arc::new(Runtime::new().map_err(ModbusError::Runtime)?);
         let runtime_clone = runtime.clone();
 
         // Spawn background polling task
         runtime.spawn(async move {
-            let socket_addr = match addr.parse() {
-                Ok(a) => a,
-                Err(e) => {
-                    error!("Invalid Modbus address {}: {}", addr, e);
-                    return;
-                }
-            };
-
-            let mut ctx = match tcp::connect(socket_addr).await {
+            let mut ctx = match transport.connect().await {
                 Ok(c) => {
-                    info!("Connected to Modbus TCP at {}", addr);
+                    info!("Connected to Modbus at {}", addr);
                     c
                 }
                 Err(e) => {
-                    error!("Failed to connect to Modbus TCP at {}: {}", addr, e);
+                    error!("Failed to connect to Modbus at {}: {}", addr, e);
                     return;
                 }
             };
 
             let mut ticker = interval(Duration::from_millis(10)); // 100Hz polling
+            let mut target_clamped = false;
 
             loop {
                 ticker.tick().await;
@@ -78,21 +324,56 @@ impl ModbusMotor {
                     }
                 }
 
+                if let Some(first_input) = register_map.alarm_inputs {
+                    let count = AlarmFlags::all().iter().count() as u16;
+                    match ctx.read_discrete_inputs(first_input, count).await {
+                        Ok(inputs) => {
+                            let mut state = state_clone.lock().unwrap();
+                            state.alarms = alarms_from_inputs(&inputs);
+                            state.alarms_unknown = false;
+                        }
+                        Err(e) => {
+                            // Unknown alarm state: report the HAL unhealthy
+                            // until an alarm read succeeds again, whatever
+                            // the register reads do.
+                            warn!("Modbus alarm input read failed: {}", e);
+                            state_clone.lock().unwrap().alarms_unknown = true;
+                        }
+                    }
+                }
+
                 // Write output
                 let target = {
                     let state = state_clone.lock().unwrap();
                     state.target_speed_rpm
                 };
 
-                // Example: Holding Register 0
-                let target_reg = target as u16;
-                if let Err(e) = ctx.write_single_register(0, target_reg).await {
+                let (words, clamped) = register_map.target_encoding.encode(target);
+                if clamped && !target_clamped {
+                    warn!(
+                        target_rpm = target,
+                        encoding = ?register_map.target_encoding,
+                        "Target speed outside the register range, clamped"
+                    );
+                }
+                target_clamped = clamped;
+                let written = match words.as_slice() {
+                    [word] => {
+                        ctx.write_single_register(register_map.target_register, *word)
+                            .await
+                    }
+                    _ => {
+                        ctx.write_multiple_registers(register_map.target_register, &words)
+                            .await
+                    }
+                };
+                if let Err(e) = written {
                     warn!("Modbus write failed: {}", e);
                 }
             }
         });
 
-        Some(Self {
+        Ok(Self {
             state,
             stats: CycleStats::default(),
             last_cycle: Instant::now(),
@@ -136,6 +417,239 @@ impl MachineIO for ModbusMotor {
     }
 
     fn is_healthy(&self) -> bool {
-        self.state.lock().unwrap().connected
+        let state = self.state.lock().unwrap();
+        state.connected && !state.alarms_unknown
+    }
+
+    fn read_alarms(&self) -> AlarmFlags {
+        self.state.lock().unwrap().alarms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    const FAILING_INPUT: u16 = 900;
+
+    /// Minimal Modbus TCP server: answers input register reads with zeros,
+    /// discrete input reads with only the first input set (or, slowly, an
+    /// exception at [`FAILING_INPUT`]), and reports every holding register write as
+    /// `(address, words)`.
+    fn mock_server() -> (String, mpsc::Receiver<(u16, Vec<u16>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut header = [0u8; 7];
+            while stream.read_exact(&mut header).is_ok() {
+                let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+                let mut pdu = vec![0u8; len - 1];
+                if stream.read_exact(&mut pdu).is_err() {
+                    break;
+                }
+                let word = |i: usize| u16::from_be_bytes([pdu[i], pdu[i + 1]]);
+                let reply = match pdu[0] {
+                    0x04 => {
+                        let count = word(3) as usize;
+                        let mut reply = vec![0x04, (count * 2) as u8];
+                        reply.resize(2 + count * 2, 0);
+                        reply
+                    }
+                    0x02 if word(1) == FAILING_INPUT => {
+                        // Slow, so a poll spends most of its time between
+                        // the register read and the failed alarm read.
+                        std::thread::sleep(Duration::from_millis(50));
+                        vec![0x82, 0x02]
+                    }
+                    0x02 => {
+                        let count = word(3) as usize;
+                        let mut reply = vec![0x02, count.div_ceil(8) as u8, 0x01];
+                        reply.resize(2 + count.div_ceil(8), 0);
+                        reply
+                    }
+                    0x06 => {
+                        let _ = tx.send((word(1), vec![word(3)]));
+                        pdu.clone()
+                    }
+                    0x10 => {
+                        let count = word(3) as usize;
+                        let words = (0..count).map(|n| word(6 + n * 2)).collect();
+                        let _ = tx.send((word(1), words));
+                        pdu[..5].to_vec()
+                    }
+                    other => vec![other | 0x80, 0x01],
+                };
+                let mut frame = header[..4].to_vec();
+                frame.extend_from_slice(&((reply.len() + 1) as u16).to_be_bytes());
+                frame.push(header[6]);
+                frame.extend_from_slice(&reply);
+                if stream.write_all(&frame).is_err() {
+                    break;
+                }
+            }
+        });
+        (addr, rx)
+    }
+
+    #[test]
+    fn test_u32_target_written_across_two_registers() {
+        let (addr, writes) = mock_server();
+        let map = RegisterMap {
+            target_register: 10,
+            target_encoding: TargetEncoding::U32 {
+                scale: 10.0,
+                word_order: WordOrder::LowFirst,
+            },
+            alarm_inputs: None,
+        };
+        let mut motor = ModbusMotor::with_register_map(&addr, map).unwrap();
+        motor.write_speed(7_000.5);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let words = loop {
+            let (register, words) = writes
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .expect("no target write received");
+            assert_eq!(register, 10);
+            if words != [0, 0] {
+                break words;
+            }
+        };
+        let value = (words[1] as u32) << 16 | words[0] as u32;
+        assert_eq!(value, 70_005);
+        assert!(motor.is_healthy());
+    }
+
+    #[test]
+    fn test_target_encoding_rounds_and_clamps() {
+        let u16_tenths = TargetEncoding::Scaled16 { scale: 10.0 };
+        assert_eq!(u16_tenths.encode(123.46), (vec![1235], false));
+        assert_eq!(u16_tenths.encode(7_000.0), (vec![u16::MAX], true));
+        assert_eq!(u16_tenths.encode(-5.0), (vec![0], true));
+        assert_eq!(u16_tenths.encode(f64::NAN), (vec![0], true));
+
+        let u32_high_first = TargetEncoding::U32 {
+            scale: 1.0,
+            word_order: WordOrder::HighFirst,
+        };
+        assert_eq!(u32_high_first.encode(70_000.0), (vec![1, 4_464], false));
+        assert_eq!(
+            u32_high_first.encode(1e12),
+            (vec![u16::MAX, u16::MAX], true)
+        );
+    }
+
+    #[test]
+    fn test_serial_spec_parse() {
+        let transport = ModbusTransport::parse("serial:/dev/ttyUSB0:19200:7E2:17").unwrap();
+        assert_eq!(
+            transport,
+            ModbusTransport::Rtu(SerialSpec {
+                path: "/dev/ttyUSB0".to_string(),
+                baud_rate: 19_200,
+                data_bits: DataBits::Seven,
+                parity: Parity::Even,
+                stop_bits: StopBits::Two,
+                slave: 17,
+            })
+        );
+
+        let ModbusTransport::Rtu(spec) = ModbusTransport::parse("serial:COM3:9600").unwrap() else {
+            panic!("expected an RTU transport");
+        };
+        assert_eq!(spec.path, "COM3");
+        assert_eq!(
+            (spec.data_bits, spec.parity, spec.stop_bits, spec.slave),
+            (DataBits::Eight, Parity::None, StopBits::One, 1)
+        );
+
+        assert!(matches!(
+            ModbusTransport::parse("10.0.0.1:502"),
+            Ok(ModbusTransport::Tcp(_))
+        ));
+        for bad in [
+            "serial:",
+            "serial:/dev/ttyUSB0",
+            "serial:/dev/ttyUSB0:fast",
+            "serial:/dev/ttyUSB0:9600:9N1",
+            "serial:/dev/ttyUSB0:9600:8N1:0",
+            "serial:/dev/ttyUSB0:9600:8N1:1:x",
+        ] {
+            assert!(
+                matches!(
+                    ModbusTransport::parse(bad),
+                    Err(ModbusError::InvalidSerialSpec { .. })
+                ),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rtu_motor_constructs_without_device() {
+        // The port is opened by the poller, so a missing device only leaves
+        // the HAL unhealthy.
+        let motor = ModbusMotor::new("serial:/nonexistent/ttyUSB9:9600:8N1:1").unwrap();
+        assert!(!motor.is_healthy());
+    }
+
+    #[test]
+    fn test_target_encoding_parse() {
+        assert_eq!(
+            TargetEncoding::parse("u16"),
+            Some(TargetEncoding::Scaled16 { scale: 1.0 })
+        );
+        assert_eq!(
+            TargetEncoding::parse("u32-swapped:10"),
+            Some(TargetEncoding::U32 {
+                scale: 10.0,
+                word_order: WordOrder::LowFirst
+            })
+        );
+        for bad in ["", "u64", "u32:0", "u16:-1", "u16:x"] {
+            assert_eq!(TargetEncoding::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_alarm_discrete_inputs_map_to_flags() {
+        assert_eq!(
+            alarms_from_inputs(&[false, true, false, true]),
+            AlarmFlags::OVERCURRENT | AlarmFlags::DRIVE_FAULT
+        );
+
+        let (addr, _writes) = mock_server();
+        let map = RegisterMap {
+            alarm_inputs: Some(100),
+            ..RegisterMap::default()
+        };
+        let motor = ModbusMotor::with_register_map(&addr, map).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while motor.read_alarms().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(motor.read_alarms(), AlarmFlags::DOOR_OPEN);
+        assert!(motor.is_healthy());
+    }
+
+    #[test]
+    fn test_failed_alarm_read_stays_unhealthy_across_register_reads() {
+        let (addr, writes) = mock_server();
+        let map = RegisterMap {
+            alarm_inputs: Some(FAILING_INPUT),
+            ..RegisterMap::default()
+        };
+        let motor = ModbusMotor::with_register_map(&addr, map).unwrap();
+        writes.recv_timeout(Duration::from_secs(5)).unwrap();
+        let until = Instant::now() + Duration::from_millis(300);
+        while Instant::now() < until {
+            assert!(!motor.is_healthy());
+            std::thread::sleep(Duration::from_millis(5));
+        }
     }
 }
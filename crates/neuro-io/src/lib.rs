@@ -1,15 +1,32 @@
+pub mod audit;
 pub mod auth;
 pub mod bridge;
+#[cfg(feature = "async")]
+pub mod bridge_async;
 pub mod hal_modbus;
 pub mod metrics;
+#[cfg(feature = "otlp")]
+pub mod otlp;
 pub mod protocol;
 #[cfg(feature = "proto")]
 pub mod protocol_proto;
 pub mod tls;
+#[cfg(feature = "ws")]
+pub mod ws;
 
-pub use auth::{AuthConfig, AuthError, TokenClaims, TokenValidator};
-pub use bridge::{run_bridge, BridgeConfig, WireProtocol};
-pub use hal_modbus::ModbusMotor;
-pub use metrics::{init_metrics, serve_metrics};
-pub use protocol::{IncomingMessage, ProtocolVersion, RecommendationMsg, StateMsg};
-pub use tls::{build_server_config, TlsConfig, TlsError};
+pub use audit::{AuditEventType, AuditLogger};
+pub use auth::{AuthAlgorithm, AuthConfig, AuthError, TokenClaims, TokenValidator};
+pub use bridge::{
+    run_bridge, BridgeConfig, BridgeError, DuplicateReasoning, PublishMode, SequenceFloors,
+    WireProtocol,
+};
+#[cfg(feature = "async")]
+pub use bridge_async::run_bridge_async;
+pub use hal_modbus::{
+    ModbusError, ModbusMotor, ModbusTransport, RegisterMap, SerialSpec, TargetEncoding, WordOrder,
+};
+pub use metrics::{
+    init_metrics, serve_metrics, serve_metrics_with_routes, MetricsAdmin, MetricsRoutes,
+};
+pub use protocol::{IncomingMessage, ParseError, ProtocolVersion, RecommendationMsg, StateMsg};
+pub use tls::{build_server_config, ReloadableServerConfig, TlsConfig, TlsError, TlsVersion};
@@ -3,11 +3,20 @@
 //! This module provides metrics collection for the control loop,
 //! safety system, and agent communication.
 
-use core_spine::tags;
-use prometheus::{Encoder, Gauge, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder};
-use std::sync::LazyLock;
+use crate::audit::to_hex;
+use core_spine::{tags, SafetyViolation, StateExchange, RECOMMENDATION_AGE_BUCKETS_US};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use prometheus::{
+    Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry,
+    TextEncoder,
+};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, OnceLock};
 use std::thread;
-use tiny_http::{Response, Server};
+use tiny_http::{Method, Request, Response, Server};
 
 /// Global metrics registry
 pub static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
@@ -38,22 +47,129 @@ pub static CYCLES_MISSED: LazyLock<IntCounter> = LazyLock::new(|| {
     counter
 });
 
+/// Upper bounds of the cycle jitter buckets unless configured otherwise.
+/// The millisecond bounds keep the worst cycles out of `+Inf`.
+pub const DEFAULT_CYCLE_JITTER_BUCKETS_US: [f64; 12] = [
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1_000.0, 2_500.0, 5_000.0, 10_000.0,
+];
+
+/// Bounds [`CYCLE_JITTER_US`] is built with, fixed on first use
+static CYCLE_JITTER_BUCKETS_US: OnceLock<Vec<f64>> = OnceLock::new();
+
 /// Control loop jitter distribution in microseconds
 pub static CYCLE_JITTER_US: LazyLock<Histogram> = LazyLock::new(|| {
+    let buckets = CYCLE_JITTER_BUCKETS_US
+        .get_or_init(|| DEFAULT_CYCLE_JITTER_BUCKETS_US.to_vec())
+        .clone();
     let histogram = Histogram::with_opts(
         HistogramOpts::new(
             tags::CYCLE_JITTER_US.metric,
             "Control loop jitter distribution in microseconds",
         )
-        .buckets(vec![
-            1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0,
-        ]),
+        .buckets(buckets),
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(histogram.clone())).unwrap();
+    histogram
+});
+
+/// Age distribution of the recommendations the control loop applied
+pub static RECOMMENDATION_AGE_US: LazyLock<Histogram> = LazyLock::new(|| {
+    let histogram = Histogram::with_opts(
+        HistogramOpts::new(
+            "neuroplc_recommendation_age_microseconds",
+            "Age of each agent recommendation when the control loop applied it",
+        )
+        .buckets(
+            RECOMMENDATION_AGE_BUCKETS_US
+                .iter()
+                .map(|&bound| bound as f64)
+                .collect(),
+        ),
     )
     .unwrap();
     REGISTRY.register(Box::new(histogram.clone())).unwrap();
     histogram
 });
 
+/// Largest cycle jitter seen since startup
+pub static MAX_JITTER_US: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_max_cycle_jitter_microseconds",
+        "Largest control loop jitter observed since startup",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Cycle timing alert: 0 ok, 1 warning, 2 critical
+pub static JITTER_ALERT_LEVEL: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_jitter_alert_level",
+        "Cycle jitter alert level (0=ok, 1=warning, 2=critical)",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Control-loop states published while the latest resuming bridge client
+/// was away, from the `last_snapshot_sequence` in its `hello`
+pub static STATE_GAP: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_state_gap",
+        "States a reconnecting bridge client missed, as of its last resume",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Age of the recommendation used in the latest cycle
+pub static LAST_RECOMMENDATION_AGE_US: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_last_recommendation_age_microseconds",
+        "Age of the most recently applied agent recommendation",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Highest motor speed seen since startup
+pub static MAX_SPEED_RPM_SESSION: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_max_speed_rpm_session",
+        "Highest motor speed observed since startup",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Highest motor temperature seen since startup
+pub static MAX_TEMP_C_SESSION: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_max_temp_c_session",
+        "Highest motor temperature observed since startup",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Highest system pressure seen since startup
+pub static MAX_PRESSURE_BAR_SESSION: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_max_pressure_bar_session",
+        "Highest system pressure observed since startup",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
 // ============================================================================
 // Safety Metrics
 // ============================================================================
@@ -69,6 +185,21 @@ pub static SAFETY_REJECTIONS: LazyLock<IntCounter> = LazyLock::new(|| {
     counter
 });
 
+/// Safety firewall rejections by the limit that was hit, labelled with
+/// [`SafetyViolation::kind`]
+pub static SAFETY_VIOLATIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "neuroplc_safety_violations_total",
+            "Recommendations rejected by safety firewall, by violated limit",
+        ),
+        &["type"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
 /// Agent recommendation timeouts
 pub static AGENT_TIMEOUTS: LazyLock<IntCounter> = LazyLock::new(|| {
     let counter = IntCounter::new(
@@ -91,6 +222,87 @@ pub static TIMING_VIOLATIONS: LazyLock<IntCounter> = LazyLock::new(|| {
     counter
 });
 
+/// Recommendations the bridge handed to the control loop
+pub static RECOMMENDATIONS_ACCEPTED: LazyLock<IntCounter> = LazyLock::new(|| {
+    let counter = IntCounter::new(
+        "neuroplc_recommendations_accepted_total",
+        "Recommendations passed by the bridge to the control loop",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// `reject` frames produced by the bridge, by reason
+pub static BRIDGE_REJECTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "neuroplc_bridge_rejects_total",
+            "Recommendations and commands rejected by the bridge, by reason",
+        ),
+        &["reason"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Inbound bridge messages that could not be parsed, by reason
+pub static PARSE_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "neuroplc_parse_errors_total",
+            "Inbound bridge messages dropped as unparseable, by reason",
+        ),
+        &["reason"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Inbound JSON lines dropped because their CRC-32 suffix was missing or
+/// did not match
+pub static FRAME_CHECKSUM_FAILURES: LazyLock<IntCounter> = LazyLock::new(|| {
+    let counter = IntCounter::new(
+        "neuroplc_frame_checksum_failures_total",
+        "Inbound JSON lines dropped for a missing or wrong CRC-32 checksum",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Process states published by the control loop and overwritten before a
+/// polling consumer read them, by consumer
+pub static STATE_SNAPSHOTS_SKIPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "neuroplc_state_snapshots_skipped_total",
+            "Process state snapshots overwritten before a consumer read them, by consumer",
+        ),
+        &["consumer"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// TLS handshakes that failed, by coarse reason from
+/// [`crate::tls::handshake_failure_reason`]
+pub static TLS_HANDSHAKE_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    let counter = IntCounterVec::new(
+        Opts::new(
+            "neuroplc_tls_handshake_failures_total",
+            "Bridge clients dropped during the TLS handshake, by reason",
+        ),
+        &["reason"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
 /// Recommendation expired before processing
 pub static RECOMMENDATION_EXPIRED: LazyLock<IntCounter> = LazyLock::new(|| {
     let counter = IntCounter::new(
@@ -135,6 +347,72 @@ pub static AUTH_MISSING: LazyLock<IntCounter> = LazyLock::new(|| {
     counter
 });
 
+/// Recommendations rejected for a missing or mismatched detached signature
+pub static SIGNATURE_FAILURES: LazyLock<IntCounter> = LazyLock::new(|| {
+    let counter = IntCounter::new(
+        "neuroplc_signature_failures_total",
+        "Recommendations rejected due to missing or invalid signatures",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Recommendations dropped for falling below the bridge confidence threshold
+pub static LOW_CONFIDENCE_DROPPED: LazyLock<IntCounter> = LazyLock::new(|| {
+    let counter = IntCounter::new(
+        "neuroplc_low_confidence_dropped_total",
+        "Recommendations dropped below the minimum confidence",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Mean agent clock offset over the bridge client's recent recommendations
+pub static AGENT_CLOCK_OFFSET_MS: LazyLock<Gauge> = LazyLock::new(|| {
+    let gauge = Gauge::new(
+        "neuroplc_agent_clock_offset_ms",
+        "Mean of issued_at_unix_us minus the spine wall clock over recent recommendations",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Recommendations that repeated the previous reasoning_hash beyond the allowance
+pub static DUPLICATE_REASONING: LazyLock<IntCounter> = LazyLock::new(|| {
+    let counter = IntCounter::new(
+        "neuroplc_duplicate_reasoning_total",
+        "Recommendations reusing the previous reasoning hash beyond the repeat allowance",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Recommendations refused by the per-client rate limiter
+pub static RECOMMENDATIONS_RATE_LIMITED: LazyLock<IntCounter> = LazyLock::new(|| {
+    let counter = IntCounter::new(
+        "neuroplc_recommendations_rate_limited_total",
+        "Recommendations rejected by the per-client rate limiter",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+/// Bridge clients dropped for not draining state frames
+pub static BRIDGE_SLOW_CLIENT_DROPS: LazyLock<IntCounter> = LazyLock::new(|| {
+    let counter = IntCounter::new(
+        "neuroplc_bridge_slow_client_drops_total",
+        "Bridge clients disconnected for not draining state frames",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
 // ============================================================================
 // Process State Metrics
 // ============================================================================
@@ -212,15 +490,244 @@ pub static SAFETY_STATE: LazyLock<Gauge> = LazyLock::new(|| {
     gauge
 });
 
+// ============================================================================
+// Build Info
+// ============================================================================
+
+/// What this instance runs, as served on `GET /version`
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct BuildInfo {
+    pub version: String,
+    /// SHA-256 of the running executable, `None` if it could not be read
+    pub binary_sha256: Option<String>,
+    /// Hash of the effective runtime configuration, as in the audit log
+    pub config_hash: String,
+    pub cortex_manifest_sha256: Option<String>,
+}
+
+/// Always 1; the labels identify the running binary and configuration
+pub static BUILD_INFO: LazyLock<GaugeVec> = LazyLock::new(|| {
+    let gauge = GaugeVec::new(
+        Opts::new(
+            "neuroplc_build_info",
+            "Build and configuration of this instance (always 1)",
+        ),
+        &["version", "binary_hash", "config_hash"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+/// Replace the `neuroplc_build_info` series with one for `info`
+pub fn publish_build_info(info: &BuildInfo) {
+    BUILD_INFO.reset();
+    BUILD_INFO
+        .with_label_values(&[
+            info.version.as_str(),
+            info.binary_sha256.as_deref().unwrap_or(""),
+            info.config_hash.as_str(),
+        ])
+        .set(1.0);
+}
+
+// ============================================================================
+// Readiness
+// ============================================================================
+
+/// Subsystem health flags published by the runtime and consulted by `/ready`
+#[derive(Debug, Default)]
+pub struct SubsystemHealth {
+    hal_healthy: AtomicBool,
+    bridge_enabled: AtomicBool,
+    bridge_listening: AtomicBool,
+    opcua_enabled: AtomicBool,
+    opcua_running: AtomicBool,
+}
+
+/// Health flags for this process
+pub static HEALTH: SubsystemHealth = SubsystemHealth::new();
+
+impl SubsystemHealth {
+    pub const fn new() -> Self {
+        Self {
+            hal_healthy: AtomicBool::new(false),
+            bridge_enabled: AtomicBool::new(false),
+            bridge_listening: AtomicBool::new(false),
+            opcua_enabled: AtomicBool::new(false),
+            opcua_running: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_hal_healthy(&self, healthy: bool) {
+        self.hal_healthy.store(healthy, Ordering::Relaxed);
+    }
+
+    /// Whether readiness should wait for the bridge listener
+    pub fn set_bridge_enabled(&self, enabled: bool) {
+        self.bridge_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn set_bridge_listening(&self, listening: bool) {
+        self.bridge_listening.store(listening, Ordering::Relaxed);
+    }
+
+    /// Whether readiness should wait for the OPC UA server
+    pub fn set_opcua_enabled(&self, enabled: bool) {
+        self.opcua_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Cleared when the OPC UA server fails to start or exits on its own
+    pub fn set_opcua_running(&self, running: bool) {
+        self.opcua_running.store(running, Ordering::Relaxed);
+    }
+
+    /// Overall readiness plus a per-subsystem JSON status body
+    pub fn readiness(&self, cycles_executed: u64) -> (bool, serde_json::Value) {
+        let control_loop = cycles_executed > 0;
+        let hal = self.hal_healthy.load(Ordering::Relaxed);
+        let bridge = if !self.bridge_enabled.load(Ordering::Relaxed) {
+            "disabled"
+        } else if self.bridge_listening.load(Ordering::Relaxed) {
+            "ok"
+        } else {
+            "not_listening"
+        };
+        let opcua = if !self.opcua_enabled.load(Ordering::Relaxed) {
+            "disabled"
+        } else if self.opcua_running.load(Ordering::Relaxed) {
+            "ok"
+        } else {
+            "failed"
+        };
+        let ready = control_loop && hal && bridge != "not_listening" && opcua != "failed";
+        let body = serde_json::json!({
+            "ready": ready,
+            "control_loop": if control_loop { "ok" } else { "starting" },
+            "hal": if hal { "ok" } else { "unhealthy" },
+            "bridge": bridge,
+            "opcua": opcua,
+        });
+        (ready, body)
+    }
+}
+
 // ============================================================================
 // Metrics HTTP Server
 // ============================================================================
 
-/// Start the metrics HTTP server on the given address.
+/// Admin access to the metrics server: `POST /metrics/reset` with
+/// `Authorization: Bearer <token>` resets the session gauges and then calls
+/// `on_reset`, e.g. to clear the control loop's own high-water marks.
+pub struct MetricsAdmin {
+    pub token: String,
+    pub on_reset: Box<dyn Fn() + Send>,
+}
+
+/// Zero the gauges that describe the session so far: the cycle jitter and
+/// process high-water marks and the last recommendation age. Counters and
+/// histograms are monotonic by Prometheus convention and are kept.
+pub fn reset_session_metrics() {
+    MAX_JITTER_US.set(0.0);
+    MAX_SPEED_RPM_SESSION.set(0.0);
+    MAX_TEMP_C_SESSION.set(0.0);
+    MAX_PRESSURE_BAR_SESSION.set(0.0);
+    LAST_RECOMMENDATION_AGE_US.set(0.0);
+}
+
+/// Routes served on top of `/metrics`, `/health` and `/ready`
+#[derive(Default)]
+pub struct MetricsRoutes {
+    /// Enables `POST /metrics/reset`
+    pub admin: Option<MetricsAdmin>,
+    /// Enables `GET /state`
+    pub exchange: Option<Arc<StateExchange>>,
+    /// Enables `GET /version`
+    pub build: Option<BuildInfo>,
+}
+
+/// Current process state as served on `GET /state`: the latest snapshot,
+/// plus the recommendation in effect as of that snapshot (`null` if none).
+pub fn state_json(exchange: &StateExchange) -> serde_json::Value {
+    let snapshot = exchange.read_state();
+    let recommendation = exchange
+        .get_recommendation(snapshot.timestamp_us)
+        .map(|rec| {
+            serde_json::json!({
+                "timestamp_us": rec.timestamp_us,
+                "target_speed_rpm": rec.target_speed_rpm,
+                "ramp_rate_rpm_per_s": rec.ramp_rate_rpm_per_s,
+                "confidence": rec.confidence,
+                "reasoning_hash": to_hex(rec.reasoning_hash.as_bytes()),
+            })
+        });
+    serde_json::json!({
+        "timestamp_us": snapshot.timestamp_us,
+        "cycle_count": snapshot.cycle_count,
+        "safety_state": snapshot.safety_state.as_str(),
+        "motor_speed_rpm": snapshot.motor_speed_rpm,
+        "motor_temp_c": snapshot.motor_temp_c,
+        "pressure_bar": snapshot.pressure_bar,
+        "cycle_jitter_us": snapshot.cycle_jitter_us,
+        "applied_reasoning_hash": to_hex(snapshot.applied_reasoning_hash.as_bytes()),
+        "hal_healthy": exchange.hal_healthy(),
+        "recommendation": recommendation,
+    })
+}
+
+/// Prefix of a `--metrics-addr` that names a Unix domain socket
+pub const UNIX_SOCKET_PREFIX: &str = "unix:";
+
+/// Socket file of a `unix:<path>` metrics address; `None` for TCP
+pub fn metrics_socket_path(bind_addr: &str) -> Option<&Path> {
+    bind_addr.strip_prefix(UNIX_SOCKET_PREFIX).map(Path::new)
+}
+
+/// Listen on TCP, or on a Unix domain socket for a `unix:<path>` address. A
+/// socket file left behind by an unclean exit is replaced; any other file at
+/// the path is an error.
+fn bind_metrics_server(
+    bind_addr: &str,
+) -> Result<Server, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let Some(path) = metrics_socket_path(bind_addr) else {
+        return Server::http(bind_addr);
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("{} exists and is not a socket", path.display()).into());
+            }
+            std::fs::remove_file(path)?;
+        }
+        Server::http_unix(path)
+    }
+    #[cfg(not(unix))]
+    {
+        Err(format!("{} needs Unix domain socket support", path.display()).into())
+    }
+}
+
+/// Start the metrics HTTP server on the given address: `host:port`, or
+/// `unix:<path>` for a Unix domain socket.
 /// Returns a join handle for the server thread.
 pub fn serve_metrics(bind_addr: String) -> thread::JoinHandle<()> {
+    serve_metrics_with_routes(bind_addr, MetricsRoutes::default())
+}
+
+/// [`serve_metrics`] plus the optional [`MetricsRoutes`]
+pub fn serve_metrics_with_routes(
+    bind_addr: String,
+    routes: MetricsRoutes,
+) -> thread::JoinHandle<()> {
+    let MetricsRoutes {
+        admin,
+        exchange,
+        build,
+    } = routes;
     thread::spawn(move || {
-        let server = match Server::http(&bind_addr) {
+        let server = match bind_metrics_server(&bind_addr) {
             Ok(s) => s,
             Err(e) => {
                 tracing::error!("Failed to start metrics server on {}: {}", bind_addr, e);
@@ -228,9 +735,26 @@ pub fn serve_metrics(bind_addr: String) -> thread::JoinHandle<()> {
             }
         };
 
-        tracing::info!("Metrics server listening on http://{}/metrics", bind_addr);
+        match metrics_socket_path(&bind_addr) {
+            Some(path) => {
+                tracing::info!("Metrics server listening on {} (/metrics)", path.display())
+            }
+            None => tracing::info!("Metrics server listening on http://{}/metrics", bind_addr),
+        }
 
         for request in server.incoming_requests() {
+            if let (Some(admin), "/metrics/reset") = (&admin, request.url()) {
+                respond_to_reset(request, admin);
+                continue;
+            }
+            if let (Some(exchange), "/state") = (&exchange, request.url()) {
+                respond_with_json(request, &state_json(exchange));
+                continue;
+            }
+            if let (Some(build), "/version") = (&build, request.url()) {
+                respond_with_json(request, &serde_json::json!(build));
+                continue;
+            }
             let path = request.url();
 
             match path {
@@ -247,27 +771,50 @@ pub fn serve_metrics(bind_addr: String) -> thread::JoinHandle<()> {
                         continue;
                     }
 
-                    let response = Response::from_data(buffer).with_header(
-                        tiny_http::Header::from_bytes(
-                            &b"Content-Type"[..],
-                            &b"text/plain; version=0.0.4"[..],
-                        )
-                        .unwrap(),
-                    );
+                    let gzip = accepts_gzip(&request);
+                    let body = if gzip {
+                        match gzip_bytes(&buffer) {
+                            Ok(compressed) => Some(compressed),
+                            Err(e) => {
+                                tracing::warn!("Failed to gzip metrics: {}", e);
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    };
+
+                    let mut response = Response::from_data(body.as_deref().unwrap_or(&buffer))
+                        .with_header(
+                            tiny_http::Header::from_bytes(
+                                &b"Content-Type"[..],
+                                &b"text/plain; version=0.0.4"[..],
+                            )
+                            .unwrap(),
+                        );
+                    if body.is_some() {
+                        response = response.with_header(
+                            tiny_http::Header::from_bytes(&b"Content-Encoding"[..], &b"gzip"[..])
+                                .unwrap(),
+                        );
+                    }
                     let _ = request.respond(response);
                 }
                 "/health" => {
                     let _ = request.respond(Response::from_string("OK"));
                 }
                 "/ready" => {
-                    // Ready when we've executed at least one cycle
-                    let cycles = CYCLES_EXECUTED.get();
-                    if cycles > 0 {
-                        let _ = request.respond(Response::from_string("Ready"));
-                    } else {
-                        let _ = request
-                            .respond(Response::from_string("Not Ready").with_status_code(503));
-                    }
+                    let (ready, body) = HEALTH.readiness(CYCLES_EXECUTED.get());
+                    let response = Response::from_string(body.to_string())
+                        .with_status_code(if ready { 200 } else { 503 })
+                        .with_header(
+                            tiny_http::Header::from_bytes(
+                                &b"Content-Type"[..],
+                                &b"application/json"[..],
+                            )
+                            .unwrap(),
+                        );
+                    let _ = request.respond(response);
                 }
                 _ => {
                     let _ =
@@ -278,19 +825,146 @@ pub fn serve_metrics(bind_addr: String) -> thread::JoinHandle<()> {
     })
 }
 
+fn respond_to_reset(request: Request, admin: &MetricsAdmin) {
+    let status = if *request.method() != Method::Post {
+        405
+    } else if !bearer_token_matches(&request, &admin.token) {
+        tracing::warn!(remote = ?request.remote_addr(), "Unauthorized metrics reset request");
+        401
+    } else {
+        reset_session_metrics();
+        (admin.on_reset)();
+        tracing::info!(remote = ?request.remote_addr(), "Session metrics reset");
+        204
+    };
+    let _ = request.respond(Response::empty(status));
+}
+
+fn respond_with_json(request: Request, body: &serde_json::Value) {
+    if *request.method() != Method::Get {
+        let _ = request.respond(Response::empty(405));
+        return;
+    }
+    let response = Response::from_string(body.to_string()).with_header(
+        tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap(),
+    );
+    let _ = request.respond(response);
+}
+
+/// True when the request carries `Authorization: Bearer <expected>`. The
+/// comparison does not stop at the first differing byte.
+fn bearer_token_matches(request: &Request, expected: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .filter(|h| h.field.equiv("Authorization"))
+        .filter_map(|h| h.value.as_str().strip_prefix("Bearer "))
+        .any(|given| {
+            let (given, expected) = (given.trim().as_bytes(), expected.as_bytes());
+            given.len() == expected.len()
+                && given
+                    .iter()
+                    .zip(expected)
+                    .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+}
+
+/// True when the request's `Accept-Encoding` lists gzip without `q=0`.
+fn accepts_gzip(request: &Request) -> bool {
+    request
+        .headers()
+        .iter()
+        .filter(|h| h.field.equiv("Accept-Encoding"))
+        .flat_map(|h| h.value.as_str().split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let disabled = parts.any(|p| {
+                p.strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !disabled
+        })
+}
+
+fn gzip_bytes(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Check histogram bucket bounds: they must be finite and strictly increasing
+pub fn validate_histogram_buckets(buckets: &[f64]) -> Result<(), String> {
+    if buckets.is_empty() {
+        return Err("no bucket bounds".to_string());
+    }
+    if let Some(bound) = buckets.iter().find(|bound| !bound.is_finite()) {
+        return Err(format!("bucket bound {bound} is not finite"));
+    }
+    match buckets.windows(2).find(|pair| pair[0] >= pair[1]) {
+        Some(pair) => Err(format!(
+            "bucket bounds must increase, but {} is followed by {}",
+            pair[0], pair[1]
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Set the [`CYCLE_JITTER_US`] bucket bounds. The histogram is built once,
+/// so this must run before [`init_metrics`] or any observation; afterwards
+/// only the bounds already in use are accepted.
+pub fn configure_cycle_jitter_buckets(buckets: &[f64]) -> Result<(), String> {
+    validate_histogram_buckets(buckets)?;
+    let in_use = CYCLE_JITTER_BUCKETS_US.get_or_init(|| buckets.to_vec());
+    if in_use.as_slice() == buckets {
+        Ok(())
+    } else {
+        Err(format!(
+            "cycle jitter histogram already uses buckets {in_use:?}"
+        ))
+    }
+}
+
 /// Initialize all metrics (forces lazy initialization)
 pub fn init_metrics() {
     // Touch each metric to force initialization
     let _ = CYCLES_EXECUTED.get();
     let _ = CYCLES_MISSED.get();
     let _ = CYCLE_JITTER_US.get_sample_count();
+    let _ = MAX_JITTER_US.get();
+    let _ = JITTER_ALERT_LEVEL.get();
+    let _ = STATE_GAP.get();
+    let _ = LAST_RECOMMENDATION_AGE_US.get();
+    let _ = RECOMMENDATION_AGE_US.get_sample_count();
+    let _ = MAX_SPEED_RPM_SESSION.get();
+    let _ = MAX_TEMP_C_SESSION.get();
+    let _ = MAX_PRESSURE_BAR_SESSION.get();
     let _ = SAFETY_REJECTIONS.get();
+    // Every type is exported from startup, so a first rejection is a rise
+    // from zero rather than a new series.
+    for kind in SafetyViolation::KINDS {
+        let _ = SAFETY_VIOLATIONS.with_label_values(&[kind]).get();
+    }
     let _ = AGENT_TIMEOUTS.get();
     let _ = TIMING_VIOLATIONS.get();
+    let _ = RECOMMENDATIONS_ACCEPTED.get();
+    LazyLock::force(&BRIDGE_REJECTS);
+    LazyLock::force(&PARSE_ERRORS);
+    let _ = FRAME_CHECKSUM_FAILURES.get();
+    LazyLock::force(&STATE_SNAPSHOTS_SKIPPED);
+    LazyLock::force(&TLS_HANDSHAKE_FAILURES);
     let _ = RECOMMENDATION_EXPIRED.get();
     let _ = RECOMMENDATION_OUT_OF_ORDER.get();
     let _ = AUTH_FAILURES.get();
     let _ = AUTH_MISSING.get();
+    let _ = SIGNATURE_FAILURES.get();
+    let _ = RECOMMENDATIONS_RATE_LIMITED.get();
+    let _ = LOW_CONFIDENCE_DROPPED.get();
+    let _ = DUPLICATE_REASONING.get();
+    let _ = AGENT_CLOCK_OFFSET_MS.get();
+    let _ = BRIDGE_SLOW_CLIENT_DROPS.get();
     let _ = MOTOR_SPEED_RPM.get();
     let _ = MOTOR_TEMP_C.get();
     let _ = PRESSURE_BAR.get();
@@ -298,4 +972,353 @@ pub fn init_metrics() {
     let _ = AGENT_TARGET_RPM.get();
     let _ = BRIDGE_CONNECTED.get();
     let _ = SAFETY_STATE.get();
+    LazyLock::force(&BUILD_INFO);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use prometheus::core::Collector;
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn scrape(addr: &str, path: &str, accept_encoding: Option<&str>) -> (String, Vec<u8>) {
+        let mut stream = None;
+        for _ in 0..50 {
+            match TcpStream::connect(addr) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => thread::sleep(Duration::from_millis(20)),
+            }
+        }
+        let stream = stream.expect("metrics server did not start");
+        scrape_stream(stream, addr, path, accept_encoding)
+    }
+
+    fn scrape_stream(
+        mut stream: impl Read + Write,
+        addr: &str,
+        path: &str,
+        accept_encoding: Option<&str>,
+    ) -> (String, Vec<u8>) {
+        let extra = accept_encoding
+            .map(|value| format!("Accept-Encoding: {value}\r\n"))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "GET {path} HTTP/1.1\r\nHost: {addr}\r\n{extra}Connection: close\r\n\r\n"
+        )
+        .unwrap();
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).unwrap();
+        let split = raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&raw[..split]).to_ascii_lowercase();
+        (head, raw[split + 4..].to_vec())
+    }
+
+    #[test]
+    fn test_disconnected_hal_is_not_ready() {
+        let health = SubsystemHealth::new();
+        health.set_hal_healthy(false);
+
+        let (ready, body) = health.readiness(100);
+        assert!(!ready);
+        assert_eq!(body["hal"], "unhealthy");
+        assert_eq!(body["control_loop"], "ok");
+
+        health.set_hal_healthy(true);
+        assert!(health.readiness(100).0);
+    }
+
+    #[test]
+    fn test_enabled_bridge_must_be_listening() {
+        let health = SubsystemHealth::new();
+        health.set_hal_healthy(true);
+        assert_eq!(health.readiness(1).1["bridge"], "disabled");
+
+        health.set_bridge_enabled(true);
+        let (ready, body) = health.readiness(1);
+        assert!(!ready);
+        assert_eq!(body["bridge"], "not_listening");
+
+        health.set_bridge_listening(true);
+        assert!(health.readiness(1).0);
+        assert!(!health.readiness(0).0);
+    }
+
+    #[test]
+    fn test_failed_opcua_server_is_not_ready() {
+        let health = SubsystemHealth::new();
+        health.set_hal_healthy(true);
+        assert_eq!(health.readiness(1).1["opcua"], "disabled");
+
+        health.set_opcua_enabled(true);
+        let (ready, body) = health.readiness(1);
+        assert!(!ready);
+        assert_eq!(body["opcua"], "failed");
+
+        health.set_opcua_running(true);
+        assert!(health.readiness(1).0);
+    }
+
+    /// Send `POST /metrics/reset` and return the status code
+    fn post_reset(addr: &str, token: Option<&str>) -> u16 {
+        let mut stream = None;
+        for _ in 0..50 {
+            match TcpStream::connect(addr) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => thread::sleep(Duration::from_millis(20)),
+            }
+        }
+        let mut stream = stream.expect("metrics server did not start");
+        let auth = token
+            .map(|token| format!("Authorization: Bearer {token}\r\n"))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "POST /metrics/reset HTTP/1.1\r\nHost: {addr}\r\n{auth}Content-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).unwrap();
+        raw.split(' ').nth(1).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn test_metrics_reset_requires_token() {
+        init_metrics();
+        let addr = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let resets = Arc::new(AtomicU32::new(0));
+        let reset_count = Arc::clone(&resets);
+        let _server = serve_metrics_with_routes(
+            addr.clone(),
+            MetricsRoutes {
+                admin: Some(MetricsAdmin {
+                    token: "s3cret".to_string(),
+                    on_reset: Box::new(move || {
+                        reset_count.fetch_add(1, Ordering::Relaxed);
+                    }),
+                }),
+                ..MetricsRoutes::default()
+            },
+        );
+
+        MAX_TEMP_C_SESSION.set(75.0);
+        assert_eq!(post_reset(&addr, None), 401);
+        assert_eq!(post_reset(&addr, Some("wrong!")), 401);
+        assert_eq!(MAX_TEMP_C_SESSION.get(), 75.0);
+        assert_eq!(resets.load(Ordering::Relaxed), 0);
+
+        let counter_before = CYCLES_EXECUTED.get();
+        assert_eq!(post_reset(&addr, Some("s3cret")), 204);
+        assert_eq!(MAX_TEMP_C_SESSION.get(), 0.0);
+        assert_eq!(resets.load(Ordering::Relaxed), 1);
+        assert!(CYCLES_EXECUTED.get() >= counter_before);
+    }
+
+    #[test]
+    fn test_version_endpoint_and_build_info_gauge() {
+        let addr = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let build = BuildInfo {
+            version: "1.2.3".to_string(),
+            binary_sha256: Some("ab".repeat(32)),
+            config_hash: "cd".repeat(32),
+            cortex_manifest_sha256: None,
+        };
+        publish_build_info(&build);
+        let _server = serve_metrics_with_routes(
+            addr.clone(),
+            MetricsRoutes {
+                build: Some(build.clone()),
+                ..MetricsRoutes::default()
+            },
+        );
+
+        let (head, body) = scrape(&addr, "/version", None);
+        assert!(head.contains("content-type: application/json"), "{head}");
+        let version: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(version["version"], "1.2.3");
+        assert_eq!(version["config_hash"], build.config_hash);
+        assert!(version["cortex_manifest_sha256"].is_null());
+
+        let (_, body) = scrape(&addr, "/metrics", None);
+        let text = String::from_utf8(body).unwrap();
+        let series = format!(
+            "neuroplc_build_info{{binary_hash=\"{}\",config_hash=\"{}\",version=\"1.2.3\"}} 1",
+            "ab".repeat(32),
+            "cd".repeat(32)
+        );
+        assert!(text.contains(&series), "{text}");
+    }
+
+    #[test]
+    fn test_state_endpoint_serves_snapshot_json() {
+        let addr = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        exchange.publish_state(core_spine::ProcessSnapshot {
+            timestamp_us: 2_000,
+            cycle_count: 2,
+            motor_speed_rpm: 420.0,
+            motor_temp_c: 41.5,
+            pressure_bar: 1.2,
+            cycle_jitter_us: 7,
+            ..Default::default()
+        });
+        let _server = serve_metrics_with_routes(
+            addr.clone(),
+            MetricsRoutes {
+                exchange: Some(Arc::clone(&exchange)),
+                ..MetricsRoutes::default()
+            },
+        );
+
+        let (head, body) = scrape(&addr, "/state", None);
+        assert!(head.contains("content-type: application/json"), "{head}");
+        let state: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(state["cycle_count"], 2);
+        assert_eq!(state["motor_speed_rpm"], 420.0);
+        assert_eq!(state["safety_state"], "normal");
+        assert_eq!(state["applied_reasoning_hash"], "00".repeat(32));
+        assert!(state["recommendation"].is_null());
+
+        exchange.submit_recommendation(core_spine::AgentRecommendation {
+            timestamp_us: 1_500,
+            target_speed_rpm: Some(500.0),
+            ramp_rate_rpm_per_s: None,
+            confidence: 0.75,
+            reasoning_hash: [0xab; 32].into(),
+        });
+        let (_, body) = scrape(&addr, "/state", None);
+        let state: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(state["recommendation"]["target_speed_rpm"], 500.0);
+        assert_eq!(state["recommendation"]["confidence"], 0.75);
+        assert_eq!(state["recommendation"]["reasoning_hash"], "ab".repeat(32));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_metrics_over_unix_socket() {
+        use std::os::unix::net::UnixStream;
+
+        init_metrics();
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("metrics.sock");
+        // A socket left behind by a previous run is replaced.
+        drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+        assert!(socket.exists());
+        let addr = format!("unix:{}", socket.display());
+        assert_eq!(metrics_socket_path(&addr), Some(socket.as_path()));
+        let _server = serve_metrics(addr);
+
+        let mut stream = None;
+        for _ in 0..50 {
+            match UnixStream::connect(&socket) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => thread::sleep(Duration::from_millis(20)),
+            }
+        }
+        let stream = stream.expect("metrics server did not start");
+        let (head, body) = scrape_stream(stream, "localhost", "/metrics", None);
+        assert!(head.starts_with("http/1.1 200"), "{head}");
+        assert!(String::from_utf8_lossy(&body).contains("neuroplc_cycles_executed_total"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_path_must_not_be_a_regular_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.sock");
+        std::fs::write(&path, "keep me").unwrap();
+        let addr = format!("unix:{}", path.display());
+        assert!(bind_metrics_server(&addr).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+        assert_eq!(metrics_socket_path("127.0.0.1:9090"), None);
+    }
+
+    /// Cumulative count of the bucket bounded by `le`
+    fn jitter_bucket_count(le: f64) -> u64 {
+        let family = CYCLE_JITTER_US.collect();
+        family[0].get_metric()[0]
+            .get_histogram()
+            .get_bucket()
+            .iter()
+            .find(|bucket| bucket.get_upper_bound() == le)
+            .map(|bucket| bucket.get_cumulative_count())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_slow_cycle_lands_in_millisecond_bucket() {
+        init_metrics();
+        let below = jitter_bucket_count(2_500.0);
+        let within = jitter_bucket_count(5_000.0);
+        CYCLE_JITTER_US.observe(3_000.0);
+        assert_eq!(jitter_bucket_count(2_500.0), below);
+        assert_eq!(jitter_bucket_count(5_000.0), within + 1);
+    }
+
+    #[test]
+    fn test_jitter_buckets_are_fixed_once_built() {
+        init_metrics();
+        assert!(configure_cycle_jitter_buckets(&DEFAULT_CYCLE_JITTER_BUCKETS_US).is_ok());
+        let err = configure_cycle_jitter_buckets(&[10.0, 100.0]).unwrap_err();
+        assert!(err.contains("already uses"), "{err}");
+
+        assert!(validate_histogram_buckets(&[]).is_err());
+        assert!(validate_histogram_buckets(&[1.0, f64::NAN]).is_err());
+        let err = validate_histogram_buckets(&[10.0, 5.0]).unwrap_err();
+        assert!(err.contains("10 is followed by 5"), "{err}");
+    }
+
+    #[test]
+    fn test_metrics_gzip_matches_plain_body() {
+        init_metrics();
+        let addr = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let _server = serve_metrics(addr.clone());
+
+        let (plain_head, plain_body) = scrape(&addr, "/metrics", None);
+        assert!(!plain_head.contains("content-encoding"));
+
+        let (gzip_head, gzip_body) = scrape(&addr, "/metrics", Some("gzip, deflate"));
+        assert!(gzip_head.contains("content-encoding: gzip"));
+        let mut decoded = Vec::new();
+        GzDecoder::new(&gzip_body[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+
+        // Other tests may bump counters between scrapes, so compare the
+        // series names rather than their values.
+        let names = |body: &[u8]| -> Vec<String> {
+            String::from_utf8_lossy(body)
+                .lines()
+                .map(|line| line.split(' ').next().unwrap_or_default().to_string())
+                .collect()
+        };
+        assert_eq!(names(&decoded), names(&plain_body));
+    }
 }
@@ -0,0 +1,178 @@
+//! OTLP push export of the Prometheus metric set.
+//!
+//! The metric definitions in [`crate::metrics`] stay the single source of
+//! truth: every family in [`REGISTRY`] is mirrored as an observable OTel
+//! instrument whose callback reads the current Prometheus value, and a
+//! periodic reader pushes them to an OTLP/HTTP collector.
+
+use crate::metrics::REGISTRY;
+use opentelemetry::metrics::{Meter, MeterProvider};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{MetricExporter, WithExportConfig};
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::Resource;
+use prometheus::proto::{Metric, MetricType};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Default push interval for OTLP export.
+pub const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum OtlpError {
+    #[error("failed to build OTLP exporter: {0}")]
+    Build(String),
+    #[error("failed to shut down OTLP exporter: {0}")]
+    Shutdown(String),
+}
+
+/// Running OTLP exporter. Call [`OtlpExporter::shutdown`] to flush the
+/// final collection before exit.
+pub struct OtlpExporter {
+    provider: SdkMeterProvider,
+}
+
+impl OtlpExporter {
+    /// Start pushing every registered metric to `endpoint`, the full
+    /// OTLP/HTTP metrics URL (e.g. `http://collector:4318/v1/metrics`).
+    pub fn start(endpoint: &str, interval: Duration) -> Result<Self, OtlpError> {
+        let exporter = MetricExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| OtlpError::Build(e.to_string()))?;
+        let reader = PeriodicReader::builder(exporter)
+            .with_interval(interval)
+            .build();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(Resource::builder().with_service_name("neuro-plc").build())
+            .build();
+
+        register_instruments(&provider.meter("neuroplc"));
+        tracing::info!(endpoint, "OTLP metrics export enabled");
+        Ok(Self { provider })
+    }
+
+    /// Export once more and stop the periodic reader.
+    pub fn shutdown(self) -> Result<(), OtlpError> {
+        self.provider
+            .shutdown()
+            .map_err(|e| OtlpError::Shutdown(e.to_string()))
+    }
+}
+
+/// Mirror each family currently in the registry; call after
+/// [`crate::metrics::init_metrics`] so every metric is present.
+fn register_instruments(meter: &Meter) {
+    for family in REGISTRY.gather() {
+        let name = family.get_name().to_string();
+        let help = family.get_help().to_string();
+        match family.get_field_type() {
+            MetricType::COUNTER => {
+                let lookup = name.clone();
+                meter
+                    .f64_observable_counter(name)
+                    .with_description(help)
+                    .with_callback(move |observer| {
+                        observe_family(&lookup, |metric, attrs| {
+                            observer.observe(metric.get_counter().get_value(), attrs)
+                        })
+                    })
+                    .build();
+            }
+            MetricType::GAUGE => {
+                let lookup = name.clone();
+                meter
+                    .f64_observable_gauge(name)
+                    .with_description(help)
+                    .with_callback(move |observer| {
+                        observe_family(&lookup, |metric, attrs| {
+                            observer.observe(metric.get_gauge().get_value(), attrs)
+                        })
+                    })
+                    .build();
+            }
+            // Observable histograms do not exist in OTel, so export the
+            // running sum and count like a Prometheus summary.
+            MetricType::HISTOGRAM => {
+                let lookup = name.clone();
+                meter
+                    .f64_observable_counter(format!("{name}_sum"))
+                    .with_description(help.clone())
+                    .with_callback(move |observer| {
+                        observe_family(&lookup, |metric, attrs| {
+                            observer.observe(metric.get_histogram().get_sample_sum(), attrs)
+                        })
+                    })
+                    .build();
+                let lookup = name.clone();
+                meter
+                    .u64_observable_counter(format!("{name}_count"))
+                    .with_description(help)
+                    .with_callback(move |observer| {
+                        observe_family(&lookup, |metric, attrs| {
+                            observer.observe(metric.get_histogram().get_sample_count(), attrs)
+                        })
+                    })
+                    .build();
+            }
+            other => {
+                tracing::debug!(metric = %name, kind = ?other, "Skipping metric for OTLP export");
+            }
+        }
+    }
+}
+
+fn observe_family(name: &str, mut observe: impl FnMut(&Metric, &[KeyValue])) {
+    for family in REGISTRY.gather() {
+        if family.get_name() != name {
+            continue;
+        }
+        for metric in family.get_metric() {
+            let attrs: Vec<KeyValue> = metric
+                .get_label()
+                .iter()
+                .map(|label| {
+                    KeyValue::new(label.get_name().to_string(), label.get_value().to_string())
+                })
+                .collect();
+            observe(metric, &attrs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{init_metrics, SAFETY_REJECTIONS};
+    use std::sync::mpsc;
+    use std::thread;
+    use tiny_http::{Response, Server};
+
+    #[test]
+    fn test_exporter_starts_and_flushes_on_shutdown() {
+        init_metrics();
+        SAFETY_REJECTIONS.inc();
+
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let collector = thread::spawn(move || {
+            if let Ok(Some(request)) = server.recv_timeout(Duration::from_secs(10)) {
+                let _ = tx.send(request.url().to_string());
+                let _ = request.respond(Response::empty(200));
+            }
+        });
+
+        let endpoint = format!("http://{addr}/v1/metrics");
+        let exporter = OtlpExporter::start(&endpoint, Duration::from_secs(3600)).unwrap();
+        exporter.shutdown().unwrap();
+
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(10)).unwrap(),
+            "/v1/metrics"
+        );
+        collector.join().unwrap();
+    }
+}
@@ -1,5 +1,7 @@
-use core_spine::tags;
+use crate::audit::to_hex;
+use core_spine::{tags, ProcessSnapshot, SafetyLimits};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 pub const STATE_TAGS: &[tags::Tag] = &[
     tags::MOTOR_SPEED_RPM,
@@ -40,6 +42,212 @@ pub struct StateMsg {
     pub motor_temp_c: f64,
     pub pressure_bar: f64,
     pub cycle_jitter_us: u32,
+    /// Hex `reasoning_hash` of the recommendation in effect; all zeros while
+    /// the spine holds its last safe setpoint
+    pub applied_reasoning_hash: String,
+    /// Control-loop states published since the spine started. Unlike
+    /// `sequence` it keeps counting while no client is connected, so a
+    /// client can report it in its next `hello` to resume.
+    pub snapshot_sequence: u64,
+}
+
+impl StateMsg {
+    pub fn from_snapshot(snapshot: &ProcessSnapshot, sequence: u64, unix_us: u64) -> Self {
+        Self {
+            msg_type: "state",
+            protocol_version: ProtocolVersion::v1(),
+            sequence,
+            timestamp_us: snapshot.timestamp_us,
+            cycle_count: snapshot.cycle_count,
+            safety_state: snapshot.safety_state.as_str(),
+            unix_us,
+            motor_speed_rpm: snapshot.motor_speed_rpm,
+            motor_temp_c: snapshot.motor_temp_c,
+            pressure_bar: snapshot.pressure_bar,
+            cycle_jitter_us: snapshot.cycle_jitter_us,
+            applied_reasoning_hash: to_hex(snapshot.applied_reasoning_hash.as_bytes()),
+            snapshot_sequence: 0,
+        }
+    }
+
+    pub fn with_snapshot_sequence(self, snapshot_sequence: u64) -> Self {
+        Self {
+            snapshot_sequence,
+            ..self
+        }
+    }
+}
+
+/// Sent in reply to a supported `hello` so the client knows what the spine
+/// speaks.
+#[derive(Debug, Serialize)]
+pub struct HelloAckMsg {
+    #[serde(rename = "type")]
+    pub msg_type: &'static str,
+    pub protocol_version: ProtocolVersion,
+    pub capabilities: Vec<String>,
+    pub wire_protocol: &'static str,
+    /// Highest sequence already accepted from this `client_id`; the next
+    /// recommendation must be above it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence_floor: Option<u64>,
+}
+
+impl HelloAckMsg {
+    pub fn new(capabilities: Vec<String>, wire_protocol: &'static str) -> Self {
+        Self {
+            msg_type: "hello_ack",
+            protocol_version: ProtocolVersion::v1(),
+            capabilities,
+            wire_protocol,
+            sequence_floor: None,
+        }
+    }
+
+    pub fn with_sequence_floor(self, sequence_floor: Option<u64>) -> Self {
+        Self {
+            sequence_floor,
+            ..self
+        }
+    }
+}
+
+/// Structured rejection sent before the spine closes a connection.
+#[derive(Debug, Serialize)]
+pub struct ErrorMsg {
+    #[serde(rename = "type")]
+    pub msg_type: &'static str,
+    pub protocol_version: ProtocolVersion,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ErrorMsg {
+    pub fn unsupported_version(requested: ProtocolVersion) -> Self {
+        let supported = ProtocolVersion::v1();
+        Self {
+            msg_type: "error",
+            protocol_version: supported,
+            code: "unsupported_version",
+            message: format!(
+                "protocol {}.{} is not supported; server speaks {}.{}",
+                requested.major, requested.minor, supported.major, supported.minor
+            ),
+        }
+    }
+}
+
+/// Why the spine refused a recommendation.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    Expired,
+    OutOfOrder,
+    AuthFailed,
+    Unsafe,
+    BadVersion,
+    Malformed,
+    RateLimited,
+    DuplicateReasoning,
+    CapabilityNotDeclared,
+    BadSignature,
+    LowConfidence,
+}
+
+impl RejectReason {
+    pub const ALL: [RejectReason; 11] = [
+        RejectReason::Expired,
+        RejectReason::OutOfOrder,
+        RejectReason::AuthFailed,
+        RejectReason::Unsafe,
+        RejectReason::BadVersion,
+        RejectReason::Malformed,
+        RejectReason::RateLimited,
+        RejectReason::DuplicateReasoning,
+        RejectReason::CapabilityNotDeclared,
+        RejectReason::BadSignature,
+        RejectReason::LowConfidence,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::Expired => "expired",
+            RejectReason::OutOfOrder => "out_of_order",
+            RejectReason::AuthFailed => "auth_failed",
+            RejectReason::Unsafe => "unsafe",
+            RejectReason::BadVersion => "bad_version",
+            RejectReason::Malformed => "malformed",
+            RejectReason::RateLimited => "rate_limited",
+            RejectReason::DuplicateReasoning => "duplicate_reasoning",
+            RejectReason::CapabilityNotDeclared => "capability_not_declared",
+            RejectReason::BadSignature => "bad_signature",
+            RejectReason::LowConfidence => "low_confidence",
+        }
+    }
+}
+
+/// Best-effort feedback sent to the client that submitted a rejected
+/// recommendation.
+#[derive(Debug, Serialize)]
+pub struct RejectMsg {
+    #[serde(rename = "type")]
+    pub msg_type: &'static str,
+    pub protocol_version: ProtocolVersion,
+    pub sequence: u64,
+    pub reasoning_hash: String,
+    pub reason: RejectReason,
+}
+
+impl RejectMsg {
+    pub fn new(reason: RejectReason, sequence: u64, reasoning_hash: String) -> Self {
+        Self {
+            msg_type: "reject",
+            protocol_version: ProtocolVersion::v1(),
+            sequence,
+            reasoning_hash,
+            reason,
+        }
+    }
+}
+
+/// Keepalive frame sent by the spine when `ping_interval` is configured.
+#[derive(Debug, Serialize)]
+pub struct PingMsg {
+    #[serde(rename = "type")]
+    pub msg_type: &'static str,
+    pub protocol_version: ProtocolVersion,
+    pub sequence: u64,
+    pub unix_us: u64,
+}
+
+/// Reply to a `get_config` command: the spine's effective configuration
+/// with secrets reduced to whether they are set.
+#[derive(Debug, Serialize)]
+pub struct ConfigMsg {
+    #[serde(rename = "type")]
+    pub msg_type: &'static str,
+    pub protocol_version: ProtocolVersion,
+    pub sequence: u64,
+    pub config: serde_json::Value,
+}
+
+impl ConfigMsg {
+    pub fn new(sequence: u64, config: serde_json::Value) -> Self {
+        Self {
+            msg_type: "config",
+            protocol_version: ProtocolVersion::v1(),
+            sequence,
+            config,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PongMsg {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    #[serde(default)]
+    pub sequence: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -51,16 +259,62 @@ pub struct RecommendationMsg {
     #[serde(default)]
     pub sequence: u64,
     pub target_speed_rpm: Option<f64>,
+    #[serde(default)]
+    pub ramp_rate_rpm_per_s: Option<f64>,
     pub confidence: f32,
     pub reasoning_hash: String,
     #[serde(default)]
     pub issued_at_unix_us: u64,
     #[serde(default)]
     pub ttl_ms: u64,
+    /// Absolute deadline used instead of `ttl_ms`; exactly one is set
+    #[serde(default)]
+    pub expires_at_unix_us: Option<u64>,
     #[allow(dead_code)]
     pub client_unix_us: Option<u64>,
     #[allow(dead_code)]
     pub auth_token: Option<String>,
+    /// Detached base64url signature over [`RecommendationMsg::signing_input`]
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl RecommendationMsg {
+    /// Canonical bytes covered by `signature`: a compact JSON array of
+    /// `"recommendation"`, `sequence`, `issued_at_unix_us`, `ttl_ms`,
+    /// `target_speed_rpm`, `ramp_rate_rpm_per_s`, `confidence` and
+    /// `reasoning_hash`, with absent optionals as `null`, followed by
+    /// `expires_at_unix_us` when it is set. Numbers use serde_json's
+    /// formatting: floats always carry a fraction or exponent (`500.0`), and
+    /// small magnitudes differ from Python's `json.dumps` (`0.00001` here,
+    /// `1e-05` there), so signers must match it byte for byte.
+    pub fn signing_input(&self) -> Vec<u8> {
+        // A tuple, not `json!`, so `confidence` keeps its f32 formatting.
+        match self.expires_at_unix_us {
+            None => serde_json::to_vec(&(
+                "recommendation",
+                self.sequence,
+                self.issued_at_unix_us,
+                self.ttl_ms,
+                self.target_speed_rpm,
+                self.ramp_rate_rpm_per_s,
+                self.confidence,
+                &self.reasoning_hash,
+            )),
+            Some(expires_at) => serde_json::to_vec(&(
+                "recommendation",
+                self.sequence,
+                self.issued_at_unix_us,
+                self.ttl_ms,
+                self.target_speed_rpm,
+                self.ramp_rate_rpm_per_s,
+                self.confidence,
+                &self.reasoning_hash,
+                expires_at,
+            )),
+        }
+        .expect("recommendation signing input serializes")
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,26 +327,325 @@ pub struct HelloMsg {
     pub capabilities: Vec<String>,
     #[serde(default)]
     pub client_id: Option<String>,
+    /// `snapshot_sequence` of the last `state` received on a previous
+    /// connection
+    #[serde(default)]
+    pub last_snapshot_sequence: Option<u64>,
+}
+
+/// One setpoint inside a `batch`; ordering, TTL and auth live on the batch.
+#[derive(Debug, Deserialize)]
+pub struct BatchMember {
+    pub target_speed_rpm: Option<f64>,
+    #[serde(default)]
+    pub ramp_rate_rpm_per_s: Option<f64>,
+    pub confidence: f32,
+    pub reasoning_hash: String,
+}
+
+/// Recommendations validated and accepted or rejected as a unit
+#[derive(Debug, Deserialize)]
+pub struct BatchMsg {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    #[serde(default)]
+    pub protocol_version: ProtocolVersion,
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default)]
+    pub issued_at_unix_us: u64,
+    #[serde(default)]
+    pub ttl_ms: u64,
+    /// Absolute deadline used instead of `ttl_ms`; exactly one is set
+    #[serde(default)]
+    pub expires_at_unix_us: Option<u64>,
+    #[serde(default)]
+    pub auth_token: Option<String>,
+    pub recommendations: Vec<BatchMember>,
+    /// Detached base64url signature over [`BatchMsg::signing_input`]
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl BatchMsg {
+    /// Canonical bytes covered by `signature`: like a recommendation's, with
+    /// `"batch"` first and each member as a `[target_speed_rpm,
+    /// ramp_rate_rpm_per_s, confidence, reasoning_hash]` array after
+    /// `ttl_ms`, and `expires_at_unix_us` last when it is set.
+    pub fn signing_input(&self) -> Vec<u8> {
+        let members: Vec<_> = self
+            .recommendations
+            .iter()
+            .map(|member| {
+                (
+                    member.target_speed_rpm,
+                    member.ramp_rate_rpm_per_s,
+                    member.confidence,
+                    &member.reasoning_hash,
+                )
+            })
+            .collect();
+        match self.expires_at_unix_us {
+            None => serde_json::to_vec(&(
+                "batch",
+                self.sequence,
+                self.issued_at_unix_us,
+                self.ttl_ms,
+                members,
+            )),
+            Some(expires_at) => serde_json::to_vec(&(
+                "batch",
+                self.sequence,
+                self.issued_at_unix_us,
+                self.ttl_ms,
+                members,
+                expires_at,
+            )),
+        }
+        .expect("batch signing input serializes")
+    }
+}
+
+/// Operator command from the agent, e.g. `{"type":"command","command":"estop"}`.
+#[derive(Debug, Deserialize)]
+pub struct CommandMsg {
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    #[serde(default)]
+    pub sequence: u64,
+    pub command: String,
+    #[serde(default)]
+    pub auth_token: Option<String>,
+    /// Limits to change; required by `set_limits`
+    #[serde(default)]
+    pub limits: Option<LimitsPatch>,
+}
+
+/// Subset of the safety limits carried by `set_limits`. Absent fields keep
+/// their current value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitsPatch {
+    pub max_speed_rpm: Option<f64>,
+    pub min_speed_rpm: Option<f64>,
+    pub max_accel_rpm_per_s: Option<f64>,
+    pub max_decel_rpm_per_s: Option<f64>,
+    pub min_temp_c: Option<f64>,
+    pub max_temp_c: Option<f64>,
+    pub max_pressure_bar: Option<f64>,
+}
+
+impl LimitsPatch {
+    /// `current` with the fields present in this patch replaced
+    pub fn apply(&self, current: SafetyLimits) -> SafetyLimits {
+        SafetyLimits {
+            max_speed_rpm: self.max_speed_rpm.unwrap_or(current.max_speed_rpm),
+            min_speed_rpm: self.min_speed_rpm.unwrap_or(current.min_speed_rpm),
+            max_accel_rpm_per_s: self
+                .max_accel_rpm_per_s
+                .unwrap_or(current.max_accel_rpm_per_s),
+            max_decel_rpm_per_s: self
+                .max_decel_rpm_per_s
+                .unwrap_or(current.max_decel_rpm_per_s),
+            min_temp_c: self.min_temp_c.unwrap_or(current.min_temp_c),
+            max_temp_c: self.max_temp_c.unwrap_or(current.max_temp_c),
+            max_pressure_bar: self.max_pressure_bar.unwrap_or(current.max_pressure_bar),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum IncomingMessage {
     Hello(HelloMsg),
     Recommendation(RecommendationMsg),
+    Pong(PongMsg),
+    Command(CommandMsg),
+    Batch(BatchMsg),
+}
+
+/// Why an incoming line was not a protocol message
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("invalid JSON: {0}")]
+    BadJson(String),
+    #[error("unknown message type '{0}'")]
+    UnknownType(String),
+    #[error("missing field '{0}'")]
+    MissingField(String),
+    #[error("invalid field: {0}")]
+    InvalidField(String),
+    #[error("malformed protocol_version: {0}")]
+    BadVersion(String),
+}
+
+impl ParseError {
+    /// Metric label for this error category
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ParseError::BadJson(_) => "bad_json",
+            ParseError::UnknownType(_) => "unknown_type",
+            ParseError::MissingField(_) => "missing_field",
+            ParseError::InvalidField(_) => "invalid_field",
+            ParseError::BadVersion(_) => "bad_version",
+        }
+    }
+
+    fn from_schema(err: serde_json::Error) -> Self {
+        // serde_json reports every data error as one category, so the
+        // missing field can only be recovered from the message.
+        let message = err.to_string();
+        match message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.split('`').next())
+        {
+            Some(field) => ParseError::MissingField(field.to_string()),
+            None => ParseError::InvalidField(message),
+        }
+    }
 }
 
 impl IncomingMessage {
-    pub fn parse(line: &str) -> Option<Self> {
-        let value: serde_json::Value = serde_json::from_str(line).ok()?;
-        let msg_type = value.get("type")?.as_str()?;
-        match msg_type {
-            "recommendation" => serde_json::from_value(value)
-                .ok()
-                .map(IncomingMessage::Recommendation),
-            "hello" => serde_json::from_value(value)
-                .ok()
-                .map(IncomingMessage::Hello),
-            _ => None,
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let value: serde_json::Value =
+            serde_json::from_str(line).map_err(|e| ParseError::BadJson(e.to_string()))?;
+        let msg_type = match value.get("type") {
+            Some(serde_json::Value::String(msg_type)) => msg_type.as_str(),
+            Some(_) => return Err(ParseError::InvalidField("type is not a string".to_string())),
+            None => return Err(ParseError::MissingField("type".to_string())),
+        };
+        if let Some(version) = value.get("protocol_version") {
+            ProtocolVersion::deserialize(version)
+                .map_err(|e| ParseError::BadVersion(e.to_string()))?;
         }
+        let msg = match msg_type {
+            "recommendation" => serde_json::from_value(value).map(IncomingMessage::Recommendation),
+            "hello" => serde_json::from_value(value).map(IncomingMessage::Hello),
+            "pong" => serde_json::from_value(value).map(IncomingMessage::Pong),
+            "command" => serde_json::from_value(value).map(IncomingMessage::Command),
+            "batch" => serde_json::from_value(value).map(IncomingMessage::Batch),
+            other => return Err(ParseError::UnknownType(other.to_string())),
+        };
+        msg.map_err(ParseError::from_schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommendation_signing_input_is_canonical() {
+        let rec: RecommendationMsg = serde_json::from_str(
+            r#"{"type":"recommendation","sequence":7,"issued_at_unix_us":1700000000000000,
+                "ttl_ms":500,"target_speed_rpm":500.0,"confidence":0.9,
+                "reasoning_hash":"ab","auth_token":"t","signature":"s"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(rec.signing_input()).unwrap(),
+            r#"["recommendation",7,1700000000000000,500,500.0,null,0.9,"ab"]"#
+        );
+
+        let rec: RecommendationMsg = serde_json::from_str(
+            r#"{"type":"recommendation","sequence":7,"issued_at_unix_us":1700000000000000,
+                "expires_at_unix_us":1700000000500000,"target_speed_rpm":500.0,
+                "confidence":0.9,"reasoning_hash":"ab"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(rec.signing_input()).unwrap(),
+            r#"["recommendation",7,1700000000000000,0,500.0,null,0.9,"ab",1700000000500000]"#
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_are_categorised() {
+        let err = |line: &str| IncomingMessage::parse(line).unwrap_err();
+
+        assert!(matches!(err(r#"{"type":"hello""#), ParseError::BadJson(_)));
+        assert_eq!(
+            err(r#"{"type":"reboot"}"#),
+            ParseError::UnknownType("reboot".to_string())
+        );
+        assert_eq!(
+            err(r#"{"sequence":1}"#),
+            ParseError::MissingField("type".to_string())
+        );
+        assert_eq!(
+            err(r#"{"type":"command","sequence":1}"#),
+            ParseError::MissingField("command".to_string())
+        );
+        assert!(matches!(
+            err(r#"{"type":"command","command":"estop","sequence":"one"}"#),
+            ParseError::InvalidField(_)
+        ));
+        assert!(matches!(err(r#"{"type":7}"#), ParseError::InvalidField(_)));
+        assert!(matches!(
+            err(r#"{"type":"hello","protocol_version":"1.0"}"#),
+            ParseError::BadVersion(_)
+        ));
+        assert!(matches!(
+            err(r#"{"type":"hello","protocol_version":{"major":256,"minor":0}}"#),
+            ParseError::BadVersion(_)
+        ));
+
+        assert_eq!(err("not json").reason(), "bad_json");
+        assert_eq!(err(r#"{"type":"reboot"}"#).reason(), "unknown_type");
+        assert!(IncomingMessage::parse(
+            r#"{"type":"hello","protocol_version":{"major":2,"minor":0}}"#
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_hello_ack_serialization() {
+        let ack = HelloAckMsg::new(vec!["recommendation.v1".to_string()], "json");
+        let value = serde_json::to_value(&ack).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "type": "hello_ack",
+                "protocol_version": {"major": 1, "minor": 0},
+                "capabilities": ["recommendation.v1"],
+                "wire_protocol": "json",
+            })
+        );
+    }
+
+    #[test]
+    fn test_state_serialization_includes_applied_hash() {
+        let mut snapshot = ProcessSnapshot {
+            applied_reasoning_hash: [0xab; 32].into(),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(StateMsg::from_snapshot(&snapshot, 3, 42)).unwrap();
+        assert_eq!(value["type"], "state");
+        assert_eq!(value["sequence"], 3);
+        assert_eq!(value["applied_reasoning_hash"], "ab".repeat(32));
+        assert_eq!(value["snapshot_sequence"], 0);
+
+        snapshot.applied_reasoning_hash = ProcessSnapshot::HOLDING_LAST_SAFE;
+        let value = serde_json::to_value(StateMsg::from_snapshot(&snapshot, 4, 42)).unwrap();
+        assert_eq!(value["applied_reasoning_hash"], "0".repeat(64));
+    }
+
+    #[test]
+    fn test_reject_serialization() {
+        let reject = RejectMsg::new(RejectReason::OutOfOrder, 7, "ab".repeat(32));
+        let value = serde_json::to_value(&reject).unwrap();
+        assert_eq!(value["type"], "reject");
+        assert_eq!(value["reason"], "out_of_order");
+        assert_eq!(value["sequence"], 7);
+        assert_eq!(value["reasoning_hash"], "ab".repeat(32));
+    }
+
+    #[test]
+    fn test_error_serialization() {
+        let err = ErrorMsg::unsupported_version(ProtocolVersion { major: 2, minor: 1 });
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["type"], "error");
+        assert_eq!(value["code"], "unsupported_version");
+        assert_eq!(value["protocol_version"]["major"], 1);
+        assert!(value["message"].as_str().unwrap().contains("2.1"));
     }
 }
@@ -8,7 +8,10 @@ pub mod proto {
 }
 
 #[cfg(feature = "proto")]
-use crate::protocol::{HelloMsg, IncomingMessage, ProtocolVersion, RecommendationMsg};
+use crate::protocol::{
+    BatchMember, BatchMsg, ErrorMsg, HelloAckMsg, HelloMsg, IncomingMessage, ProtocolVersion,
+    RecommendationMsg, RejectMsg,
+};
 
 #[cfg(feature = "proto")]
 impl From<ProtocolVersion> for proto::ProtocolVersion {
@@ -38,11 +41,39 @@ impl From<RecommendationMsg> for proto::Recommendation {
             sequence: value.sequence,
             issued_at_unix_us: value.issued_at_unix_us,
             ttl_ms: value.ttl_ms,
+            expires_at_unix_us: value.expires_at_unix_us,
             target_speed_rpm: value.target_speed_rpm,
+            ramp_rate_rpm_per_s: value.ramp_rate_rpm_per_s,
             confidence: value.confidence,
             reasoning_hash: value.reasoning_hash,
             client_unix_us: value.client_unix_us,
             auth_token: value.auth_token,
+            signature: value.signature,
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl From<BatchMsg> for proto::Batch {
+    fn from(value: BatchMsg) -> Self {
+        Self {
+            protocol_version: Some(value.protocol_version.into()),
+            sequence: value.sequence,
+            issued_at_unix_us: value.issued_at_unix_us,
+            ttl_ms: value.ttl_ms,
+            expires_at_unix_us: value.expires_at_unix_us,
+            auth_token: value.auth_token,
+            recommendations: value
+                .recommendations
+                .into_iter()
+                .map(|member| proto::BatchMember {
+                    target_speed_rpm: member.target_speed_rpm,
+                    ramp_rate_rpm_per_s: member.ramp_rate_rpm_per_s,
+                    confidence: member.confidence,
+                    reasoning_hash: member.reasoning_hash,
+                })
+                .collect(),
+            signature: value.signature,
         }
     }
 }
@@ -54,6 +85,42 @@ impl From<HelloMsg> for proto::Hello {
             protocol_version: Some(value.protocol_version.into()),
             capabilities: value.capabilities,
             client_id: value.client_id,
+            last_snapshot_sequence: value.last_snapshot_sequence,
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl From<HelloAckMsg> for proto::HelloAck {
+    fn from(value: HelloAckMsg) -> Self {
+        Self {
+            protocol_version: Some(value.protocol_version.into()),
+            capabilities: value.capabilities,
+            wire_protocol: value.wire_protocol.to_string(),
+            sequence_floor: value.sequence_floor,
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl From<ErrorMsg> for proto::Error {
+    fn from(value: ErrorMsg) -> Self {
+        Self {
+            protocol_version: Some(value.protocol_version.into()),
+            code: value.code.to_string(),
+            message: value.message,
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl From<&RejectMsg> for proto::Reject {
+    fn from(value: &RejectMsg) -> Self {
+        Self {
+            protocol_version: Some(value.protocol_version.into()),
+            sequence: value.sequence,
+            reasoning_hash: value.reasoning_hash.clone(),
+            reason: value.reason.as_str().to_string(),
         }
     }
 }
@@ -72,6 +139,7 @@ impl TryFrom<proto::Hello> for HelloMsg {
             protocol_version,
             capabilities: value.capabilities,
             client_id: value.client_id,
+            last_snapshot_sequence: value.last_snapshot_sequence,
         })
     }
 }
@@ -90,12 +158,47 @@ impl TryFrom<proto::Recommendation> for RecommendationMsg {
             protocol_version,
             sequence: value.sequence,
             target_speed_rpm: value.target_speed_rpm,
+            ramp_rate_rpm_per_s: value.ramp_rate_rpm_per_s,
             confidence: value.confidence,
             reasoning_hash: value.reasoning_hash,
             issued_at_unix_us: value.issued_at_unix_us,
             ttl_ms: value.ttl_ms,
+            expires_at_unix_us: value.expires_at_unix_us,
             client_unix_us: value.client_unix_us,
             auth_token: value.auth_token,
+            signature: value.signature,
+        })
+    }
+}
+
+#[cfg(feature = "proto")]
+impl TryFrom<proto::Batch> for BatchMsg {
+    type Error = ();
+
+    fn try_from(value: proto::Batch) -> Result<Self, Self::Error> {
+        let protocol_version = value
+            .protocol_version
+            .map(ProtocolVersion::from)
+            .unwrap_or_default();
+        Ok(Self {
+            msg_type: "batch".to_string(),
+            protocol_version,
+            sequence: value.sequence,
+            issued_at_unix_us: value.issued_at_unix_us,
+            ttl_ms: value.ttl_ms,
+            expires_at_unix_us: value.expires_at_unix_us,
+            auth_token: value.auth_token,
+            recommendations: value
+                .recommendations
+                .into_iter()
+                .map(|member| BatchMember {
+                    target_speed_rpm: member.target_speed_rpm,
+                    ramp_rate_rpm_per_s: member.ramp_rate_rpm_per_s,
+                    confidence: member.confidence,
+                    reasoning_hash: member.reasoning_hash,
+                })
+                .collect(),
+            signature: value.signature,
         })
     }
 }
@@ -112,6 +215,9 @@ impl TryFrom<proto::WireMessage> for IncomingMessage {
             Some(proto::wire_message::Payload::Recommendation(msg)) => {
                 RecommendationMsg::try_from(msg).map(IncomingMessage::Recommendation)
             }
+            Some(proto::wire_message::Payload::Batch(msg)) => {
+                BatchMsg::try_from(msg).map(IncomingMessage::Batch)
+            }
             _ => Err(()),
         }
     }
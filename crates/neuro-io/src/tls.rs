@@ -1,16 +1,25 @@
 //! TLS configuration and utilities for secure connections.
 //!
-//! This module provides TLS server configuration for the bridge.
+//! This module provides TLS server configuration for the bridge, including
+//! picking up rotated certificate/key files without restarting the listener.
 
-use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::client::danger::HandshakeSignatureValid;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
 use rustls::server::WebPkiClientVerifier;
-use rustls::{RootCertStore, ServerConfig};
+use rustls::{
+    CertificateError, DigitallySignedStruct, DistinguishedName, RootCertStore, ServerConfig,
+    SignatureScheme, SupportedProtocolVersion,
+};
 use rustls_pemfile::{certs, private_key};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use thiserror::Error;
+use tracing::{info, warn};
 
 /// Errors that can occur during TLS configuration
 #[derive(Debug, Error)]
@@ -24,10 +33,53 @@ pub enum TlsError {
     #[error("No private key found in file")]
     NoPrivateKey,
 
+    #[error("Unknown TLS cipher suite {0}")]
+    UnknownCipherSuite(String),
+
+    #[error("No cipher suite is usable at TLS {0} or later")]
+    NoUsableCipherSuites(TlsVersion),
+
     #[error("Failed to build TLS config: {0}")]
     ConfigError(String),
 }
 
+/// Oldest TLS version the server negotiates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TlsVersion {
+    #[default]
+    Tls12,
+    Tls13,
+}
+
+impl TlsVersion {
+    /// `1.2` or `1.3`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "1.2" => Some(TlsVersion::Tls12),
+            "1.3" => Some(TlsVersion::Tls13),
+            _ => None,
+        }
+    }
+
+    /// Versions from this one up
+    fn and_later(self) -> &'static [&'static SupportedProtocolVersion] {
+        const TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+        match self {
+            TlsVersion::Tls12 => rustls::ALL_VERSIONS,
+            TlsVersion::Tls13 => TLS13_ONLY,
+        }
+    }
+}
+
+impl std::fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TlsVersion::Tls12 => "1.2",
+            TlsVersion::Tls13 => "1.3",
+        })
+    }
+}
+
 /// TLS configuration for the bridge
 #[derive(Clone, Debug, Default)]
 pub struct TlsConfig {
@@ -41,6 +93,14 @@ pub struct TlsConfig {
     pub require_client_auth: bool,
     /// Path to client CA bundle (PEM format)
     pub client_ca_path: String,
+    /// Client certificate CN/SAN values allowed to connect (empty = any).
+    /// Requires `require_client_auth`.
+    pub allowed_client_cns: Vec<String>,
+    /// Oldest TLS version accepted
+    pub min_protocol_version: TlsVersion,
+    /// Cipher suites to offer by IANA name, e.g. `TLS13_AES_256_GCM_SHA384`
+    /// (empty = the crypto provider's defaults)
+    pub cipher_suites: Vec<String>,
 }
 
 impl TlsConfig {
@@ -85,11 +145,55 @@ pub fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, TlsError>
         .ok_or(TlsError::NoPrivateKey)
 }
 
+/// The default crypto provider restricted to `config`'s cipher suites, and
+/// the protocol versions to enable. Fails if an allowlisted suite is
+/// unknown or no suite is left for the enabled versions.
+fn crypto_policy(
+    config: &TlsConfig,
+) -> Result<(CryptoProvider, &'static [&'static SupportedProtocolVersion]), TlsError> {
+    let mut provider = ServerConfig::builder().crypto_provider().as_ref().clone();
+    if !config.cipher_suites.is_empty() {
+        for name in &config.cipher_suites {
+            if !provider
+                .cipher_suites
+                .iter()
+                .any(|suite| suite.suite().as_str() == Some(name.as_str()))
+            {
+                return Err(TlsError::UnknownCipherSuite(name.clone()));
+            }
+        }
+        provider.cipher_suites.retain(|suite| {
+            suite
+                .suite()
+                .as_str()
+                .is_some_and(|name| config.cipher_suites.iter().any(|n| n == name))
+        });
+    }
+    let versions = config.min_protocol_version.and_later();
+    if !provider
+        .cipher_suites
+        .iter()
+        .any(|suite| versions.contains(&suite.version()))
+    {
+        return Err(TlsError::NoUsableCipherSuites(config.min_protocol_version));
+    }
+    Ok((provider, versions))
+}
+
 /// Build a rustls ServerConfig from certificate and key files
 pub fn build_server_config(config: &TlsConfig) -> Result<Arc<ServerConfig>, TlsError> {
+    // Without client certificates there is no CN to check the list against.
+    if !config.allowed_client_cns.is_empty() && !config.require_client_auth {
+        return Err(TlsError::ConfigError(
+            "allowed client CNs need client certificates to be required".to_string(),
+        ));
+    }
+    let (provider, versions) = crypto_policy(config)?;
     let certs = load_certs(Path::new(&config.cert_path))?;
     let key = load_private_key(Path::new(&config.key_path))?;
-    let builder = ServerConfig::builder();
+    let builder = ServerConfig::builder_with_provider(Arc::new(provider))
+        .with_protocol_versions(versions)
+        .map_err(|e| TlsError::ConfigError(e.to_string()))?;
     let server_config = if config.require_client_auth {
         let ca_certs = load_certs(Path::new(&config.client_ca_path))?;
         let mut roots = RootCertStore::empty();
@@ -98,9 +202,15 @@ pub fn build_server_config(config: &TlsConfig) -> Result<Arc<ServerConfig>, TlsE
                 .add(cert)
                 .map_err(|e| TlsError::ConfigError(format!("{e:?}")))?;
         }
-        let verifier = WebPkiClientVerifier::builder(roots.into())
+        let mut verifier = WebPkiClientVerifier::builder(roots.into())
             .build()
             .map_err(|e| TlsError::ConfigError(e.to_string()))?;
+        if !config.allowed_client_cns.is_empty() {
+            verifier = Arc::new(AllowlistClientVerifier {
+                inner: verifier,
+                allowed: config.allowed_client_cns.clone(),
+            });
+        }
         builder
             .with_client_cert_verifier(verifier)
             .with_single_cert(certs, key)
@@ -115,6 +225,190 @@ pub fn build_server_config(config: &TlsConfig) -> Result<Arc<ServerConfig>, TlsE
     Ok(Arc::new(server_config))
 }
 
+/// Coarse reason a server-side TLS handshake failed, for metrics labels.
+///
+/// `StreamOwned` reports rustls errors as `InvalidData` I/O errors wrapping
+/// the `rustls::Error`; anything else is a transport failure.
+pub fn handshake_failure_reason(err: &std::io::Error) -> &'static str {
+    let Some(err) = err
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+    else {
+        return "io";
+    };
+    match err {
+        rustls::Error::InvalidCertificate(_) => "bad_certificate",
+        rustls::Error::NoCertificatesPresented => "no_certificate",
+        rustls::Error::AlertReceived(_) => "alert_received",
+        rustls::Error::PeerIncompatible(_)
+        | rustls::Error::InvalidMessage(_)
+        | rustls::Error::InappropriateMessage { .. }
+        | rustls::Error::InappropriateHandshakeMessage { .. } => "protocol_mismatch",
+        _ => "other",
+    }
+}
+
+/// Client verifier that runs the CA chain check first, then requires the
+/// leaf certificate's CN or a SAN DNS/URI entry to be on the allowlist.
+#[derive(Debug)]
+struct AllowlistClientVerifier {
+    inner: Arc<dyn ClientCertVerifier>,
+    allowed: Vec<String>,
+}
+
+impl ClientCertVerifier for AllowlistClientVerifier {
+    fn offer_client_auth(&self) -> bool {
+        self.inner.offer_client_auth()
+    }
+
+    fn client_auth_mandatory(&self) -> bool {
+        self.inner.client_auth_mandatory()
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        self.inner.root_hint_subjects()
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        let verified = self
+            .inner
+            .verify_client_cert(end_entity, intermediates, now)?;
+        let names = certificate_identities(end_entity);
+        if names.iter().any(|name| self.allowed.contains(name)) {
+            Ok(verified)
+        } else {
+            warn!(identities = ?names, "Client certificate not in allowlist");
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Subject CNs plus SAN DNS/URI names of a certificate
+fn certificate_identities(cert: &CertificateDer<'_>) -> Vec<String> {
+    use x509_parser::extensions::GeneralName;
+    use x509_parser::prelude::{FromDer, X509Certificate};
+
+    let Ok((_, cert)) = X509Certificate::from_der(cert.as_ref()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = cert
+        .subject()
+        .iter_common_name()
+        .filter_map(|cn| cn.as_str().ok().map(str::to_string))
+        .collect();
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in &san.value.general_names {
+            match name {
+                GeneralName::DNSName(n) | GeneralName::URI(n) => names.push(n.to_string()),
+                _ => {}
+            }
+        }
+    }
+    names
+}
+
+/// How often the certificate files are checked for changes
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A `ServerConfig` that is rebuilt when the certificate, key, or client CA
+/// files change on disk.
+///
+/// Only new connections see the reloaded config; established sessions keep
+/// the `Arc` they were created with. If the new files fail to load, the
+/// previous config stays in service.
+pub struct ReloadableServerConfig {
+    config: TlsConfig,
+    current: Arc<ServerConfig>,
+    modified: Vec<Option<SystemTime>>,
+    last_check: Instant,
+}
+
+impl ReloadableServerConfig {
+    /// Build the initial config; fails if the files cannot be loaded
+    pub fn new(config: &TlsConfig) -> Result<Self, TlsError> {
+        let current = build_server_config(config)?;
+        Ok(Self {
+            modified: watched_mtimes(config),
+            config: config.clone(),
+            current,
+            last_check: Instant::now(),
+        })
+    }
+
+    /// Config to use for the next connection, reloading it first if the
+    /// watched files have changed since the last check.
+    pub fn current(&mut self) -> Arc<ServerConfig> {
+        if self.last_check.elapsed() >= RELOAD_CHECK_INTERVAL {
+            self.last_check = Instant::now();
+            self.reload_if_changed();
+        }
+        self.current.clone()
+    }
+
+    /// Rebuild the config if any watched file's mtime changed.
+    /// Returns `true` when a new config was installed.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let modified = watched_mtimes(&self.config);
+        if modified == self.modified {
+            return false;
+        }
+        // Remember the new stamps either way so a broken file is reported once,
+        // not on every check.
+        self.modified = modified;
+        match build_server_config(&self.config) {
+            Ok(server_config) => {
+                self.current = server_config;
+                info!(cert = %self.config.cert_path, "Reloaded TLS certificates");
+                true
+            }
+            Err(e) => {
+                warn!(error = %e, "TLS reload failed, keeping previous certificates");
+                false
+            }
+        }
+    }
+}
+
+fn watched_mtimes(config: &TlsConfig) -> Vec<Option<SystemTime>> {
+    let mut paths = vec![&config.cert_path, &config.key_path];
+    if config.require_client_auth {
+        paths.push(&config.client_ca_path);
+    }
+    paths
+        .into_iter()
+        .map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
 /// Generate a self-signed certificate for development/testing
 /// This uses the rcgen crate if available, otherwise returns an error
 #[cfg(feature = "dev-certs")]
@@ -150,11 +444,23 @@ mod tests {
             key_path: "/path/to/key.pem".to_string(),
             require_client_auth: false,
             client_ca_path: String::new(),
+            allowed_client_cns: Vec::new(),
+            ..Default::default()
         };
 
         assert!(config.is_configured());
     }
 
+    #[test]
+    fn test_cn_allowlist_requires_client_auth() {
+        let result = build_server_config(&TlsConfig {
+            enabled: true,
+            allowed_client_cns: vec!["agent".to_string()],
+            ..Default::default()
+        });
+        assert!(matches!(result, Err(TlsError::ConfigError(_))));
+    }
+
     #[test]
     fn test_missing_cert_file() {
         let result = load_certs(Path::new("/nonexistent/cert.pem"));
@@ -166,4 +472,253 @@ mod tests {
         let result = load_private_key(Path::new("/nonexistent/key.pem"));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_cipher_suite_allowlist() {
+        let config = |min_protocol_version, suites: &[&str]| TlsConfig {
+            min_protocol_version,
+            cipher_suites: suites.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let (provider, versions) = crypto_policy(&config(
+            TlsVersion::Tls12,
+            &[
+                "TLS13_AES_256_GCM_SHA384",
+                "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+            ],
+        ))
+        .unwrap();
+        assert_eq!(provider.cipher_suites.len(), 2);
+        assert_eq!(versions.len(), 2);
+
+        let (provider, versions) = crypto_policy(&config(TlsVersion::Tls13, &[])).unwrap();
+        assert!(!provider.cipher_suites.is_empty());
+        assert_eq!(versions, &[&rustls::version::TLS13]);
+
+        assert!(matches!(
+            crypto_policy(&config(TlsVersion::Tls12, &["TLS_RSA_WITH_RC4_128_MD5"])),
+            Err(TlsError::UnknownCipherSuite(name)) if name == "TLS_RSA_WITH_RC4_128_MD5"
+        ));
+        assert!(matches!(
+            crypto_policy(&config(
+                TlsVersion::Tls13,
+                &["TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"]
+            )),
+            Err(TlsError::NoUsableCipherSuites(TlsVersion::Tls13))
+        ));
+    }
+
+    #[test]
+    fn test_handshake_failure_reason() {
+        let wrap = |err: rustls::Error| std::io::Error::new(std::io::ErrorKind::InvalidData, err);
+        assert_eq!(
+            handshake_failure_reason(&wrap(rustls::Error::InvalidCertificate(
+                CertificateError::Expired
+            ))),
+            "bad_certificate"
+        );
+        assert_eq!(
+            handshake_failure_reason(&wrap(rustls::Error::NoCertificatesPresented)),
+            "no_certificate"
+        );
+        assert_eq!(
+            handshake_failure_reason(&wrap(rustls::Error::PeerIncompatible(
+                rustls::PeerIncompatible::Tls12NotOffered
+            ))),
+            "protocol_mismatch"
+        );
+        assert_eq!(
+            handshake_failure_reason(&std::io::ErrorKind::ConnectionReset.into()),
+            "io"
+        );
+    }
+
+    #[test]
+    fn test_tls_version_parse() {
+        assert_eq!(TlsVersion::parse("1.2"), Some(TlsVersion::Tls12));
+        assert_eq!(TlsVersion::parse("1.3"), Some(TlsVersion::Tls13));
+        assert_eq!(TlsVersion::parse("1.1"), None);
+        assert_eq!(TlsVersion::default(), TlsVersion::Tls12);
+    }
+
+    #[cfg(feature = "dev-certs")]
+    #[test]
+    fn test_min_version_refuses_older_clients() {
+        use rustls::pki_types::ServerName;
+        use rustls::{ClientConfig, ClientConnection, ServerConnection};
+
+        let dir = std::env::temp_dir().join(format!("neuroplc-tls13-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        generate_dev_cert(&dir.join("server.pem"), &dir.join("server.key")).unwrap();
+        let server_config = build_server_config(&TlsConfig {
+            enabled: true,
+            cert_path: dir.join("server.pem").display().to_string(),
+            key_path: dir.join("server.key").display().to_string(),
+            min_protocol_version: TlsVersion::Tls13,
+            ..Default::default()
+        })
+        .unwrap();
+        let server_cert = load_certs(&dir.join("server.pem")).unwrap();
+
+        let handshake = |versions: &[&'static SupportedProtocolVersion]| {
+            let mut roots = RootCertStore::empty();
+            roots.add(server_cert[0].clone()).unwrap();
+            let client_config = ClientConfig::builder_with_protocol_versions(versions)
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            let mut client = ClientConnection::new(
+                Arc::new(client_config),
+                ServerName::try_from("localhost").unwrap(),
+            )
+            .unwrap();
+            let mut server = ServerConnection::new(server_config.clone()).unwrap();
+
+            for _ in 0..10 {
+                let mut buf = Vec::new();
+                while client.wants_write() {
+                    client.write_tls(&mut buf).unwrap();
+                }
+                server.read_tls(&mut buf.as_slice()).unwrap();
+                server.process_new_packets()?;
+                let mut buf = Vec::new();
+                while server.wants_write() {
+                    server.write_tls(&mut buf).unwrap();
+                }
+                client.read_tls(&mut buf.as_slice()).unwrap();
+                client.process_new_packets()?;
+                if !client.is_handshaking() && !server.is_handshaking() {
+                    return Ok(());
+                }
+            }
+            panic!("handshake did not complete");
+        };
+
+        assert!(handshake(&[&rustls::version::TLS13]).is_ok());
+        assert!(matches!(
+            handshake(&[&rustls::version::TLS12]),
+            Err(rustls::Error::PeerIncompatible(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "dev-certs")]
+    #[test]
+    fn test_reload_keeps_previous_config_on_bad_files() {
+        let dir = std::env::temp_dir().join(format!("neuroplc-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        generate_dev_cert(&cert, &key).unwrap();
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: cert.display().to_string(),
+            key_path: key.display().to_string(),
+            ..Default::default()
+        };
+
+        let mut reloadable = ReloadableServerConfig::new(&config).unwrap();
+        let original = reloadable.current();
+        assert!(!reloadable.reload_if_changed());
+
+        // Broken rotation: old config stays in service
+        std::fs::write(&cert, "not a certificate").unwrap();
+        reloadable.modified = Vec::new();
+        assert!(!reloadable.reload_if_changed());
+        assert!(Arc::ptr_eq(&original, &reloadable.current));
+
+        // Valid rotation: new config replaces it
+        generate_dev_cert(&cert, &key).unwrap();
+        reloadable.modified = Vec::new();
+        assert!(reloadable.reload_if_changed());
+        assert!(!Arc::ptr_eq(&original, &reloadable.current));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "dev-certs")]
+    #[test]
+    fn test_client_cn_allowlist() {
+        use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+        use rustls::pki_types::{PrivatePkcs8KeyDer, ServerName};
+        use rustls::{ClientConfig, ClientConnection, ServerConnection};
+
+        let dir = std::env::temp_dir().join(format!("neuroplc-mtls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "NeuroPLC test CA");
+        let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+        std::fs::write(dir.join("ca.pem"), ca_cert.pem()).unwrap();
+        generate_dev_cert(&dir.join("server.pem"), &dir.join("server.key")).unwrap();
+
+        let server_config = build_server_config(&TlsConfig {
+            enabled: true,
+            cert_path: dir.join("server.pem").display().to_string(),
+            key_path: dir.join("server.key").display().to_string(),
+            require_client_auth: true,
+            client_ca_path: dir.join("ca.pem").display().to_string(),
+            allowed_client_cns: vec!["agent-allowed".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let server_cert = load_certs(&dir.join("server.pem")).unwrap();
+
+        let handshake = |cn: &str| -> Result<(), rustls::Error> {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params.distinguished_name.push(DnType::CommonName, cn);
+            let cert = params.signed_by(&key, &ca_cert, &ca_key).unwrap();
+
+            let mut roots = RootCertStore::empty();
+            roots.add(server_cert[0].clone()).unwrap();
+            let client_config = ClientConfig::builder()
+                .with_root_certificates(roots)
+                .with_client_auth_cert(
+                    vec![cert.der().clone()],
+                    PrivatePkcs8KeyDer::from(key.serialize_der()).into(),
+                )
+                .unwrap();
+            let mut client = ClientConnection::new(
+                Arc::new(client_config),
+                ServerName::try_from("localhost").unwrap(),
+            )
+            .unwrap();
+            let mut server = ServerConnection::new(server_config.clone()).unwrap();
+
+            for _ in 0..10 {
+                let mut buf = Vec::new();
+                while client.wants_write() {
+                    client.write_tls(&mut buf).unwrap();
+                }
+                server.read_tls(&mut buf.as_slice()).unwrap();
+                server.process_new_packets()?;
+                let mut buf = Vec::new();
+                while server.wants_write() {
+                    server.write_tls(&mut buf).unwrap();
+                }
+                client.read_tls(&mut buf.as_slice()).unwrap();
+                client.process_new_packets()?;
+                if !client.is_handshaking() && !server.is_handshaking() {
+                    return Ok(());
+                }
+            }
+            panic!("handshake did not complete");
+        };
+
+        assert!(handshake("agent-allowed").is_ok());
+        assert!(matches!(
+            handshake("agent-rogue"),
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure
+            ))
+        ));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
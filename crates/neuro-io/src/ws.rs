@@ -0,0 +1,399 @@
+//! WebSocket transport for browser dashboards.
+//!
+//! Browsers cannot open the raw TCP bridge, so this listener speaks the same
+//! JSON messages over WebSocket: every state snapshot is one text frame and
+//! every incoming text frame is parsed as an
+//! [`IncomingMessage`](crate::protocol::IncomingMessage), going through the
+//! same validation, auth and rate limiting as the TCP bridge.
+//! Each connection is served on its own thread so several dashboards can
+//! watch at once. Keepalive uses WebSocket ping frames, which browsers answer
+//! automatically, instead of the bridge's JSON `ping` messages.
+
+use crate::audit::AuditLogger;
+use crate::auth::TokenValidator;
+use crate::bridge::{
+    audit_outcome, bind_listener, encode_reply, handle_incoming, parse_incoming,
+    server_capabilities, BridgeConfig, BridgeError, BridgeReply, InboundState, PublishSchedule,
+    WireProtocol, CRC32_FRAMING_CAPABILITY,
+};
+use crate::protocol::StateMsg;
+use crate::tls::ReloadableServerConfig;
+use core_spine::{Clock, Sequenced, StateExchange};
+use rustls::{ServerConnection, StreamOwned};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use tungstenite::handshake::HandshakeError;
+use tungstenite::{Message, WebSocket};
+
+/// Connections served at once; further clients are refused.
+const MAX_WS_CLIENTS: usize = 16;
+/// Upper bound on the HTTP upgrade exchange.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// A client that cannot take a frame within this long is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Serve WebSocket clients on `config.bind_addr` until `stop` is set.
+/// `config.wire_protocol` and `config.extra_bind_addrs` are ignored; frames
+/// are always JSON text. `config.poll_interval` is also each client's socket
+/// read timeout, so it bounds how late a state frame or stop request is
+/// seen. `audit` is used as in [`crate::bridge::run_bridge`].
+pub fn run_ws_bridge<C: Clock + Clone + 'static>(
+    exchange: Arc<StateExchange>,
+    clock: C,
+    config: BridgeConfig,
+    stop: Arc<AtomicBool>,
+    audit: Option<Arc<AuditLogger>>,
+) -> Result<(), BridgeError> {
+    let listener = bind_listener(&config.bind_addr)?;
+
+    let mut tls_config = if config.tls.enabled {
+        Some(ReloadableServerConfig::new(&config.tls)?)
+    } else {
+        None
+    };
+    let validator = config.token_validator();
+    let ping_interval = config
+        .ping_interval
+        .or(config.idle_timeout.map(|timeout| timeout / 3));
+    let config = Arc::new(BridgeConfig {
+        wire_protocol: WireProtocol::JsonLines,
+        ping_interval: None,
+        ..config
+    });
+
+    info!(
+        addr = %config.bind_addr,
+        tls = config.tls.enabled,
+        auth = config.auth.enabled,
+        "WebSocket bridge listening"
+    );
+
+    let poll_interval = config.effective_poll_interval();
+    let mut clients: Vec<JoinHandle<()>> = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        clients.retain(|handle| !handle.is_finished());
+        match listener.accept() {
+            Ok((stream, addr)) => {
+                if clients.len() >= MAX_WS_CLIENTS {
+                    warn!(client_addr = %addr, "Refusing WebSocket client, too many connections");
+                    continue;
+                }
+                if let Err(e) = configure_stream(&stream, poll_interval) {
+                    warn!(client_addr = %addr, error = %e, "Failed to configure WebSocket client");
+                    continue;
+                }
+                info!(client_addr = %addr, "WebSocket client connected");
+                let client = WsClient {
+                    exchange: Arc::clone(&exchange),
+                    clock: clock.clone(),
+                    config: Arc::clone(&config),
+                    validator: validator.clone(),
+                    ping_interval,
+                    stop: Arc::clone(&stop),
+                    audit: audit.clone(),
+                    peer_addr: addr,
+                };
+                let handle = match tls_config.as_mut() {
+                    Some(tls_cfg) => match ServerConnection::new(tls_cfg.current()) {
+                        Ok(conn) => {
+                            let stream = StreamOwned::new(conn, stream);
+                            thread::spawn(move || client.serve(stream))
+                        }
+                        Err(e) => {
+                            warn!(error = %e, "Failed to create TLS connection state");
+                            continue;
+                        }
+                    },
+                    None => thread::spawn(move || client.serve(stream)),
+                };
+                clients.push(handle);
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(poll_interval);
+            }
+            Err(err) => {
+                warn!(error = %err, "WebSocket accept error");
+                thread::sleep(poll_interval);
+            }
+        }
+    }
+    for handle in clients {
+        let _ = handle.join();
+    }
+    Ok(())
+}
+
+fn configure_stream(stream: &TcpStream, poll_interval: Duration) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(poll_interval))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))
+}
+
+fn is_timeout(err: &tungstenite::Error) -> bool {
+    matches!(
+        err,
+        tungstenite::Error::Io(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+    )
+}
+
+/// Per-connection state shared with the accept loop.
+struct WsClient<C> {
+    exchange: Arc<StateExchange>,
+    clock: C,
+    config: Arc<BridgeConfig>,
+    validator: Option<Arc<Mutex<TokenValidator>>>,
+    ping_interval: Option<Duration>,
+    stop: Arc<AtomicBool>,
+    audit: Option<Arc<AuditLogger>>,
+    peer_addr: SocketAddr,
+}
+
+impl<C: Clock> WsClient<C> {
+    fn serve<S: Read + Write>(self, stream: S) {
+        let Some(mut socket) = self.handshake(stream) else {
+            return;
+        };
+        let mut capabilities = server_capabilities(&self.config);
+        // WebSocket frames carry their own length; lines are never checksummed.
+        capabilities.retain(|c| c != CRC32_FRAMING_CAPABILITY);
+        let mut inbound_state =
+            InboundState::with_rate_limit(self.config.max_recommendations_per_sec)
+                .with_min_confidence(self.config.min_confidence)
+                .with_duplicate_reasoning(self.config.duplicate_reasoning)
+                .with_max_clock_skew(self.config.max_clock_skew)
+                .with_require_signature(self.config.require_signature)
+                .with_reasoning_hash_bytes(self.config.reasoning_hash_bytes)
+                .with_safety_limits(self.config.safety_limits)
+                .with_sequence_floors(self.config.sequence_floors.clone())
+                .with_effective_config(self.config.effective_config.clone());
+        inbound_state.note_peer(self.peer_addr);
+        let mut state_sequence: u64 = 0;
+        let mut publish = PublishSchedule::new(self.config.publish_mode);
+        let mut last_activity = Instant::now();
+        let mut last_ping = Instant::now();
+
+        loop {
+            if self.stop.load(Ordering::Relaxed) {
+                let _ = socket.close(None);
+                let _ = socket.flush();
+                break;
+            }
+
+            match socket.read() {
+                Ok(message) => {
+                    last_activity = Instant::now();
+                    match message {
+                        Message::Text(text) => {
+                            let reply = parse_incoming(text.as_str()).and_then(|msg| {
+                                let validator = self.validator.as_ref().map(|v| v.lock().unwrap());
+                                handle_incoming(
+                                    msg,
+                                    &self.exchange,
+                                    &self.clock,
+                                    validator.as_deref(),
+                                    self.config.require_handshake,
+                                    &mut inbound_state,
+                                    self.audit.as_deref(),
+                                )
+                            });
+                            if let Some(reply) = reply {
+                                if !self.send_reply(&mut socket, &reply, &capabilities) {
+                                    break;
+                                }
+                            }
+                        }
+                        Message::Binary(_) => debug!("Ignoring binary WebSocket frame"),
+                        // tungstenite queues the pong; push it out now.
+                        Message::Ping(_) => {
+                            if let Err(e) = socket.flush() {
+                                if !is_timeout(&e) {
+                                    break;
+                                }
+                            }
+                        }
+                        // The close reply is queued by tungstenite; the next
+                        // read reports the connection as closed.
+                        Message::Close(_) | Message::Pong(_) | Message::Frame(_) => {}
+                    }
+                }
+                Err(e) if is_timeout(&e) => {}
+                Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    info!("WebSocket client disconnected");
+                    break;
+                }
+                Err(e) => {
+                    warn!(error = %e, "WebSocket read error");
+                    break;
+                }
+            }
+
+            if let Some(audit) = self.audit.as_deref() {
+                audit_outcome(&self.exchange, &self.clock, audit, &mut inbound_state);
+            }
+
+            if let Some(idle_timeout) = self.config.idle_timeout {
+                if last_activity.elapsed() >= idle_timeout {
+                    warn!("Dropping idle WebSocket client");
+                    let _ = socket.close(None);
+                    let _ = socket.flush();
+                    break;
+                }
+            }
+
+            let Sequenced {
+                sequence: snapshot_sequence,
+                value: snapshot,
+            } = self.exchange.read_state_sequenced();
+            if publish.due(&snapshot, Instant::now()) {
+                state_sequence = state_sequence.wrapping_add(1);
+                let msg = StateMsg::from_snapshot(&snapshot, state_sequence, self.clock.unix_us())
+                    .with_snapshot_sequence(snapshot_sequence);
+                if let Ok(text) = serde_json::to_string(&msg) {
+                    if let Err(e) = socket.send(Message::text(text)) {
+                        warn!(error = %e, "Dropping WebSocket client after failed send");
+                        break;
+                    }
+                }
+                publish.note_sent(snapshot, Instant::now());
+            }
+
+            if let Some(ping_interval) = self.ping_interval {
+                if last_ping.elapsed() >= ping_interval {
+                    if let Err(e) = socket.send(Message::Ping(Default::default())) {
+                        warn!(error = %e, "Dropping WebSocket client after failed ping");
+                        break;
+                    }
+                    last_ping = Instant::now();
+                }
+            }
+        }
+    }
+
+    /// Complete the HTTP upgrade, retrying reads that hit the poll timeout.
+    fn handshake<S: Read + Write>(&self, stream: S) -> Option<WebSocket<S>> {
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        let mut attempt = tungstenite::accept(stream);
+        loop {
+            match attempt {
+                Ok(socket) => return Some(socket),
+                Err(HandshakeError::Interrupted(mid))
+                    if Instant::now() < deadline && !self.stop.load(Ordering::Relaxed) =>
+                {
+                    attempt = mid.handshake();
+                }
+                Err(HandshakeError::Interrupted(_)) => {
+                    warn!("WebSocket handshake timed out");
+                    return None;
+                }
+                Err(HandshakeError::Failure(e)) => {
+                    warn!(error = %e, "WebSocket handshake failed");
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Send a reply frame; returns false when the connection should end.
+    fn send_reply<S: Read + Write>(
+        &self,
+        socket: &mut WebSocket<S>,
+        reply: &BridgeReply,
+        capabilities: &[String],
+    ) -> bool {
+        let Some(frame) = encode_reply(reply, WireProtocol::JsonLines, capabilities) else {
+            warn!("Failed to encode WebSocket reply");
+            return true;
+        };
+        let text = String::from_utf8_lossy(&frame).trim_end().to_string();
+        if let Err(e) = socket.send(Message::text(text)) {
+            warn!(error = %e, "Dropping WebSocket client after failed send");
+            return false;
+        }
+        if matches!(reply, BridgeReply::VersionError(_)) {
+            info!("Closing WebSocket client after handshake rejection");
+            let _ = socket.close(None);
+            let _ = socket.flush();
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::PublishMode;
+    use core_spine::TimeBase;
+
+    fn read_json<S: Read + Write>(socket: &mut WebSocket<S>, msg_type: &str) -> serde_json::Value {
+        loop {
+            if let Message::Text(text) = socket.read().unwrap() {
+                let value: serde_json::Value = serde_json::from_str(text.as_str()).unwrap();
+                if value["type"] == msg_type {
+                    return value;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_handshake_state_and_hello_roundtrip() {
+        let bind_addr = {
+            let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().to_string()
+        };
+        let config = BridgeConfig {
+            bind_addr: bind_addr.clone(),
+            publish_mode: PublishMode::FixedRate(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let stop = Arc::new(AtomicBool::new(false));
+        let server = {
+            let stop = stop.clone();
+            thread::spawn(move || run_ws_bridge(exchange, TimeBase::new(), config, stop, None))
+        };
+
+        let stream = loop {
+            match TcpStream::connect(&bind_addr) {
+                Ok(stream) => break stream,
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let (mut socket, response) =
+            tungstenite::client(format!("ws://{bind_addr}/"), stream).unwrap();
+        assert_eq!(response.status(), 101);
+
+        let state = read_json(&mut socket, "state");
+        assert_eq!(state["safety_state"], "normal");
+
+        let hello = serde_json::json!({
+            "type": "hello",
+            "protocol_version": {"major": 1, "minor": 0},
+            "client_id": "dashboard",
+        });
+        socket.send(Message::text(hello.to_string())).unwrap();
+        let ack = read_json(&mut socket, "hello_ack");
+        assert_eq!(ack["wire_protocol"], "json");
+
+        socket.send(Message::Ping("keepalive".into())).unwrap();
+        loop {
+            if let Message::Pong(payload) = socket.read().unwrap() {
+                assert_eq!(payload.as_ref(), b"keepalive");
+                break;
+            }
+        }
+
+        socket.close(None).unwrap();
+        stop.store(true, Ordering::Relaxed);
+        server.join().unwrap().unwrap();
+    }
+}
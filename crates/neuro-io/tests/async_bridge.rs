@@ -0,0 +1,123 @@
+//! The tokio bridge serving two agents at once over real connections.
+#![cfg(feature = "async")]
+
+use core_spine::{Clock, MockClock, StateExchange};
+use neuro_io::bridge::{BridgeConfig, PublishMode};
+use neuro_io::run_bridge_async;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::TcpStream;
+
+fn free_addr() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+async fn connect(addr: &str) -> TcpStream {
+    for _ in 0..250 {
+        if let Ok(stream) = TcpStream::connect(addr).await {
+            return stream;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("bridge never listened on {addr}");
+}
+
+/// Next line whose `type` is `msg_type`, skipping state frames and the like
+async fn read_json(reader: &mut BufReader<OwnedReadHalf>, msg_type: &str) -> serde_json::Value {
+    let read = async {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            assert_ne!(
+                reader.read_line(&mut line).await.unwrap(),
+                0,
+                "bridge closed"
+            );
+            let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+            if value["type"] == msg_type {
+                return value;
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), read)
+        .await
+        .unwrap_or_else(|_| panic!("no {msg_type} frame"))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn two_agents_connect_and_recommend() {
+    let addr = free_addr();
+    let clock = MockClock::new(1_700_000_000_000_000);
+    // Monotonic time zero reads as "no recommendation yet".
+    clock.advance(Duration::from_secs(1));
+    let exchange = Arc::new(StateExchange::new(1_000_000));
+    let stop = Arc::new(AtomicBool::new(false));
+    let config = BridgeConfig {
+        bind_addr: addr.clone(),
+        publish_mode: PublishMode::FixedRate(Duration::from_millis(20)),
+        ..BridgeConfig::default()
+    };
+    let bridge = tokio::spawn(run_bridge_async(
+        Arc::clone(&exchange),
+        clock.clone(),
+        config,
+        Arc::clone(&stop),
+        None,
+    ));
+
+    let (first, mut first_tx) = connect(&addr).await.into_split();
+    let (second, _second_tx) = connect(&addr).await.into_split();
+    let mut first = BufReader::new(first);
+    let mut second = BufReader::new(second);
+    assert_eq!(
+        read_json(&mut first, "state").await["safety_state"],
+        "normal"
+    );
+    assert_eq!(
+        read_json(&mut second, "state").await["safety_state"],
+        "normal"
+    );
+
+    let rec = serde_json::json!({
+        "type": "recommendation",
+        "protocol_version": {"major": 1, "minor": 0},
+        "sequence": 1,
+        "target_speed_rpm": 120.0,
+        "confidence": 0.9,
+        "reasoning_hash": "ab".repeat(32),
+        "issued_at_unix_us": clock.unix_us(),
+        "ttl_ms": 1_000,
+    });
+    first_tx
+        .write_all(format!("{rec}\n").as_bytes())
+        .await
+        .unwrap();
+    let mut accepted = None;
+    for _ in 0..250 {
+        accepted = exchange.get_recommendation(clock.now_us());
+        if accepted.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(accepted.unwrap().target_speed_rpm, Some(120.0));
+    // A replay is rejected on the connection that sent it.
+    first_tx
+        .write_all(format!("{rec}\n").as_bytes())
+        .await
+        .unwrap();
+    assert_eq!(read_json(&mut first, "reject").await["sequence"], 1);
+    // The other agent keeps receiving state.
+    read_json(&mut second, "state").await;
+
+    stop.store(true, Ordering::Relaxed);
+    tokio::time::timeout(Duration::from_secs(5), bridge)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,70 @@
+//! The agent clock offset gauge, observed over a real bridge connection.
+//! Lives in its own test binary because metrics are process-global.
+
+use core_spine::{Clock, MockClock, StateExchange};
+use neuro_io::bridge::{run_bridge, BridgeConfig};
+use neuro_io::metrics::AGENT_CLOCK_OFFSET_MS;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn free_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+fn connect(addr: &str) -> TcpStream {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        match TcpStream::connect(addr) {
+            Ok(stream) => return stream,
+            Err(e) if Instant::now() > deadline => panic!("bridge never listened: {e}"),
+            Err(_) => thread::sleep(Duration::from_millis(20)),
+        }
+    }
+}
+
+#[test]
+fn gauge_reports_mean_agent_offset() {
+    let addr = free_addr();
+    let clock = MockClock::new(1_700_000_000_000_000);
+    let exchange = Arc::new(StateExchange::new(1_000_000));
+    let stop = Arc::new(AtomicBool::new(false));
+    let config = BridgeConfig {
+        bind_addr: addr.clone(),
+        max_clock_skew: Duration::from_secs(10),
+        ..BridgeConfig::default()
+    };
+    let bridge = {
+        let (exchange, clock, stop) = (Arc::clone(&exchange), clock.clone(), Arc::clone(&stop));
+        thread::spawn(move || run_bridge(exchange, clock, config, stop, None))
+    };
+
+    let mut stream = connect(&addr);
+    // Two samples 2 s and 4 s ahead average to 3 s.
+    for (sequence, ahead_ms) in [(1u64, 2_000u64), (2, 4_000)] {
+        let rec = serde_json::json!({
+            "type": "recommendation",
+            "protocol_version": {"major": 1, "minor": 0},
+            "sequence": sequence,
+            "target_speed_rpm": 10.0,
+            "confidence": 0.9,
+            "reasoning_hash": "ab".repeat(32),
+            "issued_at_unix_us": clock.unix_us() + ahead_ms * 1_000,
+            "ttl_ms": 1_000,
+        });
+        writeln!(stream, "{rec}").unwrap();
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while AGENT_CLOCK_OFFSET_MS.get() != 3_000.0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(AGENT_CLOCK_OFFSET_MS.get(), 3_000.0);
+
+    stop.store(true, Ordering::Relaxed);
+    bridge.join().unwrap().unwrap();
+}
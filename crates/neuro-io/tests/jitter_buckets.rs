@@ -0,0 +1,28 @@
+//! Configured cycle jitter buckets. Lives in its own test binary because
+//! the histogram is built once per process.
+
+use neuro_io::metrics::{configure_cycle_jitter_buckets, init_metrics, CYCLE_JITTER_US};
+use prometheus::core::Collector;
+
+#[test]
+fn test_configured_buckets_replace_the_defaults() {
+    let buckets = [100.0, 1_000.0, 4_000.0, 20_000.0];
+    configure_cycle_jitter_buckets(&buckets).unwrap();
+    init_metrics();
+    CYCLE_JITTER_US.observe(3_000.0);
+
+    let family = CYCLE_JITTER_US.collect();
+    let histogram = family[0].get_metric()[0].get_histogram();
+    let bounds: Vec<f64> = histogram
+        .get_bucket()
+        .iter()
+        .map(|bucket| bucket.get_upper_bound())
+        .collect();
+    assert_eq!(bounds, buckets);
+    let counts: Vec<u64> = histogram
+        .get_bucket()
+        .iter()
+        .map(|bucket| bucket.get_cumulative_count())
+        .collect();
+    assert_eq!(counts, [0, 0, 1, 1]);
+}
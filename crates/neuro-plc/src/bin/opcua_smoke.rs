@@ -1,16 +1,23 @@
 #[cfg(feature = "opcua")]
 mod enabled {
     use core_spine::tags;
+    use neuro_plc::RuntimeConfig;
     use opcua::client::prelude::*;
     use opcua::types::{
         AttributeId, DataValue, NodeId, QualifiedName, ReadValueId, StatusCode, TimestampsToReturn,
         UAString, UserTokenPolicy, UserTokenType, Variant,
     };
     use std::env;
-
-    const TARGET_NAMESPACE: &str = "urn:neuroplc:opcua";
+    use std::path::Path;
 
     pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+        // Namespace and node names come from the spine's own config file.
+        let config = match env::var("OPCUA_SMOKE_CONFIG") {
+            Ok(path) => RuntimeConfig::from_file(Path::new(&path))?,
+            Err(_) => RuntimeConfig::default(),
+        };
+        let target_namespace = config.opcua_namespace_uri.as_str();
+        let node_prefix = config.opcua_node_prefix.as_deref().unwrap_or("");
         let endpoint = env::args()
             .nth(1)
             .or_else(|| env::var("OPCUA_ENDPOINT").ok())
@@ -183,7 +190,7 @@ mod enabled {
         let ns_index = match read_namespace_array(&mut session) {
             Ok(namespaces) => namespaces
                 .iter()
-                .position(|ns| ns == TARGET_NAMESPACE)
+                .position(|ns| ns == target_namespace)
                 .map(|idx| idx as u16)
                 .unwrap_or_else(|| {
                     if debug {
@@ -205,13 +212,11 @@ mod enabled {
             Err(status) => return Err(status.into()),
         };
 
+        let speed_node = format!("{node_prefix}{}", tags::MOTOR_SPEED_RPM.opcua_node);
         if debug {
-            eprintln!("opcua_smoke: reading {}", tags::MOTOR_SPEED_RPM.opcua_node);
+            eprintln!("opcua_smoke: reading {speed_node}");
         }
-        let value = match read_value(
-            &mut session,
-            NodeId::new(ns_index, tags::MOTOR_SPEED_RPM.opcua_node),
-        ) {
+        let value = match read_value(&mut session, NodeId::new(ns_index, speed_node.clone())) {
             Ok(value) => value,
             Err(status)
                 if status.contains(StatusCode::BadEncodingLimitsExceeded)
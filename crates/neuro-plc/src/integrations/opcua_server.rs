@@ -1,11 +1,39 @@
-use core_spine::{tags, StateExchange, TimeBase};
-use opcua::server::address_space::{AccessLevel, UserAccessLevel};
+use core_spine::tags::{self, Tag};
+use core_spine::{AgentRecommendation, ProcessSnapshot, ReasoningHash, StateExchange, TimeBase};
+use neuro_io::metrics::{HEALTH, STATE_SNAPSHOTS_SKIPPED};
+use opcua::server::address_space::{AccessLevel, AttrFnSetter, UserAccessLevel};
+use opcua::server::callbacks::{self, AttributeSetter};
 use opcua::server::config::{ServerEndpoint, ServerUserToken, ANONYMOUS_USER_TOKEN_ID};
 use opcua::server::prelude::*;
-use std::sync::{atomic::AtomicBool, Arc};
+use opcua::server::session::SessionManager;
+use opcua::sync::Mutex;
+use sha2::{Digest, Sha256};
+use std::io;
+use std::net::TcpListener;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
-use tracing::{info, warn};
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+/// Confidence attached to operator setpoints written over OPC UA
+const OPCUA_SETPOINT_CONFIDENCE: f32 = 1.0;
+
+/// Marker hashed into `reasoning_hash` for OPC UA-originated setpoints,
+/// which carry no agent reasoning
+const OPCUA_REASONING_MARKER: &[u8] = b"opcua:AgentTargetRPM";
+
+/// Shortest accepted `update_interval`; each update holds the address space
+/// write lock while it sets every node.
+pub const MIN_UPDATE_INTERVAL_MS: u64 = 10;
+
+/// Namespace the NeuroPLC nodes are registered under unless configured
+pub const DEFAULT_NAMESPACE_URI: &str = "urn:neuroplc:opcua";
+
+/// Application and product URI the server reports unless configured
+pub const DEFAULT_APPLICATION_URI: &str = "urn:neuroplc:opcua";
 
 #[derive(Clone, Debug)]
 pub struct OpcuaConfig {
@@ -18,6 +46,14 @@ pub struct OpcuaConfig {
     pub pki_dir: String,
     pub create_sample_keypair: bool,
     pub allow_write: bool,
+    /// Tags published as variables under the NeuroPLC folder
+    pub tags: Vec<Tag>,
+    /// Namespace URI the NeuroPLC nodes are registered under
+    pub namespace_uri: String,
+    pub application_uri: String,
+    /// Prepended to the node id and browse name of every tag variable and
+    /// method, e.g. `Line1.` for `Line1.MotorSpeedRPM`
+    pub node_prefix: Option<String>,
 }
 
 impl Default for OpcuaConfig {
@@ -32,17 +68,53 @@ impl Default for OpcuaConfig {
             pki_dir: "./pki-server".to_string(),
             create_sample_keypair: true,
             allow_write: false,
+            tags: tags::ALL.to_vec(),
+            namespace_uri: DEFAULT_NAMESPACE_URI.to_string(),
+            application_uri: DEFAULT_APPLICATION_URI.to_string(),
+            node_prefix: None,
         }
     }
 }
 
+#[derive(Debug, Error)]
+pub enum OpcuaError {
+    #[error("OPC UA endpoint {endpoint} is unavailable: {source}")]
+    Bind {
+        endpoint: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid OPC UA server configuration (see preceding log)")]
+    InvalidConfig,
+    #[error("secure-only OPC UA server has no certificate and key in {pki_dir}")]
+    MissingCertificate { pki_dir: String },
+    #[error("OPC UA namespace URI {uri:?} cannot be registered")]
+    Namespace { uri: String },
+}
+
+/// Start the OPC UA server and the thread that publishes node values.
+///
+/// Fails up front when the endpoint cannot be bound, the configuration is
+/// invalid, or a secure-only server has no certificate. If the server exits
+/// later, the update thread marks OPC UA as failed in [`HEALTH`] and stops
+/// rather than publishing into a dead address space. The returned handle
+/// finishes once `stop` is set or the server is gone.
 pub fn run_opcua(
     exchange: Arc<StateExchange>,
     timebase: TimeBase,
     stop: Arc<AtomicBool>,
     config: OpcuaConfig,
-) -> thread::JoinHandle<()> {
+) -> Result<thread::JoinHandle<()>, OpcuaError> {
     let (host, port) = parse_endpoint(&config.endpoint);
+    // The server binds inside its own runtime and panics on failure, so
+    // probe the port here where the error can still be reported.
+    TcpListener::bind((host.as_str(), port)).map_err(|source| OpcuaError::Bind {
+        endpoint: config.endpoint.clone(),
+        source,
+    })?;
+    let update_interval = config
+        .update_interval
+        .max(Duration::from_millis(MIN_UPDATE_INTERVAL_MS));
 
     let mut anon_tokens = Vec::new();
     if config.allow_anonymous {
@@ -86,8 +158,8 @@ pub fn run_opcua(
 
     let server_config = builder
         .application_name("NeuroPLC OPC UA")
-        .application_uri("urn:neuroplc:opcua")
-        .product_uri("urn:neuroplc:opcua")
+        .application_uri(&config.application_uri)
+        .product_uri(&config.application_uri)
         .create_sample_keypair(config.create_sample_keypair)
         .pki_dir(&config.pki_dir)
         .host_and_port(host.clone(), port)
@@ -99,179 +171,64 @@ pub fn run_opcua(
         .endpoints(endpoints)
         .discovery_urls(vec!["/".to_string()])
         .config();
+    if !server_config.is_valid() {
+        return Err(OpcuaError::InvalidConfig);
+    }
 
     let server = Server::new(server_config);
+    if config.secure_only
+        && server
+            .certificate_store()
+            .read()
+            .read_own_cert_and_pkey()
+            .is_err()
+    {
+        return Err(OpcuaError::MissingCertificate {
+            pki_dir: config.pki_dir.clone(),
+        });
+    }
     let address_space = server.address_space();
 
-    let (ns, folder_id, nodes) = {
-        let mut space = address_space.write();
-        let ns = space
-            .register_namespace("urn:neuroplc:opcua")
-            .unwrap_or_else(|_| space.default_namespace());
-        let objects = NodeId::objects_folder_id();
-        let folder_id = space
-            .add_folder("NeuroPLC", "NeuroPLC", &objects)
-            .unwrap_or_else(|_| NodeId::objects_folder_id());
-
-        let access_level = || {
-            if config.allow_write {
-                AccessLevel::CURRENT_READ | AccessLevel::CURRENT_WRITE
-            } else {
-                AccessLevel::CURRENT_READ
-            }
-        };
-        let user_access_level = || {
-            if config.allow_write {
-                UserAccessLevel::CURRENT_READ | UserAccessLevel::CURRENT_WRITE
-            } else {
-                UserAccessLevel::CURRENT_READ
-            }
-        };
-
-        let speed_id = NodeId::new(ns, tags::MOTOR_SPEED_RPM.opcua_node);
-        let temp_id = NodeId::new(ns, tags::MOTOR_TEMP_C.opcua_node);
-        let pressure_id = NodeId::new(ns, tags::PRESSURE_BAR.opcua_node);
-        let jitter_id = NodeId::new(ns, tags::CYCLE_JITTER_US.opcua_node);
-        let timestamp_id = NodeId::new(ns, tags::TIMESTAMP_US.opcua_node);
-        let safety_state_id = NodeId::new(ns, tags::SAFETY_STATE.opcua_node);
-        let agent_target_id = NodeId::new(ns, tags::AGENT_TARGET_RPM.opcua_node);
-        let agent_conf_id = NodeId::new(ns, tags::AGENT_CONFIDENCE.opcua_node);
-
-        let variables = vec![
-            VariableBuilder::new(
-                &speed_id,
-                tags::MOTOR_SPEED_RPM.opcua_node,
-                tags::MOTOR_SPEED_RPM.opcua_node,
-            )
-            .data_type(DataTypeId::Double)
-            .value(0.0)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-            VariableBuilder::new(
-                &temp_id,
-                tags::MOTOR_TEMP_C.opcua_node,
-                tags::MOTOR_TEMP_C.opcua_node,
-            )
-            .data_type(DataTypeId::Double)
-            .value(0.0)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-            VariableBuilder::new(
-                &pressure_id,
-                tags::PRESSURE_BAR.opcua_node,
-                tags::PRESSURE_BAR.opcua_node,
-            )
-            .data_type(DataTypeId::Double)
-            .value(0.0)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-            VariableBuilder::new(
-                &jitter_id,
-                tags::CYCLE_JITTER_US.opcua_node,
-                tags::CYCLE_JITTER_US.opcua_node,
-            )
-            .data_type(DataTypeId::UInt32)
-            .value(0u32)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-            VariableBuilder::new(
-                &timestamp_id,
-                tags::TIMESTAMP_US.opcua_node,
-                tags::TIMESTAMP_US.opcua_node,
-            )
-            .data_type(DataTypeId::UInt64)
-            .value(0u64)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-            VariableBuilder::new(
-                &safety_state_id,
-                tags::SAFETY_STATE.opcua_node,
-                tags::SAFETY_STATE.opcua_node,
-            )
-            .data_type(DataTypeId::UInt32)
-            .value(0u32)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-            VariableBuilder::new(
-                &agent_target_id,
-                tags::AGENT_TARGET_RPM.opcua_node,
-                tags::AGENT_TARGET_RPM.opcua_node,
-            )
-            .data_type(DataTypeId::Double)
-            .value(0.0)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-            VariableBuilder::new(
-                &agent_conf_id,
-                tags::AGENT_CONFIDENCE.opcua_node,
-                tags::AGENT_CONFIDENCE.opcua_node,
-            )
-            .data_type(DataTypeId::Double)
-            .value(0.0)
-            .access_level(access_level())
-            .user_access_level(user_access_level())
-            .build(),
-        ];
-
-        space.add_variables(variables, &folder_id);
-
-        (
-            ns,
-            folder_id,
-            NodeIds {
-                speed_id,
-                temp_id,
-                pressure_id,
-                jitter_id,
-                timestamp_id,
-                safety_state_id,
-                agent_target_id,
-                agent_conf_id,
-            },
-        )
-    };
+    let (ns, folder_id, nodes) =
+        populate_address_space(&mut address_space.write(), &exchange, timebase, &config)?;
 
-    info!("OPC UA server namespace {} folder {:?}", ns, folder_id);
+    info!(
+        "OPC UA server namespace {} ({}) folder {:?}",
+        ns, config.namespace_uri, folder_id
+    );
 
     let server = Arc::new(opcua::sync::RwLock::new(server));
     let server_for_run = Arc::clone(&server);
     let server_for_updates = Arc::clone(&server);
     let address_for_updates = address_space.clone();
+    let server_exited = Arc::new(AtomicBool::new(false));
+    let exited = Arc::clone(&server_exited);
 
     let update_handle = thread::spawn(move || {
-        while !stop.load(std::sync::atomic::Ordering::Relaxed) {
-            let snapshot = exchange.read_state();
+        let skipped = STATE_SNAPSHOTS_SKIPPED.with_label_values(&["opcua"]);
+        let mut last_sequence = 0;
+        while !stop.load(Ordering::Relaxed) {
+            if exited.load(Ordering::Relaxed) {
+                error!("OPC UA server exited; no longer publishing node values");
+                HEALTH.set_opcua_running(false);
+                return;
+            }
+            let state = exchange.read_state_sequenced();
+            skipped.inc_by(state.skipped_since(last_sequence));
+            last_sequence = state.sequence;
+            let snapshot = state.value;
             let rec = exchange.get_recommendation(timebase.now_us());
             let now = DateTime::now();
 
             let mut space = address_for_updates.write();
-            space.set_variable_value(&nodes.speed_id, snapshot.motor_speed_rpm, &now, &now);
-            space.set_variable_value(&nodes.temp_id, snapshot.motor_temp_c, &now, &now);
-            space.set_variable_value(&nodes.pressure_id, snapshot.pressure_bar, &now, &now);
-            space.set_variable_value(&nodes.jitter_id, snapshot.cycle_jitter_us, &now, &now);
-            space.set_variable_value(&nodes.timestamp_id, snapshot.timestamp_us, &now, &now);
-            space.set_variable_value(
-                &nodes.safety_state_id,
-                snapshot.safety_state.as_u8() as u32,
-                &now,
-                &now,
-            );
-
-            if let Some(r) = rec {
-                if let Some(target) = r.target_speed_rpm {
-                    space.set_variable_value(&nodes.agent_target_id, target, &now, &now);
+            for node in &nodes {
+                if let Some(value) = tag_value(&node.tag, &snapshot, rec.as_ref()) {
+                    space.set_variable_value_by_ref(&node.node_id, value, &now, &now);
                 }
-                space.set_variable_value(&nodes.agent_conf_id, r.confidence as f64, &now, &now);
             }
+            drop(space);
 
-            thread::sleep(config.update_interval);
+            thread::sleep(update_interval);
         }
 
         warn!("OPC UA server stopping");
@@ -280,21 +237,203 @@ pub fn run_opcua(
     });
 
     thread::spawn(move || {
-        Server::run_server(server_for_run);
+        if panic::catch_unwind(AssertUnwindSafe(|| Server::run_server(server_for_run))).is_err() {
+            error!("OPC UA server thread panicked");
+        }
+        server_exited.store(true, Ordering::Relaxed);
+    });
+
+    HEALTH.set_opcua_running(true);
+    Ok(update_handle)
+}
+
+/// Register the configured namespace and add the NeuroPLC folder, its tag
+/// variables and `Methods/EmergencyStop`. Returns the namespace index, the
+/// folder and the published variables.
+fn populate_address_space(
+    space: &mut AddressSpace,
+    exchange: &Arc<StateExchange>,
+    timebase: TimeBase,
+    config: &OpcuaConfig,
+) -> Result<(u16, NodeId, Vec<TagNode>), OpcuaError> {
+    let ns = space
+        .register_namespace(&config.namespace_uri)
+        .map_err(|()| OpcuaError::Namespace {
+            uri: config.namespace_uri.clone(),
+        })?;
+    let objects = NodeId::objects_folder_id();
+    let folder_id = space
+        .add_folder("NeuroPLC", "NeuroPLC", &objects)
+        .unwrap_or_else(|_| NodeId::objects_folder_id());
+
+    let setpoint_exchange = Arc::clone(exchange);
+    let allow_write = config.allow_write;
+    let setpoint_setter = AttrFnSetter::new_boxed(move |_node_id, _attr, _range, value| {
+        submit_opcua_setpoint(&setpoint_exchange, &timebase, allow_write, value)
+    });
+    let prefix = config.node_prefix.as_deref().unwrap_or("");
+    let nodes = add_tag_variables(space, ns, &folder_id, prefix, &config.tags, setpoint_setter);
+
+    let methods_id = space
+        .add_folder("Methods", "Methods", &folder_id)
+        .unwrap_or_else(|_| folder_id.clone());
+    let estop_name = format!("{prefix}EmergencyStop");
+    let estop_id = NodeId::new(ns, estop_name.clone());
+    MethodBuilder::new(&estop_id, estop_name.as_str(), estop_name.as_str())
+        .component_of(methods_id)
+        .callback(Box::new(EmergencyStopMethod {
+            exchange: Arc::clone(exchange),
+            require_user: config.secure_only,
+        }))
+        .insert(space);
+
+    Ok((ns, folder_id, nodes))
+}
+
+/// `Methods/EmergencyStop`: trips the safety supervisor on the next control
+/// cycle; the SafetyState node picks up the result on the next update.
+struct EmergencyStopMethod {
+    exchange: Arc<StateExchange>,
+    /// Refuse anonymous sessions (set when the server is `secure_only`)
+    require_user: bool,
+}
+
+impl callbacks::Method for EmergencyStopMethod {
+    fn call(
+        &mut self,
+        session_id: &NodeId,
+        session_manager: Arc<opcua::sync::RwLock<SessionManager>>,
+        _request: &CallMethodRequest,
+    ) -> Result<CallMethodResult, StatusCode> {
+        if self.require_user {
+            let anonymous = session_manager
+                .read()
+                .find_session_by_id(session_id)
+                .map(|session| session.read().client_user_id().is_null())
+                .unwrap_or(true);
+            if anonymous {
+                warn!("Rejected anonymous OPC UA EmergencyStop call");
+                return Err(StatusCode::BadUserAccessDenied);
+            }
+        }
+
+        warn!("Emergency stop commanded via OPC UA");
+        self.exchange.request_emergency_stop();
+        Ok(CallMethodResult {
+            status_code: StatusCode::Good,
+            input_argument_results: None,
+            input_argument_diagnostic_infos: None,
+            output_arguments: None,
+        })
+    }
+}
+
+/// Turn a client write to AgentTargetRPM into an `AgentRecommendation`.
+/// The control loop validates it through the safety firewall and expires it
+/// like any bridge recommendation.
+fn submit_opcua_setpoint(
+    exchange: &StateExchange,
+    timebase: &TimeBase,
+    allow_write: bool,
+    value: DataValue,
+) -> Result<(), StatusCode> {
+    if !allow_write {
+        warn!("Rejected OPC UA write to AgentTargetRPM (writes disabled)");
+        return Err(StatusCode::BadUserAccessDenied);
+    }
+    let target = match value.value {
+        Some(Variant::Double(v)) => v,
+        _ => return Err(StatusCode::BadTypeMismatch),
+    };
+
+    info!(target_rpm = target, "OPC UA setpoint received");
+    exchange.submit_recommendation(AgentRecommendation {
+        timestamp_us: timebase.now_us(),
+        target_speed_rpm: Some(target),
+        ramp_rate_rpm_per_s: None,
+        confidence: OPCUA_SETPOINT_CONFIDENCE,
+        reasoning_hash: ReasoningHash::Bytes32(Sha256::digest(OPCUA_REASONING_MARKER).into()),
     });
+    Ok(())
+}
 
-    update_handle
+/// A published variable and the tag it mirrors
+struct TagNode {
+    tag: Tag,
+    node_id: NodeId,
 }
 
-struct NodeIds {
-    speed_id: NodeId,
-    temp_id: NodeId,
-    pressure_id: NodeId,
-    jitter_id: NodeId,
-    timestamp_id: NodeId,
-    safety_state_id: NodeId,
-    agent_target_id: NodeId,
-    agent_conf_id: NodeId,
+/// Add one variable per tag under `folder_id`, in the order given, named
+/// `prefix` followed by the tag's OPC UA node name.
+///
+/// Process values are published by the spine and are always read-only.
+/// Only AgentTargetRPM accepts writes, which are routed to the control loop
+/// as recommendations; its setter enforces `allow_write` so denied writes
+/// report BadUserAccessDenied rather than BadNotWritable.
+fn add_tag_variables(
+    space: &mut AddressSpace,
+    ns: u16,
+    folder_id: &NodeId,
+    prefix: &str,
+    tags: &[Tag],
+    setpoint_setter: Arc<Mutex<dyn AttributeSetter + Send>>,
+) -> Vec<TagNode> {
+    let mut nodes = Vec::with_capacity(tags.len());
+    let mut variables = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let name = format!("{prefix}{}", tag.opcua_node);
+        let node_id = NodeId::new(ns, name.clone());
+        let (data_type, initial) = tag_data_type(tag);
+        let builder = VariableBuilder::new(&node_id, name.as_str(), name.as_str())
+            .data_type(data_type)
+            .value(initial);
+        let builder = if tag.key == tags::AGENT_TARGET_RPM.key {
+            builder
+                .access_level(AccessLevel::CURRENT_READ | AccessLevel::CURRENT_WRITE)
+                .user_access_level(UserAccessLevel::CURRENT_READ | UserAccessLevel::CURRENT_WRITE)
+                .value_setter(Arc::clone(&setpoint_setter))
+        } else {
+            builder
+                .access_level(AccessLevel::CURRENT_READ)
+                .user_access_level(UserAccessLevel::CURRENT_READ)
+        };
+        variables.push(builder.build());
+        nodes.push(TagNode { tag: *tag, node_id });
+    }
+    space.add_variables(variables, folder_id);
+    nodes
+}
+
+/// OPC UA data type and initial value of a tag's variable
+fn tag_data_type(tag: &Tag) -> (DataTypeId, Variant) {
+    match tag.key {
+        k if k == tags::CYCLE_JITTER_US.key || k == tags::SAFETY_STATE.key => {
+            (DataTypeId::UInt32, Variant::UInt32(0))
+        }
+        k if k == tags::TIMESTAMP_US.key => (DataTypeId::UInt64, Variant::UInt64(0)),
+        _ => (DataTypeId::Double, Variant::Double(0.0)),
+    }
+}
+
+/// Current value of a tag; agent tags keep their last value while no
+/// recommendation is active.
+fn tag_value(
+    tag: &Tag,
+    snapshot: &ProcessSnapshot,
+    rec: Option<&AgentRecommendation>,
+) -> Option<Variant> {
+    let value = match tag.key {
+        k if k == tags::MOTOR_SPEED_RPM.key => snapshot.motor_speed_rpm.into(),
+        k if k == tags::MOTOR_TEMP_C.key => snapshot.motor_temp_c.into(),
+        k if k == tags::PRESSURE_BAR.key => snapshot.pressure_bar.into(),
+        k if k == tags::CYCLE_JITTER_US.key => snapshot.cycle_jitter_us.into(),
+        k if k == tags::TIMESTAMP_US.key => snapshot.timestamp_us.into(),
+        k if k == tags::SAFETY_STATE.key => (snapshot.safety_state.as_u8() as u32).into(),
+        k if k == tags::AGENT_TARGET_RPM.key => rec?.target_speed_rpm?.into(),
+        k if k == tags::AGENT_CONFIDENCE.key => (rec?.confidence as f64).into(),
+        _ => return None,
+    };
+    Some(value)
 }
 
 fn parse_endpoint(endpoint: &str) -> (String, u16) {
@@ -308,3 +447,166 @@ fn parse_endpoint(endpoint: &str) -> (String, u16) {
         .unwrap_or(4840);
     (host, port)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setpoint_rejected_when_writes_disabled() {
+        let exchange = StateExchange::new(1_000_000);
+        let timebase = TimeBase::new();
+
+        let result =
+            submit_opcua_setpoint(&exchange, &timebase, false, Variant::Double(500.0).into());
+
+        assert_eq!(result, Err(StatusCode::BadUserAccessDenied));
+        assert!(exchange.get_recommendation(timebase.now_us()).is_none());
+    }
+
+    #[test]
+    fn test_setpoint_submitted_as_recommendation() {
+        let exchange = StateExchange::new(1_000_000);
+        let timebase = TimeBase::new();
+        // A zero timestamp reads as "no recommendation"
+        std::thread::sleep(Duration::from_millis(1));
+
+        submit_opcua_setpoint(&exchange, &timebase, true, Variant::Double(500.0).into()).unwrap();
+
+        let rec = exchange.get_recommendation(timebase.now_us()).unwrap();
+        assert_eq!(rec.target_speed_rpm, Some(500.0));
+        assert_eq!(rec.confidence, OPCUA_SETPOINT_CONFIDENCE);
+        assert_ne!(rec.reasoning_hash, [0u8; 32]);
+    }
+
+    #[test]
+    fn test_setpoint_rejects_wrong_type() {
+        let exchange = StateExchange::new(1_000_000);
+        let timebase = TimeBase::new();
+
+        let result =
+            submit_opcua_setpoint(&exchange, &timebase, true, Variant::from("fast").into());
+
+        assert_eq!(result, Err(StatusCode::BadTypeMismatch));
+    }
+
+    #[test]
+    fn test_endpoint_in_use_is_a_start_error() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let pki = tempfile::tempdir().unwrap();
+        let config = OpcuaConfig {
+            endpoint: format!("opc.tcp://127.0.0.1:{port}"),
+            pki_dir: pki.path().display().to_string(),
+            create_sample_keypair: false,
+            ..OpcuaConfig::default()
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let result = run_opcua(
+            Arc::new(StateExchange::new(1_000_000)),
+            TimeBase::new(),
+            stop,
+            config,
+        );
+
+        match result {
+            Err(OpcuaError::Bind { endpoint, source }) => {
+                assert!(endpoint.ends_with(&port.to_string()));
+                assert_eq!(source.kind(), io::ErrorKind::AddrInUse);
+            }
+            Err(other) => panic!("expected a bind error, got {other}"),
+            Ok(_) => panic!("server started on a port already in use"),
+        }
+    }
+
+    #[test]
+    fn test_only_configured_tags_are_exposed() {
+        let mut space = AddressSpace::new();
+        let ns = space.register_namespace(DEFAULT_NAMESPACE_URI).unwrap();
+        let folder_id = space
+            .add_folder("NeuroPLC", "NeuroPLC", &NodeId::objects_folder_id())
+            .unwrap();
+        let subset = [tags::SAFETY_STATE, tags::MOTOR_SPEED_RPM];
+        let setter = AttrFnSetter::new_boxed(|_node_id, _attr, _range, _value| Ok(()));
+
+        let nodes = add_tag_variables(&mut space, ns, &folder_id, "", &subset, setter);
+
+        let published: Vec<_> = nodes.iter().map(|node| node.tag.key).collect();
+        assert_eq!(published, ["safety_state", "motor_speed_rpm"]);
+        for tag in tags::ALL {
+            let exposed = space
+                .find_variable(NodeId::new(ns, tag.opcua_node))
+                .is_some();
+            assert_eq!(
+                exposed,
+                subset.iter().any(|t| t.key == tag.key),
+                "{}",
+                tag.key
+            );
+        }
+        let organized =
+            space.find_references(&folder_id, Some((ReferenceTypeId::Organizes, false)));
+        assert_eq!(organized.map(|refs| refs.len()), Some(subset.len()));
+    }
+
+    #[test]
+    fn test_custom_namespace_is_registered_and_discoverable() {
+        let mut space = AddressSpace::new();
+        let config = OpcuaConfig {
+            namespace_uri: "http://example.com/plant/line1".to_string(),
+            node_prefix: Some("Line1.".to_string()),
+            ..OpcuaConfig::default()
+        };
+
+        let (ns, _, nodes) = populate_address_space(
+            &mut space,
+            &Arc::new(StateExchange::new(1_000_000)),
+            TimeBase::new(),
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(space.namespace_index(&config.namespace_uri), Some(ns));
+        assert_eq!(space.namespace_index(DEFAULT_NAMESPACE_URI), None);
+        // Clients resolve the index through Server.NamespaceArray.
+        let namespaces = space
+            .find_variable(VariableId::Server_NamespaceArray)
+            .unwrap()
+            .value(
+                TimestampsToReturn::Neither,
+                NumericRange::None,
+                &QualifiedName::null(),
+                0.0,
+            )
+            .value;
+        let Some(Variant::Array(namespaces)) = namespaces else {
+            panic!("NamespaceArray is not an array: {namespaces:?}");
+        };
+        assert_eq!(
+            namespaces.values.get(ns as usize),
+            Some(&Variant::from(config.namespace_uri.as_str()))
+        );
+        assert_eq!(nodes.len(), tags::ALL.len());
+        let speed = NodeId::new(ns, "Line1.MotorSpeedRPM");
+        assert!(space.find_variable(speed).is_some());
+        assert!(space
+            .find_variable(NodeId::new(ns, tags::MOTOR_SPEED_RPM.opcua_node))
+            .is_none());
+        assert!(space
+            .find_node(&NodeId::new(ns, "Line1.EmergencyStop"))
+            .is_some());
+
+        let empty = OpcuaConfig {
+            namespace_uri: String::new(),
+            ..OpcuaConfig::default()
+        };
+        let result = populate_address_space(
+            &mut AddressSpace::new(),
+            &Arc::new(StateExchange::new(1_000_000)),
+            TimeBase::new(),
+            &empty,
+        );
+        assert!(matches!(result, Err(OpcuaError::Namespace { .. })));
+    }
+}
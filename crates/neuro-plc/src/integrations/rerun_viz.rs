@@ -1,11 +1,18 @@
-use core_spine::{tags, StateExchange, TimeBase};
-use rerun::{RecordingStream, RecordingStreamBuilder, Scalar};
-use std::path::PathBuf;
+use core_spine::safety_supervisor::SafetyState;
+use core_spine::{tags, ExecutionStats, StateExchange, TimeBase};
+use neuro_io::audit::{AuditEventType, AuditLogger, AuditRecord};
+use rerun::{RecordingStream, RecordingStreamBuilder, Scalar, TextLog, TextLogLevel};
+use std::path::{Path, PathBuf};
 use std::sync::{atomic::AtomicBool, Arc};
 use std::thread;
 use std::time::Duration;
+use thiserror::Error;
 use tracing::{info, warn};
 
+/// Text markers for safety transitions and rejections
+const EVENTS_PATH: &str = "system/events";
+const SAFETY_REJECTIONS_PATH: &str = "system/safety_rejections";
+
 #[derive(Clone, Debug)]
 pub struct RerunConfig {
     pub update_interval: Duration,
@@ -21,18 +28,29 @@ impl Default for RerunConfig {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum RerunReplayError {
+    #[error("Failed to read audit log: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Rerun init failed: {0}")]
+    Init(String),
+}
+
+fn recording_stream(save_path: Option<PathBuf>) -> Result<RecordingStream, String> {
+    let rec = match save_path {
+        Some(path) => RecordingStreamBuilder::new("NeuroPLC").save(path),
+        None => RecordingStreamBuilder::new("NeuroPLC").spawn(),
+    };
+    rec.map_err(|err| err.to_string())
+}
+
 pub fn run_rerun(
     exchange: Arc<StateExchange>,
     timebase: TimeBase,
     stop: Arc<AtomicBool>,
     config: RerunConfig,
 ) -> Option<thread::JoinHandle<()>> {
-    let rec = match config.save_path {
-        Some(path) => RecordingStreamBuilder::new("NeuroPLC").save(path),
-        None => RecordingStreamBuilder::new("NeuroPLC").spawn(),
-    };
-
-    let rec = match rec {
+    let rec = match recording_stream(config.save_path) {
         Ok(r) => r,
         Err(err) => {
             warn!("Rerun init failed: {err}");
@@ -42,17 +60,118 @@ pub fn run_rerun(
 
     info!("Rerun viewer spawned");
 
-    Some(thread::spawn(move || loop {
-        if stop.load(std::sync::atomic::Ordering::Relaxed) {
-            break;
-        }
+    Some(thread::spawn(move || {
+        let mut incidents = IncidentTracker::default();
+        loop {
+            if stop.load(std::sync::atomic::Ordering::Relaxed) {
+                break;
+            }
 
-        log_snapshot(&rec, &exchange, &timebase);
-        thread::sleep(config.update_interval);
+            log_snapshot(&rec, &exchange, &timebase, &mut incidents);
+            thread::sleep(config.update_interval);
+        }
     }))
 }
 
-fn log_snapshot(rec: &RecordingStream, exchange: &StateExchange, timebase: &TimeBase) {
+/// Log a recorded audit trail to Rerun on its original timeline, without a
+/// live `StateExchange`. Returns the number of records logged.
+pub fn run_rerun_replay(audit_path: &Path, config: RerunConfig) -> Result<usize, RerunReplayError> {
+    let records = AuditLogger::read_timeline(audit_path)?;
+    let rec = recording_stream(config.save_path).map_err(RerunReplayError::Init)?;
+    for record in &records {
+        log_audit_record(&rec, record);
+    }
+    info!(records = records.len(), path = %audit_path.display(), "Audit replay logged to Rerun");
+    Ok(records.len())
+}
+
+fn log_audit_record(rec: &RecordingStream, record: &AuditRecord) {
+    let entry = &record.entry;
+    rec.set_time_seconds("sim_time", entry.timestamp_us as f64 / 1_000_000.0);
+    let detail = |key: &str| entry.details.get(key).and_then(serde_json::Value::as_f64);
+    let log_scalar = |path: &str, value: Option<f64>| {
+        if let Some(value) = value {
+            let _ = rec.log(path, &Scalar::new(value));
+        }
+    };
+
+    match &entry.event_type {
+        AuditEventType::RecommendationReceived => {
+            log_scalar(tags::AGENT_TARGET_RPM.rerun_path, detail("target_speed"));
+            log_scalar(tags::AGENT_CONFIDENCE.rerun_path, detail("confidence"));
+        }
+        AuditEventType::RecommendationApplied => {
+            log_scalar(tags::AGENT_TARGET_RPM.rerun_path, detail("target_speed"));
+            log_scalar(tags::MOTOR_SPEED_RPM.rerun_path, detail("previous_speed"));
+        }
+        AuditEventType::SafetyRejection => {
+            log_scalar(tags::MOTOR_SPEED_RPM.rerun_path, detail("current_speed"));
+            log_scalar(tags::MOTOR_TEMP_C.rerun_path, detail("current_temp"));
+            let violation = entry
+                .details
+                .get("violation_type")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("unknown");
+            let _ = rec.log(
+                EVENTS_PATH,
+                &TextLog::new(format!(
+                    "Rejected {:?} rpm: {violation}",
+                    detail("requested_speed")
+                ))
+                .with_level(TextLogLevel::WARN),
+            );
+        }
+        other => {
+            let level = match other {
+                AuditEventType::EmergencyStop
+                | AuditEventType::WatchdogTimeout
+                | AuditEventType::HalFallback
+                | AuditEventType::HalFailover => TextLogLevel::ERROR,
+                _ => TextLogLevel::INFO,
+            };
+            let _ = rec.log(
+                EVENTS_PATH,
+                &TextLog::new(format!("{other:?}: {}", entry.details)).with_level(level),
+            );
+        }
+    }
+}
+
+/// Turns changes in the published stats into incident markers.
+#[derive(Debug, Default)]
+struct IncidentTracker {
+    safety_state: SafetyState,
+    safety_rejections: u64,
+}
+
+impl IncidentTracker {
+    fn observe(&mut self, stats: &ExecutionStats) -> Vec<String> {
+        let mut events = Vec::new();
+        if stats.safety_rejections > self.safety_rejections {
+            events.push(format!(
+                "{} recommendation(s) rejected by the safety supervisor",
+                stats.safety_rejections - self.safety_rejections
+            ));
+        }
+        if stats.safety_state != self.safety_state {
+            events.push(format!(
+                "Safety state {} -> {}",
+                self.safety_state.as_str(),
+                stats.safety_state.as_str()
+            ));
+        }
+        self.safety_rejections = stats.safety_rejections;
+        self.safety_state = stats.safety_state;
+        events
+    }
+}
+
+fn log_snapshot(
+    rec: &RecordingStream,
+    exchange: &StateExchange,
+    timebase: &TimeBase,
+    incidents: &mut IncidentTracker,
+) {
     let snapshot = exchange.read_state();
     let time_s = snapshot.timestamp_us as f64 / 1_000_000.0;
     rec.set_time_seconds("sim_time", time_s);
@@ -78,6 +197,18 @@ fn log_snapshot(rec: &RecordingStream, exchange: &StateExchange, timebase: &Time
         &Scalar::new(snapshot.safety_state.as_u8() as f64),
     );
 
+    let stats = exchange.execution_stats();
+    let _ = rec.log(
+        SAFETY_REJECTIONS_PATH,
+        &Scalar::new(stats.safety_rejections as f64),
+    );
+    for event in incidents.observe(&stats) {
+        let _ = rec.log(
+            EVENTS_PATH,
+            &TextLog::new(event).with_level(TextLogLevel::WARN),
+        );
+    }
+
     if let Some(rec_msg) = exchange.get_recommendation(timebase.now_us()) {
         if let Some(target) = rec_msg.target_speed_rpm {
             let _ = rec.log(tags::AGENT_TARGET_RPM.rerun_path, &Scalar::new(target));
@@ -88,3 +219,24 @@ fn log_snapshot(rec: &RecordingStream, exchange: &StateExchange, timebase: &Time
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incidents_reported_once_per_change() {
+        let mut tracker = IncidentTracker::default();
+        let mut stats = ExecutionStats::default();
+        assert!(tracker.observe(&stats).is_empty());
+
+        stats.safety_rejections = 2;
+        stats.safety_state = SafetyState::Trip;
+        let events = tracker.observe(&stats);
+        assert_eq!(events.len(), 2);
+        assert!(events[0].starts_with("2 recommendation(s) rejected"));
+        assert_eq!(events[1], "Safety state normal -> trip");
+
+        assert!(tracker.observe(&stats).is_empty());
+    }
+}
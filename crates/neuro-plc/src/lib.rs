@@ -1,5 +1,8 @@
-mod infra;
 mod integrations;
 mod runtime;
 
-pub use runtime::{run, run_from_args, RuntimeConfig};
+pub use core_spine::{AgentRecommendation, ExecutionStats, ProcessSnapshot};
+pub use runtime::{
+    check_config, run, run_from_args, run_with_hal, ConfigFileError, ConfigReport, HalConstructor,
+    HalError, HalRegistry, NeuroPlc, NeuroPlcBuilder, RuntimeConfig, SelfTestReport, StartError,
+};
@@ -1,3 +1,5 @@
-fn main() {
-    neuro_plc::run_from_args();
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    neuro_plc::run_from_args()
 }
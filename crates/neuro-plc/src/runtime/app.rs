@@ -1,287 +1,232 @@
-use crate::infra::audit::{hash_bytes, hash_str, AuditEventType, AuditLogger};
 #[cfg(feature = "opcua")]
-use crate::integrations::opcua_server::{run_opcua, OpcuaConfig};
+use crate::integrations::opcua_server::{OpcuaConfig, MIN_UPDATE_INTERVAL_MS};
 #[cfg(feature = "rerun")]
-use crate::integrations::rerun_viz::{run_rerun, RerunConfig};
+use crate::integrations::rerun_viz::{run_rerun_replay, RerunConfig};
+use crate::runtime::check;
 use crate::runtime::config::RuntimeConfig;
+use crate::runtime::embed::{NeuroPlc, StartError};
+use crate::runtime::hal::{HalError, HalRegistry};
 use crate::runtime::logging::init_tracing;
-use crate::runtime::telemetry;
-use core_spine::{
-    ControlConfig, CycleStats, IronThread, MachineIO, SimulatedMotor, StateExchange, TimeBase,
+use core_spine::{ControlConfig, FailoverIO, MachineIO, SimulatedMotor, TimeBase};
+use neuro_io::audit::{hash_str, AuditEventType, AuditLogger};
+use neuro_io::auth::{load_ed25519_public_key, AuthAlgorithm, AuthConfig};
+use neuro_io::bridge::{
+    BridgeConfig, DuplicateReasoning, PublishMode, SequenceFloors, WireProtocol, SCOPED_ACTIONS,
 };
-use neuro_io::auth::AuthConfig;
-use neuro_io::bridge::{run_bridge, BridgeConfig, WireProtocol};
-use neuro_io::hal_modbus::ModbusMotor;
-use neuro_io::tls::TlsConfig;
-use std::path::PathBuf;
-use std::sync::{atomic::AtomicBool, Arc};
+use neuro_io::tls::{TlsConfig, TlsVersion};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
-use tracing::{info, warn};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
-enum NeuroPlcMotor {
-    Simulated(SimulatedMotor),
-    Modbus(ModbusMotor),
-}
+/// Entries queued for the audit writer thread before new ones are dropped
+const AUDIT_QUEUE_CAPACITY: usize = 4096;
 
-impl MachineIO for NeuroPlcMotor {
-    fn step(&mut self, dt_s: f64) {
-        match self {
-            Self::Simulated(m) => m.step(dt_s),
-            Self::Modbus(m) => m.step(dt_s),
+/// Command line entry point; returns the process exit code
+pub fn run_from_args() -> ExitCode {
+    let config = match RuntimeConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
         }
+    };
+    if config.show_help {
+        RuntimeConfig::print_help();
+        return ExitCode::SUCCESS;
     }
-
-    fn read_speed(&self) -> f64 {
-        match self {
-            Self::Simulated(m) => m.read_speed(),
-            Self::Modbus(m) => m.read_speed(),
-        }
+    if let Some(path) = &config.verify_audit {
+        return ExitCode::from(verify_audit_log(path) as u8);
     }
-
-    fn read_temperature(&self) -> f64 {
-        match self {
-            Self::Simulated(m) => m.read_temperature(),
-            Self::Modbus(m) => m.read_temperature(),
-        }
+    if config.check_config {
+        let report = check::check_config(&config, &HalRegistry::with_builtins());
+        return ExitCode::from(report.print() as u8);
     }
-
-    fn read_pressure(&self) -> f64 {
-        match self {
-            Self::Simulated(m) => m.read_pressure(),
-            Self::Modbus(m) => m.read_pressure(),
+    #[cfg(feature = "rerun")]
+    if let Some(path) = &config.rerun_replay {
+        let rerun_config = RerunConfig {
+            save_path: config.rerun_save_path.clone().map(PathBuf::from),
+            ..Default::default()
+        };
+        if let Err(e) = run_rerun_replay(path, rerun_config) {
+            eprintln!("{}: {}", path.display(), e);
+            return ExitCode::FAILURE;
         }
+        return ExitCode::SUCCESS;
     }
-
-    fn write_speed(&mut self, rpm: f64) {
-        match self {
-            Self::Simulated(m) => m.write_speed(rpm),
-            Self::Modbus(m) => m.write_speed(rpm),
+    match run(config) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!(error = %e, "Failed to start NeuroPLC");
+            ExitCode::FAILURE
         }
     }
+}
 
-    fn cycle_stats(&self) -> CycleStats {
-        match self {
-            Self::Simulated(m) => m.cycle_stats(),
-            Self::Modbus(m) => m.cycle_stats(),
+/// `--verify-audit`: check the chain and report on stdout; returns the exit code
+fn verify_audit_log(path: &Path) -> i32 {
+    match AuditLogger::verify(path) {
+        Ok(report) => {
+            println!(
+                "{}: OK ({} records, last hash {})",
+                path.display(),
+                report.records,
+                report.last_hash
+            );
+            0
         }
-    }
-
-    fn is_healthy(&self) -> bool {
-        match self {
-            Self::Simulated(m) => m.is_healthy(),
-            Self::Modbus(m) => m.is_healthy(),
+        Err(e) => {
+            eprintln!("{}: FAILED: {}", path.display(), e);
+            1
         }
     }
 }
 
-pub fn run_from_args() {
-    let config = RuntimeConfig::from_env();
-    if config.show_help {
-        RuntimeConfig::print_help();
-        return;
-    }
-    run(config);
+/// Start the controller and run it until `--run-seconds` elapse or a signal
+/// stops it
+pub fn run(config: RuntimeConfig) -> Result<(), StartError> {
+    run_with_hal(config, HalRegistry::with_builtins())
 }
 
-pub fn run(config: RuntimeConfig) {
+/// Like [`run`], with HAL backends resolved from `registry`
+pub fn run_with_hal(config: RuntimeConfig, registry: HalRegistry) -> Result<(), StartError> {
     // Initialize tracing
     init_tracing(config.json_logs);
-
-    // Initialize metrics
-    telemetry::init();
-
-    // Start metrics server if enabled
-    let metrics_enabled = config.metrics_addr.is_some();
-    let _metrics_handle = telemetry::start_metrics_server(&config.metrics_addr);
-
-    let control_config = ControlConfig::default();
-    let exchange = Arc::new(StateExchange::new(
-        control_config.recommendation_timeout.as_micros() as u64,
-    ));
-    let timebase = TimeBase::new();
-
-    // Initialize audit logger if enabled
-    let audit_logger = init_audit_logger(config.audit_path.as_ref());
-
-    // Log startup
-    if let Some(ref logger) = audit_logger {
-        let config_hash = hash_runtime_config(&config);
-        let binary_hash = current_binary_hash();
-        let cortex_hash = cortex_manifest_hash();
-        let _ = logger.log_event(
-            timebase.now_us(),
-            timebase.unix_us(),
-            AuditEventType::SystemStart,
-            serde_json::json!({
-                "version": env!("CARGO_PKG_VERSION"),
-                "bridge_enabled": config.bridge_enabled,
-                "metrics_enabled": metrics_enabled,
-                "config_hash": config_hash,
-                "binary_sha256": binary_hash,
-                "cortex_manifest_sha256": cortex_hash,
-            }),
-        );
+    if !config.unknown_args.is_empty() {
+        warn!(args = ?config.unknown_args, "Ignoring unknown command line arguments");
     }
 
     let stop = Arc::new(AtomicBool::new(false));
-    let metrics_updater = if metrics_enabled {
-        Some(telemetry::start_metrics_updater(
-            Arc::clone(&exchange),
-            Arc::clone(&stop),
-        ))
-    } else {
-        None
-    };
-
-    let exchange_iron = Arc::clone(&exchange);
-    let stop_iron = Arc::clone(&stop);
-    let timebase_iron = timebase;
-    let control_config_iron = control_config.clone();
-    let modbus_addr = config.modbus_addr.clone();
+    install_signal_handlers(&stop);
 
-    info!(
-        cycle_time_ms = control_config.cycle_time.as_millis(),
-        max_speed_rpm = control_config.safety_limits.max_speed_rpm,
-        max_temp_c = control_config.safety_limits.max_temp_c,
-        "Starting IronThread control loop"
-    );
-
-    let iron_handle = thread::spawn(move || {
-        let io = if let Some(addr) = modbus_addr {
-            info!(addr = %addr, "Connecting to Modbus HAL");
-            let motor = ModbusMotor::new(&addr).expect("Failed to initialize Modbus HAL client");
-            NeuroPlcMotor::Modbus(motor)
-        } else {
-            NeuroPlcMotor::Simulated(SimulatedMotor::new())
-        };
-
-        let mut iron = IronThread::new(io, control_config_iron, exchange_iron, timebase_iron);
-        iron.run(&stop_iron);
-        iron.stats().clone()
-    });
-
-    let bridge_handle = if config.bridge_enabled {
-        let exchange_bridge = Arc::clone(&exchange);
-        let stop_bridge = Arc::clone(&stop);
-        let timebase_bridge = timebase;
-        let bridge_config = build_bridge_config(&config);
-        info!(addr = %bridge_config.bind_addr, "Starting bridge");
-        Some(thread::spawn(move || {
-            run_bridge(exchange_bridge, timebase_bridge, bridge_config, stop_bridge);
-        }))
-    } else {
-        info!("Bridge disabled");
-        None
-    };
-
-    #[cfg(feature = "opcua")]
-    let opcua_handle = if config.opcua_enabled {
-        let opcua_config = OpcuaConfig {
-            endpoint: config.opcua_endpoint.clone(),
-            secure_only: config.opcua_secure_only,
-            allow_anonymous: config.opcua_allow_anonymous,
-            username: config.opcua_user.clone(),
-            password: config.opcua_password.clone(),
-            pki_dir: config.opcua_pki_dir.clone(),
-            create_sample_keypair: config.opcua_create_sample_keypair,
-            allow_write: config.opcua_allow_write,
-            ..Default::default()
-        };
-        info!(endpoint = %opcua_config.endpoint, "Starting OPC UA server");
-        Some(run_opcua(
-            Arc::clone(&exchange),
-            timebase,
-            Arc::clone(&stop),
-            opcua_config,
-        ))
-    } else {
-        None
-    };
-
-    #[cfg(feature = "rerun")]
-    let rerun_handle = if config.rerun_enabled {
-        let rerun_config = RerunConfig {
-            save_path: config.rerun_save_path.map(PathBuf::from),
-            ..Default::default()
-        };
-        info!("Starting Rerun visualization");
-        run_rerun(
-            Arc::clone(&exchange),
-            timebase,
-            Arc::clone(&stop),
-            rerun_config,
-        )
-    } else {
-        None
-    };
+    let run_seconds = config.run_seconds;
+    let plc = NeuroPlc::builder()
+        .config(config)
+        .hal_registry(registry)
+        .stop_flag(Arc::clone(&stop))
+        .start()?;
 
     info!("NeuroPLC running. Connect python-cortex to send recommendations.");
 
-    if let Some(seconds) = config.run_seconds {
+    if let Some(seconds) = run_seconds {
         info!(seconds, "Running for limited duration");
-        thread::sleep(Duration::from_secs(seconds));
-        stop.store(true, std::sync::atomic::Ordering::Relaxed);
-
-        let stats = iron_handle.join().unwrap();
-        if let Some(handle) = bridge_handle {
-            let _ = handle.join();
-        }
-        if let Some(handle) = metrics_updater {
-            let _ = handle.join();
-        }
-        #[cfg(feature = "opcua")]
-        if let Some(handle) = opcua_handle {
-            let _ = handle.join();
+        let deadline = Instant::now() + Duration::from_secs(seconds);
+        while Instant::now() < deadline && !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(50));
         }
-        #[cfg(feature = "rerun")]
-        if let Some(handle) = rerun_handle {
-            let _ = handle.join();
+        stop.store(true, Ordering::Relaxed);
+    }
+
+    // Without --run-seconds this blocks until a signal flips the stop flag.
+    plc.wait();
+    Ok(())
+}
+
+/// Construct the configured HAL. A Modbus backend that fails to start falls
+/// back to the simulated motor, with an audit entry, unless
+/// `--modbus-required` is set; any other failure is fatal.
+pub(super) fn build_hal(
+    registry: &HalRegistry,
+    config: &RuntimeConfig,
+    audit_logger: Option<&AuditLogger>,
+    timebase: TimeBase,
+) -> Result<Box<dyn MachineIO>, HalError> {
+    let backend = config.hal_backend();
+    info!(
+        hal = %backend,
+        sensor_voting = config.sensor_voting,
+        "Initializing HAL backend"
+    );
+    match registry.build_io(config) {
+        Ok(io) => Ok(io),
+        Err(e) if backend == "modbus" && !config.modbus_required => {
+            error!(
+                error = %e,
+                "Modbus HAL failed to start; FALLING BACK TO SIMULATED MOTOR. \
+                 Pass --modbus-required to exit instead"
+            );
+            if let Some(logger) = audit_logger {
+                let _ = logger.log_event(
+                    timebase.now_us(),
+                    timebase.unix_us(),
+                    AuditEventType::HalFallback,
+                    serde_json::json!({
+                        "backend": backend,
+                        "fallback": "simulated",
+                        "error": e.to_string(),
+                    }),
+                );
+            }
+            Ok(Box::new(SimulatedMotor::new()))
         }
+        Err(e) => Err(e),
+    }
+}
 
-        info!(
-            cycles_executed = stats.cycles_executed,
-            cycles_missed = stats.cycles_missed,
-            safety_rejections = stats.safety_rejections,
-            max_jitter_us = stats.max_jitter_us,
-            timing_violations = stats.timing_violations,
-            "Run complete"
+/// Watch the HAL for a runtime fault: once it stays unhealthy past
+/// `--hal-fault-timeout-ms`, audit the fault and, with `--hal-failover`,
+/// serve the control loop from the simulated motor. The control loop itself
+/// latches Safe on the same timeout.
+pub(super) fn with_hal_failover(
+    io: Box<dyn MachineIO>,
+    config: &RuntimeConfig,
+    audit_logger: Option<Arc<AuditLogger>>,
+    timebase: TimeBase,
+) -> Box<dyn MachineIO> {
+    if config.hal_fault_timeout_ms == 0 {
+        return io;
+    }
+    let backend = config.hal_backend().to_string();
+    let standby = config.hal_failover.then(SimulatedMotor::new);
+    let standby_name = standby.as_ref().map(|_| "simulated");
+    let failover = FailoverIO::new(
+        io,
+        standby,
+        Duration::from_millis(config.hal_fault_timeout_ms),
+    )
+    .with_failover_hook(move |unhealthy_for| {
+        error!(
+            hal = %backend,
+            unhealthy_ms = unhealthy_for.as_millis() as u64,
+            standby = ?standby_name,
+            "HAL unhealthy past the fault timeout; supervisor latched Safe"
         );
-
-        // Log shutdown
-        if let Some(ref logger) = audit_logger {
+        if let Some(logger) = audit_logger {
             let _ = logger.log_event(
                 timebase.now_us(),
                 timebase.unix_us(),
-                AuditEventType::SystemShutdown,
+                AuditEventType::HalFailover,
                 serde_json::json!({
-                    "cycles_executed": stats.cycles_executed,
-                    "cycles_missed": stats.cycles_missed,
-                    "safety_rejections": stats.safety_rejections,
-                    "timing_violations": stats.timing_violations,
+                    "backend": backend,
+                    "standby": standby_name,
+                    "unhealthy_ms": unhealthy_for.as_millis() as u64,
                 }),
             );
         }
-    } else {
-        let _ = iron_handle.join();
-        if let Some(handle) = bridge_handle {
-            let _ = handle.join();
-        }
-        if let Some(handle) = metrics_updater {
-            let _ = handle.join();
-        }
-        #[cfg(feature = "opcua")]
-        if let Some(handle) = opcua_handle {
-            let _ = handle.join();
+    });
+    Box::new(failover)
+}
+
+/// Flip `stop` on SIGINT/SIGTERM so every thread winds down through the normal
+/// shutdown path. A second signal while shutting down exits immediately.
+fn install_signal_handlers(stop: &Arc<AtomicBool>) {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::flag;
+
+    for signal in [SIGINT, SIGTERM] {
+        if let Err(e) = flag::register_conditional_shutdown(signal, 1, Arc::clone(stop)) {
+            warn!(signal, error = %e, "Failed to register shutdown escalation handler");
         }
-        #[cfg(feature = "rerun")]
-        if let Some(handle) = rerun_handle {
-            let _ = handle.join();
+        if let Err(e) = flag::register(signal, Arc::clone(stop)) {
+            warn!(signal, error = %e, "Failed to register signal handler");
         }
     }
 }
 
-fn build_bridge_config(config: &RuntimeConfig) -> BridgeConfig {
+pub(super) fn build_bridge_config(config: &RuntimeConfig) -> BridgeConfig {
     let wire_protocol = WireProtocol::parse(&config.bridge_protocol).unwrap_or_else(|| {
         warn!(
             protocol = %config.bridge_protocol,
@@ -289,47 +234,197 @@ fn build_bridge_config(config: &RuntimeConfig) -> BridgeConfig {
         );
         WireProtocol::JsonLines
     });
+    let publish_mode = PublishMode::parse(&config.publish_mode).unwrap_or_else(|| {
+        warn!(
+            mode = %config.publish_mode,
+            "Invalid publish mode, defaulting to fixed:100"
+        );
+        PublishMode::default()
+    });
+    let duplicate_reasoning = DuplicateReasoning::parse(&config.duplicate_reasoning)
+        .unwrap_or_else(|| {
+            warn!(
+                policy = %config.duplicate_reasoning,
+                "Invalid duplicate reasoning policy, defaulting to off"
+            );
+            DuplicateReasoning::Off
+        });
+
+    // `NeuroPlc::start` refuses an unreadable key; should it vanish after
+    // that, the empty key rejects every token rather than none.
+    let (algorithm, public_key) = match &config.auth_pubkey {
+        Some(path) => {
+            let key = load_ed25519_public_key(Path::new(path)).unwrap_or_else(|e| {
+                error!(path = %path, error = %e, "Failed to load auth public key");
+                Vec::new()
+            });
+            (AuthAlgorithm::Ed25519, key)
+        }
+        None => (AuthAlgorithm::HmacSha256, Vec::new()),
+    };
 
     BridgeConfig {
         bind_addr: config.bind_addr.clone(),
-        tls: TlsConfig {
-            enabled: config.tls_cert.is_some() && config.tls_key.is_some(),
-            cert_path: config.tls_cert.clone().unwrap_or_default(),
-            key_path: config.tls_key.clone().unwrap_or_default(),
-            require_client_auth: config.tls_require_client_cert,
-            client_ca_path: config.tls_client_ca.clone().unwrap_or_default(),
-        },
+        extra_bind_addrs: config.extra_bind_addrs.clone(),
+        publish_mode,
+        tls: tls_config(config),
         auth: AuthConfig {
-            enabled: config.auth_secret.is_some(),
+            enabled: config.auth_secret.is_some() || config.auth_pubkey.is_some(),
+            algorithm,
             secret: config.auth_secret.clone().unwrap_or_default().into_bytes(),
+            public_key,
             max_age_secs: config.auth_max_age_secs,
             issuer: config.auth_issuer.clone(),
             audience: config.auth_audience.clone(),
             required_scope: config.auth_scope.clone(),
+            action_scopes: config
+                .auth_action_scopes
+                .iter()
+                .filter_map(|entry| parse_action_scope(entry))
+                .map(|(action, scope)| (action.to_string(), scope.to_string()))
+                .collect(),
+            allow_legacy_format: config.auth_allow_legacy,
+            replay_state_path: config.auth_replay_state.clone(),
+            replay_snapshot_interval: Duration::from_secs(config.auth_replay_snapshot_secs),
             ..Default::default()
         },
         require_handshake: config.bridge_require_handshake,
         wire_protocol,
+        idle_timeout: (config.bridge_idle_timeout_ms > 0)
+            .then(|| Duration::from_millis(config.bridge_idle_timeout_ms)),
+        ping_interval: config.bridge_ping_ms.map(Duration::from_millis),
+        poll_interval: Duration::from_millis(config.bridge_poll_ms),
+        max_recommendations_per_sec: (config.bridge_max_rec_rate > 0)
+            .then_some(config.bridge_max_rec_rate),
+        min_confidence: config.min_confidence,
+        compress_frames_over: config.compress_frames_over,
+        duplicate_reasoning,
+        max_clock_skew: Duration::from_millis(config.max_clock_skew_ms),
+        require_signature: config.require_signed_recommendations,
+        reasoning_hash_bytes: config.reasoning_hash_bytes,
+        safety_limits: Some(ControlConfig::default().safety_limits),
+        effective_config: Some(Arc::new(serde_json::json!({
+            "config_hash": hash_runtime_config(config),
+            "settings": runtime_config_summary(config),
+        }))),
         ..Default::default()
     }
 }
 
-fn init_audit_logger(audit_path: Option<&PathBuf>) -> Option<Arc<AuditLogger>> {
-    audit_path.map(|path| match AuditLogger::new(path) {
+/// Split an `--auth-action-scope` entry into a known action and its scope
+pub(super) fn parse_action_scope(entry: &str) -> Option<(&str, &str)> {
+    let (action, scope) = entry.split_once('=')?;
+    let (action, scope) = (action.trim(), scope.trim());
+    (SCOPED_ACTIONS.contains(&action) && !scope.is_empty()).then_some((action, scope))
+}
+
+pub(super) fn tls_config(config: &RuntimeConfig) -> TlsConfig {
+    // Fail closed: a mistyped minimum must not re-enable TLS 1.2.
+    let min_protocol_version = TlsVersion::parse(&config.tls_min_version).unwrap_or_else(|| {
+        warn!(
+            version = %config.tls_min_version,
+            "Invalid TLS minimum version, requiring 1.3"
+        );
+        TlsVersion::Tls13
+    });
+    TlsConfig {
+        enabled: config.tls_cert.is_some() && config.tls_key.is_some(),
+        cert_path: config.tls_cert.clone().unwrap_or_default(),
+        key_path: config.tls_key.clone().unwrap_or_default(),
+        require_client_auth: config.tls_require_client_cert,
+        client_ca_path: config.tls_client_ca.clone().unwrap_or_default(),
+        allowed_client_cns: config.tls_allowed_cns.clone(),
+        min_protocol_version,
+        cipher_suites: config.tls_cipher_suites.clone(),
+    }
+}
+
+/// OPC UA server settings; unknown `--opcua-tag` keys are skipped with a
+/// warning, and an empty selection publishes every tag.
+#[cfg(feature = "opcua")]
+pub(super) fn opcua_config(config: &RuntimeConfig) -> OpcuaConfig {
+    let mut tags: Vec<_> = config
+        .opcua_tags
+        .iter()
+        .filter_map(|key| {
+            let tag = core_spine::tags::by_key(key);
+            if tag.is_none() {
+                warn!(tag = %key, "Unknown --opcua-tag, skipping");
+            }
+            tag
+        })
+        .collect();
+    if tags.is_empty() {
+        tags = core_spine::tags::ALL.to_vec();
+    }
+    OpcuaConfig {
+        endpoint: config.opcua_endpoint.clone(),
+        update_interval: Duration::from_millis(
+            config.opcua_update_interval_ms.max(MIN_UPDATE_INTERVAL_MS),
+        ),
+        secure_only: config.opcua_secure_only,
+        allow_anonymous: config.opcua_allow_anonymous,
+        username: config.opcua_user.clone(),
+        password: config.opcua_password.clone(),
+        pki_dir: config.opcua_pki_dir.clone(),
+        create_sample_keypair: config.opcua_create_sample_keypair,
+        allow_write: config.opcua_allow_write,
+        tags,
+        namespace_uri: config.opcua_namespace_uri.clone(),
+        application_uri: config.opcua_application_uri.clone(),
+        node_prefix: config.opcua_node_prefix.clone(),
+    }
+}
+
+/// Open the audit log if configured; on failure returns the path and error
+pub(super) fn init_audit_logger(
+    config: &RuntimeConfig,
+) -> Result<Option<Arc<AuditLogger>>, (PathBuf, std::io::Error)> {
+    let Some(path) = config.audit_path.as_ref() else {
+        return Ok(None);
+    };
+    let logger = match config.audit_max_bytes {
+        Some(max_bytes) => AuditLogger::with_rotation(path, max_bytes, config.audit_max_files),
+        None => AuditLogger::new(path),
+    };
+    match logger {
         Ok(logger) => {
             info!(path = %path.display(), "Audit logging enabled");
-            Arc::<AuditLogger>::new(logger)
-        }
-        Err(e) => {
-            warn!(error = %e, path = %path.display(), "Failed to initialize audit logger");
-            panic!("Audit logging requested but failed to initialize: {}", e);
+            Ok(Some(Arc::new(logger.into_background(AUDIT_QUEUE_CAPACITY))))
         }
-    })
+        Err(e) => Err((path.clone(), e)),
+    }
+}
+
+/// Per-client sequence floors shared by the TCP and WebSocket bridges, if
+/// enabled; on a failed load returns the path and error
+pub(super) fn open_sequence_floors(
+    config: &RuntimeConfig,
+) -> Result<Option<Arc<SequenceFloors>>, (PathBuf, std::io::Error)> {
+    match &config.sequence_floor_state {
+        Some(path) => SequenceFloors::load(path)
+            .map(|floors| Some(Arc::new(floors)))
+            .map_err(|e| (path.clone(), e)),
+        None => Ok(config
+            .sequence_floor
+            .then(|| Arc::new(SequenceFloors::in_memory()))),
+    }
 }
 
-fn hash_runtime_config(config: &RuntimeConfig) -> String {
+/// Hash of the effective settings, after any `--config` file and the flags are merged
+pub(super) fn hash_runtime_config(config: &RuntimeConfig) -> String {
+    hash_str(&runtime_config_summary(config).to_string())
+}
+
+/// The settings that shape a run, with secrets reduced to whether they are
+/// set. Hashed for the audit trail and served to `get_config`.
+pub(super) fn runtime_config_summary(config: &RuntimeConfig) -> serde_json::Value {
     let mut summary = serde_json::Map::new();
     summary.insert("bind_addr".to_string(), config.bind_addr.clone().into());
+    summary.insert(
+        "extra_bind_addrs".to_string(),
+        config.extra_bind_addrs.clone().into(),
+    );
     summary.insert(
         "bridge_enabled".to_string(),
         serde_json::Value::Bool(config.bridge_enabled),
@@ -338,6 +433,16 @@ fn hash_runtime_config(config: &RuntimeConfig) -> String {
         "metrics_addr".to_string(),
         config.metrics_addr.clone().into(),
     );
+    summary.insert("metrics_poll_ms".to_string(), config.metrics_poll_ms.into());
+    summary.insert(
+        "jitter_buckets_us".to_string(),
+        config.jitter_buckets_us.clone().into(),
+    );
+    // Only whether the reset endpoint is on; the token itself stays out.
+    summary.insert(
+        "metrics_admin_enabled".to_string(),
+        serde_json::Value::Bool(config.metrics_admin_token.is_some()),
+    );
     summary.insert(
         "tls_enabled".to_string(),
         serde_json::Value::Bool(config.tls_cert.is_some() && config.tls_key.is_some()),
@@ -346,9 +451,21 @@ fn hash_runtime_config(config: &RuntimeConfig) -> String {
         "tls_require_client_cert".to_string(),
         serde_json::Value::Bool(config.tls_require_client_cert),
     );
+    summary.insert(
+        "tls_allowed_cns".to_string(),
+        config.tls_allowed_cns.clone().into(),
+    );
+    summary.insert(
+        "tls_min_version".to_string(),
+        config.tls_min_version.clone().into(),
+    );
+    summary.insert(
+        "tls_cipher_suites".to_string(),
+        config.tls_cipher_suites.clone().into(),
+    );
     summary.insert(
         "auth_enabled".to_string(),
-        serde_json::Value::Bool(config.auth_secret.is_some()),
+        serde_json::Value::Bool(config.auth_secret.is_some() || config.auth_pubkey.is_some()),
     );
     summary.insert(
         "bridge_require_handshake".to_string(),
@@ -358,17 +475,173 @@ fn hash_runtime_config(config: &RuntimeConfig) -> String {
         "bridge_protocol".to_string(),
         config.bridge_protocol.clone().into(),
     );
+    summary.insert(
+        "publish_mode".to_string(),
+        config.publish_mode.clone().into(),
+    );
+    summary.insert("bridge_poll_ms".to_string(), config.bridge_poll_ms.into());
     summary.insert(
         "auth_max_age_secs".to_string(),
         serde_json::Value::Number(config.auth_max_age_secs.into()),
     );
+    summary.insert(
+        "auth_secret_file".to_string(),
+        config.auth_secret_file.clone().into(),
+    );
+    summary.insert("auth_pubkey".to_string(), config.auth_pubkey.clone().into());
     summary.insert("auth_issuer".to_string(), config.auth_issuer.clone().into());
     summary.insert(
         "auth_audience".to_string(),
         config.auth_audience.clone().into(),
     );
     summary.insert("auth_scope".to_string(), config.auth_scope.clone().into());
+    summary.insert(
+        "auth_action_scopes".to_string(),
+        config.auth_action_scopes.clone().into(),
+    );
+    summary.insert(
+        "auth_allow_legacy".to_string(),
+        serde_json::Value::Bool(config.auth_allow_legacy),
+    );
+    summary.insert(
+        "require_signed_recommendations".to_string(),
+        serde_json::Value::Bool(config.require_signed_recommendations),
+    );
+    summary.insert(
+        "sequence_floor".to_string(),
+        serde_json::Value::Bool(config.sequence_floor || config.sequence_floor_state.is_some()),
+    );
+    summary.insert(
+        "auth_replay_state".to_string(),
+        config
+            .auth_replay_state
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .into(),
+    );
+    summary.insert(
+        "auth_replay_snapshot_secs".to_string(),
+        serde_json::Value::Number(config.auth_replay_snapshot_secs.into()),
+    );
+    summary.insert(
+        "bridge_idle_timeout_ms".to_string(),
+        serde_json::Value::Number(config.bridge_idle_timeout_ms.into()),
+    );
+    summary.insert("bridge_ping_ms".to_string(), config.bridge_ping_ms.into());
+    summary.insert(
+        "bridge_max_rec_rate".to_string(),
+        serde_json::Value::Number(config.bridge_max_rec_rate.into()),
+    );
+    summary.insert(
+        "compress_frames_over".to_string(),
+        config.compress_frames_over.into(),
+    );
+    summary.insert(
+        "duplicate_reasoning".to_string(),
+        config.duplicate_reasoning.clone().into(),
+    );
+    summary.insert(
+        "max_clock_skew_ms".to_string(),
+        serde_json::Value::Number(config.max_clock_skew_ms.into()),
+    );
+    summary.insert(
+        "min_confidence".to_string(),
+        serde_json::json!(config.min_confidence),
+    );
+    summary.insert(
+        "reasoning_hash_bytes".to_string(),
+        serde_json::Value::Number(config.reasoning_hash_bytes.into()),
+    );
     summary.insert("modbus_addr".to_string(), config.modbus_addr.clone().into());
+    summary.insert(
+        "modbus_required".to_string(),
+        serde_json::Value::Bool(config.modbus_required),
+    );
+    summary.insert(
+        "modbus_target".to_string(),
+        config.modbus_target.clone().into(),
+    );
+    summary.insert(
+        "modbus_alarm_inputs".to_string(),
+        serde_json::json!(config.modbus_alarm_inputs),
+    );
+    summary.insert(
+        "hal_fault_timeout_ms".to_string(),
+        serde_json::Value::Number(config.hal_fault_timeout_ms.into()),
+    );
+    summary.insert(
+        "hal_failover".to_string(),
+        serde_json::Value::Bool(config.hal_failover),
+    );
+    summary.insert(
+        "self_test".to_string(),
+        serde_json::Value::Bool(config.self_test),
+    );
+    summary.insert("hal".to_string(), config.hal_backend().into());
+    summary.insert(
+        "sim_config".to_string(),
+        config
+            .sim_config
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .into(),
+    );
+    summary.insert(
+        "sensor_voting".to_string(),
+        serde_json::Value::Bool(config.sensor_voting),
+    );
+    summary.insert(
+        "voting_modbus_addrs".to_string(),
+        config.voting_modbus_addrs.clone().into(),
+    );
+    summary.insert(
+        "cycle_time_us".to_string(),
+        serde_json::Value::Number(config.cycle_time_us.into()),
+    );
+    summary.insert(
+        "max_jitter_us".to_string(),
+        serde_json::Value::Number(config.max_jitter_us.into()),
+    );
+    summary.insert(
+        "agent_timeout".to_string(),
+        config.agent_timeout.clone().into(),
+    );
+    summary.insert(
+        "wait_strategy".to_string(),
+        config.wait_strategy.clone().into(),
+    );
+    summary.insert(
+        "estop_profile".to_string(),
+        config.estop_profile.clone().into(),
+    );
+    summary.insert(
+        "setpoint_deadband_rpm".to_string(),
+        serde_json::json!(config.setpoint_deadband_rpm),
+    );
+    summary.insert(
+        "operational_max_speed_rpm".to_string(),
+        serde_json::json!(config.operational_max_speed_rpm),
+    );
+    summary.insert(
+        "temp_soft_limit_c".to_string(),
+        serde_json::json!(config.temp_soft_limit_c),
+    );
+    summary.insert(
+        "thermal_derate_gain".to_string(),
+        serde_json::json!(config.thermal_derate_gain),
+    );
+    summary.insert(
+        "jitter_trip_after".to_string(),
+        serde_json::Value::Number(config.jitter_trip_after.into()),
+    );
+    summary.insert("jitter_warn_us".to_string(), config.jitter_warn_us.into());
+    summary.insert("jitter_crit_us".to_string(), config.jitter_crit_us.into());
+    summary.insert("rt_priority".to_string(), config.rt_priority.into());
+    summary.insert("cpu_affinity".to_string(), config.cpu_affinity.into());
+    summary.insert(
+        "recommendation_history".to_string(),
+        serde_json::Value::Number(config.recommendation_history.into()),
+    );
 
     #[cfg(feature = "opcua")]
     {
@@ -404,8 +677,34 @@ fn hash_runtime_config(config: &RuntimeConfig) -> String {
             "opcua_create_sample_keypair".to_string(),
             serde_json::Value::Bool(config.opcua_create_sample_keypair),
         );
+        summary.insert(
+            "opcua_update_interval_ms".to_string(),
+            serde_json::Value::Number(config.opcua_update_interval_ms.into()),
+        );
+        summary.insert("opcua_tags".to_string(), config.opcua_tags.clone().into());
+        summary.insert(
+            "opcua_namespace_uri".to_string(),
+            config.opcua_namespace_uri.clone().into(),
+        );
+        summary.insert(
+            "opcua_application_uri".to_string(),
+            config.opcua_application_uri.clone().into(),
+        );
+        summary.insert(
+            "opcua_node_prefix".to_string(),
+            config.opcua_node_prefix.clone().into(),
+        );
     }
 
+    #[cfg(feature = "otlp")]
+    summary.insert(
+        "otlp_endpoint".to_string(),
+        config.otlp_endpoint.clone().into(),
+    );
+
+    #[cfg(feature = "ws")]
+    summary.insert("ws_bind".to_string(), config.ws_bind.clone().into());
+
     #[cfg(feature = "rerun")]
     {
         summary.insert(
@@ -418,17 +717,5 @@ fn hash_runtime_config(config: &RuntimeConfig) -> String {
         );
     }
 
-    hash_str(&serde_json::Value::Object(summary).to_string())
-}
-
-fn current_binary_hash() -> Option<String> {
-    let path = std::env::current_exe().ok()?;
-    let bytes = std::fs::read(path).ok()?;
-    Some(hash_bytes(&bytes))
-}
-
-fn cortex_manifest_hash() -> Option<String> {
-    let path = std::path::Path::new("python-cortex/pyproject.toml");
-    let bytes = std::fs::read(path).ok()?;
-    Some(hash_bytes(&bytes))
+    serde_json::Value::Object(summary)
 }
@@ -0,0 +1,737 @@
+//! `--check-config`: run the startup construction and validation steps
+//! without starting any threads, so a deployment can be checked up front.
+
+#[cfg(feature = "opcua")]
+use crate::integrations::opcua_server::MIN_UPDATE_INTERVAL_MS;
+use crate::runtime::app::{parse_action_scope, tls_config};
+use crate::runtime::config::RuntimeConfig;
+use crate::runtime::hal::{simulated_motor, HalError, HalRegistry};
+use crate::runtime::realtime::MIN_CYCLE_TIME_US;
+use core_spine::{
+    AgentTimeoutPolicy, ControlConfig, EStopProfile, ReasoningHash, SafetyLimits, WaitStrategy,
+};
+use neuro_io::auth::load_ed25519_public_key;
+use neuro_io::bridge::{DuplicateReasoning, PublishMode, SequenceFloors, WireProtocol};
+use neuro_io::hal_modbus::{ModbusTransport, TargetEncoding};
+use neuro_io::tls::{build_server_config, TlsVersion};
+use std::net::SocketAddr;
+use std::path::Path;
+
+/// Namespace 0 of every OPC UA server
+#[cfg(feature = "opcua")]
+const OPCUA_STANDARD_NAMESPACE: &str = "http://opcfoundation.org/UA/";
+
+/// Outcome of [`check_config`]
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    /// Everything that would stop or misconfigure a real start
+    pub problems: Vec<String>,
+    /// Subsystems that would be enabled, as `name: detail`
+    pub enabled: Vec<String>,
+    /// Accepted but worth fixing, such as deprecated config file keys
+    pub warnings: Vec<String>,
+}
+
+impl ConfigReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Print the summary and problems; returns the process exit code
+    pub fn print(&self) -> i32 {
+        println!("Enabled:");
+        for line in &self.enabled {
+            println!("  {line}");
+        }
+        for warning in &self.warnings {
+            eprintln!("warning: {warning}");
+        }
+        if self.is_ok() {
+            println!("Configuration OK");
+            return 0;
+        }
+        for problem in &self.problems {
+            eprintln!("error: {problem}");
+        }
+        eprintln!("Configuration has {} problem(s)", self.problems.len());
+        1
+    }
+}
+
+/// Validate `config` against `registry` the way startup would
+pub fn check_config(config: &RuntimeConfig, registry: &HalRegistry) -> ConfigReport {
+    let mut report = ConfigReport {
+        warnings: config.config_warnings.clone(),
+        ..ConfigReport::default()
+    };
+    for arg in &config.unknown_args {
+        report
+            .problems
+            .push(format!("unknown argument '{arg}' (or missing its value)"));
+    }
+    check_control(config, &mut report);
+    check_hal(config, registry, &mut report);
+    if config.bridge_enabled {
+        check_bridge(config, &mut report);
+    }
+    check_observability(config, &mut report);
+    report
+}
+
+fn check_control(config: &RuntimeConfig, report: &mut ConfigReport) {
+    if config.cycle_time_us < MIN_CYCLE_TIME_US {
+        report.problems.push(format!(
+            "--cycle-time-us {} is below the {MIN_CYCLE_TIME_US} us minimum",
+            config.cycle_time_us
+        ));
+    }
+    if config.jitter_trip_after == 0 {
+        report
+            .problems
+            .push("--jitter-trip-after must be at least 1".to_string());
+    }
+    if let (Some(warn), Some(crit)) = (config.jitter_warn_us, config.jitter_crit_us) {
+        if warn >= crit {
+            report.problems.push(format!(
+                "--jitter-warn-us {warn} must be below --jitter-crit-us {crit}"
+            ));
+        }
+    }
+    if !(config.setpoint_deadband_rpm.is_finite() && config.setpoint_deadband_rpm >= 0.0) {
+        report.problems.push(format!(
+            "--setpoint-deadband-rpm {} must be a non-negative number",
+            config.setpoint_deadband_rpm
+        ));
+    }
+    if AgentTimeoutPolicy::parse(&config.agent_timeout).is_none() {
+        report.problems.push(format!(
+            "--agent-timeout '{}' is not hold, ramp-to-zero:<RATE> or ramp-to-safe:<RPM>:<RATE>",
+            config.agent_timeout
+        ));
+    }
+    if WaitStrategy::parse(&config.wait_strategy).is_none() {
+        report.problems.push(format!(
+            "--wait-strategy '{}' is not busy-spin, sleep or hybrid:<US>",
+            config.wait_strategy
+        ));
+    }
+    if EStopProfile::parse(&config.estop_profile).is_none() {
+        report.problems.push(format!(
+            "--estop-profile '{}' is not immediate or ramp:<RATE>",
+            config.estop_profile
+        ));
+    }
+    if let Some(priority) = config.rt_priority {
+        if !(1..=99).contains(&priority) {
+            report
+                .problems
+                .push(format!("--rt-priority {priority} is outside 1-99"));
+        }
+    }
+    if let Some(cpu) = config.cpu_affinity {
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        if cpu >= cpus {
+            report.problems.push(format!(
+                "--cpu-affinity {cpu} but only {cpus} CPU(s) are available"
+            ));
+        }
+    }
+
+    let limits = ControlConfig::default().safety_limits;
+    report.problems.extend(limit_problems(&limits));
+    if let Some(cap) = config.operational_max_speed_rpm {
+        if !(cap.is_finite() && (limits.min_speed_rpm..=limits.max_speed_rpm).contains(&cap)) {
+            report.problems.push(format!(
+                "--operational-max-speed-rpm {cap} must be between {} and {} rpm",
+                limits.min_speed_rpm, limits.max_speed_rpm
+            ));
+        }
+    }
+    if let Some(soft) = config.temp_soft_limit_c {
+        if !(soft.is_finite() && soft < limits.max_temp_c) {
+            report.problems.push(format!(
+                "--temp-soft-limit-c {soft} must be below the {} C hard limit",
+                limits.max_temp_c
+            ));
+        }
+        let gain = config.thermal_derate_gain;
+        if !(gain.is_finite() && gain > 0.0 && gain <= 1.0) {
+            report.problems.push(format!(
+                "--thermal-derate-gain {gain} must be above 0 and at most 1"
+            ));
+        }
+    }
+    report.enabled.push(format!(
+        "control: {} us cycle, speed {}-{} rpm, max {} C / {} bar, agent timeout {}",
+        config.cycle_time_us,
+        limits.min_speed_rpm,
+        limits.max_speed_rpm,
+        limits.max_temp_c,
+        limits.max_pressure_bar,
+        config.agent_timeout
+    ));
+}
+
+fn limit_problems(limits: &SafetyLimits) -> Vec<String> {
+    let mut problems = Vec::new();
+    let values = [
+        ("max_speed_rpm", limits.max_speed_rpm),
+        ("min_speed_rpm", limits.min_speed_rpm),
+        ("max_accel_rpm_per_s", limits.max_accel_rpm_per_s),
+        ("max_decel_rpm_per_s", limits.max_decel_rpm_per_s),
+        ("min_temp_c", limits.min_temp_c),
+        ("max_temp_c", limits.max_temp_c),
+        ("max_pressure_bar", limits.max_pressure_bar),
+    ];
+    for (name, value) in values {
+        if !value.is_finite() {
+            problems.push(format!("safety limit {name} is not finite"));
+        }
+    }
+    if limits.min_speed_rpm >= limits.max_speed_rpm {
+        problems.push(format!(
+            "safety limit min_speed_rpm {} is not below max_speed_rpm {}",
+            limits.min_speed_rpm, limits.max_speed_rpm
+        ));
+    }
+    if limits.min_temp_c >= limits.max_temp_c {
+        problems.push(format!(
+            "safety limit min_temp_c {} is not below max_temp_c {}",
+            limits.min_temp_c, limits.max_temp_c
+        ));
+    }
+    if limits.max_accel_rpm_per_s <= 0.0 || limits.max_decel_rpm_per_s <= 0.0 {
+        problems.push("safety rate limits must be positive".to_string());
+    }
+    problems
+}
+
+fn check_hal(config: &RuntimeConfig, registry: &HalRegistry, report: &mut ConfigReport) {
+    let backend = config.hal_backend();
+    if !registry.contains(backend) {
+        report.problems.push(format!(
+            "unknown HAL backend '{backend}' (available: {})",
+            registry.names().join(", ")
+        ));
+        return;
+    }
+    if backend == "modbus" {
+        match &config.modbus_addr {
+            Some(addr) => check_modbus_addr(report, "--modbus", addr),
+            None => report
+                .problems
+                .push("HAL 'modbus' needs --modbus <ADDR>".to_string()),
+        }
+        if TargetEncoding::parse(&config.modbus_target).is_none() {
+            report.problems.push(format!(
+                "--modbus-target '{}' is not u16[:SCALE], u32[:SCALE] or u32-swapped[:SCALE]",
+                config.modbus_target
+            ));
+        }
+        if config.sensor_voting {
+            if config.voting_modbus_addrs.len() < 2 {
+                report
+                    .problems
+                    .push("--sensor-voting needs --voting-modbus twice".to_string());
+            }
+            for addr in &config.voting_modbus_addrs {
+                check_modbus_addr(report, "--voting-modbus", addr);
+            }
+        }
+    }
+    if backend == "simulated" && config.sim_config.is_some() {
+        if let Err(HalError::Init { message, .. }) = simulated_motor(config) {
+            report.problems.push(format!("--sim-config {message}"));
+        }
+    }
+    if config.hal_failover && config.hal_fault_timeout_ms == 0 {
+        report
+            .problems
+            .push("--hal-failover needs --hal-fault-timeout-ms above 0".to_string());
+    }
+    let voting = if config.sensor_voting {
+        " (2oo3 voting)"
+    } else {
+        ""
+    };
+    report.enabled.push(format!("hal: {backend}{voting}"));
+    match (config.hal_fault_timeout_ms, config.hal_failover) {
+        (0, _) => {}
+        (ms, false) => report
+            .enabled
+            .push(format!("hal fault: Safe after {ms} ms unhealthy")),
+        (ms, true) => report.enabled.push(format!(
+            "hal fault: Safe and simulated standby after {ms} ms unhealthy"
+        )),
+    }
+}
+
+fn check_bridge(config: &RuntimeConfig, report: &mut ConfigReport) {
+    check_socket_addr(report, "--bind", &config.bind_addr);
+    for addr in &config.extra_bind_addrs {
+        check_socket_addr(report, "--bind", addr);
+    }
+    if WireProtocol::parse(&config.bridge_protocol).is_none() {
+        report.problems.push(format!(
+            "unknown --protocol '{}' (expected json or proto)",
+            config.bridge_protocol
+        ));
+    }
+    if PublishMode::parse(&config.publish_mode).is_none() {
+        report.problems.push(format!(
+            "--publish-mode '{}' is not fixed:<MS> or on-change:<MIN_MS>:<MAX_MS>",
+            config.publish_mode
+        ));
+    }
+    if config.bridge_poll_ms == 0 {
+        report
+            .problems
+            .push("--bridge-poll-ms must be at least 1".to_string());
+    }
+    if DuplicateReasoning::parse(&config.duplicate_reasoning).is_none() {
+        report.problems.push(format!(
+            "--duplicate-reasoning '{}' is not off, count:<N> or reject:<N>",
+            config.duplicate_reasoning
+        ));
+    }
+    if config.compress_frames_over.is_some()
+        && WireProtocol::parse(&config.bridge_protocol) != Some(WireProtocol::Protobuf)
+    {
+        report
+            .problems
+            .push("--compress-over only applies to --protocol proto".to_string());
+    }
+    if config.max_clock_skew_ms == 0 {
+        report
+            .problems
+            .push("--max-clock-skew-ms must be above 0".to_string());
+    }
+    if !(0.0..=1.0).contains(&config.min_confidence) {
+        report.problems.push(format!(
+            "--min-confidence {} is outside 0-1",
+            config.min_confidence
+        ));
+    }
+    if !ReasoningHash::LENGTHS.contains(&config.reasoning_hash_bytes) {
+        report.problems.push(format!(
+            "--reasoning-hash-bytes {} is not 32 or 64",
+            config.reasoning_hash_bytes
+        ));
+    }
+
+    if TlsVersion::parse(&config.tls_min_version).is_none() {
+        report.problems.push(format!(
+            "--tls-min-version '{}' is not 1.2 or 1.3",
+            config.tls_min_version
+        ));
+    }
+    let tls = tls_config(config);
+    if config.tls_cert.is_some() != config.tls_key.is_some() {
+        report
+            .problems
+            .push("--tls-cert and --tls-key must be given together".to_string());
+    } else if tls.enabled {
+        if tls.require_client_auth && tls.client_ca_path.is_empty() {
+            report
+                .problems
+                .push("--tls-require-client-cert needs --tls-client-ca".to_string());
+        } else if !tls.allowed_client_cns.is_empty() && !tls.require_client_auth {
+            report
+                .problems
+                .push("--tls-allowed-cn needs --tls-require-client-cert".to_string());
+        } else if let Err(e) = build_server_config(&tls) {
+            report.problems.push(format!("TLS: {e}"));
+        }
+    }
+
+    if let Some(path) = &config.auth_pubkey {
+        if let Err(e) = load_ed25519_public_key(Path::new(path)) {
+            report.problems.push(format!("--auth-pubkey {path}: {e}"));
+        }
+    }
+    let auth_enabled = config.auth_secret.is_some() || config.auth_pubkey.is_some();
+    if config.auth_secret.is_some() && config.auth_pubkey.is_some() {
+        report
+            .problems
+            .push("--auth-secret and --auth-pubkey cannot be combined".to_string());
+    }
+    if config
+        .auth_secret
+        .as_deref()
+        .is_some_and(|secret| secret.trim().is_empty())
+    {
+        report.problems.push("--auth-secret is empty".to_string());
+    }
+    if config.auth_scope.is_some() && !auth_enabled {
+        report
+            .problems
+            .push("--auth-scope is set but no --auth-secret or --auth-pubkey is given".to_string());
+    }
+    for entry in &config.auth_action_scopes {
+        if parse_action_scope(entry).is_none() {
+            report.problems.push(format!(
+                "--auth-action-scope '{entry}' is not ACTION=SCOPE with a known action"
+            ));
+        }
+    }
+    if !config.auth_action_scopes.is_empty() && !auth_enabled {
+        report.problems.push(
+            "--auth-action-scope is set but no --auth-secret or --auth-pubkey is given".to_string(),
+        );
+    }
+    if config.require_signed_recommendations && !auth_enabled {
+        report.problems.push(
+            "--require-signed-recommendations needs --auth-secret or --auth-pubkey".to_string(),
+        );
+    }
+    if let Some(path) = &config.auth_replay_state {
+        check_parent_dir(report, "--auth-replay-state", path);
+    }
+    if let Some(path) = &config.sequence_floor_state {
+        check_parent_dir(report, "--sequence-floor-state", path);
+        if let Err(e) = SequenceFloors::load(path) {
+            report
+                .problems
+                .push(format!("--sequence-floor-state {}: {e}", path.display()));
+        }
+    }
+
+    let auth = match (&config.auth_pubkey, &config.auth_secret) {
+        (Some(_), _) => "ed25519",
+        (None, Some(_)) => "hmac",
+        (None, None) => "off",
+    };
+    report.enabled.push(format!(
+        "bridge: {} ({}, tls {}, auth {auth})",
+        std::iter::once(&config.bind_addr)
+            .chain(&config.extra_bind_addrs)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", "),
+        config.bridge_protocol,
+        if tls.enabled { "on" } else { "off" }
+    ));
+}
+
+fn check_observability(config: &RuntimeConfig, report: &mut ConfigReport) {
+    if let Some(addr) = &config.metrics_addr {
+        match neuro_io::metrics::metrics_socket_path(addr) {
+            Some(path) => check_parent_dir(report, "--metrics-addr", path),
+            None => check_socket_addr(report, "--metrics-addr", addr),
+        }
+        report.enabled.push(format!("metrics: {addr}"));
+    }
+    if config.metrics_poll_ms == 0 {
+        report
+            .problems
+            .push("--metrics-poll-ms must be at least 1".to_string());
+    }
+    if !config.jitter_buckets_us.is_empty() {
+        if let Err(e) = neuro_io::metrics::validate_histogram_buckets(&config.jitter_buckets_us) {
+            report.problems.push(format!("--jitter-buckets-us: {e}"));
+        }
+    }
+    match (&config.metrics_admin_token, &config.metrics_addr) {
+        (Some(token), _) if token.is_empty() => report
+            .problems
+            .push("--metrics-admin-token must not be empty".to_string()),
+        (Some(_), None) => report
+            .problems
+            .push("--metrics-admin-token requires --metrics-addr".to_string()),
+        (Some(_), Some(_)) => report.enabled.push("metrics reset: on".to_string()),
+        (None, _) => {}
+    }
+    if let Some(path) = &config.audit_path {
+        check_parent_dir(report, "--audit-log", path);
+        if config.audit_max_bytes.is_some() && config.audit_max_files == 0 {
+            report
+                .problems
+                .push("--audit-max-files must be a number of at least 1".to_string());
+        }
+        report
+            .enabled
+            .push(format!("audit log: {}", path.display()));
+    }
+    if let Some(path) = &config.report_path {
+        check_parent_dir(report, "--report", path);
+        report
+            .enabled
+            .push(format!("run report: {}", path.display()));
+    }
+    #[cfg(feature = "otlp")]
+    if let Some(endpoint) = &config.otlp_endpoint {
+        if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) {
+            report
+                .problems
+                .push(format!("--otlp-endpoint {endpoint} is not an http(s) URL"));
+        }
+        report.enabled.push(format!("otlp: {endpoint}"));
+    }
+    #[cfg(feature = "ws")]
+    if let Some(addr) = &config.ws_bind {
+        check_socket_addr(report, "--ws-bind", addr);
+        report.enabled.push(format!("websocket: {addr}"));
+    }
+    #[cfg(feature = "opcua")]
+    if config.opcua_enabled {
+        if config.opcua_update_interval_ms < MIN_UPDATE_INTERVAL_MS {
+            report.problems.push(format!(
+                "--opcua-update-interval-ms must be at least {MIN_UPDATE_INTERVAL_MS}"
+            ));
+        }
+        for key in &config.opcua_tags {
+            if core_spine::tags::by_key(key).is_none() {
+                report
+                    .problems
+                    .push(format!("--opcua-tag '{key}' is not a known tag"));
+            }
+        }
+        for (flag, uri) in [
+            ("--opcua-namespace-uri", &config.opcua_namespace_uri),
+            ("--opcua-application-uri", &config.opcua_application_uri),
+        ] {
+            if uri.trim().is_empty() {
+                report.problems.push(format!("{flag} must not be empty"));
+            }
+        }
+        if config.opcua_namespace_uri == OPCUA_STANDARD_NAMESPACE {
+            report.problems.push(format!(
+                "--opcua-namespace-uri must not be the standard namespace {OPCUA_STANDARD_NAMESPACE}"
+            ));
+        }
+        report
+            .enabled
+            .push(format!("opcua: {}", config.opcua_endpoint));
+    }
+    #[cfg(feature = "rerun")]
+    if config.rerun_enabled {
+        report.enabled.push("rerun".to_string());
+    }
+}
+
+fn check_socket_addr(report: &mut ConfigReport, flag: &str, addr: &str) {
+    if let Err(e) = addr.parse::<SocketAddr>() {
+        report.problems.push(format!("{flag} {addr}: {e}"));
+    }
+}
+
+fn check_modbus_addr(report: &mut ConfigReport, flag: &str, addr: &str) {
+    if let Err(e) = ModbusTransport::parse(addr) {
+        report.problems.push(format!("{flag}: {e}"));
+    }
+}
+
+fn check_parent_dir(report: &mut ConfigReport, flag: &str, path: &Path) {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => return,
+    };
+    if !parent.is_dir() {
+        report.problems.push(format!(
+            "{flag} {}: directory {} does not exist",
+            path.display(),
+            parent.display()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(args: &[&str]) -> ConfigReport {
+        let args: Vec<String> = std::iter::once("neuro-plc")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect();
+        check_config(
+            &RuntimeConfig::from_args(&args),
+            &HalRegistry::with_builtins(),
+        )
+    }
+
+    #[test]
+    fn test_defaults_pass() {
+        let report = check(&[]);
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert!(report
+            .enabled
+            .iter()
+            .any(|line| line.starts_with("bridge:")));
+        assert!(report.enabled.iter().any(|line| line == "hal: simulated"));
+    }
+
+    #[test]
+    fn test_each_problem_is_reported() {
+        let report = check(&[
+            "--tls-cert",
+            "/nonexistent/cert.pem",
+            "--tls-key",
+            "/nonexistent/key.pem",
+            "--auth-scope",
+            "control:write",
+            "--protocol",
+            "xml",
+            "--hal",
+            "modbus",
+            "--modbus",
+            "plc.local",
+            "--operational-max-speed-rpm",
+            "3500",
+        ]);
+        assert_eq!(report.problems.len(), 5, "{:?}", report.problems);
+        assert!(report.problems.iter().any(|p| p.starts_with("TLS:")));
+        assert!(report.problems.iter().any(|p| p.contains("--auth-scope")));
+        assert!(report.problems.iter().any(|p| p.contains("--protocol")));
+        assert!(report.problems.iter().any(|p| p.starts_with("--modbus")));
+        assert!(report
+            .problems
+            .iter()
+            .any(|p| p.starts_with("--operational-max-speed-rpm")));
+    }
+
+    #[test]
+    fn test_disabled_bridge_is_not_checked() {
+        let report = check(&["--no-bridge", "--bind", "not-an-address"]);
+        assert!(report.is_ok(), "{:?}", report.problems);
+        assert!(!report
+            .enabled
+            .iter()
+            .any(|line| line.starts_with("bridge:")));
+    }
+
+    #[test]
+    fn test_unknown_backend_and_missing_audit_dir() {
+        let report = check(&[
+            "--hal",
+            "ethercat",
+            "--audit-log",
+            "/nonexistent/dir/audit.jsonl",
+        ]);
+        assert_eq!(report.problems.len(), 2, "{:?}", report.problems);
+    }
+
+    #[test]
+    fn test_zero_poll_intervals_are_reported() {
+        let report = check(&["--bridge-poll-ms", "0", "--metrics-poll-ms", "0"]);
+        assert_eq!(report.problems.len(), 2, "{:?}", report.problems);
+    }
+
+    #[test]
+    fn test_action_scopes_need_known_actions_and_auth() {
+        let report = check(&[
+            "--auth-action-scope",
+            "command.estop=operator:estop",
+            "--auth-action-scope",
+            "command.reboot=operator:reboot",
+        ]);
+        assert_eq!(report.problems.len(), 2, "{:?}", report.problems);
+        assert!(report.problems.iter().any(|p| p.contains("command.reboot")));
+        assert!(check(&[
+            "--auth-secret",
+            "s3cret",
+            "--auth-action-scope",
+            "recommendation=cortex:recommend",
+            "--auth-action-scope",
+            "command.estop = operator:estop",
+        ])
+        .problems
+        .is_empty());
+    }
+
+    #[test]
+    fn test_jitter_buckets_must_increase() {
+        let report = check(&["--jitter-buckets-us", "100,50,oops"]);
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(report.problems[0].starts_with("--jitter-buckets-us"));
+        assert!(check(&["--jitter-buckets-us", "100, 2500,10000"])
+            .problems
+            .is_empty());
+    }
+
+    #[test]
+    fn test_jitter_warn_must_be_below_crit() {
+        let report = check(&["--jitter-warn-us", "800", "--jitter-crit-us", "800"]);
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(
+            check(&["--jitter-warn-us", "300", "--jitter-crit-us", "800"])
+                .problems
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_wait_strategy_is_checked() {
+        let report = check(&["--wait-strategy", "yield"]);
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(report.problems[0].starts_with("--wait-strategy"));
+        assert!(check(&["--wait-strategy", "hybrid:200"])
+            .problems
+            .is_empty());
+    }
+
+    #[test]
+    fn test_estop_profile_is_checked() {
+        let report = check(&["--estop-profile", "ramp:0"]);
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(report.problems[0].starts_with("--estop-profile"));
+        assert!(check(&["--estop-profile", "ramp:2000"]).problems.is_empty());
+    }
+
+    #[test]
+    fn test_cn_allowlist_needs_client_certs() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("server.pem").display().to_string();
+        let key = dir.path().join("server.key").display().to_string();
+        let report = check(&[
+            "--tls-cert",
+            &cert,
+            "--tls-key",
+            &key,
+            "--tls-allowed-cn",
+            "agent",
+        ]);
+        assert_eq!(
+            report.problems,
+            ["--tls-allowed-cn needs --tls-require-client-cert"]
+        );
+    }
+
+    #[test]
+    fn test_thermal_soft_limit_must_be_below_hard_limit() {
+        let report = check(&["--temp-soft-limit-c", "90", "--thermal-derate-gain", "2"]);
+        assert_eq!(report.problems.len(), 2, "{:?}", report.problems);
+        assert!(report.problems[0].starts_with("--temp-soft-limit-c"));
+        assert!(report.problems[1].starts_with("--thermal-derate-gain"));
+        assert!(check(&["--temp-soft-limit-c", "70"]).problems.is_empty());
+    }
+
+    #[cfg(feature = "opcua")]
+    #[test]
+    fn test_opcua_namespace_uri_is_checked() {
+        let report = check(&[
+            "--opcua",
+            "--opcua-namespace-uri",
+            OPCUA_STANDARD_NAMESPACE,
+            "--opcua-application-uri",
+            "",
+        ]);
+        assert_eq!(report.problems.len(), 2, "{:?}", report.problems);
+        assert!(check(&[
+            "--opcua",
+            "--opcua-namespace-uri",
+            "http://example.com/plant/line1",
+            "--opcua-node-prefix",
+            "Line1.",
+        ])
+        .problems
+        .is_empty());
+    }
+
+    #[test]
+    fn test_unix_metrics_socket_checks_its_directory() {
+        let report = check(&["--metrics-addr", "unix:/nonexistent/dir/metrics.sock"]);
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(report.problems[0].contains("/nonexistent/dir"));
+    }
+}
@@ -1,26 +1,217 @@
-use std::path::PathBuf;
+#[cfg(feature = "opcua")]
+use crate::integrations::opcua_server::{DEFAULT_APPLICATION_URI, DEFAULT_NAMESPACE_URI};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Error)]
+pub enum ConfigFileError {
+    #[error("{}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("{}: schema_version {version} is not supported (1 to {CONFIG_SCHEMA_VERSION})", path.display())]
+    UnsupportedVersion { path: PathBuf, version: String },
+    #[error("{}: both '{deprecated}' and its replacement '{replacement}' are set", path.display())]
+    DuplicateKey {
+        path: PathBuf,
+        deprecated: &'static str,
+        replacement: &'static str,
+    },
+}
+
+/// Environment variable holding the auth secret
+pub const AUTH_SECRET_ENV: &str = "NEUROPLC_AUTH_SECRET";
+
+/// Environment variable holding the OPC UA password
+#[cfg(feature = "opcua")]
+pub const OPCUA_PASSWORD_ENV: &str = "NEUROPLC_OPCUA_PASSWORD";
+
+/// Read a secret file, dropping the line ending editors and `echo` leave
+fn read_secret(path: &str) -> Result<String, ConfigFileError> {
+    let text = std::fs::read_to_string(path).map_err(|source| ConfigFileError::Read {
+        path: PathBuf::from(path),
+        source,
+    })?;
+    Ok(text.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Current `--config` file layout, set as its `schema_version` key. Files
+/// without one are version 1.
+pub const CONFIG_SCHEMA_VERSION: u32 = 2;
+
+/// Keys renamed by a layout version, as `(version, old, new)`. The old name
+/// still loads with a warning, also in files already at that version; any
+/// other key the layout does not know is an error.
+const DEPRECATED_KEYS: &[(u32, &str, &str)] = &[(
+    2,
+    "bridge_max_rec_rate",
+    "bridge_max_recommendations_per_sec",
+)];
+
+/// Bring a parsed config file from `version` to [`CONFIG_SCHEMA_VERSION`],
+/// returning a warning for each deprecated key it rewrote.
+fn migrate_config(
+    path: &Path,
+    version: u32,
+    table: &mut toml::Table,
+) -> Result<Vec<String>, ConfigFileError> {
+    let mut warnings = Vec::new();
+    for &(renamed_in, deprecated, replacement) in DEPRECATED_KEYS {
+        let Some(value) = table.remove(deprecated) else {
+            continue;
+        };
+        if table.contains_key(replacement) {
+            return Err(ConfigFileError::DuplicateKey {
+                path: path.to_path_buf(),
+                deprecated,
+                replacement,
+            });
+        }
+        table.insert(replacement.to_string(), value);
+        let note = if version < renamed_in {
+            format!("migrated to schema_version {renamed_in}")
+        } else {
+            "still accepted".to_string()
+        };
+        warnings.push(format!(
+            "{}: '{deprecated}' is deprecated, use '{replacement}' ({note})",
+            path.display()
+        ));
+    }
+    Ok(warnings)
+}
+
+/// Runtime settings. A `--config` TOML file uses the field names as keys,
+/// plus `schema_version` ([`CONFIG_SCHEMA_VERSION`]);
+/// the one-shot actions (`--help`, `--verify-audit`, `--check-config`) are
+/// command line only.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct RuntimeConfig {
+    #[serde(skip)]
     pub show_help: bool,
+    #[serde(skip)]
+    pub verify_audit: Option<PathBuf>,
+    #[serde(skip)]
+    pub check_config: bool,
+    /// File given with `--config`, loaded beneath the command line flags
+    #[serde(skip)]
+    pub config_path: Option<PathBuf>,
+    /// Deprecated keys the `--config` file was migrated from
+    #[serde(skip)]
+    pub config_warnings: Vec<String>,
+    /// Command line arguments that matched no flag
+    #[serde(skip)]
+    pub unknown_args: Vec<String>,
     pub run_seconds: Option<u64>,
+    pub cycle_time_us: u64,
+    /// Between cycles: `busy-spin`, `sleep` or `hybrid:<US>`
+    pub wait_strategy: String,
+    pub max_jitter_us: u64,
+    pub jitter_trip_after: u32,
+    /// Cycle jitter that raises the warning alert level, `None` disables
+    pub jitter_warn_us: Option<u64>,
+    /// Cycle jitter that raises the critical alert level, `None` disables
+    pub jitter_crit_us: Option<u64>,
+    pub agent_timeout: String,
+    /// Operator e-stop: `immediate` (safety rated) or `ramp:<RPM_PER_S>`
+    pub estop_profile: String,
+    pub setpoint_deadband_rpm: f64,
+    /// Clamp agent targets to this speed; `max_speed_rpm` stays the hard limit
+    pub operational_max_speed_rpm: Option<f64>,
+    /// Scale agent targets down above this temperature; `None` disables
+    pub temp_soft_limit_c: Option<f64>,
+    /// Fraction of the target shed per degree above `temp_soft_limit_c`
+    pub thermal_derate_gain: f64,
+    pub rt_priority: Option<i32>,
+    pub cpu_affinity: Option<usize>,
+    pub recommendation_history: usize,
     pub bind_addr: String,
+    /// Addresses from `--bind` after the first
+    pub extra_bind_addrs: Vec<String>,
     pub bridge_enabled: bool,
     pub json_logs: bool,
+    /// `host:port`, or `unix:<path>` for a Unix domain socket
     pub metrics_addr: Option<String>,
+    /// Bearer token that enables `POST /metrics/reset` on the metrics server
+    pub metrics_admin_token: Option<String>,
+    /// How often the metrics updater copies the control loop's state
+    pub metrics_poll_ms: u64,
+    /// Cycle jitter histogram bucket bounds in µs; empty keeps the defaults
+    pub jitter_buckets_us: Vec<f64>,
     pub audit_path: Option<PathBuf>,
+    /// Write a JSON run summary here at shutdown
+    pub report_path: Option<PathBuf>,
+    pub audit_max_bytes: Option<u64>,
+    pub audit_max_files: usize,
     pub tls_cert: Option<String>,
     pub tls_key: Option<String>,
     pub tls_client_ca: Option<String>,
     pub tls_require_client_cert: bool,
+    pub tls_allowed_cns: Vec<String>,
+    /// Oldest TLS version accepted: `1.2` or `1.3`
+    pub tls_min_version: String,
+    /// IANA cipher suite names to offer; empty keeps the defaults
+    pub tls_cipher_suites: Vec<String>,
     pub auth_secret: Option<String>,
+    /// File holding the auth secret; read at startup over `auth_secret`
+    pub auth_secret_file: Option<String>,
+    pub auth_pubkey: Option<String>,
     pub auth_max_age_secs: u64,
     pub auth_issuer: String,
     pub auth_audience: String,
     pub auth_scope: Option<String>,
+    /// `ACTION=SCOPE` entries requiring SCOPE for one message type or
+    /// command in place of `auth_scope`
+    pub auth_action_scopes: Vec<String>,
+    pub auth_allow_legacy: bool,
+    /// Reject recommendations without a valid detached signature
+    pub require_signed_recommendations: bool,
+    pub auth_replay_state: Option<PathBuf>,
+    /// Make a reconnecting `client_id` continue above its highest sequence
+    pub sequence_floor: bool,
+    /// Persist the per-client sequence floors here; implies `sequence_floor`
+    pub sequence_floor_state: Option<PathBuf>,
+    pub auth_replay_snapshot_secs: u64,
     pub bridge_require_handshake: bool,
     pub bridge_protocol: String,
+    pub publish_mode: String,
+    pub bridge_idle_timeout_ms: u64,
+    pub bridge_ping_ms: Option<u64>,
+    /// Bridge serving loop pause; trades reply latency against idle CPU
+    pub bridge_poll_ms: u64,
+    /// File key `bridge_max_recommendations_per_sec` since schema_version 2
+    #[serde(rename = "bridge_max_recommendations_per_sec")]
+    pub bridge_max_rec_rate: u32,
+    pub min_confidence: f32,
+    pub compress_frames_over: Option<usize>,
+    pub duplicate_reasoning: String,
+    pub max_clock_skew_ms: u64,
+    /// Expected `reasoning_hash` digest length: 32 or 64 bytes
+    pub reasoning_hash_bytes: usize,
     pub modbus_addr: Option<String>,
+    pub modbus_required: bool,
+    /// Target speed register encoding, see [`neuro_io::TargetEncoding::parse`]
+    pub modbus_target: String,
+    /// First discrete input of the alarm flags, `None` reads no alarms
+    pub modbus_alarm_inputs: Option<u16>,
+    /// Unhealthy HAL time before the supervisor goes Safe, 0 disables
+    pub hal_fault_timeout_ms: u64,
+    pub hal_failover: bool,
+    /// Run the power-on self-test before the control loop starts
+    pub self_test: bool,
+    pub hal: Option<String>,
+    /// TOML file of [`core_spine::SimulatedMotorConfig`] parameters
+    pub sim_config: Option<PathBuf>,
+    pub sensor_voting: bool,
+    pub voting_modbus_addrs: Vec<String>,
     #[cfg(feature = "opcua")]
     pub opcua_enabled: bool,
     #[cfg(feature = "opcua")]
@@ -33,40 +224,120 @@ pub struct RuntimeConfig {
     pub opcua_user: Option<String>,
     #[cfg(feature = "opcua")]
     pub opcua_password: Option<String>,
+    /// File holding the OPC UA password; read at startup over `opcua_password`
+    #[cfg(feature = "opcua")]
+    pub opcua_password_file: Option<String>,
     #[cfg(feature = "opcua")]
     pub opcua_allow_write: bool,
     #[cfg(feature = "opcua")]
     pub opcua_pki_dir: String,
     #[cfg(feature = "opcua")]
     pub opcua_create_sample_keypair: bool,
+    #[cfg(feature = "opcua")]
+    pub opcua_update_interval_ms: u64,
+    /// Tag keys to expose as OPC UA nodes; empty exposes every tag
+    #[cfg(feature = "opcua")]
+    pub opcua_tags: Vec<String>,
+    #[cfg(feature = "opcua")]
+    pub opcua_namespace_uri: String,
+    #[cfg(feature = "opcua")]
+    pub opcua_application_uri: String,
+    /// Prepended to every OPC UA node name, e.g. `Line1.`
+    #[cfg(feature = "opcua")]
+    pub opcua_node_prefix: Option<String>,
+    #[cfg(feature = "otlp")]
+    pub otlp_endpoint: Option<String>,
+    #[cfg(feature = "ws")]
+    pub ws_bind: Option<String>,
     #[cfg(feature = "rerun")]
     pub rerun_enabled: bool,
     #[cfg(feature = "rerun")]
     pub rerun_save_path: Option<String>,
+    #[cfg(feature = "rerun")]
+    pub rerun_replay: Option<PathBuf>,
 }
 
 impl Default for RuntimeConfig {
     fn default() -> Self {
         Self {
             show_help: false,
+            verify_audit: None,
+            check_config: false,
+            config_path: None,
+            config_warnings: Vec::new(),
+            unknown_args: Vec::new(),
             run_seconds: None,
+            cycle_time_us: 1_000,
+            max_jitter_us: 500,
+            jitter_trip_after: 3,
+            jitter_warn_us: None,
+            jitter_crit_us: None,
+            agent_timeout: "hold".to_string(),
+            estop_profile: "immediate".to_string(),
+            wait_strategy: "busy-spin".to_string(),
+            setpoint_deadband_rpm: 0.0,
+            operational_max_speed_rpm: None,
+            temp_soft_limit_c: None,
+            thermal_derate_gain: 0.05,
+            rt_priority: None,
+            cpu_affinity: None,
+            recommendation_history: 0,
             bind_addr: "127.0.0.1:7000".to_string(),
+            extra_bind_addrs: Vec::new(),
             bridge_enabled: true,
             json_logs: false,
             metrics_addr: None,
+            metrics_admin_token: None,
+            metrics_poll_ms: 200,
+            jitter_buckets_us: Vec::new(),
             audit_path: None,
+            report_path: None,
+            audit_max_bytes: None,
+            audit_max_files: 5,
             tls_cert: None,
             tls_key: None,
             tls_client_ca: None,
             tls_require_client_cert: false,
+            tls_allowed_cns: Vec::new(),
+            tls_min_version: "1.2".to_string(),
+            tls_cipher_suites: Vec::new(),
             auth_secret: None,
+            auth_secret_file: None,
+            auth_pubkey: None,
             auth_max_age_secs: 300,
             auth_issuer: "neuroplc".to_string(),
             auth_audience: "neuroplc-spine".to_string(),
             auth_scope: None,
+            auth_action_scopes: Vec::new(),
+            auth_allow_legacy: false,
+            require_signed_recommendations: false,
+            auth_replay_state: None,
+            sequence_floor: false,
+            sequence_floor_state: None,
+            auth_replay_snapshot_secs: 5,
             bridge_require_handshake: false,
             bridge_protocol: "json".to_string(),
+            publish_mode: "fixed:100".to_string(),
+            bridge_idle_timeout_ms: 30_000,
+            bridge_ping_ms: None,
+            bridge_poll_ms: 5,
+            bridge_max_rec_rate: 100,
+            min_confidence: 0.0,
+            compress_frames_over: None,
+            duplicate_reasoning: "off".to_string(),
+            max_clock_skew_ms: 5_000,
+            reasoning_hash_bytes: 32,
             modbus_addr: None,
+            modbus_required: false,
+            modbus_target: "u16".to_string(),
+            modbus_alarm_inputs: None,
+            hal_fault_timeout_ms: 1_000,
+            hal_failover: false,
+            self_test: false,
+            hal: None,
+            sim_config: None,
+            sensor_voting: false,
+            voting_modbus_addrs: Vec::new(),
             #[cfg(feature = "opcua")]
             opcua_enabled: false,
             #[cfg(feature = "opcua")]
@@ -80,41 +351,198 @@ impl Default for RuntimeConfig {
             #[cfg(feature = "opcua")]
             opcua_password: None,
             #[cfg(feature = "opcua")]
+            opcua_password_file: None,
+            #[cfg(feature = "opcua")]
             opcua_allow_write: false,
             #[cfg(feature = "opcua")]
             opcua_pki_dir: "./pki-server".to_string(),
             #[cfg(feature = "opcua")]
             opcua_create_sample_keypair: true,
+            #[cfg(feature = "opcua")]
+            opcua_update_interval_ms: 200,
+            #[cfg(feature = "opcua")]
+            opcua_tags: Vec::new(),
+            #[cfg(feature = "opcua")]
+            opcua_namespace_uri: DEFAULT_NAMESPACE_URI.to_string(),
+            #[cfg(feature = "opcua")]
+            opcua_application_uri: DEFAULT_APPLICATION_URI.to_string(),
+            #[cfg(feature = "opcua")]
+            opcua_node_prefix: None,
+            #[cfg(feature = "otlp")]
+            otlp_endpoint: None,
+            #[cfg(feature = "ws")]
+            ws_bind: None,
             #[cfg(feature = "rerun")]
             rerun_enabled: false,
             #[cfg(feature = "rerun")]
             rerun_save_path: None,
+            #[cfg(feature = "rerun")]
+            rerun_replay: None,
         }
     }
 }
 
 impl RuntimeConfig {
-    pub fn from_env() -> Self {
+    pub fn from_env() -> Result<Self, ConfigFileError> {
         let args: Vec<String> = std::env::args().collect();
-        Self::from_args(&args)
+        Self::load(&args)?.resolve_secrets(|name| std::env::var(name).ok())
+    }
+
+    /// Fill in the secrets kept off the command line. A secret file wins over
+    /// the `NEUROPLC_AUTH_SECRET` / `NEUROPLC_OPCUA_PASSWORD` variable, which wins
+    /// over `--auth-secret` / `--opcua-password` or the config file value.
+    pub fn resolve_secrets(
+        mut self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigFileError> {
+        self.auth_secret = match &self.auth_secret_file {
+            Some(path) => Some(read_secret(path)?),
+            None => env(AUTH_SECRET_ENV).or(self.auth_secret),
+        };
+        #[cfg(feature = "opcua")]
+        {
+            self.opcua_password = match &self.opcua_password_file {
+                Some(path) => Some(read_secret(path)?),
+                None => env(OPCUA_PASSWORD_ENV).or(self.opcua_password),
+            };
+        }
+        Ok(self)
+    }
+
+    /// The `--config` file, if any, with the command line flags applied over it
+    pub fn load(args: &[String]) -> Result<Self, ConfigFileError> {
+        match Self::from_args(args).config_path {
+            Some(path) => Ok(Self::from_file(&path)?.with_args(args)),
+            None => Ok(Self::from_args(args)),
+        }
+    }
+
+    /// Read a TOML config file, migrating it from an older `schema_version`;
+    /// keys left out keep their defaults
+    pub fn from_file(path: &Path) -> Result<Self, ConfigFileError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigFileError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let parse_error = |source| ConfigFileError::Parse {
+            path: path.to_path_buf(),
+            source,
+        };
+        let mut table: toml::Table = text.parse().map_err(parse_error)?;
+        let version = match table.remove("schema_version") {
+            None => 1,
+            Some(toml::Value::Integer(v))
+                if (1..=i64::from(CONFIG_SCHEMA_VERSION)).contains(&v) =>
+            {
+                v as u32
+            }
+            Some(other) => {
+                return Err(ConfigFileError::UnsupportedVersion {
+                    path: path.to_path_buf(),
+                    version: other.to_string(),
+                })
+            }
+        };
+        let config_warnings = migrate_config(path, version, &mut table)?;
+        let config = RuntimeConfig::deserialize(toml::Value::Table(table)).map_err(parse_error)?;
+        Ok(RuntimeConfig {
+            config_warnings,
+            ..config
+        })
     }
 
+    /// Command line flags over the defaults, ignoring any `--config` file
     pub fn from_args(args: &[String]) -> Self {
-        let mut cfg = RuntimeConfig::default();
+        RuntimeConfig::default().with_args(args)
+    }
+
+    /// Apply command line flags over `self`. A repeatable flag replaces the
+    /// whole list it fills rather than appending to the file's entries.
+    fn with_args(self, args: &[String]) -> Self {
+        let mut cfg = self;
+        let mut bind_given = false;
+        let mut allowed_cn_given = false;
+        let mut action_scope_given = false;
+        let mut cipher_suite_given = false;
+        let mut voting_modbus_given = false;
+        #[cfg(feature = "opcua")]
+        let mut opcua_tag_given = false;
         let mut i = 1;
         while i < args.len() {
             match args[i].as_str() {
-                "--run-seconds" => {
-                    if i + 1 < args.len() {
-                        cfg.run_seconds = args[i + 1].parse::<u64>().ok();
-                        i += 1;
-                    }
+                "--run-seconds" if i + 1 < args.len() => {
+                    cfg.run_seconds = args[i + 1].parse::<u64>().ok();
+                    i += 1;
+                }
+                "--cycle-time-us" if i + 1 < args.len() => {
+                    cfg.cycle_time_us = args[i + 1].parse().unwrap_or(1_000);
+                    i += 1;
+                }
+                "--max-jitter-us" if i + 1 < args.len() => {
+                    cfg.max_jitter_us = args[i + 1].parse().unwrap_or(500);
+                    i += 1;
+                }
+                "--agent-timeout" if i + 1 < args.len() => {
+                    cfg.agent_timeout = args[i + 1].clone();
+                    i += 1;
+                }
+                "--wait-strategy" if i + 1 < args.len() => {
+                    cfg.wait_strategy = args[i + 1].clone();
+                    i += 1;
+                }
+                "--estop-profile" if i + 1 < args.len() => {
+                    cfg.estop_profile = args[i + 1].clone();
+                    i += 1;
                 }
-                "--bind" => {
-                    if i + 1 < args.len() {
+                "--setpoint-deadband-rpm" if i + 1 < args.len() => {
+                    cfg.setpoint_deadband_rpm = args[i + 1].parse().unwrap_or(0.0);
+                    i += 1;
+                }
+                "--operational-max-speed-rpm" if i + 1 < args.len() => {
+                    cfg.operational_max_speed_rpm = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--temp-soft-limit-c" if i + 1 < args.len() => {
+                    cfg.temp_soft_limit_c = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--thermal-derate-gain" if i + 1 < args.len() => {
+                    cfg.thermal_derate_gain = args[i + 1].parse().unwrap_or(0.05);
+                    i += 1;
+                }
+                "--jitter-trip-after" if i + 1 < args.len() => {
+                    cfg.jitter_trip_after = args[i + 1].parse().unwrap_or(3);
+                    i += 1;
+                }
+                "--jitter-warn-us" if i + 1 < args.len() => {
+                    cfg.jitter_warn_us = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--jitter-crit-us" if i + 1 < args.len() => {
+                    cfg.jitter_crit_us = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--rt-priority" if i + 1 < args.len() => {
+                    cfg.rt_priority = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--cpu-affinity" if i + 1 < args.len() => {
+                    cfg.cpu_affinity = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--recommendation-history" if i + 1 < args.len() => {
+                    cfg.recommendation_history = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+                "--bind" if i + 1 < args.len() => {
+                    if bind_given {
+                        cfg.extra_bind_addrs.push(args[i + 1].clone());
+                    } else {
                         cfg.bind_addr = args[i + 1].clone();
-                        i += 1;
+                        cfg.extra_bind_addrs.clear();
+                        bind_given = true;
                     }
+                    i += 1;
                 }
                 "--no-bridge" => {
                     cfg.bridge_enabled = false;
@@ -122,94 +550,234 @@ impl RuntimeConfig {
                 "--json-logs" => {
                     cfg.json_logs = true;
                 }
-                "--metrics-addr" => {
-                    if i + 1 < args.len() {
-                        cfg.metrics_addr = Some(args[i + 1].clone());
-                        i += 1;
-                    }
+                "--metrics-addr" if i + 1 < args.len() => {
+                    cfg.metrics_addr = Some(args[i + 1].clone());
+                    i += 1;
                 }
-                "--audit-log" => {
-                    if i + 1 < args.len() {
-                        cfg.audit_path = Some(PathBuf::from(&args[i + 1]));
-                        i += 1;
-                    }
+                "--metrics-poll-ms" if i + 1 < args.len() => {
+                    cfg.metrics_poll_ms = args[i + 1].parse().unwrap_or(200);
+                    i += 1;
                 }
-                "--tls-cert" => {
-                    if i + 1 < args.len() {
-                        cfg.tls_cert = Some(args[i + 1].clone());
-                        i += 1;
-                    }
+                "--jitter-buckets-us" if i + 1 < args.len() => {
+                    // An unparsable bound becomes NaN so validation reports it.
+                    cfg.jitter_buckets_us = args[i + 1]
+                        .split(',')
+                        .map(|bound| bound.trim().parse().unwrap_or(f64::NAN))
+                        .collect();
+                    i += 1;
                 }
-                "--tls-key" => {
-                    if i + 1 < args.len() {
-                        cfg.tls_key = Some(args[i + 1].clone());
-                        i += 1;
-                    }
+                "--metrics-admin-token" if i + 1 < args.len() => {
+                    cfg.metrics_admin_token = Some(args[i + 1].clone());
+                    i += 1;
                 }
-                "--tls-client-ca" => {
-                    if i + 1 < args.len() {
-                        cfg.tls_client_ca = Some(args[i + 1].clone());
-                        i += 1;
-                    }
+                "--audit-log" if i + 1 < args.len() => {
+                    cfg.audit_path = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                "--audit-max-bytes" if i + 1 < args.len() => {
+                    cfg.audit_max_bytes = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--audit-max-files" if i + 1 < args.len() => {
+                    // An unparsable count becomes 0 so startup rejects it.
+                    cfg.audit_max_files = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+                "--report" if i + 1 < args.len() => {
+                    cfg.report_path = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                "--tls-cert" if i + 1 < args.len() => {
+                    cfg.tls_cert = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--tls-key" if i + 1 < args.len() => {
+                    cfg.tls_key = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--tls-client-ca" if i + 1 < args.len() => {
+                    cfg.tls_client_ca = Some(args[i + 1].clone());
+                    i += 1;
                 }
                 "--tls-require-client-cert" => {
                     cfg.tls_require_client_cert = true;
                 }
-                "--auth-secret" => {
-                    if i + 1 < args.len() {
-                        cfg.auth_secret = Some(args[i + 1].clone());
-                        i += 1;
+                "--tls-allowed-cn" if i + 1 < args.len() => {
+                    if !allowed_cn_given {
+                        cfg.tls_allowed_cns.clear();
+                        allowed_cn_given = true;
                     }
+                    cfg.tls_allowed_cns.push(args[i + 1].clone());
+                    i += 1;
                 }
-                "--auth-max-age" => {
-                    if i + 1 < args.len() {
-                        cfg.auth_max_age_secs = args[i + 1].parse().unwrap_or(300);
-                        i += 1;
-                    }
+                "--tls-min-version" if i + 1 < args.len() => {
+                    cfg.tls_min_version = args[i + 1].clone();
+                    i += 1;
                 }
-                "--auth-issuer" => {
-                    if i + 1 < args.len() {
-                        cfg.auth_issuer = args[i + 1].clone();
-                        i += 1;
+                "--tls-cipher-suite" if i + 1 < args.len() => {
+                    if !cipher_suite_given {
+                        cfg.tls_cipher_suites.clear();
+                        cipher_suite_given = true;
                     }
+                    cfg.tls_cipher_suites.push(args[i + 1].clone());
+                    i += 1;
                 }
-                "--auth-audience" => {
-                    if i + 1 < args.len() {
-                        cfg.auth_audience = args[i + 1].clone();
-                        i += 1;
-                    }
+                "--auth-secret" if i + 1 < args.len() => {
+                    cfg.auth_secret = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--auth-secret-file" if i + 1 < args.len() => {
+                    cfg.auth_secret_file = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--auth-pubkey" if i + 1 < args.len() => {
+                    cfg.auth_pubkey = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--auth-max-age" if i + 1 < args.len() => {
+                    cfg.auth_max_age_secs = args[i + 1].parse().unwrap_or(300);
+                    i += 1;
+                }
+                "--auth-issuer" if i + 1 < args.len() => {
+                    cfg.auth_issuer = args[i + 1].clone();
+                    i += 1;
+                }
+                "--auth-audience" if i + 1 < args.len() => {
+                    cfg.auth_audience = args[i + 1].clone();
+                    i += 1;
                 }
-                "--auth-scope" => {
-                    if i + 1 < args.len() {
-                        cfg.auth_scope = Some(args[i + 1].clone());
-                        i += 1;
+                "--auth-scope" if i + 1 < args.len() => {
+                    cfg.auth_scope = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--auth-action-scope" if i + 1 < args.len() => {
+                    if !action_scope_given {
+                        cfg.auth_action_scopes.clear();
+                        action_scope_given = true;
                     }
+                    cfg.auth_action_scopes.push(args[i + 1].clone());
+                    i += 1;
+                }
+                "--auth-allow-legacy" => {
+                    cfg.auth_allow_legacy = true;
+                }
+                "--require-signed-recommendations" => {
+                    cfg.require_signed_recommendations = true;
+                }
+                "--sequence-floor" => {
+                    cfg.sequence_floor = true;
+                }
+                "--sequence-floor-state" if i + 1 < args.len() => {
+                    cfg.sequence_floor_state = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                "--auth-replay-state" if i + 1 < args.len() => {
+                    cfg.auth_replay_state = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                "--auth-replay-snapshot-secs" if i + 1 < args.len() => {
+                    cfg.auth_replay_snapshot_secs = args[i + 1].parse().unwrap_or(5);
+                    i += 1;
                 }
                 "--require-handshake" => {
                     cfg.bridge_require_handshake = true;
                 }
-                "--protocol" => {
-                    if i + 1 < args.len() {
-                        cfg.bridge_protocol = args[i + 1].clone();
-                        i += 1;
-                    }
+                "--protocol" if i + 1 < args.len() => {
+                    cfg.bridge_protocol = args[i + 1].clone();
+                    i += 1;
+                }
+                "--publish-mode" if i + 1 < args.len() => {
+                    cfg.publish_mode = args[i + 1].clone();
+                    i += 1;
+                }
+                "--idle-timeout-ms" if i + 1 < args.len() => {
+                    cfg.bridge_idle_timeout_ms = args[i + 1].parse().unwrap_or(30_000);
+                    i += 1;
+                }
+                "--ping-ms" if i + 1 < args.len() => {
+                    cfg.bridge_ping_ms = args[i + 1].parse().ok().filter(|ms| *ms > 0);
+                    i += 1;
+                }
+                "--bridge-poll-ms" if i + 1 < args.len() => {
+                    cfg.bridge_poll_ms = args[i + 1].parse().unwrap_or(5);
+                    i += 1;
+                }
+                "--max-rec-rate" if i + 1 < args.len() => {
+                    cfg.bridge_max_rec_rate = args[i + 1].parse().unwrap_or(100);
+                    i += 1;
+                }
+                "--compress-over" if i + 1 < args.len() => {
+                    cfg.compress_frames_over = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--max-clock-skew-ms" if i + 1 < args.len() => {
+                    cfg.max_clock_skew_ms = args[i + 1].parse().unwrap_or(5_000);
+                    i += 1;
+                }
+                "--duplicate-reasoning" if i + 1 < args.len() => {
+                    cfg.duplicate_reasoning = args[i + 1].clone();
+                    i += 1;
                 }
-                "--modbus" => {
-                    if i + 1 < args.len() {
-                        cfg.modbus_addr = Some(args[i + 1].clone());
-                        i += 1;
+                "--min-confidence" if i + 1 < args.len() => {
+                    cfg.min_confidence = args[i + 1].parse().unwrap_or(0.0);
+                    i += 1;
+                }
+                "--reasoning-hash-bytes" if i + 1 < args.len() => {
+                    cfg.reasoning_hash_bytes = args[i + 1].parse().unwrap_or(0);
+                    i += 1;
+                }
+                "--modbus" if i + 1 < args.len() => {
+                    cfg.modbus_addr = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--modbus-required" => {
+                    cfg.modbus_required = true;
+                }
+                "--modbus-target" if i + 1 < args.len() => {
+                    cfg.modbus_target = args[i + 1].clone();
+                    i += 1;
+                }
+                "--modbus-alarm-inputs" if i + 1 < args.len() => {
+                    cfg.modbus_alarm_inputs = args[i + 1].parse().ok();
+                    i += 1;
+                }
+                "--hal-fault-timeout-ms" if i + 1 < args.len() => {
+                    cfg.hal_fault_timeout_ms = args[i + 1].parse().unwrap_or(1_000);
+                    i += 1;
+                }
+                "--hal-failover" => {
+                    cfg.hal_failover = true;
+                }
+                "--self-test" => {
+                    cfg.self_test = true;
+                }
+                "--hal" if i + 1 < args.len() => {
+                    cfg.hal = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--sim-config" if i + 1 < args.len() => {
+                    cfg.sim_config = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                "--sensor-voting" => {
+                    cfg.sensor_voting = true;
+                }
+                "--voting-modbus" if i + 1 < args.len() => {
+                    if !voting_modbus_given {
+                        cfg.voting_modbus_addrs.clear();
+                        voting_modbus_given = true;
                     }
+                    cfg.voting_modbus_addrs.push(args[i + 1].clone());
+                    i += 1;
                 }
                 #[cfg(feature = "opcua")]
                 "--opcua" => {
                     cfg.opcua_enabled = true;
                 }
                 #[cfg(feature = "opcua")]
-                "--opcua-endpoint" => {
-                    if i + 1 < args.len() {
-                        cfg.opcua_endpoint = args[i + 1].clone();
-                        i += 1;
-                    }
+                "--opcua-endpoint" if i + 1 < args.len() => {
+                    cfg.opcua_endpoint = args[i + 1].clone();
+                    i += 1;
                 }
                 #[cfg(feature = "opcua")]
                 "--opcua-secure-only" => {
@@ -224,57 +792,131 @@ impl RuntimeConfig {
                     cfg.opcua_allow_anonymous = false;
                 }
                 #[cfg(feature = "opcua")]
-                "--opcua-user" => {
-                    if i + 1 < args.len() {
-                        cfg.opcua_user = Some(args[i + 1].clone());
-                        i += 1;
-                    }
+                "--opcua-user" if i + 1 < args.len() => {
+                    cfg.opcua_user = Some(args[i + 1].clone());
+                    i += 1;
                 }
                 #[cfg(feature = "opcua")]
-                "--opcua-password" => {
-                    if i + 1 < args.len() {
-                        cfg.opcua_password = Some(args[i + 1].clone());
-                        i += 1;
-                    }
+                "--opcua-password" if i + 1 < args.len() => {
+                    cfg.opcua_password = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                #[cfg(feature = "opcua")]
+                "--opcua-password-file" if i + 1 < args.len() => {
+                    cfg.opcua_password_file = Some(args[i + 1].clone());
+                    i += 1;
                 }
                 #[cfg(feature = "opcua")]
                 "--opcua-allow-write" => {
                     cfg.opcua_allow_write = true;
                 }
                 #[cfg(feature = "opcua")]
-                "--opcua-pki-dir" => {
-                    if i + 1 < args.len() {
-                        cfg.opcua_pki_dir = args[i + 1].clone();
-                        i += 1;
-                    }
+                "--opcua-pki-dir" if i + 1 < args.len() => {
+                    cfg.opcua_pki_dir = args[i + 1].clone();
+                    i += 1;
                 }
                 #[cfg(feature = "opcua")]
                 "--opcua-no-sample-keypair" => {
                     cfg.opcua_create_sample_keypair = false;
                 }
+                #[cfg(feature = "opcua")]
+                "--opcua-update-interval-ms" if i + 1 < args.len() => {
+                    cfg.opcua_update_interval_ms = args[i + 1].parse().unwrap_or(200);
+                    i += 1;
+                }
+                #[cfg(feature = "opcua")]
+                "--opcua-tag" if i + 1 < args.len() => {
+                    if !opcua_tag_given {
+                        cfg.opcua_tags.clear();
+                        opcua_tag_given = true;
+                    }
+                    cfg.opcua_tags.push(args[i + 1].clone());
+                    i += 1;
+                }
+                #[cfg(feature = "opcua")]
+                "--opcua-namespace-uri" if i + 1 < args.len() => {
+                    cfg.opcua_namespace_uri = args[i + 1].clone();
+                    i += 1;
+                }
+                #[cfg(feature = "opcua")]
+                "--opcua-application-uri" if i + 1 < args.len() => {
+                    cfg.opcua_application_uri = args[i + 1].clone();
+                    i += 1;
+                }
+                #[cfg(feature = "opcua")]
+                "--opcua-node-prefix" if i + 1 < args.len() => {
+                    cfg.opcua_node_prefix = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                #[cfg(feature = "otlp")]
+                "--otlp-endpoint" if i + 1 < args.len() => {
+                    cfg.otlp_endpoint = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                #[cfg(feature = "ws")]
+                "--ws-bind" if i + 1 < args.len() => {
+                    cfg.ws_bind = Some(args[i + 1].clone());
+                    i += 1;
+                }
                 #[cfg(feature = "rerun")]
                 "--rerun" => {
                     cfg.rerun_enabled = true;
                 }
                 #[cfg(feature = "rerun")]
-                "--rerun-save" => {
-                    if i + 1 < args.len() {
-                        cfg.rerun_enabled = true;
-                        cfg.rerun_save_path = Some(args[i + 1].clone());
-                        i += 1;
-                    }
+                "--rerun-replay" if i + 1 < args.len() => {
+                    cfg.rerun_replay = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                #[cfg(feature = "rerun")]
+                "--rerun-save" if i + 1 < args.len() => {
+                    cfg.rerun_enabled = true;
+                    cfg.rerun_save_path = Some(args[i + 1].clone());
+                    i += 1;
+                }
+                "--config" if i + 1 < args.len() => {
+                    cfg.config_path = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                "--verify-audit" if i + 1 < args.len() => {
+                    cfg.verify_audit = Some(PathBuf::from(&args[i + 1]));
+                    i += 1;
+                }
+                "--check-config" => {
+                    cfg.check_config = true;
                 }
                 "--help" | "-h" => {
                     cfg.show_help = true;
                     break;
                 }
-                _ => {}
+                // Includes a known flag missing its value.
+                unknown => cfg.unknown_args.push(unknown.to_string()),
             }
             i += 1;
         }
         cfg
     }
 
+    /// Configuration for redundant channel `index` (0-2) under sensor voting.
+    /// Channels 1 and 2 read from the `--voting-modbus` addresses when given.
+    pub fn voting_channel(&self, index: usize) -> RuntimeConfig {
+        let mut channel = self.clone();
+        if index > 0 {
+            if let Some(addr) = self.voting_modbus_addrs.get(index - 1) {
+                channel.modbus_addr = Some(addr.clone());
+            }
+        }
+        channel
+    }
+
+    /// The HAL backend to use: `--hal`, else `modbus` when `--modbus` is set
+    pub fn hal_backend(&self) -> &str {
+        match (&self.hal, &self.modbus_addr) {
+            (Some(name), _) => name,
+            (None, Some(_)) => "modbus",
+            (None, None) => "simulated",
+        }
+    }
+
     pub fn print_help() {
         println!(
             r#"NeuroPLC - Safety-first agentic industrial controller
@@ -283,24 +925,137 @@ USAGE:
     neuro-plc [OPTIONS]
 
 OPTIONS:
-    --bind <ADDR>           Bridge TCP bind address [default: 127.0.0.1:7000]
+    --config <PATH>         Load settings from a TOML file; flags given here override it.
+                            Older files (schema_version < 2) are migrated with a warning
+    --bind <ADDR>           Bridge TCP bind address, repeat to listen on several [default: 127.0.0.1:7000]
     --no-bridge             Disable the TCP bridge (standalone simulation)
     --run-seconds <SECS>    Run for a fixed duration then exit
+    --cycle-time-us <US>    Control loop cycle time in microseconds [default: 1000, min: 100]
+    --wait-strategy <STRATEGY>
+                            Between cycles: busy-spin (lowest jitter, a full core), sleep, or
+                            hybrid:<US> to sleep until US before the deadline and then spin
+                            [default: busy-spin]
+    --max-jitter-us <US>    Cycle overrun counted as a timing violation [default: 500]
+    --jitter-trip-after <N> Consecutive timing violations before the supervisor trips [default: 3]
+    --jitter-warn-us <US>   Cycle jitter that logs a warning and sets neuroplc_jitter_alert_level
+                            to 1 [default: off]
+    --jitter-crit-us <US>   Cycle jitter that logs an error and sets the alert level to 2
+                            [default: off]
+    --agent-timeout <POLICY> On stale recommendations: hold, ramp-to-zero:<RPM_PER_S> or
+                            ramp-to-safe:<RPM>:<RPM_PER_S> [default: hold]
+    --estop-profile <PROFILE>
+                            Operator e-stop: immediate (safety rated) or ramp:<RPM_PER_S> for a
+                            controlled stop; watchdog and alarm trips stay immediate
+                            [default: immediate]
+    --setpoint-deadband-rpm <RPM>
+                            Skip setpoint writes that change the command by less than RPM
+                            [default: 0 (off)]
+    --operational-max-speed-rpm <RPM>
+                            Clamp agent targets above RPM instead of rejecting them; targets
+                            above the hard speed limit are still rejected [default: off]
+    --temp-soft-limit-c <C> Above this temperature scale agent targets down to shed heat; the
+                            hard temperature limit still trips [default: off]
+    --thermal-derate-gain <FRACTION>
+                            Share of the target shed per degree above the soft limit
+                            [default: 0.05]
+    --rt-priority <1-99>    Run the control thread with SCHED_FIFO priority (Linux, needs privileges)
+    --cpu-affinity <CPU>    Pin the control thread to a CPU core (Linux)
+    --recommendation-history <N>
+                            Keep the last N agent recommendations for diagnostics [default: 0 (off)]
     --json-logs             Output logs in JSON format (for log aggregation)
-    --metrics-addr <ADDR>   Enable Prometheus metrics server on address (e.g., 0.0.0.0:9090)
+    --metrics-addr <ADDR>   Enable Prometheus metrics server on address (e.g., 0.0.0.0:9090,
+                            or unix:<PATH> for a Unix domain socket)
+    --metrics-admin-token <TOKEN>
+                            Enable POST /metrics/reset, authorized by this bearer token
+    --metrics-poll-ms <MS>  Copy control loop state into the metrics every MS [default: 200]
+    --jitter-buckets-us <LIST>
+                            Cycle jitter histogram bucket bounds, comma-separated
+                            [default: 1,5,10,25,50,100,250,500,1000,2500,5000,10000]
     --audit-log <PATH>      Enable audit logging to specified JSONL file
+    --audit-max-bytes <N>   Rotate the audit log once it exceeds N bytes
+    --audit-max-files <N>   Number of rotated audit files to keep, at least 1 [default: 5]
+    --report <PATH>         Write a JSON run summary (stats, recommendation outcomes, config hash)
+                            at shutdown
     --tls-cert <PATH>       Path to TLS certificate (PEM) for bridge security
     --tls-key <PATH>        Path to TLS private key (PEM)
     --tls-client-ca <PATH>  Path to client CA bundle (PEM) for mTLS
     --tls-require-client-cert Require client certificates for TLS
+    --tls-allowed-cn <NAME> Allow only client certs with this CN/SAN (repeatable)
+    --tls-min-version <1.2|1.3>
+                            Oldest TLS version accepted [default: 1.2]
+    --tls-cipher-suite <NAME>
+                            Offer only this cipher suite, by IANA name such as
+                            TLS13_AES_256_GCM_SHA384 (repeatable) [default: provider defaults]
     --auth-secret <STR>     Shared secret for HMAC token authentication
+                            (visible in the process list; prefer the file or env)
+    --auth-secret-file <PATH>
+                            Read the HMAC secret from a file, trailing newline
+                            dropped [env: NEUROPLC_AUTH_SECRET]
+    --auth-pubkey <PATH>    Ed25519 public key (PEM or raw) for asymmetric token auth
     --auth-max-age <SECS>   Maximum age for auth tokens in seconds [default: 300]
     --auth-issuer <STR>     Expected token issuer [default: neuroplc]
     --auth-audience <STR>   Expected token audience [default: neuroplc-spine]
-    --auth-scope <STR>      Required scope for recommendations (optional)
+    --auth-scope <STR>      Required scope for authenticated messages (optional)
+    --auth-action-scope <ACTION=SCOPE>
+                            Require SCOPE instead for one action: recommendation, batch,
+                            command.estop, command.set_limits or command.get_config (repeatable)
+    --auth-allow-legacy     Also accept legacy two-part (non-JWT) auth tokens
+    --require-signed-recommendations
+                            Reject recommendations without a detached signature made with the auth key
+    --sequence-floor        Keep each client's highest recommendation sequence across
+                            reconnects (keyed by token sub with auth, else client_id);
+                            a reconnecting client must continue above it
+    --sequence-floor-state <PATH>
+                            Persist the sequence floors here across restarts (implies --sequence-floor)
+    --auth-replay-state <PATH>
+                            Persist the token replay window here across restarts
+    --auth-replay-snapshot-secs <SECS>
+                            Interval between replay window snapshots; tokens accepted since
+                            the last one can be replayed after a crash [default: 5]
     --require-handshake     Require a protocol handshake before accepting recommendations
     --protocol <NAME>       Bridge protocol (json|proto) [default: json]
-    --modbus <ADDR>         Connect to real hardware via Modbus TCP (e.g. 192.168.1.10:502)
+    --publish-mode <MODE>   State frames every MS (fixed:<MS>) or on change, at most every MIN_MS and
+                            at least every MAX_MS (on-change:<MIN_MS>:<MAX_MS>) [default: fixed:100]
+    --idle-timeout-ms <MS>  Drop bridge clients silent for this long, 0 disables [default: 30000]
+    --ping-ms <MS>          Send keepalive ping frames to bridge clients (json only)
+    --bridge-poll-ms <MS>   Bridge loop pause: lower cuts reply latency, higher cuts idle CPU
+                            [default: 5, minimum: 1]
+    --max-rec-rate <N>      Max recommendations/sec per bridge client, 0 disables [default: 100]
+    --compress-over <BYTES> zstd-compress protobuf frames above this size for clients that negotiate it
+    --duplicate-reasoning <POLICY>
+                            Recommendations repeating the last reasoning_hash more than N times in a
+                            row: off, count:<N> (metric only) or reject:<N> [default: off]
+    --min-confidence <0-1>  Drop recommendations with lower confidence [default: 0 (accept all)]
+    --max-clock-skew-ms <MS>
+                            Reject recommendations issued further ahead of the spine clock
+                            [default: 5000]
+    --reasoning-hash-bytes <32|64>
+                            Digest length agents use for reasoning_hash: 32 (SHA-256) or 64 (SHA-512)
+                            [default: 32]
+    --modbus <ADDR>         Connect to real hardware via Modbus TCP (e.g. 192.168.1.10:502), or Modbus
+                            RTU with serial:<PATH>:<BAUD>[:<FRAMING>[:<SLAVE>]]
+                            (e.g. serial:/dev/ttyUSB0:9600:8N1:1)
+    --modbus-required       Exit if the Modbus HAL cannot start instead of falling back to simulation
+    --modbus-target <ENCODING>
+                            Target speed holding register: u16[:SCALE], u32[:SCALE] (two registers,
+                            high word first) or u32-swapped[:SCALE] (low word first) [default: u16]
+    --modbus-alarm-inputs <ADDR>
+                            First of four discrete inputs read as alarms (door open, overcurrent,
+                            e-stop button, drive fault); any set latches the controller Safe
+    --hal <NAME>            Hardware backend (simulated|modbus) [default: modbus if --modbus is set, else simulated]
+    --sim-config <PATH>     TOML file of simulated motor parameters (inertia, friction_coeff,
+                            thermal_mass, heat_generation, cooling_rate, ambient_temp_c,
+                            base_pressure_bar, pressure_coeff) and an optional [faults] table of
+                            seeded sensor noise, spikes and stuck-at readings
+    --hal-fault-timeout-ms <MS>
+                            Latch the supervisor Safe once the HAL is unhealthy this long, 0 disables
+                            [default: 1000]
+    --hal-failover          Also switch to the simulated motor on a HAL fault to keep the loop running
+    --self-test             Before starting, check the safety validator rejects known bad
+                            inputs, the HAL responds, the audit log is writable and metrics
+                            are registered; refuse to start if any check fails
+    --sensor-voting         Read three redundant HAL channels and vote 2-out-of-3
+    --voting-modbus <ADDR>  Modbus address of a redundant voting channel (give twice)
     --opcua                 Enable OPC UA server (requires 'opcua' feature)
     --opcua-endpoint <URL>  OPC UA endpoint URL [default: opc.tcp://0.0.0.0:4840]
     --opcua-secure-only     Disable insecure OPC UA endpoints (no SecurityMode=None)
@@ -308,11 +1063,29 @@ OPTIONS:
     --opcua-no-anon         Disable anonymous OPC UA user token
     --opcua-user <USER>     OPC UA username for password auth
     --opcua-password <PW>   OPC UA password for user auth
-    --opcua-allow-write     Allow OPC UA write access (default: read-only)
+    --opcua-password-file <PATH>
+                            Read the OPC UA password from a file, trailing newline
+                            dropped [env: NEUROPLC_OPCUA_PASSWORD]
+    --opcua-allow-write     Accept AgentTargetRPM setpoint writes (default: read-only)
     --opcua-pki-dir <PATH>  OPC UA PKI directory [default: ./pki-server]
     --opcua-no-sample-keypair Disable generating sample OPC UA keypair
+    --opcua-update-interval-ms <MS>
+                            Interval between OPC UA node updates, at least 10 [default: 200]
+    --opcua-tag <KEY>       Expose only this tag as an OPC UA node (repeatable, e.g. motor_speed_rpm)
+                            [default: all tags]
+    --opcua-namespace-uri <URI>
+                            Namespace URI for the NeuroPLC nodes [default: urn:neuroplc:opcua]
+    --opcua-application-uri <URI>
+                            OPC UA application and product URI [default: urn:neuroplc:opcua]
+    --opcua-node-prefix <PREFIX>
+                            Prepend PREFIX to every OPC UA node name, e.g. Line1.
+    --otlp-endpoint <URL>   Push metrics to an OTLP/HTTP collector, e.g. http://host:4318/v1/metrics (requires 'otlp' feature)
+    --ws-bind <ADDR>        Serve the bridge protocol over WebSocket for browser dashboards (requires 'ws' feature)
     --rerun                 Enable Rerun visualization (requires 'rerun' feature)
     --rerun-save <PATH>     Save Rerun recording to file
+    --rerun-replay <PATH>   Log an audit JSONL file to Rerun and exit (honors --rerun-save)
+    --verify-audit <PATH>   Verify the hash chain of an audit log file and exit
+    --check-config          Validate the configuration, print what would be enabled and exit
     -h, --help              Print this help message
 
 ENVIRONMENT VARIABLES:
@@ -327,7 +1100,253 @@ EXAMPLES:
 
     # Short test run
     neuro-plc --run-seconds 10 --no-bridge
+
+    # Validate a deployment's flags without starting the controller
+    neuro-plc --check-config --tls-cert server.pem --tls-key server.key --auth-secret $SECRET
+
+    # Settings from a file, with one flag overridden
+    neuro-plc --config /etc/neuroplc/neuroplc.toml --run-seconds 10
+
+    # Check an audit log for tampering
+    neuro-plc --verify-audit /var/log/neuroplc/audit.jsonl
 "#
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn args(flags: &[&str]) -> Vec<String> {
+        std::iter::once("neuro-plc")
+            .chain(flags.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    fn write_config(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_file_matches_equivalent_flags() {
+        let file = write_config(
+            r#"
+            run_seconds = 10
+            cycle_time_us = 500
+            agent_timeout = "ramp-to-zero:200"
+            bind_addr = "0.0.0.0:7000"
+            extra_bind_addrs = ["[::]:7000"]
+            bridge_enabled = true
+            json_logs = true
+            metrics_addr = "0.0.0.0:9090"
+            audit_path = "/var/log/neuroplc/audit.jsonl"
+            tls_allowed_cns = ["agent-a", "agent-b"]
+            auth_secret = "s3cret"
+            auth_max_age_secs = 60
+            publish_mode = "on-change:10:1000"
+            min_confidence = 0.5
+            sensor_voting = true
+            voting_modbus_addrs = ["10.0.0.2:502", "10.0.0.3:502"]
+            "#,
+        );
+        let from_file = RuntimeConfig::from_file(file.path()).unwrap();
+        let from_flags = RuntimeConfig::from_args(&args(&[
+            "--run-seconds",
+            "10",
+            "--cycle-time-us",
+            "500",
+            "--agent-timeout",
+            "ramp-to-zero:200",
+            "--bind",
+            "0.0.0.0:7000",
+            "--bind",
+            "[::]:7000",
+            "--json-logs",
+            "--metrics-addr",
+            "0.0.0.0:9090",
+            "--audit-log",
+            "/var/log/neuroplc/audit.jsonl",
+            "--tls-allowed-cn",
+            "agent-a",
+            "--tls-allowed-cn",
+            "agent-b",
+            "--auth-secret",
+            "s3cret",
+            "--auth-max-age",
+            "60",
+            "--publish-mode",
+            "on-change:10:1000",
+            "--min-confidence",
+            "0.5",
+            "--sensor-voting",
+            "--voting-modbus",
+            "10.0.0.2:502",
+            "--voting-modbus",
+            "10.0.0.3:502",
+        ]));
+        assert_eq!(from_file, from_flags);
+    }
+
+    #[test]
+    fn test_flags_override_file() {
+        let file = write_config(
+            r#"
+            cycle_time_us = 500
+            bind_addr = "0.0.0.0:7000"
+            extra_bind_addrs = ["[::]:7000"]
+            tls_allowed_cns = ["agent-a"]
+            "#,
+        );
+        let path = file.path().to_str().unwrap();
+        let config = RuntimeConfig::load(&args(&[
+            "--config",
+            path,
+            "--bind",
+            "127.0.0.1:7100",
+            "--tls-allowed-cn",
+            "agent-b",
+        ]))
+        .unwrap();
+        assert_eq!(config.cycle_time_us, 500);
+        assert_eq!(config.bind_addr, "127.0.0.1:7100");
+        assert!(config.extra_bind_addrs.is_empty());
+        assert_eq!(config.tls_allowed_cns, vec!["agent-b".to_string()]);
+        assert_eq!(config.config_path.as_deref(), Some(file.path()));
+    }
+
+    #[test]
+    fn test_unknown_key_rejected() {
+        let file = write_config("cycle_time_us = 500\nbind_adress = \"0.0.0.0:7000\"\n");
+        let err = RuntimeConfig::from_file(file.path()).unwrap_err();
+        assert!(matches!(err, ConfigFileError::Parse { .. }));
+        assert!(err.to_string().contains("bind_adress"), "{err}");
+
+        // One-shot actions are command line only.
+        let file = write_config("check_config = true\n");
+        assert!(RuntimeConfig::from_file(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_v1_file_migrates_to_current_schema() {
+        let v1 = write_config("cycle_time_us = 500\nbridge_max_rec_rate = 20\n");
+        let migrated = RuntimeConfig::from_file(v1.path()).unwrap();
+        assert_eq!(migrated.bridge_max_rec_rate, 20);
+        assert_eq!(migrated.config_warnings.len(), 1);
+        assert!(migrated.config_warnings[0].contains("bridge_max_rec_rate"));
+        assert!(migrated.config_warnings[0].contains("migrated to schema_version 2"));
+
+        let v2 = write_config(
+            "schema_version = 2\ncycle_time_us = 500\nbridge_max_recommendations_per_sec = 20\n",
+        );
+        let current = RuntimeConfig::from_file(v2.path()).unwrap();
+        assert!(current.config_warnings.is_empty());
+        assert_eq!(
+            current,
+            RuntimeConfig {
+                config_warnings: Vec::new(),
+                ..migrated
+            }
+        );
+
+        // The deprecated name stays allowed, with a warning, but not twice.
+        let both = write_config(
+            "schema_version = 2\nbridge_max_rec_rate = 20\nbridge_max_recommendations_per_sec = 30\n",
+        );
+        assert!(matches!(
+            RuntimeConfig::from_file(both.path()),
+            Err(ConfigFileError::DuplicateKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_schema_version_rejected() {
+        for version in ["3", "0", "\"2\""] {
+            let file = write_config(&format!("schema_version = {version}\n"));
+            let err = RuntimeConfig::from_file(file.path()).unwrap_err();
+            assert!(
+                matches!(err, ConfigFileError::UnsupportedVersion { .. }),
+                "{version}: {err}"
+            );
+        }
+        // Unknown keys are still errors in a migrated file.
+        let file = write_config("schema_version = 1\nbind_adress = \"0.0.0.0:7000\"\n");
+        assert!(matches!(
+            RuntimeConfig::from_file(file.path()),
+            Err(ConfigFileError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unknown_flags_are_collected() {
+        let config = RuntimeConfig::from_args(&args(&["--cycle-time", "500", "--bind"]));
+        assert_eq!(config.unknown_args, ["--cycle-time", "500", "--bind"]);
+        assert!(RuntimeConfig::from_args(&args(&["--cycle-time-us", "500"]))
+            .unknown_args
+            .is_empty());
+    }
+
+    #[test]
+    fn test_auth_secret_loaded_from_file() {
+        let secret = write_config("from-file\r\n");
+        let path = secret.path().to_str().unwrap();
+        let config = RuntimeConfig::from_args(&args(&[
+            "--auth-secret",
+            "from-arg",
+            "--auth-secret-file",
+            path,
+        ]))
+        .resolve_secrets(|_| Some("from-env".to_string()))
+        .unwrap();
+        assert_eq!(config.auth_secret.as_deref(), Some("from-file"));
+
+        let missing =
+            RuntimeConfig::from_args(&args(&["--auth-secret-file", "/nonexistent/secret"]))
+                .resolve_secrets(|_| None);
+        assert!(matches!(missing, Err(ConfigFileError::Read { .. })));
+    }
+
+    #[test]
+    fn test_auth_secret_env_and_arg() {
+        let from_arg = RuntimeConfig::from_args(&args(&["--auth-secret", "from-arg"]))
+            .resolve_secrets(|_| None)
+            .unwrap();
+        assert_eq!(from_arg.auth_secret.as_deref(), Some("from-arg"));
+
+        let from_env = RuntimeConfig::from_args(&args(&["--auth-secret", "from-arg"]))
+            .resolve_secrets(|name| (name == AUTH_SECRET_ENV).then(|| "from-env".to_string()))
+            .unwrap();
+        assert_eq!(from_env.auth_secret.as_deref(), Some("from-env"));
+
+        let unset = RuntimeConfig::from_args(&args(&[]))
+            .resolve_secrets(|_| None)
+            .unwrap();
+        assert!(unset.auth_secret.is_none());
+    }
+
+    #[cfg(feature = "opcua")]
+    #[test]
+    fn test_opcua_password_loaded_from_file() {
+        let password = write_config("pw\n");
+        let config = RuntimeConfig::from_args(&args(&[
+            "--opcua-password",
+            "from-arg",
+            "--opcua-password-file",
+            password.path().to_str().unwrap(),
+        ]))
+        .resolve_secrets(|_| None)
+        .unwrap();
+        assert_eq!(config.opcua_password.as_deref(), Some("pw"));
+    }
+
+    #[test]
+    fn test_missing_file_reported() {
+        let err =
+            RuntimeConfig::load(&args(&["--config", "/nonexistent/neuroplc.toml"])).unwrap_err();
+        assert!(matches!(err, ConfigFileError::Read { .. }));
+    }
+}
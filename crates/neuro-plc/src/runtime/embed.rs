@@ -0,0 +1,682 @@
+//! Embedding NeuroPLC in another Rust process.
+//!
+//! [`NeuroPlc`] owns the control, bridge and observability threads that the
+//! `neuro-plc` binary runs, without touching process-level state: it installs
+//! no tracing subscriber and no signal handlers. Metrics still live in the
+//! process-global Prometheus registry, so run one controller per process.
+
+#[cfg(feature = "opcua")]
+use crate::integrations::opcua_server::run_opcua;
+#[cfg(feature = "rerun")]
+use crate::integrations::rerun_viz::{run_rerun, RerunConfig};
+#[cfg(feature = "opcua")]
+use crate::runtime::app::opcua_config;
+use crate::runtime::app::{
+    build_bridge_config, build_hal, hash_runtime_config, init_audit_logger, open_sequence_floors,
+    parse_action_scope, with_hal_failover,
+};
+use crate::runtime::config::{ConfigFileError, RuntimeConfig};
+use crate::runtime::hal::{HalError, HalRegistry};
+use crate::runtime::realtime;
+use crate::runtime::report;
+use crate::runtime::self_test;
+use crate::runtime::telemetry;
+use core_spine::{
+    AgentRecommendation, AgentTimeoutPolicy, ControlConfig, EStopProfile, ExecutionStats,
+    IronThread, ProcessSnapshot, StateExchange, ThermalDerate, TimeBase, WaitStrategy,
+};
+use neuro_io::audit::{hash_bytes, AuditEventType, AuditLogger, SafetyTransitionDetails};
+use neuro_io::auth::{load_ed25519_public_key, TokenValidator};
+use neuro_io::bridge::run_bridge;
+use neuro_io::bridge::BridgeConfig;
+use neuro_io::metrics::BuildInfo;
+#[cfg(feature = "ws")]
+use neuro_io::ws::run_ws_bridge;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Error)]
+pub enum StartError {
+    #[error(transparent)]
+    Hal(#[from] HalError),
+    #[error(transparent)]
+    Secret(#[from] ConfigFileError),
+    #[error("audit log {}: {source}", path.display())]
+    Audit {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("sequence floor state {}: {source}", path.display())]
+    SequenceFloors {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("self-test failed: {}", failed_checks(.0))]
+    SelfTest(self_test::SelfTestReport),
+    #[error("--auth-action-scope '{0}' is not ACTION=SCOPE with a known action")]
+    ActionScope(String),
+    #[error("{option}: {reason}")]
+    InvalidOption {
+        option: &'static str,
+        reason: String,
+    },
+}
+
+/// Log and build a [`StartError::InvalidOption`]
+fn invalid_option(option: &'static str, reason: impl Into<String>) -> StartError {
+    let reason = reason.into();
+    error!(option, reason = %reason, "Invalid configuration");
+    StartError::InvalidOption { option, reason }
+}
+
+fn failed_checks(report: &self_test::SelfTestReport) -> String {
+    report
+        .failed
+        .iter()
+        .map(|(check, reason)| format!("{check}: {reason}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Configures a [`NeuroPlc`] before its threads start.
+pub struct NeuroPlcBuilder {
+    config: RuntimeConfig,
+    registry: HalRegistry,
+    stop: Arc<AtomicBool>,
+}
+
+impl NeuroPlcBuilder {
+    /// Settings as the binary would take them; defaults otherwise. The
+    /// one-shot CLI actions (`show_help`, `check_config`, ...) are ignored.
+    pub fn config(self, config: RuntimeConfig) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Resolve `config.hal` from `registry` instead of the built-in backends
+    pub fn hal_registry(self, registry: HalRegistry) -> Self {
+        Self { registry, ..self }
+    }
+
+    /// Share a stop flag with the host, e.g. one set by its signal handlers.
+    /// Setting it winds the controller down as [`NeuroPlc::shutdown`] does.
+    pub fn stop_flag(self, stop: Arc<AtomicBool>) -> Self {
+        Self { stop, ..self }
+    }
+
+    /// Build the HAL and spawn the control loop and configured services
+    pub fn start(self) -> Result<NeuroPlc, StartError> {
+        NeuroPlc::start(self)
+    }
+}
+
+/// A running controller. Dropping it stops and joins its threads.
+pub struct NeuroPlc {
+    exchange: Arc<StateExchange>,
+    timebase: TimeBase,
+    stop: Arc<AtomicBool>,
+    iron: Option<thread::JoinHandle<ExecutionStats>>,
+    /// Joined in order after the control loop
+    services: Vec<thread::JoinHandle<()>>,
+    audit_logger: Option<Arc<AuditLogger>>,
+    /// `--report` path and the config hash it records
+    report: Option<(PathBuf, String)>,
+    /// `--metrics-addr unix:<path>` socket file, removed on shutdown
+    metrics_socket: Option<PathBuf>,
+    #[cfg(feature = "otlp")]
+    otlp_exporter: Option<neuro_io::otlp::OtlpExporter>,
+}
+
+impl NeuroPlc {
+    pub fn builder() -> NeuroPlcBuilder {
+        NeuroPlcBuilder {
+            config: RuntimeConfig::default(),
+            registry: HalRegistry::with_builtins(),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn start(builder: NeuroPlcBuilder) -> Result<Self, StartError> {
+        let NeuroPlcBuilder {
+            config,
+            registry,
+            stop,
+        } = builder;
+        for warning in &config.config_warnings {
+            warn!("{warning}");
+        }
+
+        // Embedders may build the config without `from_env`; read secret
+        // files here so a set `auth_secret_file` never leaves auth off.
+        let config = config
+            .resolve_secrets(|name| std::env::var(name).ok())
+            .inspect_err(|e| error!(error = %e, "Failed to read secret"))?;
+        if config
+            .auth_secret
+            .as_deref()
+            .is_some_and(|secret| secret.trim().is_empty())
+        {
+            return Err(invalid_option("--auth-secret", "is empty"));
+        }
+
+        let hal_backend = config.hal_backend();
+        if !registry.contains(hal_backend) {
+            return Err(HalError::UnknownBackend {
+                name: hal_backend.to_string(),
+                available: registry.names().join(", "),
+            }
+            .into());
+        }
+
+        // Fail closed: a mistyped entry would leave its action guarded only
+        // by the global required scope.
+        if let Some(entry) = config
+            .auth_action_scopes
+            .iter()
+            .find(|entry| parse_action_scope(entry).is_none())
+        {
+            error!(entry = %entry, "Invalid --auth-action-scope");
+            return Err(StartError::ActionScope(entry.clone()));
+        }
+
+        // Auth must start with the key it was given, or not at all.
+        if config.auth_secret.is_some() && config.auth_pubkey.is_some() {
+            return Err(invalid_option(
+                "--auth-pubkey",
+                "cannot be combined with --auth-secret",
+            ));
+        }
+        if let Some(path) = &config.auth_pubkey {
+            load_ed25519_public_key(Path::new(path))
+                .map_err(|e| invalid_option("--auth-pubkey", format!("{path}: {e}")))?;
+        }
+        if config.require_signed_recommendations
+            && config.auth_secret.is_none()
+            && config.auth_pubkey.is_none()
+        {
+            return Err(invalid_option(
+                "--require-signed-recommendations",
+                "needs --auth-secret or --auth-pubkey",
+            ));
+        }
+
+        if !config.tls_allowed_cns.is_empty() && !config.tls_require_client_cert {
+            return Err(invalid_option(
+                "--tls-allowed-cn",
+                "needs --tls-require-client-cert",
+            ));
+        }
+
+        // Rotating with no kept files would delete the active audit log.
+        if config.audit_path.is_some()
+            && config.audit_max_bytes.is_some()
+            && config.audit_max_files == 0
+        {
+            return Err(invalid_option(
+                "--audit-max-files",
+                "must be a number of at least 1",
+            ));
+        }
+
+        // A mistyped policy or profile must not silently fall back to a
+        // different behaviour on agent loss or e-stop.
+        let agent_timeout = AgentTimeoutPolicy::parse(&config.agent_timeout).ok_or_else(|| {
+            invalid_option(
+                "--agent-timeout",
+                format!(
+                    "'{}' is not hold, ramp-to-zero:<RATE> or ramp-to-safe:<RPM>:<RATE>",
+                    config.agent_timeout
+                ),
+            )
+        })?;
+        let estop_profile = EStopProfile::parse(&config.estop_profile).ok_or_else(|| {
+            invalid_option(
+                "--estop-profile",
+                format!("'{}' is not immediate or ramp:<RATE>", config.estop_profile),
+            )
+        })?;
+        let wait_strategy = WaitStrategy::parse(&config.wait_strategy).ok_or_else(|| {
+            invalid_option(
+                "--wait-strategy",
+                format!(
+                    "'{}' is not busy-spin, sleep or hybrid:<US>",
+                    config.wait_strategy
+                ),
+            )
+        })?;
+
+        // Initialize metrics
+        if !config.jitter_buckets_us.is_empty() {
+            if let Err(e) =
+                neuro_io::metrics::configure_cycle_jitter_buckets(&config.jitter_buckets_us)
+            {
+                warn!(error = %e, "Ignoring --jitter-buckets-us");
+            }
+        }
+        telemetry::init();
+
+        // Metrics exporters
+        neuro_io::metrics::HEALTH.set_bridge_enabled(config.bridge_enabled);
+        #[cfg(feature = "opcua")]
+        neuro_io::metrics::HEALTH.set_opcua_enabled(config.opcua_enabled);
+        #[cfg(feature = "otlp")]
+        let otlp_exporter = telemetry::start_otlp_exporter(&config.otlp_endpoint);
+        #[cfg(feature = "otlp")]
+        let metrics_enabled = config.metrics_addr.is_some() || otlp_exporter.is_some();
+        #[cfg(not(feature = "otlp"))]
+        let metrics_enabled = config.metrics_addr.is_some();
+
+        let control_config = ControlConfig {
+            cycle_time: realtime::validated_cycle_time(config.cycle_time_us),
+            max_jitter_us: config.max_jitter_us,
+            jitter_trip_after: config.jitter_trip_after,
+            jitter_warn_us: config.jitter_warn_us,
+            jitter_crit_us: config.jitter_crit_us,
+            agent_timeout,
+            hal_fault_timeout: (config.hal_fault_timeout_ms > 0)
+                .then(|| Duration::from_millis(config.hal_fault_timeout_ms)),
+            setpoint_deadband_rpm: config.setpoint_deadband_rpm.max(0.0),
+            operational_max_speed_rpm: config.operational_max_speed_rpm,
+            wait_strategy,
+            estop_profile,
+            thermal_derate: config
+                .temp_soft_limit_c
+                .and_then(|soft| ThermalDerate::new(soft, config.thermal_derate_gain)),
+            ..ControlConfig::default()
+        };
+        let exchange = Arc::new(StateExchange::with_history(
+            control_config.recommendation_timeout.as_micros() as u64,
+            config.recommendation_history,
+        ));
+        let build = metrics_enabled.then(|| build_info(&config));
+        let timebase = TimeBase::new();
+
+        // Initialize audit logger if enabled
+        let audit_logger = init_audit_logger(&config).map_err(|(path, source)| {
+            error!(error = %source, path = %path.display(), "Failed to initialize audit logger");
+            StartError::Audit { path, source }
+        })?;
+
+        // Log startup
+        if let Some(ref logger) = audit_logger {
+            let build = build.clone().unwrap_or_else(|| build_info(&config));
+            let _ = logger.log_event(
+                timebase.now_us(),
+                timebase.unix_us(),
+                AuditEventType::SystemStart,
+                serde_json::json!({
+                    "version": build.version,
+                    "bridge_enabled": config.bridge_enabled,
+                    "metrics_enabled": metrics_enabled,
+                    "config_hash": build.config_hash,
+                    "binary_sha256": build.binary_sha256,
+                    "cortex_manifest_sha256": build.cortex_manifest_sha256,
+                }),
+            );
+        }
+
+        let sequence_floors = open_sequence_floors(&config).map_err(|(path, source)| {
+            error!(error = %source, path = %path.display(), "Failed to load sequence floors");
+            StartError::SequenceFloors { path, source }
+        })?;
+
+        let io = build_hal(&registry, &config, audit_logger.as_deref(), timebase)?;
+        let io = with_hal_failover(io, &config, audit_logger.clone(), timebase);
+
+        if config.self_test {
+            if !self_test::wait_for_healthy(&*io, self_test::HAL_READY_TIMEOUT) {
+                warn!(timeout = ?self_test::HAL_READY_TIMEOUT, "HAL not healthy yet");
+            }
+            let audit = config.audit_path.as_deref().zip(audit_logger.as_deref());
+            let report = self_test::run_self_test(&control_config.safety_limits, &*io, audit);
+            if let Some(ref logger) = audit_logger {
+                let _ = logger.log_event(
+                    timebase.now_us(),
+                    timebase.unix_us(),
+                    AuditEventType::SelfTest,
+                    report.to_json(),
+                );
+            }
+            if !report.ok() {
+                for (check, reason) in &report.failed {
+                    error!(check, reason = %reason, "Self-test check failed");
+                }
+                if let Some(ref logger) = audit_logger {
+                    logger.flush_and_join();
+                }
+                return Err(StartError::SelfTest(report));
+            }
+            info!(checks = ?report.passed, "Self-test passed");
+        }
+
+        // Bound only once nothing can fail: the server thread is never
+        // joined and would keep the port after an early return.
+        let _metrics_handle = telemetry::start_metrics_server(
+            &config.metrics_addr,
+            config.metrics_admin_token.clone(),
+            &exchange,
+            build,
+        );
+
+        let mut services = Vec::new();
+        if metrics_enabled {
+            services.push(telemetry::start_metrics_updater(
+                Arc::clone(&exchange),
+                Arc::clone(&stop),
+                Duration::from_millis(config.metrics_poll_ms),
+            ));
+        } else if config.jitter_warn_us.is_some() || config.jitter_crit_us.is_some() {
+            // Nothing else reads the threshold counts without metrics.
+            services.push(telemetry::start_jitter_alerts(
+                Arc::clone(&exchange),
+                Arc::clone(&stop),
+                Duration::from_millis(config.metrics_poll_ms),
+            ));
+        }
+
+        let exchange_iron = Arc::clone(&exchange);
+        let stop_iron = Arc::clone(&stop);
+        let timebase_iron = timebase;
+        let control_config_iron = control_config.clone();
+        let rt_priority = config.rt_priority;
+        let cpu_affinity = config.cpu_affinity;
+
+        info!(
+            cycle_time_us = control_config.cycle_time.as_micros() as u64,
+            max_jitter_us = control_config.max_jitter_us,
+            jitter_trip_after = control_config.jitter_trip_after,
+            agent_timeout = ?control_config.agent_timeout,
+            hal_fault_timeout = ?control_config.hal_fault_timeout,
+            setpoint_deadband_rpm = control_config.setpoint_deadband_rpm,
+            operational_max_speed_rpm = ?control_config.operational_max_speed_rpm,
+            thermal_derate = ?control_config.thermal_derate,
+            estop_profile = ?control_config.estop_profile,
+            wait_strategy = ?control_config.wait_strategy,
+            max_speed_rpm = control_config.safety_limits.max_speed_rpm,
+            max_temp_c = control_config.safety_limits.max_temp_c,
+            "Starting IronThread control loop"
+        );
+
+        let audit_iron = audit_logger.clone();
+        let iron_handle = thread::spawn(move || {
+            realtime::apply_to_current_thread(rt_priority, cpu_affinity);
+
+            let mut iron = IronThread::new(io, control_config_iron, exchange_iron, timebase_iron);
+            if let Some(logger) = audit_iron {
+                iron = iron.with_transition_hook(move |timestamp_us, transition| {
+                    let details = SafetyTransitionDetails::from(transition);
+                    let _ = logger.log_event(
+                        timestamp_us,
+                        timebase_iron.unix_us(),
+                        AuditEventType::SafetyTransition,
+                        serde_json::to_value(details).unwrap_or_default(),
+                    );
+                });
+            }
+            iron.run(&stop_iron);
+            iron.stats().clone()
+        });
+
+        // One validator across transports, so a token accepted over TCP is
+        // a replay over WebSocket and one replay snapshot is written.
+        let token_validator = {
+            let auth = build_bridge_config(&config).auth;
+            #[cfg(feature = "ws")]
+            let serving = config.bridge_enabled || config.ws_bind.is_some();
+            #[cfg(not(feature = "ws"))]
+            let serving = config.bridge_enabled;
+            (serving && auth.enabled)
+                .then(|| Arc::new(Mutex::new(TokenValidator::from_config(&auth))))
+        };
+
+        if config.bridge_enabled {
+            let exchange_bridge = Arc::clone(&exchange);
+            let stop_bridge = Arc::clone(&stop);
+            let timebase_bridge = timebase;
+            let audit_bridge = audit_logger.clone();
+            let bridge_config = BridgeConfig {
+                sequence_floors: sequence_floors.clone(),
+                token_validator: token_validator.clone(),
+                ..build_bridge_config(&config)
+            };
+            info!(addrs = ?bridge_config.bind_addrs().collect::<Vec<_>>(), "Starting bridge");
+            services.push(thread::spawn(move || {
+                // The control loop and observability keep running without the
+                // bridge; readiness reports it as not listening.
+                if let Err(e) = run_bridge(
+                    exchange_bridge,
+                    timebase_bridge,
+                    bridge_config,
+                    stop_bridge,
+                    audit_bridge,
+                ) {
+                    error!(error = %e, "Bridge failed to start");
+                }
+            }));
+        } else {
+            info!("Bridge disabled");
+        }
+
+        #[cfg(feature = "ws")]
+        if let Some(bind_addr) = config.ws_bind.clone() {
+            let exchange_ws = Arc::clone(&exchange);
+            let stop_ws = Arc::clone(&stop);
+            let audit_ws = audit_logger.clone();
+            let ws_config = BridgeConfig {
+                bind_addr,
+                extra_bind_addrs: Vec::new(),
+                sequence_floors: sequence_floors.clone(),
+                token_validator: token_validator.clone(),
+                ..build_bridge_config(&config)
+            };
+            info!(addr = %ws_config.bind_addr, "Starting WebSocket bridge");
+            services.push(thread::spawn(move || {
+                if let Err(e) = run_ws_bridge(exchange_ws, timebase, ws_config, stop_ws, audit_ws) {
+                    error!(error = %e, "WebSocket bridge failed to start");
+                }
+            }));
+        }
+
+        #[cfg(feature = "opcua")]
+        if config.opcua_enabled {
+            let opcua_config = opcua_config(&config);
+            info!(endpoint = %opcua_config.endpoint, "Starting OPC UA server");
+            match run_opcua(
+                Arc::clone(&exchange),
+                timebase,
+                Arc::clone(&stop),
+                opcua_config,
+            ) {
+                Ok(handle) => services.push(handle),
+                // The controller runs on without OPC UA; `/ready` reports it.
+                Err(e) => error!(error = %e, "OPC UA server failed to start"),
+            }
+        }
+
+        #[cfg(feature = "rerun")]
+        if config.rerun_enabled {
+            let rerun_config = RerunConfig {
+                save_path: config.rerun_save_path.clone().map(PathBuf::from),
+                ..Default::default()
+            };
+            info!("Starting Rerun visualization");
+            services.extend(run_rerun(
+                Arc::clone(&exchange),
+                timebase,
+                Arc::clone(&stop),
+                rerun_config,
+            ));
+        }
+
+        Ok(Self {
+            exchange,
+            timebase,
+            stop,
+            iron: Some(iron_handle),
+            services,
+            audit_logger,
+            report: config
+                .report_path
+                .clone()
+                .map(|path| (path, hash_runtime_config(&config))),
+            metrics_socket: config
+                .metrics_addr
+                .as_deref()
+                .and_then(neuro_io::metrics::metrics_socket_path)
+                .map(Path::to_path_buf),
+            #[cfg(feature = "otlp")]
+            otlp_exporter,
+        })
+    }
+
+    /// Hand a recommendation to the control loop, which validates it through
+    /// the safety supervisor like one from the bridge. `timestamp_us` is
+    /// overwritten with the controller's clock.
+    pub fn submit_recommendation(&self, rec: AgentRecommendation) {
+        self.exchange.submit_recommendation(AgentRecommendation {
+            timestamp_us: self.timebase.now_us(),
+            ..rec
+        });
+    }
+
+    /// Latest process state published by the control loop
+    pub fn read_snapshot(&self) -> ProcessSnapshot {
+        self.exchange.read_state()
+    }
+
+    /// Shared state between the control loop and its clients, for anything
+    /// not covered above (e-stop, rejections, recommendation history).
+    pub fn exchange(&self) -> &Arc<StateExchange> {
+        &self.exchange
+    }
+
+    /// True once the stop flag is set, by [`Self::shutdown`] or the host
+    pub fn is_stopping(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Block until the stop flag is set elsewhere, then wind down as
+    /// [`Self::shutdown`] does.
+    pub fn wait(mut self) -> ExecutionStats {
+        self.join()
+    }
+
+    /// Stop the control loop and every service thread, flush the audit log,
+    /// and return the control loop's final statistics.
+    pub fn shutdown(mut self) -> ExecutionStats {
+        self.stop.store(true, Ordering::Relaxed);
+        self.join()
+    }
+
+    fn join(&mut self) -> ExecutionStats {
+        let Some(iron) = self.iron.take() else {
+            return ExecutionStats::default();
+        };
+        // This may run inside Drop, where a second panic would abort, so a
+        // panicked control loop still winds down the services and audit log.
+        let stats = iron.join().unwrap_or_else(|_| {
+            error!("Control loop thread panicked");
+            self.stop.store(true, Ordering::Relaxed);
+            ExecutionStats::default()
+        });
+        for handle in self.services.drain(..) {
+            let _ = handle.join();
+        }
+        #[cfg(feature = "otlp")]
+        if let Some(exporter) = self.otlp_exporter.take() {
+            if let Err(e) = exporter.shutdown() {
+                warn!(error = %e, "OTLP metrics flush failed");
+            }
+        }
+        if let Some(path) = self.metrics_socket.take() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!(error = %e, path = %path.display(), "Failed to remove metrics socket");
+                }
+            }
+        }
+
+        info!(
+            cycles_executed = stats.cycles_executed,
+            cycles_missed = stats.cycles_missed,
+            safety_rejections = stats.safety_rejections,
+            max_jitter_us = stats.max_jitter_us,
+            timing_violations = stats.timing_violations,
+            max_speed_rpm = stats.max_speed_rpm,
+            max_temp_c = stats.max_temp_c,
+            max_pressure_bar = stats.max_pressure_bar,
+            "Run complete"
+        );
+
+        if let Some((path, config_hash)) = self.report.take() {
+            let summary = report::shutdown_report(&stats, &config_hash);
+            match report::write_report(&path, &summary) {
+                Ok(()) => info!(path = %path.display(), "Run report written"),
+                Err(e) => error!(error = %e, path = %path.display(), "Failed to write run report"),
+            }
+        }
+
+        // Log shutdown
+        if let Some(logger) = self.audit_logger.take() {
+            let _ = logger.log_event(
+                self.timebase.now_us(),
+                self.timebase.unix_us(),
+                AuditEventType::SystemShutdown,
+                serde_json::json!({
+                    "cycles_executed": stats.cycles_executed,
+                    "cycles_missed": stats.cycles_missed,
+                    "safety_rejections": stats.safety_rejections,
+                    "timing_violations": stats.timing_violations,
+                    "max_jitter_us": stats.max_jitter_us,
+                    "max_speed_rpm": stats.max_speed_rpm,
+                    "max_temp_c": stats.max_temp_c,
+                    "max_pressure_bar": stats.max_pressure_bar,
+                }),
+            );
+            logger.flush_and_join();
+            if logger.dropped_count() > 0 {
+                warn!(
+                    dropped = logger.dropped_count(),
+                    "Audit entries were dropped"
+                );
+            }
+        }
+        stats
+    }
+}
+
+impl Drop for NeuroPlc {
+    fn drop(&mut self) {
+        if self.iron.is_some() {
+            self.stop.store(true, Ordering::Relaxed);
+            self.join();
+        }
+    }
+}
+
+/// Version and hashes identifying this instance, for the audit log and the
+/// metrics server
+fn build_info(config: &RuntimeConfig) -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        binary_sha256: current_binary_hash(),
+        config_hash: hash_runtime_config(config),
+        cortex_manifest_sha256: cortex_manifest_hash(),
+    }
+}
+
+fn current_binary_hash() -> Option<String> {
+    let path = std::env::current_exe().ok()?;
+    let bytes = std::fs::read(path).ok()?;
+    Some(hash_bytes(&bytes))
+}
+
+fn cortex_manifest_hash() -> Option<String> {
+    let path = std::path::Path::new("python-cortex/pyproject.toml");
+    let bytes = std::fs::read(path).ok()?;
+    Some(hash_bytes(&bytes))
+}
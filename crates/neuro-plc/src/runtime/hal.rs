@@ -0,0 +1,274 @@
+//! Named hardware backends for the control loop.
+//!
+//! `--hal <name>` selects a constructor from a [`HalRegistry`]. The built-in
+//! registry knows `simulated` and `modbus`; integrators embedding the runtime
+//! can register their own backends and pass the registry to
+//! [`run_with_hal`](crate::run_with_hal).
+
+use crate::runtime::config::RuntimeConfig;
+use core_spine::{MachineIO, SimulatedMotor, SimulatedMotorConfig, VotingSensor, VotingTolerance};
+use neuro_io::hal_modbus::{ModbusMotor, RegisterMap, TargetEncoding};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Builds a backend from the runtime configuration
+pub type HalConstructor =
+    Box<dyn Fn(&RuntimeConfig) -> Result<Box<dyn MachineIO>, HalError> + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum HalError {
+    #[error("unknown HAL backend '{name}' (available: {available})")]
+    UnknownBackend { name: String, available: String },
+    #[error("HAL backend '{backend}' failed to initialize: {message}")]
+    Init { backend: String, message: String },
+}
+
+/// Maps backend names to constructors
+pub struct HalRegistry {
+    backends: BTreeMap<String, HalConstructor>,
+}
+
+impl HalRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self {
+            backends: BTreeMap::new(),
+        }
+    }
+
+    /// A registry holding the `simulated` and `modbus` backends
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("simulated", |config| Ok(Box::new(simulated_motor(config)?)));
+        registry.register("modbus", |config| {
+            let addr = config
+                .modbus_addr
+                .as_deref()
+                .ok_or_else(|| HalError::Init {
+                    backend: "modbus".to_string(),
+                    message: "--modbus <ADDR> is required".to_string(),
+                })?;
+            let target_encoding =
+                TargetEncoding::parse(&config.modbus_target).ok_or_else(|| HalError::Init {
+                    backend: "modbus".to_string(),
+                    message: format!("invalid --modbus-target '{}'", config.modbus_target),
+                })?;
+            let register_map = RegisterMap {
+                target_encoding,
+                alarm_inputs: config.modbus_alarm_inputs,
+                ..RegisterMap::default()
+            };
+            let motor =
+                ModbusMotor::with_register_map(addr, register_map).map_err(|e| HalError::Init {
+                    backend: "modbus".to_string(),
+                    message: e.to_string(),
+                })?;
+            Ok(Box::new(motor))
+        });
+        registry
+    }
+
+    /// Add or replace a backend
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn(&RuntimeConfig) -> Result<Box<dyn MachineIO>, HalError> + Send + Sync + 'static,
+    {
+        self.backends
+            .insert(name.to_string(), Box::new(constructor));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.backends.contains_key(name)
+    }
+
+    /// Registered backend names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        self.backends.keys().map(String::as_str).collect()
+    }
+
+    /// Construct the configured backend, wrapped in 2-out-of-3 voting over
+    /// three channels when `--sensor-voting` is set
+    pub fn build_io(&self, config: &RuntimeConfig) -> Result<Box<dyn MachineIO>, HalError> {
+        let backend = config.hal_backend();
+        if !config.sensor_voting {
+            return self.build(backend, config);
+        }
+        if backend == "modbus" && config.voting_modbus_addrs.len() < 2 {
+            return Err(HalError::Init {
+                backend: backend.to_string(),
+                message: "--sensor-voting needs --voting-modbus twice".to_string(),
+            });
+        }
+        let channels = [
+            self.build(backend, &config.voting_channel(0))?,
+            self.build(backend, &config.voting_channel(1))?,
+            self.build(backend, &config.voting_channel(2))?,
+        ];
+        let voting = VotingSensor::new(channels, VotingTolerance::default());
+        // Simulated channels are three independent motors, each needing
+        // the command to keep agreeing.
+        if backend == "simulated" {
+            Ok(Box::new(voting.with_mirrored_writes()))
+        } else {
+            Ok(Box::new(voting))
+        }
+    }
+
+    /// Construct the backend registered under `name`
+    pub fn build(
+        &self,
+        name: &str,
+        config: &RuntimeConfig,
+    ) -> Result<Box<dyn MachineIO>, HalError> {
+        match self.backends.get(name) {
+            Some(constructor) => constructor(config),
+            None => Err(HalError::UnknownBackend {
+                name: name.to_string(),
+                available: self.names().join(", "),
+            }),
+        }
+    }
+}
+
+/// The simulated motor, with physical parameters from `--sim-config` if given
+pub(super) fn simulated_motor(config: &RuntimeConfig) -> Result<SimulatedMotor, HalError> {
+    let Some(path) = &config.sim_config else {
+        return Ok(SimulatedMotor::new());
+    };
+    let init_error = |message: String| HalError::Init {
+        backend: "simulated".to_string(),
+        message: format!("{}: {message}", path.display()),
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| init_error(e.to_string()))?;
+    let sim_config: SimulatedMotorConfig =
+        toml::from_str(&text).map_err(|e| init_error(e.to_string()))?;
+    SimulatedMotor::with_config(sim_config).map_err(|e| init_error(e.to_string()))
+}
+
+impl Default for HalRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_backends() {
+        let registry = HalRegistry::with_builtins();
+        assert_eq!(registry.names(), vec!["modbus", "simulated"]);
+
+        let config = RuntimeConfig::default();
+        let io = registry.build("simulated", &config).unwrap();
+        assert!(io.is_healthy());
+
+        // Modbus without an address is a configuration error, not a panic.
+        assert!(matches!(
+            registry.build("modbus", &config),
+            Err(HalError::Init { .. })
+        ));
+
+        let config = RuntimeConfig {
+            modbus_addr: Some("plc.local".to_string()),
+            ..RuntimeConfig::default()
+        };
+        match registry.build("modbus", &config) {
+            Err(HalError::Init { message, .. }) => {
+                assert!(message.starts_with("invalid Modbus address"), "{message}")
+            }
+            _ => panic!("expected an invalid address error"),
+        }
+    }
+
+    #[test]
+    fn test_sensor_voting_over_simulated() {
+        let registry = HalRegistry::with_builtins();
+        let config = RuntimeConfig {
+            sensor_voting: true,
+            ..RuntimeConfig::default()
+        };
+        let mut io = registry.build_io(&config).unwrap();
+        io.write_speed(500.0);
+        for _ in 0..100 {
+            io.step(0.01);
+        }
+        assert!(io.read_speed() > 0.0);
+        assert!(io.is_healthy());
+    }
+
+    #[test]
+    fn test_voting_channels_use_redundant_modbus_addrs() {
+        let config = RuntimeConfig {
+            modbus_addr: Some("10.0.0.1:502".to_string()),
+            voting_modbus_addrs: vec!["10.0.0.2:502".to_string(), "10.0.0.3:502".to_string()],
+            ..RuntimeConfig::default()
+        };
+        let addrs: Vec<_> = (0..3)
+            .map(|i| config.voting_channel(i).modbus_addr.unwrap())
+            .collect();
+        assert_eq!(addrs, ["10.0.0.1:502", "10.0.0.2:502", "10.0.0.3:502"]);
+    }
+
+    #[test]
+    fn test_modbus_voting_needs_two_redundant_addrs() {
+        let config = RuntimeConfig {
+            modbus_addr: Some("10.0.0.1:502".to_string()),
+            sensor_voting: true,
+            voting_modbus_addrs: vec!["10.0.0.2:502".to_string()],
+            ..RuntimeConfig::default()
+        };
+        match HalRegistry::with_builtins().build_io(&config) {
+            Err(HalError::Init { message, .. }) => {
+                assert!(message.contains("--voting-modbus twice"), "{message}")
+            }
+            other => panic!("expected an init error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_sim_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sim.toml");
+        std::fs::write(&path, "inertia = 2.0\nambient_temp_c = 30.0\n").unwrap();
+        let config = RuntimeConfig {
+            sim_config: Some(path.clone()),
+            ..RuntimeConfig::default()
+        };
+        let motor = simulated_motor(&config).unwrap();
+        assert_eq!(motor.config().inertia, 2.0);
+        assert_eq!(motor.config().friction_coeff, 0.01);
+        assert_eq!(motor.read_temperature(), 30.0);
+
+        std::fs::write(&path, "inertia = -1.0\n").unwrap();
+        match HalRegistry::with_builtins().build("simulated", &config) {
+            Err(HalError::Init { message, .. }) => {
+                assert!(message.contains("inertia must be positive"), "{message}")
+            }
+            other => panic!("expected an init error, got {:?}", other.err()),
+        }
+        std::fs::write(&path, "intertia = 2.0\n").unwrap();
+        assert!(simulated_motor(&config).is_err());
+    }
+
+    #[test]
+    fn test_custom_backend_and_unknown_name() {
+        let mut registry = HalRegistry::with_builtins();
+        registry.register("bench", |_| {
+            let mut motor = SimulatedMotor::new();
+            motor.write_speed(100.0);
+            Ok(Box::new(motor))
+        });
+        assert!(registry.contains("bench"));
+        assert!(registry.build("bench", &RuntimeConfig::default()).is_ok());
+
+        match registry.build("ethercat", &RuntimeConfig::default()) {
+            Err(HalError::UnknownBackend { name, available }) => {
+                assert_eq!(name, "ethercat");
+                assert_eq!(available, "bench, modbus, simulated");
+            }
+            other => panic!("expected unknown backend, got {:?}", other.err()),
+        }
+    }
+}
@@ -1,7 +1,17 @@
 mod app;
+mod check;
 mod config;
+mod embed;
+mod hal;
 mod logging;
+mod realtime;
+mod report;
+mod self_test;
 mod telemetry;
 
-pub use app::{run, run_from_args};
-pub use config::RuntimeConfig;
+pub use app::{run, run_from_args, run_with_hal};
+pub use check::{check_config, ConfigReport};
+pub use config::{ConfigFileError, RuntimeConfig};
+pub use embed::{NeuroPlc, NeuroPlcBuilder, StartError};
+pub use hal::{HalConstructor, HalError, HalRegistry};
+pub use self_test::SelfTestReport;
@@ -0,0 +1,129 @@
+//! Cycle-time validation and real-time scheduling for the control thread.
+//!
+//! `SCHED_FIFO` and CPU pinning need privileges (root or `CAP_SYS_NICE`);
+//! when they are refused the control loop keeps running at normal priority.
+
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Shortest cycle time accepted for the control loop
+pub const MIN_CYCLE_TIME_US: u64 = 100;
+
+/// Clamp a requested cycle time to the supported floor
+pub fn validated_cycle_time(cycle_time_us: u64) -> Duration {
+    if cycle_time_us < MIN_CYCLE_TIME_US {
+        warn!(
+            requested_us = cycle_time_us,
+            min_us = MIN_CYCLE_TIME_US,
+            "Cycle time below floor, using minimum"
+        );
+        return Duration::from_micros(MIN_CYCLE_TIME_US);
+    }
+    Duration::from_micros(cycle_time_us)
+}
+
+/// Apply `SCHED_FIFO` priority and CPU affinity to the calling thread.
+/// Each setting is attempted independently and its outcome logged.
+pub fn apply_to_current_thread(priority: Option<i32>, cpu: Option<usize>) {
+    if let Some(priority) = priority {
+        match set_fifo_priority(priority) {
+            Ok(()) => info!(priority, "Real-time scheduling (SCHED_FIFO) granted"),
+            Err(e) => warn!(
+                priority,
+                error = %e,
+                "Real-time scheduling not granted, running at normal priority"
+            ),
+        }
+    }
+    if let Some(cpu) = cpu {
+        match set_cpu_affinity(cpu) {
+            Ok(()) => info!(cpu, "Control thread pinned to CPU"),
+            Err(e) => warn!(cpu, error = %e, "CPU affinity not applied"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_fifo_priority(priority: i32) -> std::io::Result<()> {
+    let (min, max) = unsafe {
+        (
+            libc::sched_get_priority_min(libc::SCHED_FIFO),
+            libc::sched_get_priority_max(libc::SCHED_FIFO),
+        )
+    };
+    if !(min..=max).contains(&priority) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("priority must be in {min}..={max}"),
+        ));
+    }
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    let rc = unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::from_raw_os_error(rc))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(cpu: usize) -> std::io::Result<()> {
+    // CPU_SET panics past the fixed-size set.
+    let max = libc::CPU_SETSIZE as usize;
+    if cpu >= max {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("CPU must be below {max}"),
+        ));
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut set);
+        libc::CPU_SET(cpu, &mut set);
+        // pid 0 targets the calling thread
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_fifo_priority(_priority: i32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "real-time scheduling is only supported on Linux",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_cpu: usize) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "CPU affinity is only supported on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_time_floor() {
+        assert_eq!(
+            validated_cycle_time(10),
+            Duration::from_micros(MIN_CYCLE_TIME_US)
+        );
+        assert_eq!(validated_cycle_time(2_000), Duration::from_millis(2));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_out_of_range_cpu_is_invalid_input() {
+        let err = set_cpu_affinity(libc::CPU_SETSIZE as usize).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,41 @@
+//! `--report`: a single JSON file summarizing a run, written at shutdown.
+
+use core_spine::ExecutionStats;
+use neuro_io::metrics::{BRIDGE_REJECTS, RECOMMENDATIONS_ACCEPTED};
+use neuro_io::protocol::RejectReason;
+use std::path::Path;
+
+/// Run summary: the control loop's final statistics and high-water marks,
+/// bridge recommendation outcomes and the config hash. Bridge counts come
+/// from the process-wide metrics.
+pub(super) fn shutdown_report(stats: &ExecutionStats, config_hash: &str) -> serde_json::Value {
+    let rejected: serde_json::Map<String, serde_json::Value> = RejectReason::ALL
+        .iter()
+        .map(|reason| {
+            let count = BRIDGE_REJECTS.with_label_values(&[reason.as_str()]).get();
+            (reason.as_str().to_string(), count.into())
+        })
+        .collect();
+    serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "config_hash": config_hash,
+        "stats": stats,
+        "high_water": {
+            "max_jitter_us": stats.max_jitter_us,
+            "max_speed_rpm": stats.max_speed_rpm,
+            "max_temp_c": stats.max_temp_c,
+            "max_pressure_bar": stats.max_pressure_bar,
+        },
+        "recommendations": {
+            "accepted": RECOMMENDATIONS_ACCEPTED.get(),
+            "rejected": rejected,
+            "safety_rejections": stats.safety_rejections,
+        },
+    })
+}
+
+pub(super) fn write_report(path: &Path, report: &serde_json::Value) -> std::io::Result<()> {
+    let mut text = serde_json::to_string_pretty(report)?;
+    text.push('\n');
+    std::fs::write(path, text)
+}
@@ -0,0 +1,242 @@
+//! Power-on self-test (`--self-test`).
+//!
+//! Run once the HAL is built and before the control loop starts, so a
+//! miswired safety layer is caught before the machine moves. A HAL that
+//! connects in the background, such as Modbus, is first given
+//! [`HAL_READY_TIMEOUT`] to report healthy. The safety
+//! checks use fixed inputs just outside the compiled-in limits rather than
+//! inputs derived from the configured limits: a limit that was widened or
+//! corrupted on the way in would otherwise test itself.
+
+use core_spine::{ControlConfig, MachineIO, SafetyLimits, SafetyViolation, Setpoint};
+use neuro_io::audit::AuditLogger;
+use neuro_io::metrics::REGISTRY;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a HAL may take to report healthy before it is checked
+pub(super) const HAL_READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Metric families every running controller exports
+const REQUIRED_METRICS: &[&str] = &[
+    "neuroplc_cycles_executed_total",
+    "neuroplc_safety_rejections_total",
+];
+
+/// Outcome of each self-test check
+#[derive(Debug, Default)]
+pub struct SelfTestReport {
+    pub passed: Vec<&'static str>,
+    /// Check name and why it failed
+    pub failed: Vec<(&'static str, String)>,
+}
+
+impl SelfTestReport {
+    pub fn ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    fn record(&mut self, check: &'static str, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.passed.push(check),
+            Err(reason) => self.failed.push((check, reason)),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "passed": self.passed,
+            "failed": self
+                .failed
+                .iter()
+                .map(|(check, reason)| serde_json::json!({"check": check, "reason": reason}))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Run every check against the limits the control loop will enforce, the
+/// built HAL and, when enabled, the audit log and the file it writes to.
+pub(super) fn run_self_test(
+    limits: &SafetyLimits,
+    io: &dyn MachineIO,
+    audit: Option<(&Path, &AuditLogger)>,
+) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    report.record("safety_validator", check_safety_validator(limits));
+    report.record("hal", check_hal(io));
+    if let Some((path, logger)) = audit {
+        report.record("audit_log", check_audit_log(path, logger));
+    }
+    report.record("metrics", check_metrics());
+    report
+}
+
+/// Poll `io` until it reports healthy or `timeout` passes
+pub(super) fn wait_for_healthy(io: &dyn MachineIO, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while !io.is_healthy() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    true
+}
+
+fn check_safety_validator(limits: &SafetyLimits) -> Result<(), String> {
+    let ControlConfig {
+        safety_limits: reference,
+        cycle_time,
+        ..
+    } = ControlConfig::default();
+    let dt_s = cycle_time.as_secs_f64();
+    let cool = reference.max_temp_c.min(limits.max_temp_c) - 10.0;
+    let hold = limits.min_speed_rpm.max(0.0);
+
+    // Current speed equals the request so only the check under test can fire.
+    let validate = |setpoint: f64, temp: f64| {
+        Setpoint::new(setpoint).validate(limits, setpoint, temp, 0.0, dt_s)
+    };
+    let expect_rejected = |input: &str,
+                           result: Result<_, SafetyViolation>,
+                           expected: fn(&SafetyViolation) -> bool| {
+        match result {
+            Err(violation) if expected(&violation) => Ok(()),
+            Err(violation) => Err(format!(
+                "{input} rejected for the wrong reason: {violation:?}"
+            )),
+            Ok(_) => Err(format!("{input} was accepted")),
+        }
+    };
+
+    validate(hold, cool).map_err(|violation| {
+        format!("holding {hold} rpm at {cool} °C was rejected: {violation:?}")
+    })?;
+    let overspeed = reference.max_speed_rpm + 1.0;
+    expect_rejected(
+        &format!("overspeed {overspeed} rpm"),
+        validate(overspeed, cool),
+        |v| matches!(v, SafetyViolation::ExceedsMaxSpeed { .. }),
+    )?;
+    let overtemp = reference.max_temp_c + 1.0;
+    expect_rejected(
+        &format!("over-temperature {overtemp} °C"),
+        validate(hold, overtemp),
+        |v| matches!(v, SafetyViolation::TemperatureInterlock { .. }),
+    )?;
+    expect_rejected("NaN setpoint", validate(f64::NAN, cool), |v| {
+        matches!(v, SafetyViolation::NonFiniteSetpoint { .. })
+    })?;
+    expect_rejected(
+        "NaN temperature",
+        Setpoint::new(hold).validate(limits, hold, f64::NAN, 0.0, dt_s),
+        |v| matches!(v, SafetyViolation::NonFiniteSensor { .. }),
+    )
+}
+
+fn check_hal(io: &dyn MachineIO) -> Result<(), String> {
+    if !io.is_healthy() {
+        return Err("HAL reports unhealthy".to_string());
+    }
+    let readings = [
+        ("speed", io.read_speed()),
+        ("temperature", io.read_temperature()),
+        ("pressure", io.read_pressure()),
+    ];
+    match readings.iter().find(|(_, value)| !value.is_finite()) {
+        Some((name, value)) => Err(format!("HAL {name} reading is {value}")),
+        None => Ok(()),
+    }
+}
+
+/// The logger writes from a background thread and cannot report a failed
+/// write, so reopen the file the way it does.
+fn check_audit_log(path: &Path, logger: &AuditLogger) -> Result<(), String> {
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{} is not writable: {e}", path.display()))?;
+    match logger.dropped_count() {
+        0 => Ok(()),
+        dropped => Err(format!("audit logger already dropped {dropped} entries")),
+    }
+}
+
+fn check_metrics() -> Result<(), String> {
+    let families = REGISTRY.gather();
+    let missing: Vec<_> = REQUIRED_METRICS
+        .iter()
+        .filter(|name| !families.iter().any(|family| family.get_name() == **name))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("metrics not registered: {missing:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::telemetry;
+    use core_spine::SimulatedMotor;
+
+    #[test]
+    fn test_default_configuration_passes() {
+        telemetry::init();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let logger = AuditLogger::new(&path).unwrap();
+
+        let report = run_self_test(
+            &ControlConfig::default().safety_limits,
+            &SimulatedMotor::new(),
+            Some((&path, &logger)),
+        );
+        assert!(report.ok(), "{:?}", report.failed);
+        assert_eq!(
+            report.passed,
+            ["safety_validator", "hal", "audit_log", "metrics"]
+        );
+    }
+
+    #[test]
+    fn test_broken_limit_fails() {
+        telemetry::init();
+        let io = SimulatedMotor::new();
+        let mut limits = ControlConfig::default().safety_limits;
+        limits.max_speed_rpm = 1.0e9;
+        let report = run_self_test(&limits, &io, None);
+        assert!(!report.ok());
+        assert_eq!(report.failed[0].0, "safety_validator");
+        assert!(report.failed[0].1.contains("overspeed"));
+
+        let mut limits = ControlConfig::default().safety_limits;
+        limits.max_temp_c = f64::NAN;
+        let report = run_self_test(&limits, &io, None);
+        assert!(report.failed[0].1.contains("over-temperature"));
+
+        assert_eq!(report.to_json()["failed"][0]["check"], "safety_validator");
+    }
+
+    #[test]
+    fn test_unwritable_audit_log_fails() {
+        telemetry::init();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let logger = AuditLogger::new(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir(&path).unwrap();
+
+        let report = run_self_test(
+            &ControlConfig::default().safety_limits,
+            &SimulatedMotor::new(),
+            Some((&path, &logger)),
+        );
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "audit_log");
+    }
+}
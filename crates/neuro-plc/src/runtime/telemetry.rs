@@ -1,51 +1,545 @@
-use core_spine::StateExchange;
+use core_spine::{ExecutionStats, RecommendationAgeHistogram, SafetyViolation, StateExchange};
 use neuro_io::metrics::{
-    init_metrics, serve_metrics, AGENT_CONFIDENCE, AGENT_TARGET_RPM, CYCLES_EXECUTED,
-    CYCLE_JITTER_US, MOTOR_SPEED_RPM, MOTOR_TEMP_C, PRESSURE_BAR, SAFETY_STATE,
+    init_metrics, publish_build_info, serve_metrics_with_routes, BuildInfo, MetricsAdmin,
+    MetricsRoutes, AGENT_CONFIDENCE, AGENT_TARGET_RPM, AGENT_TIMEOUTS, CYCLES_EXECUTED,
+    CYCLES_MISSED, CYCLE_JITTER_US, HEALTH, JITTER_ALERT_LEVEL, LAST_RECOMMENDATION_AGE_US,
+    MAX_JITTER_US, MAX_PRESSURE_BAR_SESSION, MAX_SPEED_RPM_SESSION, MAX_TEMP_C_SESSION,
+    MOTOR_SPEED_RPM, MOTOR_TEMP_C, PRESSURE_BAR, RECOMMENDATION_AGE_US, SAFETY_REJECTIONS,
+    SAFETY_STATE, SAFETY_VIOLATIONS, STATE_SNAPSHOTS_SKIPPED, TIMING_VIOLATIONS,
 };
 use std::sync::{atomic::AtomicBool, Arc};
 use std::thread;
-use std::time::Duration;
-use tracing::info;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
 pub fn init() {
     init_metrics();
 }
 
-pub fn start_metrics_server(addr: &Option<String>) -> Option<thread::JoinHandle<()>> {
+/// Also serves `GET /state` from `exchange` and, given `build`, publishes
+/// `neuroplc_build_info` and serves `GET /version`. With an admin token,
+/// `POST /metrics/reset` also clears the Iron Thread's session high-water
+/// marks so the next publish does not restore them.
+pub fn start_metrics_server(
+    addr: &Option<String>,
+    admin_token: Option<String>,
+    exchange: &Arc<StateExchange>,
+    build: Option<BuildInfo>,
+) -> Option<thread::JoinHandle<()>> {
+    if let Some(build) = &build {
+        publish_build_info(build);
+    }
     addr.as_ref().map(|addr| {
-        info!(addr = %addr, "Starting metrics server");
-        serve_metrics(addr.clone())
+        info!(addr = %addr, reset_enabled = admin_token.is_some(), "Starting metrics server");
+        let admin = admin_token.map(|token| {
+            let exchange = Arc::clone(exchange);
+            MetricsAdmin {
+                token,
+                on_reset: Box::new(move || exchange.request_session_reset()),
+            }
+        });
+        let routes = MetricsRoutes {
+            admin,
+            exchange: Some(Arc::clone(exchange)),
+            build,
+        };
+        serve_metrics_with_routes(addr.clone(), routes)
     })
 }
 
+#[cfg(feature = "otlp")]
+pub fn start_otlp_exporter(endpoint: &Option<String>) -> Option<neuro_io::otlp::OtlpExporter> {
+    let endpoint = endpoint.as_ref()?;
+    match neuro_io::otlp::OtlpExporter::start(endpoint, neuro_io::otlp::DEFAULT_EXPORT_INTERVAL) {
+        Ok(exporter) => Some(exporter),
+        Err(e) => {
+            tracing::error!(error = %e, "OTLP metrics export disabled");
+            None
+        }
+    }
+}
+
+/// Last exported value of each Iron Thread counter, so the Prometheus
+/// counters advance by the delta since the previous poll.
+#[derive(Default)]
+struct ExportedStats {
+    cycles_executed: u64,
+    cycles_missed: u64,
+    safety_rejections: u64,
+    safety_violations: [u64; SafetyViolation::KINDS.len()],
+    agent_timeouts: u64,
+    timing_violations: u64,
+    recommendation_age: RecommendationAgeHistogram,
+    jitter_warn_cycles: u64,
+    jitter_crit_cycles: u64,
+    jitter_alert: JitterAlert,
+    /// Write sequence of the last process state exported
+    state_sequence: u64,
+}
+
+/// Shortest gap between repeated jitter alert logs while timing stays
+/// degraded, and how long the alert level holds after the last cycle that
+/// reached it, so a single late cycle is still visible to a slow scraper.
+const JITTER_ALERT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Alert level derived from the cycles that reached the jitter thresholds:
+/// 0 ok, 1 warning, 2 critical. Each level holds for
+/// [`JITTER_ALERT_INTERVAL`] after the last cycle that reached it. Rising is
+/// logged at once; sustained degradation at most once per interval.
+#[derive(Default)]
+struct JitterAlert {
+    level: u8,
+    /// When a cycle last reached the warning and the critical threshold
+    seen: [Option<Instant>; 2],
+    last_logged: Option<Instant>,
+    /// Cycles over a threshold not yet reported in a log line
+    unlogged_cycles: u64,
+}
+
+impl JitterAlert {
+    /// Fold in the cycles that reached each threshold since the last poll
+    /// and return the level to export.
+    fn update(&mut self, warn_cycles: u64, crit_cycles: u64, now: Instant) -> u8 {
+        let observed = if crit_cycles > 0 {
+            2
+        } else if warn_cycles > 0 {
+            1
+        } else {
+            0
+        };
+        self.unlogged_cycles += warn_cycles.max(crit_cycles);
+        // A critical cycle also reached the warning threshold.
+        for seen in &mut self.seen[..observed as usize] {
+            *seen = Some(now);
+        }
+        let held = |seen: Option<Instant>| {
+            seen.is_some_and(|seen| now.duration_since(seen) < JITTER_ALERT_INTERVAL)
+        };
+        let level = match self.seen {
+            [_, critical] if held(critical) => 2,
+            [warning, _] if held(warning) => 1,
+            _ => 0,
+        };
+        let raised = level > self.level;
+        if level == 0 && self.level > 0 {
+            info!("Cycle jitter back within alert thresholds");
+            self.last_logged = None;
+            self.unlogged_cycles = 0;
+        }
+        self.level = level;
+
+        let log_due = raised
+            || (observed > 0
+                && self
+                    .last_logged
+                    .is_none_or(|logged| now.duration_since(logged) >= JITTER_ALERT_INTERVAL));
+        if log_due {
+            let cycles = std::mem::take(&mut self.unlogged_cycles);
+            if observed == 2 {
+                error!(cycles, "Cycle jitter above the critical threshold");
+            } else {
+                warn!(cycles, "Cycle jitter above the warning threshold");
+            }
+            self.last_logged = Some(now);
+        }
+        self.level
+    }
+}
+
+fn advance_counter(inc_by: impl Fn(u64), exported: &mut u64, current: u64) {
+    if current > *exported {
+        inc_by(current - *exported);
+        *exported = current;
+    }
+}
+
+/// Replay the ages recorded since the last poll into the histogram. Each
+/// new sample in a bucket is observed at that bucket's mean age, which lands
+/// in the same bucket and keeps the histogram sum exact.
+fn advance_age_histogram(
+    exported: &mut RecommendationAgeHistogram,
+    current: &RecommendationAgeHistogram,
+) {
+    for bucket in 0..current.counts.len() {
+        let new_samples = current.counts[bucket].saturating_sub(exported.counts[bucket]);
+        if new_samples == 0 {
+            continue;
+        }
+        let new_sum = current.sums_us[bucket].saturating_sub(exported.sums_us[bucket]);
+        let mean_age_us = new_sum as f64 / new_samples as f64;
+        for _ in 0..new_samples {
+            RECOMMENDATION_AGE_US.observe(mean_age_us);
+        }
+    }
+    *exported = *current;
+}
+
+/// Fold the cycles that reached the jitter thresholds since the last poll
+/// into the alert and return its level
+fn update_jitter_alert(stats: &ExecutionStats, exported: &mut ExportedStats) -> u8 {
+    let warn_cycles = stats
+        .jitter_warn_cycles
+        .saturating_sub(exported.jitter_warn_cycles);
+    let crit_cycles = stats
+        .jitter_crit_cycles
+        .saturating_sub(exported.jitter_crit_cycles);
+    exported.jitter_warn_cycles = stats.jitter_warn_cycles;
+    exported.jitter_crit_cycles = stats.jitter_crit_cycles;
+    exported
+        .jitter_alert
+        .update(warn_cycles, crit_cycles, Instant::now())
+}
+
+/// Copy the latest process state and execution stats into the metrics
+fn update_metrics(exchange: &StateExchange, exported: &mut ExportedStats) {
+    let state = exchange.read_state_sequenced();
+    STATE_SNAPSHOTS_SKIPPED
+        .with_label_values(&["metrics"])
+        .inc_by(state.skipped_since(exported.state_sequence));
+    exported.state_sequence = state.sequence;
+    let snapshot = state.value;
+    MOTOR_SPEED_RPM.set(snapshot.motor_speed_rpm);
+    MOTOR_TEMP_C.set(snapshot.motor_temp_c);
+    PRESSURE_BAR.set(snapshot.pressure_bar);
+    CYCLE_JITTER_US.observe(snapshot.cycle_jitter_us as f64);
+    SAFETY_STATE.set(snapshot.safety_state.as_u8() as f64);
+    HEALTH.set_hal_healthy(exchange.hal_healthy());
+
+    let stats = exchange.execution_stats();
+    advance_counter(
+        |n| CYCLES_EXECUTED.inc_by(n),
+        &mut exported.cycles_executed,
+        stats.cycles_executed,
+    );
+    advance_counter(
+        |n| CYCLES_MISSED.inc_by(n),
+        &mut exported.cycles_missed,
+        stats.cycles_missed,
+    );
+    advance_counter(
+        |n| SAFETY_REJECTIONS.inc_by(n),
+        &mut exported.safety_rejections,
+        stats.safety_rejections,
+    );
+    for (index, kind) in SafetyViolation::KINDS.into_iter().enumerate() {
+        advance_counter(
+            |n| SAFETY_VIOLATIONS.with_label_values(&[kind]).inc_by(n),
+            &mut exported.safety_violations[index],
+            stats.safety_violations[index],
+        );
+    }
+    advance_counter(
+        |n| AGENT_TIMEOUTS.inc_by(n),
+        &mut exported.agent_timeouts,
+        stats.agent_timeouts,
+    );
+    advance_counter(
+        |n| TIMING_VIOLATIONS.inc_by(n),
+        &mut exported.timing_violations,
+        stats.timing_violations,
+    );
+    MAX_JITTER_US.set(stats.max_jitter_us as f64);
+    MAX_SPEED_RPM_SESSION.set(stats.max_speed_rpm);
+    MAX_TEMP_C_SESSION.set(stats.max_temp_c);
+    MAX_PRESSURE_BAR_SESSION.set(stats.max_pressure_bar);
+    LAST_RECOMMENDATION_AGE_US.set(stats.last_recommendation_age_us as f64);
+    advance_age_histogram(&mut exported.recommendation_age, &stats.recommendation_age);
+    JITTER_ALERT_LEVEL.set(update_jitter_alert(&stats, exported) as f64);
+
+    if let Some(rec) = exchange.get_recommendation(snapshot.timestamp_us) {
+        if let Some(target) = rec.target_speed_rpm {
+            AGENT_TARGET_RPM.set(target);
+        }
+        AGENT_CONFIDENCE.set(rec.confidence as f64);
+    }
+}
+
+/// Copy the control loop's state into the metrics every `interval`, raised
+/// to at least a millisecond
 pub fn start_metrics_updater(
     exchange: Arc<StateExchange>,
     stop: Arc<AtomicBool>,
+    interval: Duration,
+) -> thread::JoinHandle<()> {
+    let interval = interval.max(Duration::from_millis(1));
+    thread::spawn(move || {
+        let mut exported = ExportedStats::default();
+        while !stop.load(std::sync::atomic::Ordering::Relaxed) {
+            update_metrics(&exchange, &mut exported);
+            thread::sleep(interval);
+        }
+    })
+}
+
+/// Evaluate and log jitter alerts every `interval` when no metrics updater
+/// runs to do it, raised to at least a millisecond
+pub fn start_jitter_alerts(
+    exchange: Arc<StateExchange>,
+    stop: Arc<AtomicBool>,
+    interval: Duration,
 ) -> thread::JoinHandle<()> {
+    let interval = interval.max(Duration::from_millis(1));
     thread::spawn(move || {
-        let mut last_cycle_count = 0u64;
+        let mut exported = ExportedStats::default();
         while !stop.load(std::sync::atomic::Ordering::Relaxed) {
-            let snapshot = exchange.read_state();
-            MOTOR_SPEED_RPM.set(snapshot.motor_speed_rpm);
-            MOTOR_TEMP_C.set(snapshot.motor_temp_c);
-            PRESSURE_BAR.set(snapshot.pressure_bar);
-            CYCLE_JITTER_US.observe(snapshot.cycle_jitter_us as f64);
-            SAFETY_STATE.set(snapshot.safety_state.as_u8() as f64);
-            if snapshot.cycle_count > last_cycle_count {
-                let delta = snapshot.cycle_count - last_cycle_count;
-                CYCLES_EXECUTED.inc_by(delta);
-                last_cycle_count = snapshot.cycle_count;
-            }
-
-            if let Some(rec) = exchange.get_recommendation(snapshot.timestamp_us) {
-                if let Some(target) = rec.target_speed_rpm {
-                    AGENT_TARGET_RPM.set(target);
+            update_jitter_alert(&exchange.execution_stats(), &mut exported);
+            thread::sleep(interval);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_spine::{
+        AgentRecommendation, Clock, ControlConfig, CycleStats, IronThread, LogicalClock, MachineIO,
+        SensorFaults, SimulatedMotor, SimulatedMotorConfig,
+    };
+    use std::sync::Mutex;
+
+    /// Metrics are process-global; serialize tests that assert on them
+    static METRICS_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_safety_rejection_counter_moves() {
+        let _guard = METRICS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        clock.advance(Duration::from_millis(1));
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+        let mut exported = ExportedStats::default();
+        update_metrics(&exchange, &mut exported);
+        let before = SAFETY_REJECTIONS.get();
+
+        exchange.submit_recommendation(AgentRecommendation {
+            timestamp_us: clock.now_us(),
+            target_speed_rpm: Some(5_000.0),
+            ramp_rate_rpm_per_s: None,
+            confidence: 1.0,
+            reasoning_hash: [0u8; 32].into(),
+        });
+        iron.step();
+        update_metrics(&exchange, &mut exported);
+
+        assert!(SAFETY_REJECTIONS.get() > before);
+    }
+
+    #[test]
+    fn test_over_temperature_increase_counts_as_temperature_interlock() {
+        let _guard = METRICS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        clock.advance(Duration::from_millis(1));
+        let config = ControlConfig::default();
+        let motor = SimulatedMotor::with_config(SimulatedMotorConfig {
+            faults: SensorFaults {
+                stuck_temp_c: Some(config.safety_limits.max_temp_c + 10.0),
+                ..SensorFaults::default()
+            },
+            ..SimulatedMotorConfig::default()
+        })
+        .unwrap();
+        let mut iron = IronThread::new(motor, config, Arc::clone(&exchange), clock.clone());
+        let mut exported = ExportedStats::default();
+        update_metrics(&exchange, &mut exported);
+        let count = |kind: &str| SAFETY_VIOLATIONS.with_label_values(&[kind]).get();
+        let interlocks = count("temperature_interlock");
+        let overspeeds = count("exceeds_max_speed");
+
+        exchange.submit_recommendation(AgentRecommendation {
+            timestamp_us: clock.now_us(),
+            target_speed_rpm: Some(10.0),
+            ramp_rate_rpm_per_s: None,
+            confidence: 1.0,
+            reasoning_hash: [0u8; 32].into(),
+        });
+        iron.step();
+        update_metrics(&exchange, &mut exported);
+
+        assert_eq!(count("temperature_interlock"), interlocks + 1);
+        assert_eq!(count("exceeds_max_speed"), overspeeds);
+    }
+
+    #[test]
+    fn test_recommendation_age_histogram_accumulates() {
+        let _guard = METRICS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let clock = LogicalClock::new();
+        let mut iron = IronThread::new(
+            SimulatedMotor::new(),
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            clock.clone(),
+        );
+        let mut exported = ExportedStats::default();
+        let count_before = RECOMMENDATION_AGE_US.get_sample_count();
+        let sum_before = RECOMMENDATION_AGE_US.get_sample_sum();
+
+        // A fresh recommendation every 10 ms, applied on each 1 ms cycle.
+        for cycle in 0..100u64 {
+            clock.advance(Duration::from_millis(1));
+            if cycle % 10 == 0 {
+                exchange.submit_recommendation(AgentRecommendation {
+                    timestamp_us: clock.now_us(),
+                    target_speed_rpm: Some(0.0),
+                    ramp_rate_rpm_per_s: None,
+                    confidence: 1.0,
+                    reasoning_hash: [0u8; 32].into(),
+                });
+            }
+            iron.step();
+            if cycle % 25 == 24 {
+                update_metrics(&exchange, &mut exported);
+            }
+        }
+
+        assert_eq!(RECOMMENDATION_AGE_US.get_sample_count() - count_before, 100);
+        // Ages 0..=9 ms repeat ten times.
+        let sum = RECOMMENDATION_AGE_US.get_sample_sum() - sum_before;
+        assert!((sum - 450_000.0).abs() < 1e-6, "{sum}");
+    }
+
+    #[test]
+    fn test_jitter_alert_holds_and_decays() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut alert = JitterAlert::default();
+
+        assert_eq!(alert.update(0, 0, at(0)), 0);
+        assert_eq!(alert.update(3, 0, at(1)), 1);
+        assert_eq!(alert.update(1, 1, at(2)), 2);
+        assert_eq!(alert.last_logged, Some(at(2)));
+        // Sustained warnings within the interval are not logged again and
+        // do not lower the level before the hold expires.
+        assert_eq!(alert.update(5, 0, at(5)), 2);
+        assert_eq!(alert.last_logged, Some(at(2)));
+        assert_eq!(alert.unlogged_cycles, 5);
+        // The critical hold expires first, leaving the later warning's.
+        assert_eq!(alert.update(0, 0, at(12)), 1);
+        assert_eq!(alert.update(0, 0, at(15)), 0);
+        assert_eq!(alert.unlogged_cycles, 0);
+    }
+
+    #[test]
+    fn test_overrun_raises_jitter_alert_to_critical() {
+        /// Holds the cycle for 5 ms once, well past a 1 ms cycle
+        struct StallingIo {
+            cycle: u32,
+        }
+        impl MachineIO for StallingIo {
+            fn step(&mut self, _dt_s: f64) {
+                self.cycle += 1;
+                if self.cycle == 3 {
+                    thread::sleep(Duration::from_millis(5));
+                }
+            }
+            fn read_speed(&self) -> f64 {
+                0.0
+            }
+            fn read_temperature(&self) -> f64 {
+                30.0
+            }
+            fn read_pressure(&self) -> f64 {
+                1.0
+            }
+            fn write_speed(&mut self, _rpm: f64) {}
+            fn cycle_stats(&self) -> CycleStats {
+                CycleStats::default()
+            }
+            fn is_healthy(&self) -> bool {
+                true
+            }
+        }
+
+        let _guard = METRICS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let config = ControlConfig {
+            jitter_warn_us: Some(1_000),
+            jitter_crit_us: Some(3_000),
+            ..ControlConfig::default()
+        };
+        let mut iron = IronThread::new(
+            StallingIo { cycle: 0 },
+            config,
+            Arc::clone(&exchange),
+            LogicalClock::new(),
+        );
+        let mut exported = ExportedStats::default();
+        update_metrics(&exchange, &mut exported);
+        assert_eq!(JITTER_ALERT_LEVEL.get(), 0.0);
+
+        let stop = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| iron.run(&stop));
+            while exchange.execution_stats().cycles_executed < 10 {
+                thread::sleep(Duration::from_millis(1));
+            }
+            stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        update_metrics(&exchange, &mut exported);
+
+        assert!(exchange.execution_stats().jitter_crit_cycles >= 1);
+        assert_eq!(JITTER_ALERT_LEVEL.get(), 2.0);
+    }
+
+    #[test]
+    fn test_high_water_gauge_keeps_transient_spike() {
+        /// Temperature spikes on the second cycle only
+        struct SpikingIo {
+            cycle: u32,
+        }
+        impl MachineIO for SpikingIo {
+            fn step(&mut self, _dt_s: f64) {
+                self.cycle += 1;
+            }
+            fn read_speed(&self) -> f64 {
+                0.0
+            }
+            fn read_temperature(&self) -> f64 {
+                if self.cycle == 2 {
+                    75.0
+                } else {
+                    30.0
                 }
-                AGENT_CONFIDENCE.set(rec.confidence as f64);
             }
+            fn read_pressure(&self) -> f64 {
+                1.0
+            }
+            fn write_speed(&mut self, _rpm: f64) {}
+            fn cycle_stats(&self) -> CycleStats {
+                CycleStats::default()
+            }
+            fn is_healthy(&self) -> bool {
+                true
+            }
+        }
 
-            thread::sleep(Duration::from_millis(200));
+        let _guard = METRICS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        init();
+        let exchange = Arc::new(StateExchange::new(1_000_000));
+        let mut iron = IronThread::new(
+            SpikingIo { cycle: 0 },
+            ControlConfig::default(),
+            Arc::clone(&exchange),
+            LogicalClock::new(),
+        );
+        let mut exported = ExportedStats::default();
+        for _ in 0..4 {
+            iron.step();
+            update_metrics(&exchange, &mut exported);
         }
-    })
+
+        assert_eq!(MOTOR_TEMP_C.get(), 30.0);
+        assert_eq!(MAX_TEMP_C_SESSION.get(), 75.0);
+        assert_eq!(MAX_PRESSURE_BAR_SESSION.get(), 1.0);
+    }
 }
@@ -0,0 +1,450 @@
+//! Drives the controller through the embedding API only: no CLI, no
+//! tracing subscriber, no bridge.
+
+use neuro_io::metrics::REGISTRY;
+use neuro_plc::{AgentRecommendation, HalRegistry, NeuroPlc, RuntimeConfig, StartError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn config() -> RuntimeConfig {
+    RuntimeConfig {
+        bridge_enabled: false,
+        // Shared CI hosts miss 1 ms deadlines; keep jitter from latching Safe.
+        max_jitter_us: 1_000_000,
+        ..RuntimeConfig::default()
+    }
+}
+
+#[test]
+fn test_embedded_controller_follows_recommendations() {
+    let plc = NeuroPlc::builder()
+        .config(config())
+        .start()
+        .expect("controller starts");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut speed = 0.0;
+    while Instant::now() < deadline {
+        // Within the per-cycle rate limit, so no ramp is needed.
+        plc.submit_recommendation(AgentRecommendation {
+            timestamp_us: 0,
+            target_speed_rpm: Some(40.0),
+            ramp_rate_rpm_per_s: None,
+            confidence: 0.9,
+            reasoning_hash: [0xab; 32].into(),
+        });
+        thread::sleep(Duration::from_millis(20));
+        speed = plc.read_snapshot().motor_speed_rpm;
+        if speed > 1.0 {
+            break;
+        }
+    }
+    assert!(speed > 1.0, "motor never spun up: {speed} rpm");
+    assert_eq!(plc.read_snapshot().applied_reasoning_hash, [0xab; 32]);
+
+    let stats = plc.shutdown();
+    assert!(stats.cycles_executed > 0);
+    assert!(stats.max_speed_rpm > 1.0);
+}
+
+#[test]
+fn test_unknown_hal_is_a_start_error() {
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            hal: Some("ethercat".to_string()),
+            ..config()
+        })
+        .hal_registry(HalRegistry::with_builtins())
+        .start()
+        .err()
+        .expect("unknown backend is rejected");
+    assert!(matches!(err, StartError::Hal(_)), "{err}");
+}
+
+#[test]
+fn test_mistyped_action_scope_is_a_start_error() {
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            auth_action_scopes: vec!["comand.estop=operator:estop".to_string()],
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("mistyped action scope is rejected");
+    assert!(matches!(err, StartError::ActionScope(_)), "{err}");
+}
+
+#[test]
+fn test_zero_kept_audit_files_is_a_start_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            audit_path: Some(dir.path().join("audit.jsonl")),
+            audit_max_bytes: Some(1_024),
+            audit_max_files: 0,
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("zero kept files is rejected");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+}
+
+#[test]
+fn test_required_signatures_without_a_key_is_a_start_error() {
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            require_signed_recommendations: true,
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("required signatures need a key");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+}
+
+#[test]
+fn test_invalid_agent_timeout_is_a_start_error() {
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            agent_timeout: "ramp-to-zero:fast".to_string(),
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("invalid policy is rejected");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+}
+
+#[test]
+fn test_invalid_estop_profile_is_a_start_error() {
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            estop_profile: "ramp:-5".to_string(),
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("invalid profile is rejected");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+}
+
+#[test]
+fn test_invalid_wait_strategy_is_a_start_error() {
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            wait_strategy: "hybird:200".to_string(),
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("invalid strategy is rejected");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+}
+
+#[test]
+fn test_unusable_auth_pubkey_is_a_start_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let start = |config: RuntimeConfig| NeuroPlc::builder().config(config).start().err();
+
+    let err = start(RuntimeConfig {
+        auth_pubkey: Some(dir.path().join("missing.pub").display().to_string()),
+        ..config()
+    })
+    .expect("unreadable key is rejected");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+
+    let path = dir.path().join("agent.pub");
+    std::fs::write(&path, [7u8; 32]).unwrap();
+    let err = start(RuntimeConfig {
+        auth_pubkey: Some(path.display().to_string()),
+        auth_secret: Some("secret".to_string()),
+        ..config()
+    })
+    .expect("two keys are rejected");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+}
+
+#[test]
+fn test_auth_secret_file_is_read_and_must_not_be_empty() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("secret");
+    std::fs::write(&path, " \n").unwrap();
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            auth_secret_file: Some(path.display().to_string()),
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("empty secret file is rejected");
+    assert!(matches!(err, StartError::InvalidOption { .. }), "{err}");
+
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            auth_secret_file: Some(dir.path().join("missing").display().to_string()),
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("missing secret file is rejected");
+    assert!(matches!(err, StartError::Secret(_)), "{err}");
+}
+
+#[test]
+fn test_build_info_metric_carries_crate_version() {
+    let plc = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            metrics_addr: Some("127.0.0.1:0".to_string()),
+            ..config()
+        })
+        .start()
+        .expect("controller starts");
+
+    let family = REGISTRY
+        .gather()
+        .into_iter()
+        .find(|family| family.get_name() == "neuroplc_build_info")
+        .expect("build info is published");
+    let metric = &family.get_metric()[0];
+    let label = |name: &str| {
+        metric
+            .get_label()
+            .iter()
+            .find(|label| label.get_name() == name)
+            .map(|label| label.get_value().to_string())
+    };
+    assert_eq!(label("version").as_deref(), Some(env!("CARGO_PKG_VERSION")));
+    assert_eq!(label("config_hash").map(|hash| hash.len()), Some(64));
+    assert_eq!(metric.get_gauge().get_value(), 1.0);
+
+    plc.shutdown();
+}
+
+#[test]
+fn test_run_report_is_written_at_shutdown() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("report.json");
+    let plc = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            report_path: Some(path.clone()),
+            ..config()
+        })
+        .start()
+        .expect("controller starts");
+    thread::sleep(Duration::from_millis(50));
+    let stats = plc.shutdown();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(report["config_hash"].as_str().map(str::len), Some(64));
+    assert_eq!(report["stats"]["cycles_executed"], stats.cycles_executed);
+    assert!(report["stats"]["recommendation_age"].is_object());
+    for key in [
+        "max_jitter_us",
+        "max_speed_rpm",
+        "max_temp_c",
+        "max_pressure_bar",
+    ] {
+        assert!(report["high_water"][key].is_number(), "{key}");
+    }
+    assert!(report["recommendations"]["accepted"].is_u64());
+    assert!(report["recommendations"]["rejected"]["expired"].is_u64());
+    assert!(report["recommendations"]["safety_rejections"].is_u64());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_metrics_socket_is_removed_at_shutdown() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("metrics.sock");
+    let plc = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            metrics_addr: Some(format!("unix:{}", socket.display())),
+            ..config()
+        })
+        .start()
+        .expect("controller starts");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !socket.exists() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(socket.exists(), "metrics socket was not created");
+
+    plc.shutdown();
+    assert!(!socket.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_failed_start_leaves_no_metrics_server() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("metrics.sock");
+    let not_a_dir = dir.path().join("file");
+    std::fs::write(&not_a_dir, b"").unwrap();
+    let err = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            metrics_addr: Some(format!("unix:{}", socket.display())),
+            audit_path: Some(not_a_dir.join("audit.jsonl")),
+            ..config()
+        })
+        .start()
+        .err()
+        .expect("unopenable audit log is rejected");
+    assert!(matches!(err, StartError::Audit { .. }), "{err}");
+
+    thread::sleep(Duration::from_millis(200));
+    assert!(!socket.exists(), "metrics server outlived the failed start");
+}
+
+#[test]
+fn test_self_test_result_is_audited() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let plc = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            self_test: true,
+            audit_path: Some(path.clone()),
+            ..config()
+        })
+        .start()
+        .expect("self-test passes on the simulated motor");
+    plc.shutdown();
+
+    let timeline = neuro_io::audit::AuditLogger::read_timeline(&path).unwrap();
+    let record = timeline
+        .iter()
+        .find(|record| {
+            matches!(
+                record.entry.event_type,
+                neuro_io::audit::AuditEventType::SelfTest
+            )
+        })
+        .expect("self-test is audited");
+    assert_eq!(record.entry.details["failed"], serde_json::json!([]));
+}
+
+/// Simulated motor that, like the Modbus HAL before its first poll,
+/// reports unhealthy for a while after it is built
+struct SlowToConnect {
+    motor: core_spine::SimulatedMotor,
+    healthy_at: Instant,
+}
+
+impl core_spine::MachineIO for SlowToConnect {
+    fn step(&mut self, dt_s: f64) {
+        self.motor.step(dt_s)
+    }
+    fn read_speed(&self) -> f64 {
+        self.motor.read_speed()
+    }
+    fn read_temperature(&self) -> f64 {
+        self.motor.read_temperature()
+    }
+    fn read_pressure(&self) -> f64 {
+        self.motor.read_pressure()
+    }
+    fn write_speed(&mut self, rpm: f64) {
+        self.motor.write_speed(rpm)
+    }
+    fn cycle_stats(&self) -> core_spine::CycleStats {
+        self.motor.cycle_stats()
+    }
+    fn is_healthy(&self) -> bool {
+        Instant::now() >= self.healthy_at
+    }
+}
+
+#[test]
+fn test_self_test_waits_for_a_connecting_hal() {
+    let mut registry = HalRegistry::with_builtins();
+    registry.register("slow", |_| {
+        Ok(Box::new(SlowToConnect {
+            motor: core_spine::SimulatedMotor::new(),
+            healthy_at: Instant::now() + Duration::from_millis(200),
+        }))
+    });
+    let plc = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            hal: Some("slow".to_string()),
+            self_test: true,
+            ..config()
+        })
+        .hal_registry(registry)
+        .start()
+        .expect("self-test passes once the HAL connects");
+    plc.shutdown();
+}
+
+#[test]
+fn test_get_config_masks_the_auth_secret() {
+    use neuro_io::auth::TokenValidator;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    let secret = "library-api-get-config-secret";
+    let bind_addr = {
+        let probe = TcpListener::bind("127.0.0.1:0").unwrap();
+        probe.local_addr().unwrap().to_string()
+    };
+    let plc = NeuroPlc::builder()
+        .config(RuntimeConfig {
+            bridge_enabled: true,
+            bind_addr: bind_addr.clone(),
+            auth_secret: Some(secret.to_string()),
+            ..config()
+        })
+        .start()
+        .expect("controller starts");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut stream = loop {
+        match TcpStream::connect(&bind_addr) {
+            Ok(stream) => break stream,
+            Err(e) if Instant::now() > deadline => panic!("bridge did not start: {e}"),
+            Err(_) => thread::sleep(Duration::from_millis(20)),
+        }
+    };
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let token = TokenValidator::new(secret.as_bytes().to_vec(), 60).generate_token();
+    for message in [
+        serde_json::json!({
+            "type": "hello",
+            "protocol_version": {"major": 1, "minor": 0},
+            "capabilities": ["command.get_config"],
+        }),
+        serde_json::json!({
+            "type": "command",
+            "command": "get_config",
+            "sequence": 1,
+            "auth_token": token,
+        }),
+    ] {
+        writeln!(stream, "{message}").unwrap();
+    }
+
+    let mut reader = BufReader::new(&stream);
+    let reply = loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let frame: serde_json::Value = serde_json::from_str(&line).unwrap();
+        if frame["type"] == "config" {
+            break line;
+        }
+    };
+    plc.shutdown();
+
+    assert!(!reply.contains(secret));
+    let frame: serde_json::Value = serde_json::from_str(&reply).unwrap();
+    assert_eq!(frame["sequence"], 1);
+    assert_eq!(
+        frame["config"]["config_hash"].as_str().map(str::len),
+        Some(64)
+    );
+    let settings = &frame["config"]["settings"];
+    assert_eq!(settings["auth_enabled"], true);
+    assert_eq!(settings["bind_addr"], bind_addr);
+    assert_eq!(settings["cycle_time_us"], 1_000);
+}
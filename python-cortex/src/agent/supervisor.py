@@ -211,13 +211,37 @@ def run(host: str, port: int, attack_mode: bool, model_path: Optional[str] = Non
             "exp": now_s + auth_max_age,
             "nonce": secrets.token_hex(16),
         }
+        header = json.dumps({"alg": "HS256", "typ": "JWT"}, separators=(",", ":")).encode("utf-8")
         payload = json.dumps(claims, separators=(",", ":"), sort_keys=True).encode("utf-8")
-        signature = hmac.new(auth_secret.encode("utf-8"), payload, hashlib.sha256).digest()
-        return f"{_b64url(payload)}.{_b64url(signature)}"
+        signing_input = f"{_b64url(header)}.{_b64url(payload)}"
+        signature = hmac.new(
+            auth_secret.encode("utf-8"), signing_input.encode("ascii"), hashlib.sha256
+        ).digest()
+        return f"{signing_input}.{_b64url(signature)}"
+
+    def _sign_recommendation(msg: dict) -> str:
+        # Canonical form checked by the spine; see docs/protocol/README.md.
+        signing_input = json.dumps(
+            [
+                "recommendation",
+                msg["sequence"],
+                msg["issued_at_unix_us"],
+                msg["ttl_ms"],
+                msg["target_speed_rpm"],
+                msg.get("ramp_rate_rpm_per_s"),
+                msg["confidence"],
+                msg["reasoning_hash"],
+            ],
+            separators=(",", ":"),
+        ).encode("utf-8")
+        signature = hmac.new(auth_secret.encode("utf-8"), signing_input, hashlib.sha256).digest()
+        return _b64url(signature)
 
     last_llm_at = 0.0
     last_llm_candidate = None
     last_llm_meta = None
+    # Reported in the next hello so the spine can count the states we missed.
+    last_snapshot_sequence = None
 
     while True:
         try:
@@ -232,6 +256,8 @@ def run(host: str, port: int, attack_mode: bool, model_path: Optional[str] = Non
                         "capabilities": ["recommendation.v1", "auth.hmac-sha256"],
                         "client_id": "python-cortex",
                     }
+                    if last_snapshot_sequence is not None:
+                        hello["last_snapshot_sequence"] = last_snapshot_sequence
                     file.write((json.dumps(hello) + "\n").encode("utf-8"))
                     file.flush()
                 sequence = 0
@@ -243,8 +269,16 @@ def run(host: str, port: int, attack_mode: bool, model_path: Optional[str] = Non
                         state = json.loads(line.decode("utf-8"))
                     except json.JSONDecodeError:
                         continue
+                    if state.get("type") == "ping":
+                        pong = {"type": "pong", "sequence": state.get("sequence", 0)}
+                        file.write((json.dumps(pong) + "\n").encode("utf-8"))
+                        file.flush()
+                        continue
                     if state.get("type") != "state":
                         continue
+                    last_snapshot_sequence = state.get(
+                        "snapshot_sequence", last_snapshot_sequence
+                    )
 
                     cycle += 1
                     obs = StateObservation.model_validate(state)
@@ -416,13 +450,15 @@ def run(host: str, port: int, attack_mode: bool, model_path: Optional[str] = Non
                         "sequence": sequence,
                         "issued_at_unix_us": issued_at_unix_us,
                         "ttl_ms": 1000,
-                        "target_speed_rpm": rec.target_speed_rpm if rec.approved else None,
-                        "confidence": rec.confidence if rec.approved else 0.0,
+                        "target_speed_rpm": float(rec.target_speed_rpm) if rec.approved else None,
+                        # The spine reads confidence as f32; six digits survive that.
+                        "confidence": round(rec.confidence, 6) if rec.approved else 0.0,
                         "reasoning_hash": reasoning_hash,
                         "client_unix_us": issued_at_unix_us,
                     }
                     if auth_secret:
                         msg["auth_token"] = _auth_token(int(time.time()))
+                        msg["signature"] = _sign_recommendation(msg)
                     file.write((json.dumps(msg) + "\n").encode("utf-8"))
                     file.flush()
 